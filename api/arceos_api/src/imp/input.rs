@@ -0,0 +1,6 @@
+pub use axinput::MouseEvent as AxMouseEvent;
+
+/// Reads the oldest pending mouse event, if any.
+pub fn ax_read_mouse_event() -> Option<AxMouseEvent> {
+    axinput::read_mouse_event()
+}
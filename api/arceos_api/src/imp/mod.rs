@@ -16,10 +16,24 @@ cfg_display! {
     pub use display::*;
 }
 
+cfg_usb! {
+    mod usb;
+    pub use usb::*;
+}
+
+cfg_input! {
+    mod input;
+    pub use input::*;
+}
+
 mod stdio {
     use core::fmt;
 
     pub fn ax_console_read_byte() -> Option<u8> {
+        #[cfg(feature = "input")]
+        if let Some(c) = axinput::read_byte() {
+            return Some(c);
+        }
         axhal::console::getchar().map(|c| if c == b'\r' { b'\n' } else { c })
     }
 
@@ -64,6 +64,30 @@ pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult {
     socket.0.shutdown()
 }
 
+#[cfg(feature = "async")]
+pub async fn ax_tcp_connect_async(socket: &AxTcpSocketHandle, addr: SocketAddr) -> AxResult {
+    socket.0.connect_async(addr).await
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_tcp_accept_async(
+    socket: &AxTcpSocketHandle,
+) -> AxResult<(AxTcpSocketHandle, SocketAddr)> {
+    let new_sock = socket.0.accept_async().await?;
+    let addr = new_sock.peer_addr()?;
+    Ok((AxTcpSocketHandle(new_sock), addr))
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_tcp_send_async(socket: &AxTcpSocketHandle, buf: &[u8]) -> AxResult<usize> {
+    socket.0.send_async(buf).await
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_tcp_recv_async(socket: &AxTcpSocketHandle, buf: &mut [u8]) -> AxResult<usize> {
+    socket.0.recv_async(buf).await
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // UDP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -117,6 +141,33 @@ pub fn ax_udp_poll(socket: &AxUdpSocketHandle) -> AxResult<AxPollState> {
     socket.0.poll()
 }
 
+#[cfg(feature = "async")]
+pub async fn ax_udp_recv_from_async(
+    socket: &AxUdpSocketHandle,
+    buf: &mut [u8],
+) -> AxResult<(usize, SocketAddr)> {
+    socket.0.recv_from_async(buf).await
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_udp_send_to_async(
+    socket: &AxUdpSocketHandle,
+    buf: &[u8],
+    addr: SocketAddr,
+) -> AxResult<usize> {
+    socket.0.send_to_async(buf, addr).await
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_udp_send_async(socket: &AxUdpSocketHandle, buf: &[u8]) -> AxResult<usize> {
+    socket.0.send_async(buf).await
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_udp_recv_async(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<usize> {
+    socket.0.recv_async(buf).await
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Miscellaneous
 ////////////////////////////////////////////////////////////////////////////////
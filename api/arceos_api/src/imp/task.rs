@@ -26,6 +26,10 @@ pub fn ax_exit(_exit_code: i32) -> ! {
 cfg_task! {
     use core::time::Duration;
 
+    pub use axtask::CpuMask as AxCpuMask;
+    pub use axtask::TaskInfo as AxTaskInfo;
+    pub use axtask::TaskStats as AxTaskStats;
+
     /// A handle to a task.
     pub struct AxTaskHandle {
         inner: axtask::AxTaskRef,
@@ -67,6 +71,22 @@ cfg_task! {
         }
     }
 
+    pub fn ax_spawn_with_affinity<F>(
+        f: F,
+        name: alloc::string::String,
+        stack_size: usize,
+        cpumask: AxCpuMask,
+    ) -> AxTaskHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let inner = axtask::spawn_raw_with_affinity(f, name, stack_size, cpumask);
+        AxTaskHandle {
+            id: inner.id().as_u64(),
+            inner,
+        }
+    }
+
     pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32> {
         task.inner.join()
     }
@@ -82,6 +102,42 @@ cfg_task! {
         }
     }
 
+    pub fn ax_set_affinity(task: &AxTaskHandle, cpumask: AxCpuMask) -> crate::AxResult {
+        if axtask::set_affinity(&task.inner, cpumask) {
+            Ok(())
+        } else {
+            axerrno::ax_err!(InvalidInput, "ax_set_affinity: no CPU to run on")
+        }
+    }
+
+    pub fn ax_get_affinity(task: &AxTaskHandle) -> AxCpuMask {
+        axtask::get_affinity(&task.inner)
+    }
+
+    pub fn ax_set_current_affinity(cpumask: AxCpuMask) -> crate::AxResult {
+        if axtask::set_affinity(axtask::current().as_task_ref(), cpumask) {
+            Ok(())
+        } else {
+            axerrno::ax_err!(InvalidInput, "ax_set_current_affinity: no CPU to run on")
+        }
+    }
+
+    pub fn ax_get_current_affinity() -> AxCpuMask {
+        axtask::get_affinity(axtask::current().as_task_ref())
+    }
+
+    pub fn ax_task_stats(task: &AxTaskHandle) -> AxTaskStats {
+        axtask::task_stats(&task.inner)
+    }
+
+    pub fn ax_current_task_stats() -> AxTaskStats {
+        axtask::task_stats(axtask::current().as_task_ref())
+    }
+
+    pub fn ax_task_list() -> alloc::vec::Vec<AxTaskInfo> {
+        axtask::tasks().iter().map(axtask::task_info).collect()
+    }
+
     pub fn ax_wait_queue_wait(
         wq: &AxWaitQueueHandle,
         until_condition: impl Fn() -> bool,
@@ -109,3 +165,23 @@ cfg_task! {
         }
     }
 }
+
+#[cfg(feature = "async")]
+pub fn ax_block_on(fut: impl core::future::Future<Output = ()>) {
+    axtask::future::block_on(fut)
+}
+
+#[cfg(feature = "async")]
+pub fn ax_spawn_async(fut: impl core::future::Future<Output = ()> + Send + 'static) {
+    axtask::future::spawn(fut)
+}
+
+#[cfg(feature = "async")]
+pub async fn ax_wait_queue_wait_async(wq: &AxWaitQueueHandle, until_condition: impl Fn() -> bool) {
+    wq.0.wait_until_async(until_condition).await
+}
+
+#[cfg(all(feature = "async", feature = "irq"))]
+pub async fn ax_sleep_until_async(deadline: crate::time::AxTimeValue) {
+    axtask::future::sleep_until(deadline).await
+}
@@ -0,0 +1,11 @@
+use alloc::boxed::Box;
+
+pub use axusb::{HotplugEvent as AxUsbHotplugEvent, UsbDevice as AxUsbDevice};
+
+pub fn ax_usb_register_hotplug_callback(callback: Box<dyn Fn(&AxUsbHotplugEvent) + Send + Sync>) {
+    axusb::register_hotplug_callback(Box::new(move |_, event| callback(event)))
+}
+
+pub fn ax_usb_poll_hotplug() {
+    axusb::poll_hotplug()
+}
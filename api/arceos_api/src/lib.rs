@@ -13,6 +13,7 @@
     feature = "fs",
     feature = "net",
     feature = "multitask",
+    feature = "usb",
     feature = "dummy-if-not-enabled"
 ))]
 extern crate alloc;
@@ -85,11 +86,16 @@ pub mod task {
         @cfg "multitask";
         pub type AxTaskHandle;
         pub type AxWaitQueueHandle;
+        pub type AxCpuMask;
+        pub type AxTaskStats;
+        pub type AxTaskInfo;
     }
 
     define_api! {
         /// Current task is going to sleep, it will be woken up at the given deadline.
         ///
+        /// It may return early if the task is interrupted, e.g., by a signal.
+        ///
         /// If the feature `multitask` is not enabled, it uses busy-wait instead
         pub fn ax_sleep_until(deadline: crate::time::AxTimeValue);
 
@@ -114,11 +120,35 @@ pub mod task {
             name: alloc::string::String,
             stack_size: usize
         ) -> AxTaskHandle;
+        /// Spawns a new task with the given entry point and other arguments,
+        /// which is only allowed to run on the CPUs in `cpumask`.
+        pub fn ax_spawn_with_affinity(
+            f: impl FnOnce() + Send + 'static,
+            name: alloc::string::String,
+            stack_size: usize,
+            cpumask: AxCpuMask,
+        ) -> AxTaskHandle;
         /// Waits for the given task to exit, and returns its exit code (the
         /// argument of [`ax_exit`]).
         pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32>;
         /// Sets the priority of the current task.
         pub fn ax_set_current_priority(prio: isize) -> crate::AxResult;
+        /// Sets the CPUs that the given task is allowed to run on.
+        pub fn ax_set_affinity(task: &AxTaskHandle, cpumask: AxCpuMask) -> crate::AxResult;
+        /// Returns the CPUs that the given task is allowed to run on.
+        pub fn ax_get_affinity(task: &AxTaskHandle) -> AxCpuMask;
+        /// Sets the CPUs that the current task is allowed to run on. It is
+        /// migrated immediately if the current CPU is not one of them.
+        pub fn ax_set_current_affinity(cpumask: AxCpuMask) -> crate::AxResult;
+        /// Returns the CPUs that the current task is allowed to run on.
+        pub fn ax_get_current_affinity() -> AxCpuMask;
+        /// Takes a snapshot of the accounting information of the given task,
+        /// such as its CPU time and context switches.
+        pub fn ax_task_stats(task: &AxTaskHandle) -> AxTaskStats;
+        /// Takes a snapshot of the accounting information of the current task.
+        pub fn ax_current_task_stats() -> AxTaskStats;
+        /// Returns the information of all live tasks, sorted by ID.
+        pub fn ax_task_list() -> alloc::vec::Vec<AxTaskInfo>;
 
         /// Blocks the current task and put it into the wait queue, until the
         /// given condition becomes true, or the the given duration has elapsed
@@ -134,6 +164,36 @@ pub mod task {
         /// `count` is `u32::MAX`, it will wake up all tasks in the wait queue.
         pub fn ax_wait_queue_wake(wq: &AxWaitQueueHandle, count: u32);
     }
+
+    define_api! {
+        @cfg "async";
+
+        /// Runs a future to completion on the current task.
+        ///
+        /// The current task is blocked while the future is pending, until the
+        /// future is woken, e.g., by a wait queue, a timer or a socket.
+        pub fn ax_block_on(fut: impl core::future::Future<Output = ()>);
+        /// Spawns a future on the executor shared by all the spawned futures.
+        ///
+        /// The future is polled by a fixed pool of worker tasks when it is
+        /// woken, instead of by a task of its own.
+        pub fn ax_spawn_async(fut: impl core::future::Future<Output = ()> + Send + 'static);
+    }
+
+    define_api! {
+        @cfg "async";
+
+        /// Waits asynchronously in the wait queue until the given condition
+        /// becomes true, without blocking the current task.
+        pub async fn ax_wait_queue_wait_async(
+            wq: &AxWaitQueueHandle,
+            until_condition: impl Fn() -> bool,
+        );
+        /// Waits asynchronously until the given deadline, without blocking the
+        /// current task.
+        #[cfg(feature = "irq")]
+        pub async fn ax_sleep_until_async(deadline: crate::time::AxTimeValue);
+    }
 }
 
 /// Filesystem manipulation operations.
@@ -314,6 +374,42 @@ pub mod net {
         /// packets to the NIC.
         pub fn ax_poll_interfaces() -> AxResult;
     }
+
+    define_api! {
+        @cfg "net";
+
+        /// Connects the TCP socket to the given address and port
+        /// asynchronously.
+        #[cfg(feature = "async")]
+        pub async fn ax_tcp_connect_async(socket: &AxTcpSocketHandle, addr: SocketAddr) -> AxResult;
+        /// Accepts a new connection on the TCP socket asynchronously.
+        #[cfg(feature = "async")]
+        pub async fn ax_tcp_accept_async(socket: &AxTcpSocketHandle) -> AxResult<(AxTcpSocketHandle, SocketAddr)>;
+        /// Transmits data in the given buffer on the TCP socket asynchronously.
+        #[cfg(feature = "async")]
+        pub async fn ax_tcp_send_async(socket: &AxTcpSocketHandle, buf: &[u8]) -> AxResult<usize>;
+        /// Receives data on the TCP socket asynchronously, and stores it in the
+        /// given buffer. On success, returns the number of bytes read.
+        #[cfg(feature = "async")]
+        pub async fn ax_tcp_recv_async(socket: &AxTcpSocketHandle, buf: &mut [u8]) -> AxResult<usize>;
+
+        /// Receives a single datagram message on the UDP socket asynchronously.
+        #[cfg(feature = "async")]
+        pub async fn ax_udp_recv_from_async(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)>;
+        /// Sends data on the UDP socket to the given address asynchronously. On
+        /// success, returns the number of bytes written.
+        #[cfg(feature = "async")]
+        pub async fn ax_udp_send_to_async(socket: &AxUdpSocketHandle, buf: &[u8], addr: SocketAddr) -> AxResult<usize>;
+        /// Sends data on the UDP socket to the remote address to which it is
+        /// connected asynchronously.
+        #[cfg(feature = "async")]
+        pub async fn ax_udp_send_async(socket: &AxUdpSocketHandle, buf: &[u8]) -> AxResult<usize>;
+        /// Receives a single datagram message on the UDP socket from the remote
+        /// address to which it is connected asynchronously. On success, returns
+        /// the number of bytes read.
+        #[cfg(feature = "async")]
+        pub async fn ax_udp_recv_async(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<usize>;
+    }
 }
 
 /// Graphics manipulation operations.
@@ -332,6 +428,43 @@ pub mod display {
     }
 }
 
+/// Input device operations.
+pub mod input {
+    define_api_type! {
+        @cfg "input";
+        pub type AxMouseEvent;
+    }
+
+    define_api! {
+        @cfg "input";
+        /// Reads the oldest pending mouse event, if any.
+        pub fn ax_read_mouse_event() -> Option<AxMouseEvent>;
+    }
+}
+
+/// USB device operations.
+pub mod usb {
+    define_api_type! {
+        @cfg "usb";
+        pub type AxUsbDevice;
+        pub type AxUsbHotplugEvent;
+    }
+
+    define_api! {
+        @cfg "usb";
+        /// Registers a callback notified of every USB device attached or
+        /// detached from now on.
+        pub fn ax_usb_register_hotplug_callback(
+            callback: alloc::boxed::Box<dyn Fn(&AxUsbHotplugEvent) + Send + Sync>,
+        );
+        /// Handles the USB devices attached or detached since the last call,
+        /// and notifies the callbacks.
+        ///
+        /// This is done periodically if the `multitask` feature is enabled.
+        pub fn ax_usb_poll_hotplug();
+    }
+}
+
 /// Input/output operations.
 pub mod io {
     define_api_type! {
@@ -20,6 +20,25 @@ macro_rules! define_api_type {
 }
 
 macro_rules! define_api {
+    (
+        @cfg $feature:literal;
+        $( $(#[$attr:meta])* $vis:vis async fn $name:ident( $($arg:ident : $type:ty),* $(,)? ) $( -> $ret:ty )? ; )+
+    ) => {
+        $(
+            #[cfg(feature = $feature)]
+            $(#[$attr])*
+            $vis async fn $name( $($arg : $type),* ) $( -> $ret )? {
+                $crate::imp::$name( $($arg),* ).await
+            }
+
+            #[allow(unused_variables)]
+            #[cfg(all(feature = "dummy-if-not-enabled", not(feature = $feature)))]
+            $(#[$attr])*
+            $vis async fn $name( $($arg : $type),* ) $( -> $ret )? {
+                unimplemented!(stringify!($name))
+            }
+        )+
+    };
     ($( $(#[$attr:meta])* $vis:vis fn $name:ident( $($arg:ident : $type:ty),* $(,)? ) $( -> $ret:ty )? ; )+) => {
         $(
             $(#[$attr])*
@@ -74,6 +93,14 @@ macro_rules! cfg_display {
     ($($item:item)*) => { _cfg_common!{ "display" $($item)* } }
 }
 
+macro_rules! cfg_input {
+    ($($item:item)*) => { _cfg_common!{ "input" $($item)* } }
+}
+
+macro_rules! cfg_usb {
+    ($($item:item)*) => { _cfg_common!{ "usb" $($item)* } }
+}
+
 macro_rules! cfg_task {
     ($($item:item)*) => { _cfg_common!{ "multitask" $($item)* } }
 }
@@ -1,16 +1,32 @@
 fn main() {
     use std::io::Write;
 
-    fn gen_pthread_mutex(out_file: &str) -> std::io::Result<()> {
+    fn gen_pthread_types(out_file: &str) -> std::io::Result<()> {
         // TODO: generate size and initial content automatically.
-        let (mutex_size, mutex_init) = if cfg!(feature = "multitask") {
+        // `core::mem::transmute::<_, [usize; N]>(T::new())` of the types in
+        // `src/imp/pthread`, where `T` is `PthreadMutex`, `PthreadCond`,
+        // `PthreadRwLock` or `PthreadBarrier`.
+        let (mutex, cond, rwlock, barrier_size) = if cfg!(feature = "multitask") {
             if cfg!(feature = "smp") {
-                (6, "{0, 8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 6]>(axsync::Mutex::new(()))
+                (
+                    (13, "{0, 8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0}"),
+                    (12, "{0, 8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0}"),
+                    (
+                        22,
+                        "{0, 8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0}",
+                    ),
+                    14,
+                )
             } else {
-                (5, "{8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 5]>(axsync::Mutex::new(()))
+                (
+                    (11, "{0, 8, 0, 0, 0, 8, 0, 0, 0, 0, 0}"),
+                    (10, "{0, 8, 0, 0, 0, 8, 0, 0, 0, 0}"),
+                    (18, "{0, 8, 0, 0, 0, 8, 0, 0, 0, 8, 0, 0, 0, 8, 0, 0, 0, 0}"),
+                    11,
+                )
             }
         } else {
-            (1, "{0}")
+            ((1, "{0}"), (1, "{0}"), (1, "{0}"), 1)
         };
 
         let mut output = Vec::new();
@@ -18,14 +34,27 @@ fn main() {
             output,
             "// Generated by arceos_posix_api/build.rs, DO NOT edit!"
         )?;
+        for (name, initializer, (size, init)) in [
+            ("mutex", "MUTEX", mutex),
+            ("cond", "COND", cond),
+            ("rwlock", "RWLOCK", rwlock),
+        ] {
+            writeln!(
+                output,
+                r#"
+typedef struct {{
+    long __l[{size}];
+}} pthread_{name}_t;
+
+#define PTHREAD_{initializer}_INITIALIZER {{ .__l = {init}}}"#
+            )?;
+        }
         writeln!(
             output,
             r#"
 typedef struct {{
-    long __l[{mutex_size}];
-}} pthread_mutex_t;
-
-#define PTHREAD_MUTEX_INITIALIZER {{ .__l = {mutex_init}}}
+    long __l[{barrier_size}];
+}} pthread_barrier_t;
 "#
         )?;
         std::fs::write(out_file, output)?;
@@ -48,11 +77,27 @@ typedef struct {{
             "pthread_attr_t",
             "pthread_mutex_t",
             "pthread_mutexattr_t",
+            "pthread_cond_t",
+            "pthread_condattr_t",
+            "pthread_rwlock_t",
+            "pthread_rwlockattr_t",
+            "pthread_barrier_t",
+            "pthread_barrierattr_t",
+            "pthread_once_t",
+            "pthread_key_t",
+            "cpu_set_t",
+            "sched_param",
+            "pid_t",
             "epoll_event",
             "iovec",
             "clockid_t",
             "rlimit",
+            "rusage",
             "aibuf",
+            "sigset_t",
+            "sigaction",
+            "siginfo_t",
+            "itimerval",
         ];
         let allow_vars = [
             "O_.*",
@@ -65,8 +110,18 @@ typedef struct {{
             "EPOLL_CTL_.*",
             "EPOLL.*",
             "RLIMIT_.*",
+            "RUSAGE_.*",
+            "CLOCK_.*",
             "EAI_.*",
             "MAXADDRS",
+            "SCHED_.*",
+            "PTHREAD_.*",
+            "FUTEX_.*",
+            "_NSIG",
+            "SIG.*",
+            "SA_.*",
+            "SI_.*",
+            "ITIMER_.*",
         ];
 
         #[derive(Debug)]
@@ -74,7 +129,7 @@ typedef struct {{
 
         impl bindgen::callbacks::ParseCallbacks for MyCallbacks {
             fn include_file(&self, fname: &str) {
-                if !fname.contains("ax_pthread_mutex.h") {
+                if !fname.contains("ax_pthread_types.h") {
                     println!("cargo:rerun-if-changed={}", fname);
                 }
             }
@@ -101,6 +156,6 @@ typedef struct {{
             .expect("Couldn't write bindings!");
     }
 
-    gen_pthread_mutex("../../ulib/axlibc/include/ax_pthread_mutex.h").unwrap();
+    gen_pthread_types("../../ulib/axlibc/include/ax_pthread_types.h").unwrap();
     gen_c_to_rust_bindings("ctypes.h", "src/ctypes_gen.rs");
 }
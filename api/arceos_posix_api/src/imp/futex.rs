@@ -0,0 +1,299 @@
+//! Fast user-space locking, i.e., `futex(2)`.
+//!
+//! The waiters are kept in a table keyed by the address of the futex word, and
+//! each of them blocks on its own [`WaitQueue`], so only the ones taken from
+//! the table are woken up. As all tasks share the same address space, private
+//! futexes are the same as the shared ones.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::spin::SpinNoIrq;
+use axtask::WaitQueue;
+
+use crate::ctypes;
+
+/// A task waiting on a futex.
+struct Waiter {
+    /// The bitset to match the wakeups, see `FUTEX_WAIT_BITSET`.
+    bitset: u32,
+    /// The address of the futex it is queued on, changed by `FUTEX_REQUEUE`.
+    key: AtomicUsize,
+    woken: AtomicBool,
+    wq: WaitQueue,
+}
+
+/// All the futexes with waiters, keyed by address. The queue of an address is
+/// removed when its last waiter leaves.
+static FUTEXES: SpinNoIrq<BTreeMap<usize, VecDeque<Arc<Waiter>>>> = SpinNoIrq::new(BTreeMap::new());
+
+fn futex_key(uaddr: *mut u32) -> LinuxResult<usize> {
+    if uaddr.is_null() || !uaddr.is_aligned() {
+        Err(LinuxError::EINVAL)
+    } else {
+        Ok(uaddr as usize)
+    }
+}
+
+fn load(uaddr: *mut u32) -> u32 {
+    unsafe { AtomicU32::from_ptr(uaddr) }.load(Ordering::SeqCst)
+}
+
+/// Removes `waiter` from the queue of its current address, returns `false` if
+/// it has been woken up.
+fn remove_waiter(waiter: &Arc<Waiter>) -> bool {
+    let mut futexes = FUTEXES.lock();
+    if waiter.woken.load(Ordering::Acquire) {
+        return false;
+    }
+    let key = waiter.key.load(Ordering::Acquire);
+    if let Some(queue) = futexes.get_mut(&key) {
+        queue.retain(|w| !Arc::ptr_eq(w, waiter));
+        if queue.is_empty() {
+            futexes.remove(&key);
+        }
+    }
+    true
+}
+
+/// Blocks the current task if the futex word at `uaddr` is still `val`, until
+/// it is woken up by a wakeup matching `bitset`, or the `deadline` (of
+/// [`axhal::time::current_time`]) is reached.
+fn futex_wait(uaddr: *mut u32, val: u32, deadline: Option<Duration>, bitset: u32) -> LinuxResult {
+    let key = futex_key(uaddr)?;
+    if bitset == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let waiter = Arc::new(Waiter {
+        bitset,
+        key: AtomicUsize::new(key),
+        woken: AtomicBool::new(false),
+        wq: WaitQueue::new(),
+    });
+    let signals = super::signal::current_signals();
+    {
+        // the wakers change the word before they lock the table, so the
+        // wakeup is not missed after it is checked
+        let mut futexes = FUTEXES.lock();
+        if load(uaddr) != val {
+            return Err(LinuxError::EAGAIN);
+        }
+        futexes.entry(key).or_default().push_back(waiter.clone());
+    }
+
+    loop {
+        // also wakes up if it is interrupted by a signal
+        let condition = || waiter.woken.load(Ordering::Acquire) || signals.has_pending();
+        let wq = &waiter.wq;
+        let timed_out = match deadline {
+            None => {
+                wq.wait_until(condition);
+                false
+            }
+            #[cfg(feature = "irq")]
+            Some(deadline) => match deadline.checked_sub(axhal::time::current_time()) {
+                Some(dur) if !dur.is_zero() => wq.wait_timeout_until(dur, condition),
+                _ => !condition(),
+            },
+            #[cfg(not(feature = "irq"))]
+            Some(_) => {
+                warn!("futex: the timeout is ignored without the `irq` feature");
+                wq.wait_until(condition);
+                false
+            }
+        };
+        if waiter.woken.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        if timed_out {
+            return if remove_waiter(&waiter) {
+                Err(LinuxError::ETIMEDOUT)
+            } else {
+                Ok(())
+            };
+        }
+        // restarted after the handlers installed with `SA_RESTART`
+        if signals.has_pending() && super::signal::handle_signals(true) {
+            return if remove_waiter(&waiter) {
+                Err(LinuxError::EINTR)
+            } else {
+                Ok(())
+            };
+        }
+    }
+}
+
+/// Takes at most `nr_wake` waiters matching `bitset` from the queue of `key`
+/// and marks them woken, then moves at most `nr_requeue` of the remaining ones
+/// to the queue of `key2` if it is specified.
+///
+/// Returns the number of the waiters requeued, and the woken ones to be
+/// notified.
+fn take_waiters(
+    futexes: &mut BTreeMap<usize, VecDeque<Arc<Waiter>>>,
+    key: usize,
+    nr_wake: usize,
+    bitset: u32,
+    requeue: Option<(usize, usize)>,
+) -> (usize, Vec<Arc<Waiter>>) {
+    let Some(queue) = futexes.get_mut(&key) else {
+        return (0, Vec::new());
+    };
+    let mut woken = Vec::new();
+    queue.retain(|waiter| {
+        if woken.len() < nr_wake && waiter.bitset & bitset != 0 {
+            waiter.woken.store(true, Ordering::Release);
+            woken.push(waiter.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    let mut moved = VecDeque::new();
+    if let Some((key2, nr_requeue)) = requeue {
+        if key2 != key {
+            let n = nr_requeue.min(queue.len());
+            moved = queue.drain(..n).collect();
+        }
+    }
+    if queue.is_empty() {
+        futexes.remove(&key);
+    }
+    let requeued = moved.len();
+    if let Some((key2, _)) = requeue {
+        if !moved.is_empty() {
+            let queue2 = futexes.entry(key2).or_default();
+            for waiter in moved {
+                waiter.key.store(key2, Ordering::Release);
+                queue2.push_back(waiter);
+            }
+        }
+    }
+    (requeued, woken)
+}
+
+/// Wakes up at most `nr_wake` waiters on `uaddr` matching `bitset`, and
+/// requeues at most `nr_requeue` of the others to `uaddr2` if it is specified.
+///
+/// If `cmp` is specified, fails with `EAGAIN` if the futex word is not it.
+fn futex_wake(
+    uaddr: *mut u32,
+    nr_wake: usize,
+    bitset: u32,
+    requeue: Option<(*mut u32, usize)>,
+    cmp: Option<u32>,
+) -> LinuxResult<usize> {
+    let key = futex_key(uaddr)?;
+    if bitset == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let requeue = match requeue {
+        Some((uaddr2, nr_requeue)) => Some((futex_key(uaddr2)?, nr_requeue)),
+        None => None,
+    };
+    let (requeued, woken) = {
+        let mut futexes = FUTEXES.lock();
+        if cmp.is_some_and(|val| load(uaddr) != val) {
+            return Err(LinuxError::EAGAIN);
+        }
+        take_waiters(&mut futexes, key, nr_wake, bitset, requeue)
+    };
+    // the requeued waiters keep waiting on their own queues. A taken waiter
+    // that is not blocked yet is not notified, but it will see it is woken
+    // before it blocks, so it is counted as well.
+    for waiter in &woken {
+        waiter.wq.notify_one(true);
+    }
+    Ok(woken.len() + requeued)
+}
+
+/// Converts the timeout of `FUTEX_WAIT` (relative) or `FUTEX_WAIT_BITSET`
+/// (absolute) to a deadline of [`axhal::time::current_time`].
+unsafe fn futex_deadline(
+    timeout: *const ctypes::timespec,
+    absolute: bool,
+) -> LinuxResult<Option<Duration>> {
+    if timeout.is_null() {
+        return Ok(None);
+    }
+    let ts = unsafe { *timeout };
+    if ts.tv_sec < 0 || ts.tv_nsec < 0 || ts.tv_nsec > 999999999 {
+        return Err(LinuxError::EINVAL);
+    }
+    let dur = Duration::from(ts);
+    Ok(Some(if absolute {
+        // the monotonic and real-time clocks are the same
+        dur
+    } else {
+        axhal::time::current_time() + dur
+    }))
+}
+
+/// Manipulate the futex at `uaddr`, see `futex(2)`.
+///
+/// The supported operations are `FUTEX_WAIT`, `FUTEX_WAKE`, `FUTEX_REQUEUE`,
+/// `FUTEX_CMP_REQUEUE`, `FUTEX_WAIT_BITSET` and `FUTEX_WAKE_BITSET`, with
+/// optional `FUTEX_PRIVATE_FLAG` and `FUTEX_CLOCK_REALTIME`. For the requeue
+/// operations, `timeout` is the maximum number of waiters to be requeued.
+///
+/// Returns 0 for the wait operations, or the number of waiters woken up (and
+/// requeued) for the others.
+pub unsafe fn sys_futex(
+    uaddr: *mut u32,
+    futex_op: c_int,
+    val: u32,
+    timeout: *const ctypes::timespec,
+    uaddr2: *mut u32,
+    val3: u32,
+) -> c_int {
+    debug!(
+        "sys_futex <= {:#x}, {:#x}, {}",
+        uaddr as usize, futex_op, val
+    );
+    syscall_body!(sys_futex, {
+        let nr_requeue = timeout as usize;
+        let cmd = futex_op as u32 & !(ctypes::FUTEX_PRIVATE_FLAG | ctypes::FUTEX_CLOCK_REALTIME);
+        let count = match cmd {
+            ctypes::FUTEX_WAIT => {
+                let deadline = unsafe { futex_deadline(timeout, false)? };
+                futex_wait(uaddr, val, deadline, ctypes::FUTEX_BITSET_MATCH_ANY)?;
+                0
+            }
+            ctypes::FUTEX_WAIT_BITSET => {
+                let deadline = unsafe { futex_deadline(timeout, true)? };
+                futex_wait(uaddr, val, deadline, val3)?;
+                0
+            }
+            ctypes::FUTEX_WAKE => futex_wake(
+                uaddr,
+                val as usize,
+                ctypes::FUTEX_BITSET_MATCH_ANY,
+                None,
+                None,
+            )?,
+            ctypes::FUTEX_WAKE_BITSET => futex_wake(uaddr, val as usize, val3, None, None)?,
+            ctypes::FUTEX_REQUEUE => futex_wake(
+                uaddr,
+                val as usize,
+                ctypes::FUTEX_BITSET_MATCH_ANY,
+                Some((uaddr2, nr_requeue)),
+                None,
+            )?,
+            ctypes::FUTEX_CMP_REQUEUE => futex_wake(
+                uaddr,
+                val as usize,
+                ctypes::FUTEX_BITSET_MATCH_ANY,
+                Some((uaddr2, nr_requeue)),
+                Some(val3),
+            )?,
+            _ => return Err(LinuxError::ENOSYS),
+        };
+        Ok(count as c_int)
+    })
+}
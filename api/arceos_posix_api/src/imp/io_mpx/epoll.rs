@@ -199,6 +199,10 @@ pub unsafe fn sys_epoll_wait(
                 debug!("    timeout!");
                 return Ok(0);
             }
+            #[cfg(feature = "multitask")]
+            if crate::imp::signal::handle_signals(false) {
+                return Err(LinuxError::EINTR);
+            }
             crate::sys_sched_yield();
         }
     })
@@ -145,6 +145,10 @@ pub unsafe fn sys_select(
                 debug!("    timeout!");
                 return Ok(0);
             }
+            #[cfg(feature = "multitask")]
+            if crate::imp::signal::handle_signals(false) {
+                return Err(LinuxError::EINTR);
+            }
             crate::sys_sched_yield();
         }
     })
@@ -0,0 +1,163 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::Condvar;
+
+use core::ffi::c_int;
+use core::mem::size_of;
+use core::time::Duration;
+
+use super::mutex::PthreadMutex;
+
+static_assertions::const_assert_eq!(
+    size_of::<PthreadCond>(),
+    size_of::<ctypes::pthread_cond_t>()
+);
+
+#[repr(C)]
+pub struct PthreadCond {
+    inner: Condvar,
+    /// The clock of the timeouts in `pthread_cond_timedwait`.
+    clock: ctypes::clockid_t,
+}
+
+impl PthreadCond {
+    const fn new(clock: ctypes::clockid_t) -> Self {
+        Self {
+            inner: Condvar::new(),
+            clock,
+        }
+    }
+
+    /// Waits for a notification, or until the `timeout` elapses if it is
+    /// specified.
+    fn wait(&self, mutex: &PthreadMutex, timeout: Option<Duration>) -> LinuxResult {
+        let (guard, count) = mutex.take_guard()?;
+        let (guard, timed_out) = match timeout {
+            None => (self.inner.wait(guard), false),
+            #[cfg(feature = "irq")]
+            Some(dur) => {
+                let (guard, res) = self.inner.wait_timeout(guard, dur);
+                (guard, res.timed_out())
+            }
+            #[cfg(not(feature = "irq"))]
+            Some(_) => {
+                warn!("pthread_cond_timedwait: the timeout is ignored without the `irq` feature");
+                (self.inner.wait(guard), false)
+            }
+        };
+        mutex.restore_guard(guard, count);
+        if timed_out {
+            Err(LinuxError::ETIMEDOUT)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Initialize a condition variable.
+pub fn sys_pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    debug!("sys_pthread_cond_init <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_init, {
+        check_null_mut_ptr(cond)?;
+        let clock = if attr.is_null() {
+            ctypes::CLOCK_REALTIME
+        } else {
+            unsafe { (*attr).__attr & 0x7fff_ffff }
+        };
+        unsafe {
+            cond.cast::<PthreadCond>()
+                .write(PthreadCond::new(clock as ctypes::clockid_t));
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable.
+pub fn sys_pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_destroy <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_destroy, {
+        check_null_mut_ptr(cond)?;
+        Ok(0)
+    })
+}
+
+/// Unlock the given mutex and wait on the condition variable, then lock the
+/// mutex again before returning.
+pub fn sys_pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_wait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_wait, {
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        unsafe {
+            (*cond.cast::<PthreadCond>()).wait(&*mutex.cast::<PthreadMutex>(), None)?;
+        }
+        Ok(0)
+    })
+}
+
+/// Like [`sys_pthread_cond_wait`], but fails with `ETIMEDOUT` if it is not
+/// notified before the absolute time `abstime` of the clock of the condition
+/// variable.
+pub unsafe fn sys_pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_timedwait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_timedwait, {
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        let (cond, mutex, abstime) = unsafe {
+            if abstime.is_null() || (*abstime).tv_nsec < 0 || (*abstime).tv_nsec > 999999999 {
+                return Err(LinuxError::EINVAL);
+            }
+            (
+                &*cond.cast::<PthreadCond>(),
+                &*mutex.cast::<PthreadMutex>(),
+                *abstime,
+            )
+        };
+        let now = match cond.clock as u32 {
+            ctypes::CLOCK_REALTIME | ctypes::CLOCK_MONOTONIC => axhal::time::current_time(),
+            _ => return Err(LinuxError::EINVAL),
+        };
+        match Duration::from(abstime).checked_sub(now) {
+            Some(timeout) if !timeout.is_zero() => cond.wait(mutex, Some(timeout))?,
+            _ => return Err(LinuxError::ETIMEDOUT),
+        }
+        Ok(0)
+    })
+}
+
+/// Wake up one thread waiting on the condition variable.
+pub fn sys_pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_signal <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_signal, {
+        check_null_mut_ptr(cond)?;
+        unsafe { (*cond.cast::<PthreadCond>()).inner.notify_one() };
+        Ok(0)
+    })
+}
+
+/// Wake up all threads waiting on the condition variable.
+pub fn sys_pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_broadcast <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_broadcast, {
+        check_null_mut_ptr(cond)?;
+        unsafe { (*cond.cast::<PthreadCond>()).inner.notify_all() };
+        Ok(0)
+    })
+}
@@ -8,7 +8,14 @@ use spin::RwLock;
 
 use crate::ctypes;
 
+use self::tsd::ThreadSpecificData;
+
+pub mod barrier;
+pub mod cond;
 pub mod mutex;
+pub mod once;
+pub mod rwlock;
+pub mod tsd;
 
 lazy_static::lazy_static! {
     static ref TID_TO_PTHREAD: RwLock<BTreeMap<u64, ForceSendSync<ctypes::pthread_t>>> = {
@@ -20,6 +27,7 @@ lazy_static::lazy_static! {
             retval: Arc::new(Packet {
                 result: UnsafeCell::new(core::ptr::null_mut()),
             }),
+            tsd: ThreadSpecificData::new(),
         };
         let ptr = Box::into_raw(Box::new(main_thread)) as *mut c_void;
         map.insert(main_tid, ForceSendSync(ptr));
@@ -37,6 +45,7 @@ unsafe impl<T> Sync for Packet<T> {}
 pub struct Pthread {
     inner: AxTaskRef,
     retval: Arc<Packet<*mut c_void>>,
+    tsd: ThreadSpecificData,
 }
 
 impl Pthread {
@@ -55,18 +64,27 @@ impl Pthread {
         let main = move || {
             let arg = arg_wrapper;
             let ret = start_routine(arg.0);
+            if let Some(thread) = Self::current() {
+                thread.tsd.run_destructors();
+            }
             unsafe { *their_packet.result.get() = ret };
             drop(their_packet);
+            super::signal::exit_thread();
         };
 
+        // hold the lock until the thread is registered, as it may look up
+        // itself (e.g., for thread-specific data) as soon as it is spawned
+        let mut tid_to_pthread = TID_TO_PTHREAD.write();
         let task_inner = axtask::spawn(main);
         let tid = task_inner.id().as_u64();
+        super::signal::init_thread(&task_inner);
         let thread = Pthread {
             inner: task_inner,
             retval: my_packet,
+            tsd: ThreadSpecificData::new(),
         };
         let ptr = Box::into_raw(Box::new(thread)) as *mut c_void;
-        TID_TO_PTHREAD.write().insert(tid, ForceSendSync(ptr));
+        tid_to_pthread.insert(tid, ForceSendSync(ptr));
         Ok(ptr)
     }
 
@@ -78,13 +96,22 @@ impl Pthread {
         }
     }
 
+    pub(crate) fn task(ptr: ctypes::pthread_t) -> LinuxResult<AxTaskRef> {
+        if ptr.is_null() {
+            return Err(LinuxError::ESRCH);
+        }
+        Ok(unsafe { &*(ptr as *const Pthread) }.inner.clone())
+    }
+
     fn current() -> Option<&'static Pthread> {
         unsafe { core::ptr::NonNull::new(Self::current_ptr()).map(|ptr| ptr.as_ref()) }
     }
 
     fn exit_current(retval: *mut c_void) -> ! {
         let thread = Self::current().expect("fail to get current thread");
+        thread.tsd.run_destructors();
         unsafe { *thread.retval.result.get() = retval };
+        super::signal::exit_thread();
         axtask::exit(0);
     }
 
@@ -103,6 +130,15 @@ impl Pthread {
     }
 }
 
+/// Returns the task of the thread whose ID is `tid`, if it is created by
+/// `pthread_create` and has not been joined, or it is the main thread.
+pub(crate) fn task_of_tid(tid: u64) -> Option<AxTaskRef> {
+    TID_TO_PTHREAD
+        .read()
+        .get(&tid)
+        .map(|ptr| unsafe { &*(ptr.0 as *const Pthread) }.inner.clone())
+}
+
 /// Returns the `pthread` struct of current thread.
 pub fn sys_pthread_self() -> ctypes::pthread_t {
     Pthread::current().expect("fail to get current thread") as *const Pthread as _
@@ -147,6 +183,42 @@ pub unsafe fn sys_pthread_join(thread: ctypes::pthread_t, retval: *mut *mut c_vo
     })
 }
 
+/// Sets the CPUs that the given thread is allowed to run on to the first
+/// `cpusetsize` bytes of `cpuset`.
+pub unsafe fn sys_pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_setaffinity_np <= {:#x}, {}",
+        thread as usize, cpusetsize
+    );
+    syscall_body!(sys_pthread_setaffinity_np, {
+        let cpumask = unsafe { crate::imp::task::cpuset_to_cpumask(cpusetsize, cpuset)? };
+        crate::imp::task::set_task_affinity(&Pthread::task(thread)?, cpumask)?;
+        Ok(0)
+    })
+}
+
+/// Gets the CPUs that the given thread is allowed to run on, and stores them
+/// in the first `cpusetsize` bytes of `cpuset`.
+pub unsafe fn sys_pthread_getaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_getaffinity_np <= {:#x}, {}",
+        thread as usize, cpusetsize
+    );
+    syscall_body!(sys_pthread_getaffinity_np, {
+        let cpumask = axtask::get_affinity(&Pthread::task(thread)?);
+        unsafe { crate::imp::task::cpumask_to_cpuset(cpumask, cpusetsize, cpuset)? };
+        Ok(0)
+    })
+}
+
 #[derive(Clone, Copy)]
 struct ForceSendSync<T>(T);
 
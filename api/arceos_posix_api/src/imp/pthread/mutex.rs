@@ -1,10 +1,11 @@
 use crate::{ctypes, utils::check_null_mut_ptr};
 
-use axerrno::LinuxResult;
-use axsync::Mutex;
+use axerrno::{LinuxError, LinuxResult};
+use axsync::{Mutex, MutexGuard};
 
 use core::ffi::c_int;
 use core::mem::{size_of, ManuallyDrop};
+use core::sync::atomic::{AtomicU32, Ordering};
 
 static_assertions::const_assert_eq!(
     size_of::<PthreadMutex>(),
@@ -12,40 +13,121 @@ static_assertions::const_assert_eq!(
 );
 
 #[repr(C)]
-pub struct PthreadMutex(Mutex<()>);
+pub struct PthreadMutex {
+    inner: Mutex<()>,
+    /// One of `PTHREAD_MUTEX_NORMAL`, `PTHREAD_MUTEX_RECURSIVE` and
+    /// `PTHREAD_MUTEX_ERRORCHECK`.
+    kind: u32,
+    /// The number of times a recursive mutex is locked again by its owner.
+    count: AtomicU32,
+}
 
 impl PthreadMutex {
-    const fn new() -> Self {
-        Self(Mutex::new(()))
+    const fn new(kind: u32, inherit: bool) -> Self {
+        Self {
+            inner: if inherit {
+                Mutex::with_priority_inheritance(())
+            } else {
+                Mutex::new(())
+            },
+            kind,
+            count: AtomicU32::new(0),
+        }
     }
 
     fn lock(&self) -> LinuxResult {
-        let _guard = ManuallyDrop::new(self.0.lock());
+        if self.inner.is_owned_by_current() {
+            if self.kind == ctypes::PTHREAD_MUTEX_RECURSIVE {
+                self.count.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+            // a normal mutex would deadlock, which is reported instead
+            return Err(LinuxError::EDEADLK);
+        }
+        let _guard = ManuallyDrop::new(self.inner.lock());
+        Ok(())
+    }
+
+    fn try_lock(&self) -> LinuxResult {
+        if self.kind == ctypes::PTHREAD_MUTEX_RECURSIVE && self.inner.is_owned_by_current() {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let _guard = ManuallyDrop::new(self.inner.try_lock().ok_or(LinuxError::EBUSY)?);
         Ok(())
     }
 
     fn unlock(&self) -> LinuxResult {
-        unsafe { self.0.force_unlock() };
+        // it is undefined for a normal mutex, which is also reported, as the
+        // lock can not be released by another task
+        if !self.inner.is_owned_by_current() {
+            return Err(LinuxError::EPERM);
+        }
+        if self.count.load(Ordering::Relaxed) > 0 {
+            self.count.fetch_sub(1, Ordering::Relaxed);
+        } else {
+            unsafe { self.inner.force_unlock() };
+        }
         Ok(())
     }
+
+    /// Turns the lock held by the current task into a guard to wait on a
+    /// condition variable, and returns it with the recursion count.
+    pub(super) fn take_guard(&self) -> LinuxResult<(MutexGuard<()>, u32)> {
+        if !self.inner.is_owned_by_current() {
+            return Err(LinuxError::EPERM);
+        }
+        let count = self.count.swap(0, Ordering::Relaxed);
+        Ok((unsafe { self.inner.make_guard_unchecked() }, count))
+    }
+
+    /// Keeps the lock of the guard returned by [`take_guard`](Self::take_guard)
+    /// after waiting on a condition variable.
+    pub(super) fn restore_guard(&self, guard: MutexGuard<()>, count: u32) {
+        let _guard = ManuallyDrop::new(guard);
+        self.count.store(count, Ordering::Relaxed);
+    }
 }
 
-/// Initialize a mutex.
+/// Initialize a mutex, with priority inheritance if the protocol of `attr` is
+/// `PTHREAD_PRIO_INHERIT`.
 pub fn sys_pthread_mutex_init(
     mutex: *mut ctypes::pthread_mutex_t,
-    _attr: *const ctypes::pthread_mutexattr_t,
+    attr: *const ctypes::pthread_mutexattr_t,
 ) -> c_int {
     debug!("sys_pthread_mutex_init <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_init, {
         check_null_mut_ptr(mutex)?;
+        let (kind, inherit) = if attr.is_null() {
+            (ctypes::PTHREAD_MUTEX_NORMAL, false)
+        } else {
+            // the same encoding as musl
+            let attr = unsafe { (*attr).__attr };
+            (attr & 3, attr & 8 != 0)
+        };
         unsafe {
-            mutex.cast::<PthreadMutex>().write(PthreadMutex::new());
+            mutex
+                .cast::<PthreadMutex>()
+                .write(PthreadMutex::new(kind, inherit));
         }
         Ok(0)
     })
 }
 
-/// Lock the given mutex.
+/// Destroy a mutex.
+pub fn sys_pthread_mutex_destroy(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    debug!("sys_pthread_mutex_destroy <= {:#x}", mutex as usize);
+    syscall_body!(sys_pthread_mutex_destroy, {
+        check_null_mut_ptr(mutex)?;
+        if unsafe { (*mutex.cast::<PthreadMutex>()).inner.is_locked() } {
+            return Err(LinuxError::EBUSY);
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the given mutex, fails with `EDEADLK` if it is not recursive and it
+/// is already locked by the current thread.
 pub fn sys_pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     debug!("sys_pthread_mutex_lock <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_lock, {
@@ -57,7 +139,20 @@ pub fn sys_pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     })
 }
 
-/// Unlock the given mutex.
+/// Try to lock the given mutex, fails with `EBUSY` if it is already locked.
+pub fn sys_pthread_mutex_trylock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    debug!("sys_pthread_mutex_trylock <= {:#x}", mutex as usize);
+    syscall_body!(sys_pthread_mutex_trylock, {
+        check_null_mut_ptr(mutex)?;
+        unsafe {
+            (*mutex.cast::<PthreadMutex>()).try_lock()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Unlock the given mutex, fails with `EPERM` if the current thread does not
+/// hold it.
 pub fn sys_pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     debug!("sys_pthread_mutex_unlock <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_unlock, {
@@ -0,0 +1,39 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+const INCOMPLETE: c_int = ctypes::PTHREAD_ONCE_INIT as c_int;
+const RUNNING: c_int = 1;
+const COMPLETE: c_int = 2;
+
+/// The wait queue of the threads waiting for any `pthread_once_t` to be
+/// completed.
+static ONCE_WQ: WaitQueue = WaitQueue::new();
+
+/// Call `init_routine` once and only once for the given `once_control`.
+///
+/// The other threads calling it with the same `once_control` are blocked until
+/// `init_routine` returns.
+pub fn sys_pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    debug!("sys_pthread_once <= {:#x}", once_control as usize);
+    syscall_body!(sys_pthread_once, {
+        check_null_mut_ptr(once_control)?;
+        let state = unsafe { AtomicI32::from_ptr(once_control) };
+        match state.compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire) {
+            Ok(_) => {
+                init_routine();
+                state.store(COMPLETE, Ordering::Release);
+                ONCE_WQ.notify_all(true);
+            }
+            Err(COMPLETE) => {}
+            Err(_) => ONCE_WQ.wait_until(|| state.load(Ordering::Acquire) == COMPLETE),
+        }
+        Ok(0)
+    })
+}
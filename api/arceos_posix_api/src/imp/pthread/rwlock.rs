@@ -0,0 +1,141 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::RwLock;
+
+use core::ffi::c_int;
+use core::mem::{size_of, ManuallyDrop};
+
+static_assertions::const_assert_eq!(
+    size_of::<PthreadRwLock>(),
+    size_of::<ctypes::pthread_rwlock_t>()
+);
+
+#[repr(C)]
+pub struct PthreadRwLock(RwLock<()>);
+
+impl PthreadRwLock {
+    const fn new() -> Self {
+        Self(RwLock::new(()))
+    }
+
+    fn read(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.read());
+        Ok(())
+    }
+
+    fn try_read(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.try_read().ok_or(LinuxError::EBUSY)?);
+        Ok(())
+    }
+
+    fn write(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.write());
+        Ok(())
+    }
+
+    fn try_write(&self) -> LinuxResult {
+        let _guard = ManuallyDrop::new(self.0.try_write().ok_or(LinuxError::EBUSY)?);
+        Ok(())
+    }
+
+    fn unlock(&self) -> LinuxResult {
+        // the current thread holds the lock, so it is the writer if the lock
+        // is held by a writer
+        if self.0.is_write_locked() {
+            unsafe { self.0.force_write_unlock() };
+        } else {
+            unsafe { self.0.force_read_unlock() };
+        }
+        Ok(())
+    }
+}
+
+/// Initialize a reader-writer lock.
+pub fn sys_pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    _attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    debug!("sys_pthread_rwlock_init <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_init, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            rwlock.cast::<PthreadRwLock>().write(PthreadRwLock::new());
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy a reader-writer lock.
+pub fn sys_pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_destroy <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_destroy, {
+        check_null_mut_ptr(rwlock)?;
+        Ok(0)
+    })
+}
+
+/// Lock the given reader-writer lock for reading.
+///
+/// The lock prefers writers, so it may deadlock if the current thread already
+/// holds it for reading and a writer is waiting.
+pub fn sys_pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_rdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_rdlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).read()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Try to lock the given reader-writer lock for reading, fails with `EBUSY`
+/// if it is held by a writer or any writer is waiting.
+pub fn sys_pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_tryrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_tryrdlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).try_read()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the given reader-writer lock for writing.
+pub fn sys_pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_wrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_wrlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).write()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Try to lock the given reader-writer lock for writing, fails with `EBUSY`
+/// if it is already locked.
+pub fn sys_pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_trywrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_trywrlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).try_write()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Unlock the given reader-writer lock held by the current thread.
+pub fn sys_pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_unlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_unlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).unlock()?;
+        }
+        Ok(0)
+    })
+}
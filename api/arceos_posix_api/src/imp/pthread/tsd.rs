@@ -0,0 +1,124 @@
+//! Thread-specific data, i.e., `pthread_key_*`.
+
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use spin::Mutex;
+
+use core::ffi::{c_int, c_void};
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use super::{Pthread, TID_TO_PTHREAD};
+
+const KEYS_MAX: usize = ctypes::PTHREAD_KEYS_MAX as usize;
+
+type Destructor = extern "C" fn(*mut c_void);
+
+/// The allocated keys, and their destructors.
+static KEYS: Mutex<[Option<Option<Destructor>>; KEYS_MAX]> = Mutex::new([None; KEYS_MAX]);
+
+/// The values of all keys of a thread.
+pub struct ThreadSpecificData([AtomicPtr<c_void>; KEYS_MAX]);
+
+impl ThreadSpecificData {
+    pub const fn new() -> Self {
+        Self([const { AtomicPtr::new(null_mut()) }; KEYS_MAX])
+    }
+
+    /// Calls the destructors of the keys with non-null values, as the thread
+    /// exits.
+    ///
+    /// The destructors may set the values again, so it repeats at most
+    /// `PTHREAD_DESTRUCTOR_ITERATIONS` times.
+    pub fn run_destructors(&self) {
+        for _ in 0..ctypes::PTHREAD_DESTRUCTOR_ITERATIONS {
+            let mut called = false;
+            for (key, value) in self.0.iter().enumerate() {
+                let value = value.swap(null_mut(), Ordering::Relaxed);
+                if value.is_null() {
+                    continue;
+                }
+                // do not hold the lock while calling the destructor
+                let dtor = KEYS.lock()[key].flatten();
+                if let Some(dtor) = dtor {
+                    dtor(value);
+                    called = true;
+                }
+            }
+            if !called {
+                break;
+            }
+        }
+    }
+}
+
+fn current_tsd() -> LinuxResult<&'static ThreadSpecificData> {
+    Pthread::current()
+        .map(|thread| &thread.tsd)
+        .ok_or(LinuxError::ESRCH)
+}
+
+fn check_key(key: ctypes::pthread_key_t) -> LinuxResult<usize> {
+    let key = key as usize;
+    if key < KEYS_MAX && KEYS.lock()[key].is_some() {
+        Ok(key)
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Create a new key for thread-specific data, with an optional destructor
+/// called with the non-null value when a thread exits.
+pub fn sys_pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Option<Destructor>,
+) -> c_int {
+    debug!("sys_pthread_key_create <= {:#x}", key as usize);
+    syscall_body!(sys_pthread_key_create, {
+        check_null_mut_ptr(key)?;
+        let mut keys = KEYS.lock();
+        let new_key = keys
+            .iter()
+            .position(Option::is_none)
+            .ok_or(LinuxError::EAGAIN)?;
+        keys[new_key] = Some(destructor);
+        unsafe { *key = new_key as ctypes::pthread_key_t };
+        Ok(0)
+    })
+}
+
+/// Delete a key for thread-specific data, without calling the destructor.
+pub fn sys_pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    debug!("sys_pthread_key_delete <= {}", key);
+    syscall_body!(sys_pthread_key_delete, {
+        let key = check_key(key)?;
+        // clear the values, in case the key is created again
+        for thread in TID_TO_PTHREAD.read().values() {
+            let thread = unsafe { &*(thread.0 as *const Pthread) };
+            thread.tsd.0[key].store(null_mut(), Ordering::Relaxed);
+        }
+        KEYS.lock()[key] = None;
+        Ok(0)
+    })
+}
+
+/// Get the value of a key for the current thread, or null if it is not set.
+pub fn sys_pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    debug!("sys_pthread_getspecific <= {}", key);
+    let key = key as usize;
+    match current_tsd() {
+        Ok(tsd) if key < KEYS_MAX => tsd.0[key].load(Ordering::Relaxed),
+        _ => null_mut(),
+    }
+}
+
+/// Set the value of a key for the current thread.
+pub fn sys_pthread_setspecific(key: ctypes::pthread_key_t, value: *const c_void) -> c_int {
+    debug!("sys_pthread_setspecific <= {}, {:#x}", key, value as usize);
+    syscall_body!(sys_pthread_setspecific, {
+        let key = check_key(key)?;
+        current_tsd()?.0[key].store(value as *mut c_void, Ordering::Relaxed);
+        Ok(0)
+    })
+}
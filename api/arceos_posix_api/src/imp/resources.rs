@@ -1,6 +1,44 @@
 use crate::ctypes;
 use axerrno::LinuxError;
 use core::ffi::c_int;
+use core::time::Duration;
+
+/// The CPU time and context switches of the current thread, or of all threads.
+#[derive(Default)]
+pub(crate) struct CpuUsage {
+    pub user_time: Duration,
+    pub kernel_time: Duration,
+    pub voluntary_switches: u64,
+    pub involuntary_switches: u64,
+}
+
+/// Returns the CPU usage of the current thread if `thread` is true, otherwise
+/// of all threads, as all of them are in the same process.
+pub(crate) fn cpu_usage(thread: bool) -> CpuUsage {
+    #[cfg(feature = "multitask")]
+    {
+        let usage = if thread {
+            axtask::task_stats(axtask::current().as_task_ref()).usage
+        } else {
+            axtask::total_cpu_usage()
+        };
+        CpuUsage {
+            user_time: usage.user_time,
+            kernel_time: usage.kernel_time,
+            voluntary_switches: usage.voluntary_switches,
+            involuntary_switches: usage.involuntary_switches,
+        }
+    }
+    #[cfg(not(feature = "multitask"))]
+    {
+        let _ = thread;
+        // the only thread runs since booting
+        CpuUsage {
+            user_time: axhal::time::current_time(),
+            ..Default::default()
+        }
+    }
+}
 
 /// Get resource limitations
 ///
@@ -49,3 +87,34 @@ pub unsafe fn sys_setrlimit(resource: c_int, rlimits: *mut crate::ctypes::rlimit
         Ok(0)
     })
 }
+
+/// Get resource usage
+///
+/// `RUSAGE_SELF` reports the usage of all threads, and `RUSAGE_CHILDREN`
+/// reports nothing as there are no child processes. Only the CPU time and the
+/// context switches are supported.
+pub unsafe fn sys_getrusage(who: c_int, usage: *mut ctypes::rusage) -> c_int {
+    debug!("sys_getrusage <= {} {:#x}", who, usage as usize);
+    syscall_body!(sys_getrusage, {
+        if usage.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let cpu_usage = if who == ctypes::RUSAGE_SELF as c_int {
+            cpu_usage(false)
+        } else if who == ctypes::RUSAGE_THREAD as c_int {
+            cpu_usage(true)
+        } else if who == ctypes::RUSAGE_CHILDREN as c_int {
+            CpuUsage::default()
+        } else {
+            return Err(LinuxError::EINVAL);
+        };
+        unsafe {
+            *usage = core::mem::zeroed();
+            (*usage).ru_utime = cpu_usage.user_time.into();
+            (*usage).ru_stime = cpu_usage.kernel_time.into();
+            (*usage).ru_nvcsw = cpu_usage.voluntary_switches as _;
+            (*usage).ru_nivcsw = cpu_usage.involuntary_switches as _;
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,539 @@
+//! POSIX signals.
+//!
+//! Every thread has a signal mask and a set of pending signals sent to it by
+//! `pthread_kill`, while the signals sent to the process (by `kill` or the
+//! timers) are pending in a shared set, until a thread not blocking them takes
+//! them. The handlers are called at the safe points of the receiving thread:
+//! when it returns from the blocking calls (`nanosleep`, `futex`, `select`,
+//! etc.), which fail with `EINTR` then, or from the calls that send or unblock
+//! signals. A `futex` wait is restarted instead if all the called handlers are
+//! installed with `SA_RESTART`, while `nanosleep`, `select` and `epoll_wait`
+//! always fail, as on Linux.
+
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::ffi::{c_int, c_ulong, c_void};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::spin::SpinNoIrq;
+use axtask::{AxTaskRef, WeakAxTaskRef};
+
+use crate::ctypes;
+
+/// The number of signals, from 1 to 64.
+const NSIG: usize = ctypes::_NSIG as usize - 1;
+
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+const fn sig_bit(sig: usize) -> u64 {
+    1 << (sig - 1)
+}
+
+/// The signals that can not be caught, blocked or ignored.
+const UNBLOCKABLE: u64 = sig_bit(ctypes::SIGKILL as _) | sig_bit(ctypes::SIGSTOP as _);
+
+#[derive(Clone, Copy)]
+struct SigAction {
+    /// `SIG_DFL`, `SIG_IGN`, or the address of the handler.
+    handler: usize,
+    flags: u32,
+    /// The signals blocked while the handler is running.
+    mask: u64,
+}
+
+impl SigAction {
+    const DEFAULT: Self = Self {
+        handler: SIG_DFL,
+        flags: 0,
+        mask: 0,
+    };
+
+    /// Whether the signal is discarded as soon as it is sent.
+    fn is_ignored(&self, sig: usize) -> bool {
+        match self.handler {
+            SIG_IGN => true,
+            SIG_DFL => matches!(
+                sig as u32,
+                ctypes::SIGCHLD | ctypes::SIGCONT | ctypes::SIGURG | ctypes::SIGWINCH
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// The signal states of a thread.
+pub(crate) struct ThreadSignals {
+    /// Not a strong reference, so the task is freed after it exits even if it
+    /// is never unregistered.
+    task: WeakAxTaskRef,
+    mask: AtomicU64,
+    pending: AtomicU64,
+}
+
+impl ThreadSignals {
+    /// Whether there are pending signals not blocked by the thread, which
+    /// interrupt its blocking calls.
+    pub(crate) fn has_pending(&self) -> bool {
+        let pending =
+            self.pending.load(Ordering::Acquire) | PROCESS_PENDING.load(Ordering::Acquire);
+        pending & !self.mask.load(Ordering::Acquire) != 0
+    }
+
+    /// Takes a pending signal not blocked by the thread, the ones sent to the
+    /// thread first.
+    fn take(&self) -> Option<usize> {
+        let mask = self.mask.load(Ordering::Acquire);
+        for pending in [&self.pending, &PROCESS_PENDING] {
+            let mut taken = 0;
+            let _ = pending.fetch_update(Ordering::AcqRel, Ordering::Acquire, |set| {
+                let ready = set & !mask;
+                taken = ready & ready.wrapping_neg();
+                (taken != 0).then_some(set & !taken)
+            });
+            if taken != 0 {
+                return Some(taken.trailing_zeros() as usize + 1);
+            }
+        }
+        None
+    }
+}
+
+static ACTIONS: SpinNoIrq<[SigAction; NSIG]> = SpinNoIrq::new([SigAction::DEFAULT; NSIG]);
+
+/// The signal states of the threads, registered when they are created or use
+/// signals for the first time, keyed by task ID.
+static THREADS: SpinNoIrq<BTreeMap<u64, Arc<ThreadSignals>>> = SpinNoIrq::new(BTreeMap::new());
+
+/// The pending signals sent to the process.
+static PROCESS_PENDING: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the signal states of `task`, and registers it if it is not
+/// registered yet. Returns `None` if it has exited.
+fn signals_of(task: &AxTaskRef) -> Option<Arc<ThreadSignals>> {
+    if task.is_exited() {
+        return None;
+    }
+    let mut threads = THREADS.lock();
+    if let Some(thread) = threads.get(&task.id().as_u64()) {
+        return Some(thread.clone());
+    }
+    // drop the states of the freed tasks, which exited without unregistering
+    threads.retain(|_, thread| thread.task.strong_count() != 0);
+    let thread = Arc::new(ThreadSignals {
+        task: Arc::downgrade(task),
+        mask: AtomicU64::new(0),
+        pending: AtomicU64::new(0),
+    });
+    threads.insert(task.id().as_u64(), thread.clone());
+    Some(thread)
+}
+
+/// Returns the signal states of the current thread.
+pub(crate) fn current_signals() -> Arc<ThreadSignals> {
+    signals_of(axtask::current().as_task_ref()).expect("the current task has exited")
+}
+
+/// Registers a new thread, which inherits the signal mask of the current one.
+pub(crate) fn init_thread(task: &AxTaskRef) {
+    let mask = current_signals().mask.load(Ordering::Acquire);
+    if let Some(thread) = signals_of(task) {
+        thread.mask.store(mask, Ordering::Release);
+    }
+}
+
+/// Unregisters the current thread when it exits, so it no longer receives
+/// the signals sent to the process.
+pub(crate) fn exit_thread() {
+    THREADS.lock().remove(&axtask::current().id().as_u64());
+}
+
+fn check_signal(sig: c_int) -> LinuxResult<usize> {
+    if (1..=NSIG as c_int).contains(&sig) {
+        Ok(sig as usize)
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Sends `sig` to the thread of `target`, or the process if it is `None`.
+///
+/// The receiving thread is interrupted to handle it, unless it is the current
+/// thread in the task context, which handles it before returning to the
+/// caller. `in_irq` is true if it is sent in the interrupt context, e.g., by
+/// a timer.
+fn send_signal(target: Option<&AxTaskRef>, sig: usize, in_irq: bool) {
+    if ACTIONS.lock()[sig - 1].is_ignored(sig) {
+        return;
+    }
+    let receiver = match target {
+        Some(task) => {
+            // the signals sent to an exited thread are discarded
+            let Some(thread) = signals_of(task) else {
+                return;
+            };
+            thread.pending.fetch_or(sig_bit(sig), Ordering::AcqRel);
+            Some(task.clone())
+        }
+        None => {
+            PROCESS_PENDING.fetch_or(sig_bit(sig), Ordering::AcqRel);
+            // threads not created by pthreads are not unregistered when they
+            // exit
+            THREADS
+                .lock()
+                .values()
+                .filter(|thread| thread.mask.load(Ordering::Acquire) & sig_bit(sig) == 0)
+                .filter_map(|thread| thread.task.upgrade())
+                .find(|task| !task.is_exited())
+        }
+    };
+    if let Some(task) = receiver {
+        if in_irq || !Arc::ptr_eq(axtask::current().as_task_ref(), &task) {
+            axtask::interrupt(&task);
+        }
+    }
+}
+
+fn default_action(sig: usize) {
+    match sig as u32 {
+        ctypes::SIGCHLD | ctypes::SIGCONT | ctypes::SIGURG | ctypes::SIGWINCH => {}
+        ctypes::SIGSTOP | ctypes::SIGTSTP | ctypes::SIGTTIN | ctypes::SIGTTOU => {
+            warn!("signal {}: stopping is not supported", sig);
+        }
+        _ => {
+            ax_println!("Terminated by signal {}", sig);
+            axhal::misc::terminate();
+        }
+    }
+}
+
+/// Calls the handlers of the pending signals not blocked by the current
+/// thread, or takes their default actions.
+///
+/// Returns `true` if any handler is called, so the interrupted call should
+/// fail with `EINTR`. If the call is `restartable`, the handlers installed
+/// with `SA_RESTART` are not counted, so it is restarted after them.
+pub(crate) fn handle_signals(restartable: bool) -> bool {
+    let thread = current_signals();
+    let mut interrupted = false;
+    while let Some(sig) = thread.take() {
+        let action = {
+            let mut actions = ACTIONS.lock();
+            let action = actions[sig - 1];
+            if action.flags & ctypes::SA_RESETHAND != 0 && action.handler > SIG_IGN {
+                actions[sig - 1] = SigAction::DEFAULT;
+            }
+            action
+        };
+        match action.handler {
+            SIG_IGN => {}
+            SIG_DFL => default_action(sig),
+            handler => {
+                let old_mask = thread.mask.load(Ordering::Acquire);
+                let mut mask = old_mask | action.mask;
+                if action.flags & ctypes::SA_NODEFER == 0 {
+                    mask |= sig_bit(sig);
+                }
+                thread.mask.store(mask & !UNBLOCKABLE, Ordering::Release);
+                if action.flags & ctypes::SA_SIGINFO != 0 {
+                    let handler: extern "C" fn(c_int, *mut ctypes::siginfo_t, *mut c_void) =
+                        unsafe { core::mem::transmute(handler) };
+                    let mut info: ctypes::siginfo_t = unsafe { core::mem::zeroed() };
+                    info.si_signo = sig as c_int;
+                    info.si_code = ctypes::SI_USER as c_int;
+                    handler(sig as c_int, &mut info, core::ptr::null_mut());
+                } else {
+                    let handler: extern "C" fn(c_int) = unsafe { core::mem::transmute(handler) };
+                    handler(sig as c_int);
+                }
+                thread.mask.store(old_mask, Ordering::Release);
+                interrupted |= !restartable || action.flags & ctypes::SA_RESTART == 0;
+            }
+        }
+    }
+    interrupted
+}
+
+const WORD_BITS: usize = c_ulong::BITS as usize;
+
+#[allow(clippy::unnecessary_cast)] // `c_ulong` is `u32` on 32-bit platforms
+fn sigset_to_bits(set: &ctypes::sigset_t) -> u64 {
+    set.__bits
+        .iter()
+        .take(64 / WORD_BITS)
+        .enumerate()
+        .fold(0, |bits, (i, word)| {
+            bits | ((*word as u64) << (i * WORD_BITS))
+        })
+}
+
+fn bits_to_sigset(bits: u64) -> ctypes::sigset_t {
+    let mut set: ctypes::sigset_t = unsafe { core::mem::zeroed() };
+    for (i, word) in set.__bits.iter_mut().take(64 / WORD_BITS).enumerate() {
+        *word = (bits >> (i * WORD_BITS)) as c_ulong;
+    }
+    set
+}
+
+/// Examine and change the action taken on receipt of `signum`.
+pub unsafe fn sys_sigaction(
+    signum: c_int,
+    act: *const ctypes::sigaction,
+    oldact: *mut ctypes::sigaction,
+) -> c_int {
+    debug!("sys_sigaction <= {}", signum);
+    syscall_body!(sys_sigaction, {
+        let sig = check_signal(signum)?;
+        if !act.is_null() && UNBLOCKABLE & sig_bit(sig) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        // the thread installing the handlers is ready to receive the signals
+        current_signals();
+        let mut actions = ACTIONS.lock();
+        if !oldact.is_null() {
+            let old = actions[sig - 1];
+            let mut oldact_val: ctypes::sigaction = unsafe { core::mem::zeroed() };
+            oldact_val.__sa_handler.sa_handler = unsafe {
+                core::mem::transmute::<usize, Option<unsafe extern "C" fn(c_int)>>(old.handler)
+            };
+            oldact_val.sa_mask = bits_to_sigset(old.mask);
+            oldact_val.sa_flags = old.flags as c_int;
+            unsafe { oldact.write(oldact_val) };
+        }
+        if !act.is_null() {
+            let act = unsafe { &*act };
+            actions[sig - 1] = SigAction {
+                handler: unsafe { act.__sa_handler.sa_handler }.map_or(SIG_DFL, |f| f as usize),
+                flags: act.sa_flags as u32,
+                mask: sigset_to_bits(&act.sa_mask) & !UNBLOCKABLE,
+            };
+            if actions[sig - 1].is_ignored(sig) {
+                // discard the pending ones sent to the process
+                PROCESS_PENDING.fetch_and(!sig_bit(sig), Ordering::AcqRel);
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Examine and change the signal mask of the current thread.
+pub unsafe fn sys_pthread_sigmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    debug!("sys_pthread_sigmask <= {}", how);
+    syscall_body!(sys_pthread_sigmask, {
+        let thread = current_signals();
+        let old_mask = thread.mask.load(Ordering::Acquire);
+        if !set.is_null() {
+            let bits = sigset_to_bits(unsafe { &*set });
+            let mask = match how as u32 {
+                ctypes::SIG_BLOCK => old_mask | bits,
+                ctypes::SIG_UNBLOCK => old_mask & !bits,
+                ctypes::SIG_SETMASK => bits,
+                _ => return Err(LinuxError::EINVAL),
+            };
+            thread.mask.store(mask & !UNBLOCKABLE, Ordering::Release);
+        }
+        if !oldset.is_null() {
+            unsafe { oldset.write(bits_to_sigset(old_mask)) };
+        }
+        // the signals unblocked just now
+        handle_signals(false);
+        Ok(0)
+    })
+}
+
+/// Send a signal to the process, if `pid` is 0, -1, or the ID of any of its
+/// threads. No signal is sent if `sig` is 0.
+pub fn sys_kill(pid: ctypes::pid_t, sig: c_int) -> c_int {
+    debug!("sys_kill <= {} {}", pid, sig);
+    syscall_body!(sys_kill, {
+        if pid > 0
+            && pid as u64 != axtask::current().id().as_u64()
+            && super::pthread::task_of_tid(pid as u64).is_none()
+        {
+            return Err(LinuxError::ESRCH);
+        }
+        if sig != 0 {
+            send_signal(None, check_signal(sig)?, false);
+            handle_signals(false);
+        }
+        Ok(0)
+    })
+}
+
+/// Send a signal to the given thread. No signal is sent if `sig` is 0.
+pub fn sys_pthread_kill(thread: ctypes::pthread_t, sig: c_int) -> c_int {
+    debug!("sys_pthread_kill <= {:#x} {}", thread as usize, sig);
+    syscall_body!(sys_pthread_kill, {
+        let task = super::pthread::Pthread::task(thread)?;
+        if sig != 0 {
+            send_signal(Some(&task), check_signal(sig)?, false);
+            handle_signals(false);
+        }
+        Ok(0)
+    })
+}
+
+/// Send a signal to the current thread, and returns after its handler is
+/// called (if it is not blocked).
+pub fn sys_raise(sig: c_int) -> c_int {
+    debug!("sys_raise <= {}", sig);
+    syscall_body!(sys_raise, {
+        let sig = check_signal(sig)?;
+        send_signal(Some(axtask::current().as_task_ref()), sig, false);
+        handle_signals(false);
+        Ok(0)
+    })
+}
+
+#[cfg(feature = "irq")]
+mod itimer {
+    use axhal::time::{current_time, TimeValue};
+    use core::time::Duration;
+
+    use super::*;
+
+    /// The real-time timer of the process, which sends `SIGALRM`.
+    struct RealTimer {
+        /// The ID of the armed timer of `axtask`.
+        id: Option<u64>,
+        /// Increased every time it is set, to ignore the stale timers.
+        seq: u64,
+        deadline: TimeValue,
+        interval: Duration,
+    }
+
+    static REAL_TIMER: SpinNoIrq<RealTimer> = SpinNoIrq::new(RealTimer {
+        id: None,
+        seq: 0,
+        deadline: Duration::ZERO,
+        interval: Duration::ZERO,
+    });
+
+    fn arm(timer: &mut RealTimer, deadline: TimeValue) {
+        let seq = timer.seq;
+        timer.deadline = deadline;
+        timer.id = Some(axtask::set_timer(deadline, move |now| fire(seq, now)));
+    }
+
+    fn fire(seq: u64, now: TimeValue) {
+        let mut timer = REAL_TIMER.lock();
+        if timer.seq != seq {
+            return;
+        }
+        timer.id = None;
+        if !timer.interval.is_zero() {
+            // skip the missed periods
+            let deadline = (timer.deadline + timer.interval).max(now);
+            arm(&mut timer, deadline);
+        }
+        drop(timer);
+        send_signal(None, ctypes::SIGALRM as usize, true);
+    }
+
+    /// Sets the timer to expire after `value` (disarms it if it is zero), and
+    /// then every `interval`. Returns the old remaining time and interval.
+    pub(super) fn set(value: Duration, interval: Duration) -> (Duration, Duration) {
+        let mut timer = REAL_TIMER.lock();
+        let old = get_locked(&timer);
+        if let Some(id) = timer.id.take() {
+            axtask::cancel_timer(id);
+        }
+        timer.seq += 1;
+        timer.interval = interval;
+        if !value.is_zero() {
+            arm(&mut timer, current_time() + value);
+        }
+        old
+    }
+
+    fn get_locked(timer: &RealTimer) -> (Duration, Duration) {
+        let remaining = match timer.id {
+            // at least 1us if it is armed
+            Some(_) => timer
+                .deadline
+                .saturating_sub(current_time())
+                .max(Duration::from_micros(1)),
+            None => Duration::ZERO,
+        };
+        (remaining, timer.interval)
+    }
+
+    /// Returns the remaining time and the interval of the timer.
+    pub(super) fn get() -> (Duration, Duration) {
+        get_locked(&REAL_TIMER.lock())
+    }
+}
+
+#[cfg(feature = "irq")]
+fn to_itimerval(value: core::time::Duration, interval: core::time::Duration) -> ctypes::itimerval {
+    ctypes::itimerval {
+        it_interval: interval.into(),
+        it_value: value.into(),
+    }
+}
+
+/// Set the timer `which`, and store its old value in `old_value` if it is not
+/// null. Only `ITIMER_REAL`, which sends `SIGALRM` to the process, is
+/// supported.
+#[cfg(feature = "irq")]
+pub unsafe fn sys_setitimer(
+    which: c_int,
+    new_value: *const ctypes::itimerval,
+    old_value: *mut ctypes::itimerval,
+) -> c_int {
+    debug!("sys_setitimer <= {}", which);
+    syscall_body!(sys_setitimer, {
+        crate::utils::check_null_ptr(new_value)?;
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        let new_value = unsafe { *new_value };
+        for tv in [new_value.it_value, new_value.it_interval] {
+            if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+                return Err(LinuxError::EINVAL);
+            }
+        }
+        let (value, interval) =
+            itimer::set(new_value.it_value.into(), new_value.it_interval.into());
+        if !old_value.is_null() {
+            unsafe { old_value.write(to_itimerval(value, interval)) };
+        }
+        Ok(0)
+    })
+}
+
+/// Get the value of the timer `which`, see [`sys_setitimer`].
+#[cfg(feature = "irq")]
+pub unsafe fn sys_getitimer(which: c_int, curr_value: *mut ctypes::itimerval) -> c_int {
+    debug!("sys_getitimer <= {}", which);
+    syscall_body!(sys_getitimer, {
+        crate::utils::check_null_mut_ptr(curr_value)?;
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        let (value, interval) = itimer::get();
+        unsafe { curr_value.write(to_itimerval(value, interval)) };
+        Ok(0)
+    })
+}
+
+/// Send `SIGALRM` to the process after `seconds` (cancels the alarm if it is
+/// 0), and returns the remaining seconds of the previous alarm.
+#[cfg(feature = "irq")]
+pub fn sys_alarm(seconds: core::ffi::c_uint) -> core::ffi::c_uint {
+    debug!("sys_alarm <= {}", seconds);
+    let (old, _) = itimer::set(
+        core::time::Duration::from_secs(seconds as u64),
+        core::time::Duration::ZERO,
+    );
+    // round to the nearest second, but not 0 if it is still armed
+    match old.as_secs() as core::ffi::c_uint {
+        0 if !old.is_zero() => 1,
+        secs if old.subsec_millis() >= 500 => secs + 1,
+        secs => secs,
+    }
+}
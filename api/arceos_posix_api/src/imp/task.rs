@@ -1,5 +1,13 @@
 use core::ffi::c_int;
 
+#[cfg(feature = "multitask")]
+use axerrno::{LinuxError, LinuxResult};
+#[cfg(feature = "multitask")]
+use axtask::{AxTaskRef, CpuMask, SchedPolicy};
+
+#[cfg(feature = "multitask")]
+use crate::ctypes;
+
 /// Relinquish the CPU, and switches to another task.
 ///
 /// For single-threaded configuration (`multitask` feature is disabled), we just
@@ -38,3 +46,220 @@ pub fn sys_exit(exit_code: c_int) -> ! {
     #[cfg(not(feature = "multitask"))]
     axhal::misc::terminate();
 }
+
+/// Converts the first `cpusetsize` bytes of `cpuset` to a CPU mask. CPUs that
+/// do not exist are ignored.
+#[cfg(feature = "multitask")]
+pub(crate) unsafe fn cpuset_to_cpumask(
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> LinuxResult<CpuMask> {
+    crate::utils::check_null_ptr(cpuset)?;
+    let bytes = unsafe { core::slice::from_raw_parts(cpuset as *const u8, cpusetsize) };
+    let mut cpumask = CpuMask::new();
+    for cpu_id in 0..axconfig::SMP.min(cpusetsize * 8) {
+        cpumask.set(cpu_id, bytes[cpu_id / 8] & (1 << (cpu_id % 8)) != 0);
+    }
+    Ok(cpumask)
+}
+
+/// Stores `cpumask` in the first `cpusetsize` bytes of `cpuset`.
+///
+/// It fails if `cpuset` is too small to hold all CPUs.
+#[cfg(feature = "multitask")]
+pub(crate) unsafe fn cpumask_to_cpuset(
+    cpumask: CpuMask,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> LinuxResult {
+    crate::utils::check_null_mut_ptr(cpuset)?;
+    if cpusetsize * 8 < axconfig::SMP {
+        return Err(LinuxError::EINVAL);
+    }
+    let bytes = unsafe { core::slice::from_raw_parts_mut(cpuset as *mut u8, cpusetsize) };
+    bytes.fill(0);
+    for cpu_id in cpumask.iter() {
+        bytes[cpu_id / 8] |= 1 << (cpu_id % 8);
+    }
+    Ok(())
+}
+
+/// Sets the CPUs that `task` is allowed to run on.
+#[cfg(feature = "multitask")]
+pub(crate) fn set_task_affinity(task: &AxTaskRef, cpumask: CpuMask) -> LinuxResult {
+    if axtask::set_affinity(task, cpumask) {
+        Ok(())
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Returns the task of the thread whose ID is `pid`, or the current one if
+/// `pid` is 0.
+#[cfg(feature = "multitask")]
+fn task_of_pid(pid: ctypes::pid_t) -> LinuxResult<AxTaskRef> {
+    let curr = axtask::current();
+    if pid == 0 || pid as u64 == curr.id().as_u64() {
+        Ok(curr.as_task_ref().clone())
+    } else if pid > 0 {
+        crate::imp::pthread::task_of_tid(pid as u64).ok_or(LinuxError::ESRCH)
+    } else {
+        Err(LinuxError::ESRCH)
+    }
+}
+
+/// Set the CPU affinity mask of the thread whose ID is `pid` (the current
+/// thread if it is 0) to the first `cpusetsize` bytes of `mask`.
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_setaffinity(
+    pid: ctypes::pid_t,
+    cpusetsize: usize,
+    mask: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_sched_setaffinity <= {} {}", pid, cpusetsize);
+    syscall_body!(sys_sched_setaffinity, {
+        let cpumask = unsafe { cpuset_to_cpumask(cpusetsize, mask)? };
+        set_task_affinity(&task_of_pid(pid)?, cpumask)?;
+        Ok(0)
+    })
+}
+
+/// Get the CPU affinity mask of the thread whose ID is `pid` (the current
+/// thread if it is 0), and store it in the first `cpusetsize` bytes of `mask`.
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_getaffinity(
+    pid: ctypes::pid_t,
+    cpusetsize: usize,
+    mask: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_sched_getaffinity <= {} {}", pid, cpusetsize);
+    syscall_body!(sys_sched_getaffinity, {
+        let cpumask = axtask::get_affinity(&task_of_pid(pid)?);
+        unsafe { cpumask_to_cpuset(cpumask, cpusetsize, mask)? };
+        Ok(0)
+    })
+}
+
+/// Converts a POSIX scheduling policy.
+#[cfg(feature = "multitask")]
+fn sched_policy_from_posix(policy: c_int) -> LinuxResult<SchedPolicy> {
+    match policy as u32 {
+        ctypes::SCHED_OTHER => Ok(SchedPolicy::Normal),
+        ctypes::SCHED_FIFO => Ok(SchedPolicy::Fifo),
+        ctypes::SCHED_RR => Ok(SchedPolicy::RoundRobin),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+/// Returns the range of the POSIX priorities of a scheduling policy.
+#[cfg(feature = "multitask")]
+fn sched_priority_range(policy: SchedPolicy) -> core::ops::RangeInclusive<c_int> {
+    if policy.is_rt() {
+        1..=99
+    } else {
+        0..=0
+    }
+}
+
+/// Sets the scheduling policy and the real-time priority of a task. The nice
+/// value is kept for normal tasks.
+#[cfg(feature = "multitask")]
+fn set_task_scheduler(task: &AxTaskRef, policy: SchedPolicy, prio: c_int) -> LinuxResult {
+    if !sched_priority_range(policy).contains(&prio) {
+        return Err(LinuxError::EINVAL);
+    }
+    let prio = match (policy, axtask::get_scheduler(task)) {
+        (SchedPolicy::Normal, (SchedPolicy::Normal, nice)) => nice,
+        (SchedPolicy::Normal, _) => 0,
+        _ => prio as isize,
+    };
+    if axtask::set_scheduler(task, policy, prio) {
+        Ok(())
+    } else {
+        // not supported by the scheduler
+        Err(LinuxError::EPERM)
+    }
+}
+
+/// Set the scheduling policy and the real-time priority of the thread whose ID
+/// is `pid` (the current thread if it is 0).
+///
+/// `SCHED_FIFO` and `SCHED_RR` need the real-time scheduler (the `sched_rt`
+/// feature).
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_setscheduler(
+    pid: ctypes::pid_t,
+    policy: c_int,
+    param: *const ctypes::sched_param,
+) -> c_int {
+    debug!("sys_sched_setscheduler <= {} {}", pid, policy);
+    syscall_body!(sys_sched_setscheduler, {
+        crate::utils::check_null_ptr(param)?;
+        let policy = sched_policy_from_posix(policy)?;
+        let prio = unsafe { (*param).sched_priority };
+        set_task_scheduler(&task_of_pid(pid)?, policy, prio)?;
+        Ok(0)
+    })
+}
+
+/// Get the scheduling policy of the thread whose ID is `pid` (the current
+/// thread if it is 0).
+#[cfg(feature = "multitask")]
+pub fn sys_sched_getscheduler(pid: ctypes::pid_t) -> c_int {
+    debug!("sys_sched_getscheduler <= {}", pid);
+    syscall_body!(sys_sched_getscheduler, {
+        let policy = match axtask::get_scheduler(&task_of_pid(pid)?).0 {
+            SchedPolicy::Normal => ctypes::SCHED_OTHER,
+            SchedPolicy::Fifo => ctypes::SCHED_FIFO,
+            SchedPolicy::RoundRobin => ctypes::SCHED_RR,
+        };
+        Ok(policy as c_int)
+    })
+}
+
+/// Set the real-time priority of the thread whose ID is `pid` (the current
+/// thread if it is 0), and keep its scheduling policy.
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_setparam(pid: ctypes::pid_t, param: *const ctypes::sched_param) -> c_int {
+    debug!("sys_sched_setparam <= {}", pid);
+    syscall_body!(sys_sched_setparam, {
+        crate::utils::check_null_ptr(param)?;
+        let prio = unsafe { (*param).sched_priority };
+        let task = task_of_pid(pid)?;
+        let policy = axtask::get_scheduler(&task).0;
+        set_task_scheduler(&task, policy, prio)?;
+        Ok(0)
+    })
+}
+
+/// Get the real-time priority of the thread whose ID is `pid` (the current
+/// thread if it is 0), which is 0 for normal threads.
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_getparam(pid: ctypes::pid_t, param: *mut ctypes::sched_param) -> c_int {
+    debug!("sys_sched_getparam <= {}", pid);
+    syscall_body!(sys_sched_getparam, {
+        crate::utils::check_null_mut_ptr(param)?;
+        let prio = match axtask::get_scheduler(&task_of_pid(pid)?) {
+            (SchedPolicy::Normal, _) => 0,
+            (_, prio) => prio as c_int,
+        };
+        unsafe { (*param).sched_priority = prio };
+        Ok(0)
+    })
+}
+
+/// Get the maximum real-time priority of the scheduling policy.
+#[cfg(feature = "multitask")]
+pub fn sys_sched_get_priority_max(policy: c_int) -> c_int {
+    syscall_body!(sys_sched_get_priority_max, {
+        Ok(*sched_priority_range(sched_policy_from_posix(policy)?).end())
+    })
+}
+
+/// Get the minimum real-time priority of the scheduling policy.
+#[cfg(feature = "multitask")]
+pub fn sys_sched_get_priority_min(policy: c_int) -> c_int {
+    syscall_body!(sys_sched_get_priority_min, {
+        Ok(*sched_priority_range(sched_policy_from_posix(policy)?).start())
+    })
+}
@@ -2,6 +2,7 @@ use axerrno::LinuxError;
 use core::ffi::{c_int, c_long};
 use core::time::Duration;
 
+use super::resources::cpu_usage;
 use crate::ctypes;
 
 impl From<ctypes::timespec> for Duration {
@@ -34,13 +35,31 @@ impl From<Duration> for ctypes::timeval {
     }
 }
 
-/// Get clock time since booting
-pub unsafe fn sys_clock_gettime(_clk: ctypes::clockid_t, ts: *mut ctypes::timespec) -> c_int {
+/// Get clock time since booting, or the CPU time of the process or the
+/// current thread
+pub unsafe fn sys_clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::timespec) -> c_int {
     syscall_body!(sys_clock_gettime, {
         if ts.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        let now = axhal::time::current_time().into();
+        let time = match clk as u32 {
+            // all the clocks are the time since booting
+            ctypes::CLOCK_REALTIME
+            | ctypes::CLOCK_MONOTONIC
+            | ctypes::CLOCK_MONOTONIC_RAW
+            | ctypes::CLOCK_REALTIME_COARSE
+            | ctypes::CLOCK_MONOTONIC_COARSE
+            | ctypes::CLOCK_BOOTTIME
+            | ctypes::CLOCK_REALTIME_ALARM
+            | ctypes::CLOCK_BOOTTIME_ALARM
+            | ctypes::CLOCK_TAI => axhal::time::current_time(),
+            ctypes::CLOCK_PROCESS_CPUTIME_ID | ctypes::CLOCK_THREAD_CPUTIME_ID => {
+                let usage = cpu_usage(clk as u32 == ctypes::CLOCK_THREAD_CPUTIME_ID);
+                usage.user_time + usage.kernel_time
+            }
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let now: ctypes::timespec = time.into();
         unsafe { *ts = now };
         debug!("sys_clock_gettime: {}.{:09}s", now.tv_sec, now.tv_nsec);
         Ok(0)
@@ -49,7 +68,8 @@ pub unsafe fn sys_clock_gettime(_clk: ctypes::clockid_t, ts: *mut ctypes::timesp
 
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// Fails with `EINTR` if it is interrupted by a signal handler, and the
+/// remaining time is stored in `rem`.
 pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::timespec) -> c_int {
     syscall_body!(sys_nanosleep, {
         unsafe {
@@ -65,8 +85,11 @@ pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::time
 
         let now = axhal::time::current_time();
 
+        // sleep again if it is woken up by a signal without a handler
         #[cfg(feature = "multitask")]
-        axtask::sleep(dur);
+        while !super::signal::handle_signals(false) && axhal::time::current_time() < now + dur {
+            axtask::sleep_until(now + dur);
+        }
         #[cfg(not(feature = "multitask"))]
         axhal::time::busy_wait(dur);
 
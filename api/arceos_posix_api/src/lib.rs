@@ -32,7 +32,7 @@ pub mod config {
 pub mod ctypes;
 
 pub use imp::io::{sys_read, sys_write, sys_writev};
-pub use imp::resources::{sys_getrlimit, sys_setrlimit};
+pub use imp::resources::{sys_getrlimit, sys_getrusage, sys_setrlimit};
 pub use imp::sys::sys_sysconf;
 pub use imp::task::{sys_exit, sys_getpid, sys_sched_yield};
 pub use imp::time::{sys_clock_gettime, sys_nanosleep};
@@ -41,6 +41,8 @@ pub use imp::time::{sys_clock_gettime, sys_nanosleep};
 pub use imp::fd_ops::{sys_close, sys_dup, sys_dup2, sys_fcntl};
 #[cfg(feature = "fs")]
 pub use imp::fs::{sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_rename, sys_stat};
+#[cfg(feature = "multitask")]
+pub use imp::futex::sys_futex;
 #[cfg(feature = "select")]
 pub use imp::io_mpx::sys_select;
 #[cfg(feature = "epoll")]
@@ -54,8 +56,44 @@ pub use imp::net::{
 #[cfg(feature = "pipe")]
 pub use imp::pipe::sys_pipe;
 #[cfg(feature = "multitask")]
+pub use imp::pthread::barrier::{
+    sys_pthread_barrier_destroy, sys_pthread_barrier_init, sys_pthread_barrier_wait,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::cond::{
+    sys_pthread_cond_broadcast, sys_pthread_cond_destroy, sys_pthread_cond_init,
+    sys_pthread_cond_signal, sys_pthread_cond_timedwait, sys_pthread_cond_wait,
+};
+#[cfg(feature = "multitask")]
 pub use imp::pthread::mutex::{
-    sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_unlock,
+    sys_pthread_mutex_destroy, sys_pthread_mutex_init, sys_pthread_mutex_lock,
+    sys_pthread_mutex_trylock, sys_pthread_mutex_unlock,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::once::sys_pthread_once;
+#[cfg(feature = "multitask")]
+pub use imp::pthread::rwlock::{
+    sys_pthread_rwlock_destroy, sys_pthread_rwlock_init, sys_pthread_rwlock_rdlock,
+    sys_pthread_rwlock_tryrdlock, sys_pthread_rwlock_trywrlock, sys_pthread_rwlock_unlock,
+    sys_pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::tsd::{
+    sys_pthread_getspecific, sys_pthread_key_create, sys_pthread_key_delete,
+    sys_pthread_setspecific,
 };
 #[cfg(feature = "multitask")]
-pub use imp::pthread::{sys_pthread_create, sys_pthread_exit, sys_pthread_join, sys_pthread_self};
+pub use imp::pthread::{
+    sys_pthread_create, sys_pthread_exit, sys_pthread_getaffinity_np, sys_pthread_join,
+    sys_pthread_self, sys_pthread_setaffinity_np,
+};
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use imp::signal::{sys_alarm, sys_getitimer, sys_setitimer};
+#[cfg(feature = "multitask")]
+pub use imp::signal::{sys_kill, sys_pthread_kill, sys_pthread_sigmask, sys_raise, sys_sigaction};
+#[cfg(feature = "multitask")]
+pub use imp::task::{
+    sys_sched_get_priority_max, sys_sched_get_priority_min, sys_sched_getaffinity,
+    sys_sched_getparam, sys_sched_getscheduler, sys_sched_setaffinity, sys_sched_setparam,
+    sys_sched_setscheduler,
+};
@@ -1,7 +1,11 @@
 #include <assert.h>
+#include <errno.h>
+#include <limits.h>
+#include <linux/futex.h>
 #include <pthread.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <time.h>
 #include <unistd.h>
 
 void *ThreadFunc1(void *arg)
@@ -117,6 +121,198 @@ void test_mutex()
     assert(data == NUM_THREADS);
 }
 
+static pthread_cond_t cond = PTHREAD_COND_INITIALIZER;
+static int ready = 0;
+
+void *ThreadFunc4(void *arg)
+{
+    pthread_mutex_lock(&lock);
+    while (!ready) pthread_cond_wait(&cond, &lock);
+    (*(int *)arg)++;
+    pthread_mutex_unlock(&lock);
+    return NULL;
+}
+
+void test_cond()
+{
+    const int NUM_THREADS = 10;
+    int data = 0;
+    pthread_t t[NUM_THREADS];
+
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc4, &data);
+
+    // the deadline has passed
+    struct timespec ts;
+    clock_gettime(CLOCK_REALTIME, &ts);
+    pthread_mutex_lock(&lock);
+    assert(pthread_cond_timedwait(&cond, &lock, &ts) == ETIMEDOUT);
+    ready = 1;
+    pthread_cond_broadcast(&cond);
+    pthread_mutex_unlock(&lock);
+
+    for (int i = 0; i < NUM_THREADS; i++) pthread_join(t[i], NULL);
+    printf("test_cond: data = %d\n", data);
+    assert(data == NUM_THREADS);
+}
+
+void *ThreadFunc5(void *arg)
+{
+    return (void *)(long)pthread_mutex_trylock((pthread_mutex_t *)arg);
+}
+
+void test_mutex_types()
+{
+    pthread_mutex_t m;
+    pthread_mutexattr_t attr;
+    pthread_t t;
+    void *res;
+
+    pthread_mutexattr_init(&attr);
+    pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_RECURSIVE);
+    pthread_mutex_init(&m, &attr);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_trylock(&m) == 0);
+    pthread_create(&t, NULL, ThreadFunc5, &m);
+    pthread_join(t, &res);
+    assert((long)res == EBUSY);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == EPERM);
+    assert(pthread_mutex_destroy(&m) == 0);
+
+    pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_ERRORCHECK);
+    pthread_mutex_init(&m, &attr);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_lock(&m) == EDEADLK);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == EPERM);
+
+    int protocol;
+    assert(pthread_mutexattr_setprotocol(&attr, PTHREAD_PRIO_INHERIT) == 0);
+    assert(pthread_mutexattr_getprotocol(&attr, &protocol) == 0);
+    assert(protocol == PTHREAD_PRIO_INHERIT);
+    pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_RECURSIVE);
+    pthread_mutex_init(&m, &attr);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_trylock(&m) == 0);
+    pthread_create(&t, NULL, ThreadFunc5, &m);
+    pthread_join(t, &res);
+    assert((long)res == EBUSY);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_destroy(&m) == 0);
+    pthread_mutexattr_destroy(&attr);
+    puts("test_mutex_types: OK");
+}
+
+static pthread_rwlock_t rwlock = PTHREAD_RWLOCK_INITIALIZER;
+static pthread_barrier_t barrier;
+
+void *ThreadFunc6(void *arg)
+{
+    pthread_rwlock_wrlock(&rwlock);
+    int value = *(int *)arg;
+    for (int i = 0; i < 10000; i++) getpid();
+    *(int *)arg = value + 1;
+    pthread_rwlock_unlock(&rwlock);
+
+    pthread_rwlock_rdlock(&rwlock);
+    assert(pthread_rwlock_trywrlock(&rwlock) == EBUSY);
+    pthread_rwlock_unlock(&rwlock);
+
+    return (void *)(long)pthread_barrier_wait(&barrier);
+}
+
+void test_rwlock_barrier()
+{
+    const int NUM_THREADS = 10;
+    int data = 0, serial = 0;
+    pthread_t t[NUM_THREADS];
+
+    pthread_barrier_init(&barrier, NULL, NUM_THREADS);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc6, &data);
+    for (int i = 0; i < NUM_THREADS; i++) {
+        void *res;
+        pthread_join(t[i], &res);
+        if ((long)res == PTHREAD_BARRIER_SERIAL_THREAD)
+            serial++;
+    }
+    pthread_barrier_destroy(&barrier);
+    printf("test_rwlock_barrier: data = %d, serial = %d\n", data, serial);
+    assert(data == NUM_THREADS && serial == 1);
+}
+
+static pthread_once_t once = PTHREAD_ONCE_INIT;
+static pthread_key_t key;
+static int destructed = 0;
+
+void init_once() { destructed = 0; }
+
+void destructor(void *value)
+{
+    pthread_mutex_lock(&lock);
+    destructed += *(int *)value;
+    pthread_mutex_unlock(&lock);
+}
+
+void *ThreadFunc7(void *arg)
+{
+    pthread_once(&once, init_once);
+    assert(pthread_getspecific(key) == NULL);
+    pthread_setspecific(key, arg);
+    assert(pthread_getspecific(key) == arg);
+    return NULL;
+}
+
+void test_once_key()
+{
+    const int NUM_THREADS = 10;
+    int values[NUM_THREADS];
+    pthread_t t[NUM_THREADS];
+
+    assert(pthread_key_create(&key, destructor) == 0);
+    for (int i = 0; i < NUM_THREADS; i++) {
+        values[i] = i;
+        pthread_create(&t[i], NULL, ThreadFunc7, &values[i]);
+    }
+    for (int i = 0; i < NUM_THREADS; i++) pthread_join(t[i], NULL);
+    assert(pthread_key_delete(key) == 0);
+    printf("test_once_key: destructed = %d\n", destructed);
+    assert(destructed == NUM_THREADS * (NUM_THREADS - 1) / 2);
+}
+
+static uint32_t futex_a, futex_b;
+
+void *ThreadFunc8(void *arg)
+{
+    while (__atomic_load_n(&futex_a, __ATOMIC_ACQUIRE) == 0)
+        futex(&futex_a, FUTEX_WAIT_PRIVATE, 0, NULL, NULL, 0);
+    __atomic_fetch_add((int *)arg, 1, __ATOMIC_RELAXED);
+    return NULL;
+}
+
+void test_futex()
+{
+    const int NUM_THREADS = 10;
+    pthread_t t[NUM_THREADS];
+    int woken = 0;
+
+    assert(futex(&futex_a, FUTEX_WAIT_PRIVATE, 1, NULL, NULL, 0) == -1 && errno == EAGAIN);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc8, &woken);
+    for (int i = 0; i < 10000; i++) getpid();
+
+    __atomic_store_n(&futex_a, 1, __ATOMIC_RELEASE);
+    // wake one, move the others to `futex_b` and wake them there
+    assert(futex(&futex_a, FUTEX_CMP_REQUEUE_PRIVATE, 1, (void *)(long)INT_MAX, &futex_b, 0) ==
+               -1 &&
+           errno == EAGAIN);
+    assert(futex(&futex_a, FUTEX_CMP_REQUEUE_PRIVATE, 1, (void *)(long)INT_MAX, &futex_b, 1) >=
+           0);
+    assert(futex(&futex_b, FUTEX_WAKE_PRIVATE, INT_MAX, NULL, NULL, 0) >= 0);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_join(t[i], NULL);
+    printf("test_futex: woken = %d\n", woken);
+}
+
 int main()
 {
     pthread_t main_thread = pthread_self();
@@ -125,6 +321,11 @@ int main()
     test_create_join();
     test_create_exit();
     test_mutex();
+    test_cond();
+    test_mutex_types();
+    test_rwlock_barrier();
+    test_once_key();
+    test_futex();
     puts("(C)Pthread basic tests run OK!");
 
     return 0;
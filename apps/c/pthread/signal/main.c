@@ -0,0 +1,100 @@
+#include <assert.h>
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile int handled[_NSIG];
+
+void handler(int sig)
+{
+    handled[sig]++;
+}
+
+void info_handler(int sig, siginfo_t *info, void *ctx)
+{
+    if (info->si_signo == sig)
+        handled[sig]++;
+}
+
+void test_raise_mask()
+{
+    assert(signal(SIGUSR1, handler) == SIG_DFL);
+    raise(SIGUSR1);
+    assert(handled[SIGUSR1] == 1);
+
+    // blocked signals are pending until they are unblocked
+    sigset_t set, old;
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    pthread_sigmask(SIG_BLOCK, &set, &old);
+    raise(SIGUSR1);
+    raise(SIGUSR1);
+    assert(handled[SIGUSR1] == 1);
+    pthread_sigmask(SIG_SETMASK, &old, NULL);
+    assert(handled[SIGUSR1] == 2);
+
+    struct sigaction act = {.sa_sigaction = info_handler, .sa_flags = SA_SIGINFO | SA_RESETHAND};
+    sigemptyset(&act.sa_mask);
+    assert(sigaction(SIGUSR1, &act, NULL) == 0);
+    kill(getpid(), SIGUSR1);
+    assert(handled[SIGUSR1] == 3);
+    assert(signal(SIGUSR1, SIG_IGN) == SIG_DFL);
+    raise(SIGUSR1);
+    assert(handled[SIGUSR1] == 3);
+
+    assert(sigaction(SIGKILL, &act, NULL) == -1 && errno == EINVAL);
+    printf("test_raise_mask: handled = %d\n", handled[SIGUSR1]);
+}
+
+void *ThreadFunc1(void *arg)
+{
+    struct timespec req = {10, 0}, rem;
+    int ret = nanosleep(&req, &rem);
+    assert(ret == -1 && rem.tv_sec > 0);
+    assert(handled[SIGUSR2] == 1);
+    return NULL;
+}
+
+void test_pthread_kill()
+{
+    signal(SIGUSR2, handler);
+    pthread_t t;
+    pthread_create(&t, NULL, ThreadFunc1, NULL);
+    usleep(100000);
+    assert(pthread_kill(t, SIGUSR2) == 0);
+    pthread_join(t, NULL);
+    printf("test_pthread_kill: handled = %d\n", handled[SIGUSR2]);
+}
+
+void test_alarm()
+{
+    signal(SIGALRM, handler);
+    alarm(1);
+    struct timespec req = {5, 0}, rem;
+    assert(nanosleep(&req, &rem) == -1 && errno == EINTR);
+    assert(rem.tv_sec >= 3);
+    assert(handled[SIGALRM] == 1);
+
+    struct itimerval it = {.it_interval = {0, 100000}, .it_value = {0, 100000}}, old;
+    setitimer(ITIMER_REAL, &it, NULL);
+    while (handled[SIGALRM] < 4) usleep(1000000);
+    it = (struct itimerval){0};
+    setitimer(ITIMER_REAL, &it, &old);
+    assert(old.it_interval.tv_sec == 0 && old.it_interval.tv_usec == 100000);
+    getitimer(ITIMER_REAL, &it);
+    assert(it.it_value.tv_sec == 0 && it.it_value.tv_usec == 0);
+    printf("test_alarm: handled = %d\n", handled[SIGALRM]);
+}
+
+int main()
+{
+    test_raise_mask();
+    test_pthread_kill();
+    test_alarm();
+    puts("(C)Signal tests run OK!");
+    return 0;
+}
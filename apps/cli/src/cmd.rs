@@ -17,6 +17,10 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("exit", do_exit),
     ("help", do_help),
     ("uname", do_uname),
+    #[cfg(feature = "multitask")]
+    ("ps", do_ps),
+    #[cfg(feature = "multitask")]
+    ("top", do_top),
     ("ldr", do_ldr),
     ("str", do_str)
 ];
@@ -38,6 +42,32 @@ fn do_uname(_args: &str) {
     );
 }
 
+#[cfg(feature = "multitask")]
+fn do_ps(_args: &str) {
+    print!("{}", std::thread::task_table());
+}
+
+#[cfg(feature = "multitask")]
+fn do_top(args: &str) {
+    let iterations = if args.is_empty() {
+        1
+    } else {
+        match args.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                print_err!("top", args, e);
+                return;
+            }
+        }
+    };
+
+    let mut sampler = std::thread::TaskUsageSampler::new();
+    for _ in 0..iterations {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        println!("{}", sampler.sample());
+    }
+}
+
 fn do_help(_args: &str) {
     println!("Available commands:");
     for (name, _) in CMD_TABLE {
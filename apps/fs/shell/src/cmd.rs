@@ -24,8 +24,12 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("help", do_help),
     ("ls", do_ls),
     ("mkdir", do_mkdir),
+    #[cfg(feature = "multitask")]
+    ("ps", do_ps),
     ("pwd", do_pwd),
     ("rm", do_rm),
+    #[cfg(feature = "multitask")]
+    ("top", do_top),
     ("uname", do_uname),
     ("ldr", do_ldr),
     ("str", do_str)
@@ -245,6 +249,32 @@ fn do_pwd(_args: &str) {
     println!("{}", path_to_str!(pwd));
 }
 
+#[cfg(feature = "multitask")]
+fn do_ps(_args: &str) {
+    print!("{}", std::thread::task_table());
+}
+
+#[cfg(feature = "multitask")]
+fn do_top(args: &str) {
+    let iterations = if args.is_empty() {
+        1
+    } else {
+        match args.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                print_err!("top", args, e);
+                return;
+            }
+        }
+    };
+
+    let mut sampler = std::thread::TaskUsageSampler::new();
+    for _ in 0..iterations {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        println!("{}", sampler.sample());
+    }
+}
+
 fn do_uname(_args: &str) {
     let arch = option_env!("AX_ARCH").unwrap_or("");
     let platform = option_env!("AX_PLATFORM").unwrap_or("");
@@ -17,11 +17,16 @@ fn main() {
         thread::spawn(move || {
             println!("Hello, task {}! id = {:?}", i, thread::current().id());
 
-            #[cfg(all(not(feature = "sched_rr"), not(feature = "sched_cfs")))]
+            #[cfg(not(any(
+                feature = "sched_rr",
+                feature = "sched_cfs",
+                feature = "sched_rt",
+                feature = "sched_edf"
+            )))]
             thread::yield_now();
 
             let _order = FINISHED_TASKS.fetch_add(1, Ordering::Relaxed);
-            #[cfg(not(feature = "sched_cfs"))]
+            #[cfg(not(any(feature = "sched_cfs", feature = "sched_rt", feature = "sched_edf")))]
             if option_env!("AX_SMP") == Some("1") {
                 assert!(_order == i); // FIFO scheduler
             }
@@ -29,7 +34,12 @@ fn main() {
     }
     println!("Hello, main task!");
     while FINISHED_TASKS.load(Ordering::Relaxed) < NUM_TASKS {
-        #[cfg(all(not(feature = "sched_rr"), not(feature = "sched_cfs")))]
+        #[cfg(not(any(
+            feature = "sched_rr",
+            feature = "sched_cfs",
+            feature = "sched_rt",
+            feature = "sched_edf"
+        )))]
         thread::yield_now();
     }
     println!("Task yielding tests run OK!");
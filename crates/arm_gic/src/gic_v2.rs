@@ -4,7 +4,7 @@
 
 use core::ptr::NonNull;
 
-use crate::{TriggerMode, GIC_MAX_IRQ, SPI_RANGE};
+use crate::{SgiTarget, TriggerMode, GIC_MAX_IRQ, SPI_RANGE};
 use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::register_structs;
 use tock_registers::registers::{ReadOnly, ReadWrite, WriteOnly};
@@ -177,6 +177,21 @@ impl GicDistributor {
         }
     }
 
+    /// Sends the SGI `sgi` to the target CPUs. (write GICD_SGIR)
+    ///
+    /// [`SgiTarget::Cpu`] is the number of the CPU interface of the target
+    /// CPU, in `0..8`.
+    pub fn send_sgi(&mut self, sgi: usize, target: SgiTarget) {
+        let intid = sgi as u32 & 0xf;
+        let value = match target {
+            // TargetListFilter = 0b01
+            SgiTarget::AllExceptSelf => intid | (0b01 << 24),
+            // TargetListFilter = 0b00, CPUTargetList
+            SgiTarget::Cpu(cpu) => intid | ((1u32 << (cpu & 0x7)) << 16),
+        };
+        self.regs().SGIR.set(value);
+    }
+
     /// Initializes the GIC distributor.
     ///
     /// It disables all interrupts, sets the target of all SPIs to CPU 0,
@@ -0,0 +1,445 @@
+//! Types and definitions for GICv3.
+//!
+//! Compared to GICv2, the SGIs and PPIs are configured in the per-CPU
+//! redistributors, SPIs are routed by affinity (`MPIDR_EL1`), and the CPU
+//! interface is accessed through system registers.
+//!
+//! The official documentation: <https://developer.arm.com/documentation/ihi0069/latest/>
+
+use core::arch::asm;
+use core::ptr::NonNull;
+
+use crate::{SgiTarget, TriggerMode, GIC_MAX_IRQ, SPI_RANGE};
+use tock_registers::interfaces::{Readable, Writeable};
+use tock_registers::register_structs;
+use tock_registers::registers::{ReadOnly, ReadWrite};
+
+/// Default priority of all interrupts.
+const DEFAULT_PRIORITY: u32 = 0xa0;
+
+/// Size of the register frames (`RD_base` and `SGI_base`) of a redistributor.
+const GICR_FRAME_SIZE: usize = 0x2_0000;
+
+const GICD_CTLR_RWP: u32 = 1 << 31;
+const GICD_CTLR_ARE_NS: u32 = 1 << 4;
+const GICD_CTLR_ENABLE_G1A: u32 = 1 << 1;
+const GICD_CTLR_ENABLE_G1: u32 = 1 << 0;
+
+const GICR_TYPER_LAST: u64 = 1 << 4;
+const GICR_TYPER_VLPIS: u64 = 1 << 1;
+const GICR_WAKER_PROCESSOR_SLEEP: u32 = 1 << 1;
+const GICR_WAKER_CHILDREN_ASLEEP: u32 = 1 << 2;
+
+register_structs! {
+    /// GIC Distributor registers.
+    #[allow(non_snake_case)]
+    GicDistributorRegs {
+        /// Distributor Control Register.
+        (0x0000 => CTLR: ReadWrite<u32>),
+        /// Interrupt Controller Type Register.
+        (0x0004 => TYPER: ReadOnly<u32>),
+        /// Distributor Implementer Identification Register.
+        (0x0008 => IIDR: ReadOnly<u32>),
+        (0x000c => _reserved_0),
+        /// Interrupt Group Registers.
+        (0x0080 => IGROUPR: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Set-Enable Registers.
+        (0x0100 => ISENABLER: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Clear-Enable Registers.
+        (0x0180 => ICENABLER: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Set-Pending Registers.
+        (0x0200 => ISPENDR: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Clear-Pending Registers.
+        (0x0280 => ICPENDR: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Set-Active Registers.
+        (0x0300 => ISACTIVER: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Clear-Active Registers.
+        (0x0380 => ICACTIVER: [ReadWrite<u32>; 0x20]),
+        /// Interrupt Priority Registers.
+        (0x0400 => IPRIORITYR: [ReadWrite<u32>; 0x100]),
+        (0x0800 => _reserved_1),
+        /// Interrupt Configuration Registers.
+        (0x0c00 => ICFGR: [ReadWrite<u32>; 0x40]),
+        /// Interrupt Group Modifier Registers.
+        (0x0d00 => IGRPMODR: [ReadWrite<u32>; 0x20]),
+        (0x0d80 => _reserved_2),
+        /// Interrupt Routing Registers, starting from the first SPI.
+        (0x6100 => IROUTER: [ReadWrite<u64>; 988]),
+        (0x7fe0 => @END),
+    }
+}
+
+register_structs! {
+    /// GIC Redistributor registers (`RD_base` and `SGI_base` frames).
+    #[allow(non_snake_case)]
+    GicRedistributorRegs {
+        /// Redistributor Control Register.
+        (0x0000 => CTLR: ReadWrite<u32>),
+        /// Implementer Identification Register.
+        (0x0004 => IIDR: ReadOnly<u32>),
+        /// Redistributor Type Register.
+        (0x0008 => TYPER: ReadOnly<u64>),
+        /// Error Reporting Status Register.
+        (0x0010 => STATUSR: ReadWrite<u32>),
+        /// Redistributor Wake Register.
+        (0x0014 => WAKER: ReadWrite<u32>),
+        (0x0018 => _reserved_0),
+        /// Interrupt Group Register 0.
+        (0x1_0080 => IGROUPR0: ReadWrite<u32>),
+        (0x1_0084 => _reserved_1),
+        /// Interrupt Set-Enable Register 0.
+        (0x1_0100 => ISENABLER0: ReadWrite<u32>),
+        (0x1_0104 => _reserved_2),
+        /// Interrupt Clear-Enable Register 0.
+        (0x1_0180 => ICENABLER0: ReadWrite<u32>),
+        (0x1_0184 => _reserved_3),
+        /// Interrupt Clear-Pending Register 0.
+        (0x1_0280 => ICPENDR0: ReadWrite<u32>),
+        (0x1_0284 => _reserved_4),
+        /// Interrupt Priority Registers.
+        (0x1_0400 => IPRIORITYR: [ReadWrite<u32>; 8]),
+        (0x1_0420 => _reserved_5),
+        /// Interrupt Configuration Registers (SGIs and PPIs).
+        (0x1_0c00 => ICFGR: [ReadWrite<u32>; 2]),
+        (0x1_0c08 => _reserved_6),
+        /// Interrupt Group Modifier Register 0.
+        (0x1_0d00 => IGRPMODR0: ReadWrite<u32>),
+        (0x1_0d04 => @END),
+    }
+}
+
+/// Returns the affinity fields (`Aff3.Aff2.Aff1.Aff0`) of `MPIDR_EL1` of the
+/// current CPU, in the layout of `MPIDR_EL1` and `GICD_IROUTER`, where `Aff3`
+/// is at bits 32..40. It is also the target of [`SgiTarget::Cpu`].
+pub fn current_affinity() -> u64 {
+    let mpidr: u64;
+    unsafe { asm!("mrs {}, mpidr_el1", out(reg) mpidr) };
+    mpidr & 0xff_00ff_ffff
+}
+
+/// Packs the affinity in the layout of `MPIDR_EL1` into 32 bits, with `Aff3`
+/// at bits 24..32, as in `GICR_TYPER[63:32]`.
+const fn packed_affinity(affinity: u64) -> u64 {
+    ((affinity >> 8) & 0xff00_0000) | (affinity & 0xff_ffff)
+}
+
+/// The GIC distributor.
+///
+/// Unlike GICv2, it only handles SPIs (affinity routing is enabled), while
+/// SGIs and PPIs are handled by the [`GicRedistributor`] of each CPU.
+pub struct GicDistributor {
+    base: NonNull<GicDistributorRegs>,
+    max_irqs: usize,
+}
+
+/// The GIC redistributor of a CPU.
+///
+/// It configures the SGIs and PPIs of the CPU, and manages its power state
+/// with respect to the GIC.
+pub struct GicRedistributor {
+    base: NonNull<GicRedistributorRegs>,
+}
+
+/// The GIC CPU interface, accessed through the `ICC_*_EL1` system registers.
+///
+/// It is per-CPU by nature, so it has no state.
+pub struct GicCpuInterface;
+
+unsafe impl Send for GicDistributor {}
+unsafe impl Sync for GicDistributor {}
+
+unsafe impl Send for GicRedistributor {}
+unsafe impl Sync for GicRedistributor {}
+
+impl GicDistributor {
+    /// Construct a new GIC distributor instance from the base address.
+    pub const fn new(base: *mut u8) -> Self {
+        Self {
+            base: NonNull::new(base).unwrap().cast(),
+            max_irqs: GIC_MAX_IRQ,
+        }
+    }
+
+    const fn regs(&self) -> &GicDistributorRegs {
+        unsafe { self.base.as_ref() }
+    }
+
+    /// Waits until the last write to `GICD_CTLR` takes effect.
+    fn wait_for_rwp(&self) {
+        while self.regs().CTLR.get() & GICD_CTLR_RWP != 0 {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// The maximum number of interrupts that the GIC supports
+    pub fn max_irqs(&self) -> usize {
+        ((self.regs().TYPER.get() as usize & 0b11111) + 1) * 32
+    }
+
+    /// Configures the trigger mode for the given interrupt.
+    pub fn configure_interrupt(&mut self, vector: usize, tm: TriggerMode) {
+        // Only configurable for SPI interrupts
+        if vector >= self.max_irqs || vector < SPI_RANGE.start {
+            return;
+        }
+
+        // type is encoded with two bits, MSB of the two determine type
+        // 16 irqs encoded per ICFGR register
+        let reg_idx = vector >> 4;
+        let bit_shift = ((vector & 0xf) << 1) + 1;
+        let mut reg_val = self.regs().ICFGR[reg_idx].get();
+        match tm {
+            TriggerMode::Edge => reg_val |= 1 << bit_shift,
+            TriggerMode::Level => reg_val &= !(1 << bit_shift),
+        }
+        self.regs().ICFGR[reg_idx].set(reg_val);
+    }
+
+    /// Enables or disables the given SPI.
+    pub fn set_enable(&mut self, vector: usize, enable: bool) {
+        if vector >= self.max_irqs || vector < SPI_RANGE.start {
+            return;
+        }
+        let reg = vector / 32;
+        let mask = 1 << (vector % 32);
+        if enable {
+            self.regs().ISENABLER[reg].set(mask);
+        } else {
+            self.regs().ICENABLER[reg].set(mask);
+        }
+    }
+
+    /// Routes the given SPI to the CPU with the given affinity.
+    pub fn set_route(&mut self, vector: usize, affinity: u64) {
+        if vector >= self.max_irqs || vector < SPI_RANGE.start {
+            return;
+        }
+        self.regs().IROUTER[vector - SPI_RANGE.start].set(affinity);
+    }
+
+    /// Initializes the GIC distributor.
+    ///
+    /// It disables all SPIs, puts them in non-secure group 1, routes them to
+    /// the current CPU, configures them to be edge-triggered, and finally
+    /// enables the GICD with affinity routing.
+    ///
+    /// This function should be called only once.
+    pub fn init(&mut self) {
+        let max_irqs = self.max_irqs();
+        assert!(max_irqs <= GIC_MAX_IRQ);
+        self.max_irqs = max_irqs;
+
+        self.regs().CTLR.set(0);
+        self.wait_for_rwp();
+
+        // Disable all interrupts
+        for i in (SPI_RANGE.start..max_irqs).step_by(32) {
+            self.regs().ICENABLER[i / 32].set(u32::MAX);
+            self.regs().ICPENDR[i / 32].set(u32::MAX);
+            self.regs().IGROUPR[i / 32].set(u32::MAX);
+            self.regs().IGRPMODR[i / 32].set(0);
+        }
+        self.wait_for_rwp();
+
+        let priority = DEFAULT_PRIORITY * 0x01_01_01_01;
+        for i in (SPI_RANGE.start..max_irqs).step_by(4) {
+            self.regs().IPRIORITYR[i / 4].set(priority);
+        }
+        // Initialize all the SPIs to edge triggered, and route them to the
+        // current CPU
+        let affinity = current_affinity();
+        for i in SPI_RANGE.start..max_irqs {
+            self.configure_interrupt(i, TriggerMode::Edge);
+            self.set_route(i, affinity);
+        }
+
+        self.regs()
+            .CTLR
+            .set(GICD_CTLR_ARE_NS | GICD_CTLR_ENABLE_G1A | GICD_CTLR_ENABLE_G1);
+        self.wait_for_rwp();
+    }
+}
+
+impl GicRedistributor {
+    /// Construct a new GIC redistributor instance from the base address of
+    /// its `RD_base` frame.
+    pub const fn new(base: *mut u8) -> Self {
+        Self {
+            base: NonNull::new(base).unwrap().cast(),
+        }
+    }
+
+    /// Finds the redistributor of the current CPU in the contiguous
+    /// redistributor region starting at `base`, by matching the affinity.
+    ///
+    /// # Safety
+    ///
+    /// The whole region (until the redistributor marked as the last one) must
+    /// be mapped at `base`.
+    pub unsafe fn for_current_cpu(base: *mut u8) -> Option<Self> {
+        let affinity = packed_affinity(current_affinity());
+        let mut addr = base;
+        loop {
+            let rd = Self::new(addr);
+            let typer = rd.regs().TYPER.get();
+            if typer >> 32 == affinity {
+                return Some(rd);
+            }
+            if typer & GICR_TYPER_LAST != 0 {
+                return None;
+            }
+            // two more frames for virtual LPIs
+            let stride = if typer & GICR_TYPER_VLPIS != 0 {
+                GICR_FRAME_SIZE * 2
+            } else {
+                GICR_FRAME_SIZE
+            };
+            addr = addr.add(stride);
+        }
+    }
+
+    /// The base address of the `RD_base` frame.
+    pub fn base(&self) -> *mut u8 {
+        self.base.as_ptr().cast()
+    }
+
+    const fn regs(&self) -> &GicRedistributorRegs {
+        unsafe { self.base.as_ref() }
+    }
+
+    /// Enables or disables the given SGI or PPI.
+    pub fn set_enable(&self, vector: usize, enable: bool) {
+        if vector >= SPI_RANGE.start {
+            return;
+        }
+        let mask = 1 << vector;
+        if enable {
+            self.regs().ISENABLER0.set(mask);
+        } else {
+            self.regs().ICENABLER0.set(mask);
+        }
+    }
+
+    /// Initializes the GIC redistributor.
+    ///
+    /// It wakes up the redistributor, disables all SGIs and PPIs and puts them
+    /// in non-secure group 1.
+    ///
+    /// This function should be called once on each CPU.
+    pub fn init(&self) {
+        let waker = self.regs().WAKER.get();
+        self.regs().WAKER.set(waker & !GICR_WAKER_PROCESSOR_SLEEP);
+        while self.regs().WAKER.get() & GICR_WAKER_CHILDREN_ASLEEP != 0 {
+            core::hint::spin_loop();
+        }
+
+        self.regs().ICENABLER0.set(u32::MAX);
+        self.regs().ICPENDR0.set(u32::MAX);
+        self.regs().IGROUPR0.set(u32::MAX);
+        self.regs().IGRPMODR0.set(0);
+        let priority = DEFAULT_PRIORITY * 0x01_01_01_01;
+        for reg in self.regs().IPRIORITYR.iter() {
+            reg.set(priority);
+        }
+    }
+}
+
+impl GicCpuInterface {
+    /// Construct a new GIC CPU interface instance.
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Returns the interrupt ID of the highest priority pending group 1
+    /// interrupt. (read ICC_IAR1_EL1)
+    ///
+    /// The read returns a spurious interrupt ID of `1023` if there is no
+    /// pending interrupt.
+    pub fn iar(&self) -> u32 {
+        let iar: u64;
+        unsafe { asm!("mrs {}, icc_iar1_el1", out(reg) iar) };
+        iar as u32
+    }
+
+    /// Informs the CPU interface that it has completed the processing of the
+    /// specified interrupt. (write ICC_EOIR1_EL1)
+    ///
+    /// The value written must be the value returns from [`Self::iar`].
+    pub fn eoi(&self, iar: u32) {
+        unsafe { asm!("msr icc_eoir1_el1, {}", in(reg) iar as u64) };
+    }
+
+    /// handles the signaled interrupt.
+    ///
+    /// It first reads ICC_IAR1_EL1 to obtain the pending interrupt ID and then
+    /// calls the given handler. After the handler returns, it writes
+    /// ICC_EOIR1_EL1 to acknowledge the interrupt.
+    ///
+    /// If read ICC_IAR1_EL1 returns a spurious interrupt ID (`1020`-`1023`),
+    /// it does nothing.
+    pub fn handle_irq<F>(&self, handler: F)
+    where
+        F: FnOnce(u32),
+    {
+        let iar = self.iar();
+        let vector = iar & 0xff_ffff;
+        if vector < 1020 {
+            handler(vector);
+            self.eoi(iar);
+        } else {
+            // spurious
+        }
+    }
+
+    /// Sends the SGI `sgi` to the target CPUs. (write ICC_SGI1R_EL1)
+    ///
+    /// [`SgiTarget::Cpu`] is the affinity (`MPIDR_EL1`) of the target CPU.
+    pub fn send_sgi(&self, sgi: usize, target: SgiTarget) {
+        let intid = (sgi as u64 & 0xf) << 24;
+        let value = match target {
+            SgiTarget::AllExceptSelf => intid | (1 << 40),
+            SgiTarget::Cpu(mpidr) => {
+                let aff0 = mpidr & 0xff;
+                let aff1 = (mpidr >> 8) & 0xff;
+                let aff2 = (mpidr >> 16) & 0xff;
+                let aff3 = (mpidr >> 32) & 0xff;
+                // the target list covers 16 CPUs, `RS` selects which ones
+                intid
+                    | (1 << (aff0 & 0xf))
+                    | (aff1 << 16)
+                    | (aff2 << 32)
+                    | ((aff0 >> 4) << 44)
+                    | (aff3 << 48)
+            }
+        };
+        unsafe {
+            asm!("dsb ishst", "msr icc_sgi1r_el1, {}", "isb", in(reg) value);
+        }
+    }
+
+    /// Initializes the GIC CPU interface.
+    ///
+    /// It enables the system register interface, unmask interrupts at all
+    /// priority levels and enables group 1 interrupts.
+    ///
+    /// This function should be called once on each CPU.
+    pub fn init(&self) {
+        unsafe {
+            let sre: u64;
+            asm!("mrs {}, icc_sre_el1", out(reg) sre);
+            asm!("msr icc_sre_el1, {}", "isb", in(reg) sre | 1);
+            // unmask interrupts at all priority levels
+            asm!("msr icc_pmr_el1, {}", in(reg) 0xffu64);
+            // no preemption grouping
+            asm!("msr icc_bpr1_el1, {}", in(reg) 0u64);
+            // enable group 1 interrupts
+            asm!("msr icc_igrpen1_el1, {}", "isb", in(reg) 1u64);
+        }
+    }
+}
+
+impl Default for GicCpuInterface {
+    fn default() -> Self {
+        Self::new()
+    }
+}
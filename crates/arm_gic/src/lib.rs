@@ -7,6 +7,8 @@
 #![feature(const_nonnull_new)]
 
 pub mod gic_v2;
+#[cfg(target_arch = "aarch64")]
+pub mod gic_v3;
 
 use core::ops::Range;
 
@@ -63,6 +65,15 @@ pub enum InterruptType {
     SPI,
 }
 
+/// Target CPUs of an SGI.
+pub enum SgiTarget {
+    /// All CPUs except the current one.
+    AllExceptSelf,
+    /// A single CPU, identified by its affinity (`MPIDR_EL1`) on GICv3, or by
+    /// the number of its CPU interface on GICv2.
+    Cpu(u64),
+}
+
 /// Translate an interrupt of a given type to a GIC INTID.
 pub const fn translate_irq(id: usize, int_type: InterruptType) -> Option<usize> {
     match int_type {
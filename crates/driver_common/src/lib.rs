@@ -16,6 +16,8 @@
 #![no_std]
 #![feature(const_trait_impl)]
 
+use core::{fmt, time::Duration};
+
 /// All supported device types.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum DeviceType {
@@ -26,7 +28,7 @@ pub enum DeviceType {
     /// Network device (e.g., ethernet card).
     Net,
     /// Graphic display device (e.g., GPU)
-    Display,    
+    Display,
     /// USB host controller
     USBHost,
 }
@@ -62,4 +64,56 @@ pub trait BaseDriverOps: Send + Sync {
 
     /// The type of the device.
     fn device_type(&self) -> DeviceType;
+
+    /// The interrupt line of the device, or `None` if the device is polled.
+    fn irq(&self) -> Option<DeviceIrq> {
+        None
+    }
+}
+
+/// Waits for device interrupts. It is implemented by the OS and given to
+/// interrupt-driven drivers.
+pub trait IrqWaiter: Send + Sync {
+    /// Returns the number of interrupts handled so far. It may also count
+    /// the interrupts of other devices.
+    fn irq_count(&self) -> usize;
+
+    /// Blocks the current task until [`irq_count`](Self::irq_count) differs
+    /// from `count`, or `timeout` elapses.
+    ///
+    /// It may return early (e.g., if interrupts are disabled), so callers must
+    /// check their completion condition again.
+    fn wait_irq(&self, count: usize, timeout: Option<Duration>);
+}
+
+/// The interrupt line of a device.
+#[derive(Clone, Copy)]
+pub struct DeviceIrq {
+    /// The IRQ number.
+    pub irq_num: usize,
+    /// How to wait for the interrupts.
+    pub waiter: &'static dyn IrqWaiter,
+}
+
+impl DeviceIrq {
+    /// Blocks the current task until `done` returns `true`, checking it again
+    /// after every interrupt.
+    ///
+    /// `done` should acknowledge the interrupt on the device before checking
+    /// the completion, so that the device can raise the next one.
+    pub fn wait_until(&self, mut done: impl FnMut() -> bool) {
+        loop {
+            let count = self.waiter.irq_count();
+            if done() {
+                return;
+            }
+            self.waiter.wait_irq(count, None);
+        }
+    }
+}
+
+impl fmt::Debug for DeviceIrq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeviceIrq({})", self.irq_num)
+    }
 }
@@ -0,0 +1,285 @@
+//! PCI capability list, and programming of the MSI and MSI-X capabilities.
+
+use bit_field::BitField;
+
+use crate::err::{PciError, Result};
+
+/// Offset of the capabilities pointer in the configuration space.
+const CAPABILITIES_POINTER: usize = 0x34;
+
+/// Each capability takes at least 4 bytes in the 256-byte configuration
+/// space, which bounds the length of a (possibly corrupted) list.
+const MAX_CAPABILITIES: usize = 48;
+
+fn read<T>(addr: usize) -> T {
+    unsafe { (addr as *const T).read_volatile() }
+}
+
+fn write<T>(addr: usize, value: T) {
+    unsafe { (addr as *mut T).write_volatile(value) }
+}
+
+/// The ID of a PCI capability.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapabilityId {
+    PowerManagement,
+    Msi,
+    VendorSpecific,
+    PciExpress,
+    MsiX,
+    Unknown(u8),
+}
+
+impl From<u8> for CapabilityId {
+    fn from(id: u8) -> Self {
+        match id {
+            0x01 => Self::PowerManagement,
+            0x05 => Self::Msi,
+            0x09 => Self::VendorSpecific,
+            0x10 => Self::PciExpress,
+            0x11 => Self::MsiX,
+            id => Self::Unknown(id),
+        }
+    }
+}
+
+/// A capability in the capability list of a function.
+#[derive(Clone, Copy, Debug)]
+pub struct Capability {
+    pub id: CapabilityId,
+    /// Offset of the capability in the configuration space.
+    pub offset: u8,
+}
+
+/// An iterator over the capability list of a function.
+pub struct CapabilityIter {
+    cfg_addr: usize,
+    next: u8,
+    remaining: usize,
+}
+
+impl CapabilityIter {
+    /// Creates an iterator over the capability list of the function whose
+    /// configuration space is mapped at `cfg_addr`.
+    ///
+    /// `has_capabilities` is the "capabilities list" bit of the status
+    /// register.
+    pub(crate) fn new(cfg_addr: usize, has_capabilities: bool) -> Self {
+        let next = if has_capabilities {
+            read::<u8>(cfg_addr + CAPABILITIES_POINTER)
+        } else {
+            0
+        };
+        Self {
+            cfg_addr,
+            next,
+            remaining: MAX_CAPABILITIES,
+        }
+    }
+}
+
+impl Iterator for CapabilityIter {
+    type Item = Capability;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the bottom two bits are reserved, and the first 64 bytes are the header
+        let offset = self.next & !0b11;
+        if offset < 0x40 || self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let id = read::<u8>(self.cfg_addr + offset as usize);
+        self.next = read::<u8>(self.cfg_addr + offset as usize + 1);
+        Some(Capability {
+            id: id.into(),
+            offset,
+        })
+    }
+}
+
+/// The MSI capability.
+pub struct MsiCapability {
+    base: usize,
+}
+
+impl MsiCapability {
+    /// Creates the capability at `offset` in the configuration space mapped at
+    /// `cfg_addr`.
+    pub fn new(cfg_addr: usize, offset: u8) -> Self {
+        Self {
+            base: cfg_addr + offset as usize,
+        }
+    }
+
+    fn control(&self) -> u16 {
+        read(self.base + 2)
+    }
+
+    fn set_control(&self, control: u16) {
+        write(self.base + 2, control)
+    }
+
+    /// Whether MSI is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.control().get_bit(0)
+    }
+
+    /// Enables or disables MSI.
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut control = self.control();
+        control.set_bit(0, enabled);
+        self.set_control(control);
+    }
+
+    /// Whether the function can generate 64-bit message addresses.
+    pub fn is_64bit(&self) -> bool {
+        self.control().get_bit(7)
+    }
+
+    /// Whether the function supports masking each vector.
+    pub fn per_vector_masking(&self) -> bool {
+        self.control().get_bit(8)
+    }
+
+    /// The number of vectors the function requests.
+    pub fn max_vectors(&self) -> usize {
+        1 << self.control().get_bits(1..4)
+    }
+
+    /// Programs the message of the function, and lets it use a single vector.
+    ///
+    /// It fails if the function can not generate the given address.
+    pub fn set_message(&self, address: u64, data: u16) -> Result {
+        let is_64bit = self.is_64bit();
+        if !is_64bit && address > u32::MAX as u64 {
+            return Err(PciError::InvalidMsiAddress);
+        }
+        let mut control = self.control();
+        control.set_bits(4..7, 0);
+        self.set_control(control);
+
+        write(self.base + 4, address as u32);
+        if is_64bit {
+            write(self.base + 8, (address >> 32) as u32);
+            write(self.base + 0xc, data);
+        } else {
+            write(self.base + 8, data);
+        }
+        if self.per_vector_masking() {
+            let mask = if is_64bit { 0x10 } else { 0xc };
+            write(self.base + mask, 0u32);
+        }
+        Ok(())
+    }
+}
+
+/// The MSI-X capability.
+pub struct MsixCapability {
+    base: usize,
+}
+
+impl MsixCapability {
+    /// Creates the capability at `offset` in the configuration space mapped at
+    /// `cfg_addr`.
+    pub fn new(cfg_addr: usize, offset: u8) -> Self {
+        Self {
+            base: cfg_addr + offset as usize,
+        }
+    }
+
+    fn control(&self) -> u16 {
+        read(self.base + 2)
+    }
+
+    fn set_control(&self, control: u16) {
+        write(self.base + 2, control)
+    }
+
+    /// Whether MSI-X is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.control().get_bit(15)
+    }
+
+    /// Enables or disables MSI-X.
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut control = self.control();
+        control.set_bit(15, enabled);
+        self.set_control(control);
+    }
+
+    /// Masks or unmasks all vectors of the function, regardless of the mask
+    /// bits of the table entries.
+    pub fn set_function_mask(&self, masked: bool) {
+        let mut control = self.control();
+        control.set_bit(14, masked);
+        self.set_control(control);
+    }
+
+    /// The number of entries in the MSI-X table.
+    pub fn table_size(&self) -> usize {
+        self.control().get_bits(0..11) as usize + 1
+    }
+
+    /// The BAR slot and the offset in it of the MSI-X table.
+    pub fn table_location(&self) -> (u8, usize) {
+        let reg: u32 = read(self.base + 4);
+        (reg.get_bits(0..3) as u8, (reg & !0b111) as usize)
+    }
+
+    /// The BAR slot and the offset in it of the pending bit array.
+    pub fn pba_location(&self) -> (u8, usize) {
+        let reg: u32 = read(self.base + 8);
+        (reg.get_bits(0..3) as u8, (reg & !0b111) as usize)
+    }
+}
+
+/// The MSI-X table of a function, mapped in one of its BARs.
+pub struct MsixTable {
+    base: usize,
+    size: usize,
+}
+
+impl MsixTable {
+    const ENTRY_SIZE: usize = 16;
+
+    /// Creates the table of `size` entries mapped at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must be the virtual address of the table (see
+    /// [`MsixCapability::table_location`]), mapped as device memory.
+    pub unsafe fn new(base: usize, size: usize) -> Self {
+        Self { base, size }
+    }
+
+    /// The number of entries.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn entry(&self, index: usize) -> Result<usize> {
+        if index < self.size {
+            Ok(self.base + index * Self::ENTRY_SIZE)
+        } else {
+            Err(PciError::InvalidMsixIndex)
+        }
+    }
+
+    /// Programs the message of the entry `index`.
+    pub fn set_message(&self, index: usize, address: u64, data: u32) -> Result {
+        let entry = self.entry(index)?;
+        write(entry, address as u32);
+        write(entry + 4, (address >> 32) as u32);
+        write(entry + 8, data);
+        Ok(())
+    }
+
+    /// Masks or unmasks the entry `index`.
+    pub fn set_masked(&self, index: usize, masked: bool) -> Result {
+        let entry = self.entry(index)?;
+        let mut control: u32 = read(entry + 12);
+        control.set_bit(0, masked);
+        write(entry + 12, control);
+        Ok(())
+    }
+}
@@ -5,13 +5,18 @@ use core::fmt::{Display, Formatter};
 pub enum PciError {
     /// The device reported an invalid BAR type.
     InvalidBarType,
-
+    /// The function can not generate the MSI address.
+    InvalidMsiAddress,
+    /// The index is out of the MSI-X table.
+    InvalidMsixIndex,
 }
 
 impl Display for PciError {
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
         match self {
             Self::InvalidBarType => write!(f, "Invalid PCI BAR type."),
+            Self::InvalidMsiAddress => write!(f, "Invalid MSI address."),
+            Self::InvalidMsixIndex => write!(f, "Invalid MSI-X table index."),
         }
     }
 }
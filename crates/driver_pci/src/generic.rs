@@ -0,0 +1,27 @@
+use crate::types::{ConfigCommand, ConifgPciPciBridge};
+use crate::{Access, PciAddress};
+
+/// Standard PCIe Enhanced Configuration Access Mechanism (ECAM).
+///
+/// Used by QEMU `virt` machines and most firmware-initialized root complexes,
+/// where every function has a 4 KiB configuration space laid out linearly by
+/// its bus/device/function number.
+pub struct Generic;
+
+impl Access for Generic {
+    fn setup(_mmio_base: usize) {}
+
+    fn probe_bridge(_mmio_base: usize, bridge: &ConifgPciPciBridge) {
+        bridge.to_header().set_command([
+            ConfigCommand::MemorySpaceEnable,
+            ConfigCommand::BusMasterEnable,
+            ConfigCommand::ParityErrorResponse,
+            ConfigCommand::SERREnable,
+        ])
+    }
+
+    fn map_conf(mmio_base: usize, addr: PciAddress) -> Option<usize> {
+        let offset = (addr.bus << 20) | (addr.device << 15) | (addr.function << 12);
+        Some(mmio_base + offset)
+    }
+}
@@ -10,9 +10,12 @@
 
 #[cfg(feature = "bcm2711")]
 mod bcm2711;
+#[cfg(not(feature = "bcm2711"))]
+mod generic;
 extern crate alloc;
 pub mod types;
 pub mod err;
+pub mod capability;
 mod root_complex;
 use core::ops::Range;
 
@@ -37,6 +40,8 @@ impl core::fmt::Display for PciAddress {
 
 #[cfg(feature="bcm2711")]
 pub type RootComplex = PciRootComplex<bcm2711::BCM2711>;
+#[cfg(not(feature="bcm2711"))]
+pub type RootComplex = PciRootComplex<generic::Generic>;
 
 pub type  PciRoot = RootComplex;
 pub type DeviceFunction = PciAddress;
@@ -1,3 +1,4 @@
+use crate::capability::{CapabilityId, CapabilityIter, MsiCapability, MsixCapability};
 use crate::PciAddress;
 use bit_field::BitField;
 use tock_registers::interfaces::ReadWriteable;
@@ -172,6 +173,22 @@ impl PciHeader {
             .fold(0u16, |acc, a| acc + a.clone() as u16);
         self.regs().command.set(cmd)
     }
+
+    /// Disables or enables the INTx# interrupt of the function.
+    pub fn set_interrupt_disable(&self, disabled: bool) {
+        let value = if disabled {
+            RC_CFG_COMMAND::INTERRUPT_DISABLE::SET
+        } else {
+            RC_CFG_COMMAND::INTERRUPT_DISABLE::CLEAR
+        };
+        self.regs().command.modify(value)
+    }
+
+    /// Returns an iterator over the capability list of the function.
+    pub fn capabilities(&self) -> CapabilityIter {
+        let has_capabilities = self.regs().status.is_set(RC_CFG_STATUS::CAPABILITIES_LIST);
+        CapabilityIter::new(self.cfg_base, has_capabilities)
+    }
 }
 
 pub type Revision = u8;
@@ -408,6 +425,24 @@ pub struct ConfigSpace {
     pub kind: ConfigKind,
 }
 
+impl ConfigSpace {
+    /// Returns the MSI capability of the function, if any.
+    pub fn msi(&self) -> Option<MsiCapability> {
+        self.header
+            .capabilities()
+            .find(|cap| cap.id == CapabilityId::Msi)
+            .map(|cap| MsiCapability::new(self.cfg_addr, cap.offset))
+    }
+
+    /// Returns the MSI-X capability of the function, if any.
+    pub fn msix(&self) -> Option<MsixCapability> {
+        self.header
+            .capabilities()
+            .find(|cap| cap.id == CapabilityId::MsiX)
+            .map(|cap| MsixCapability::new(self.cfg_addr, cap.offset))
+    }
+}
+
 pub enum ConfigKind {
     Endpoint { inner: ConifgEndpoint },
     PciPciBridge { inner: ConifgPciPciBridge },
@@ -0,0 +1,231 @@
+//! HID class driver for boot protocol keyboards and mice (HID 1.11 spec,
+//! appendix B).
+
+use driver_common::{DevError, DevResult};
+use log::{debug, warn};
+
+use crate::host::{SetupPacket, USBHostDriverOps};
+use crate::usb::{descriptors::EndpointTransferType, DeviceMatch, UsbDevice};
+
+const CLASS_HID: u8 = 0x03;
+const SUBCLASS_BOOT: u8 = 0x01;
+const PROTOCOL_KEYBOARD: u8 = 0x01;
+const PROTOCOL_MOUSE: u8 = 0x02;
+
+const REQ_SET_IDLE: u8 = 0x0a;
+const REQ_SET_PROTOCOL: u8 = 0x0b;
+/// `bmRequestType`: class request to an interface, host to device.
+const REQ_TYPE_CLASS_INTERFACE_OUT: u8 = 0x21;
+
+const BOOT_PROTOCOL: u16 = 0;
+const REPORT_SIZE: usize = 8;
+
+/// Devices handled by this driver.
+pub const MATCHES: [DeviceMatch; 2] = [
+    DeviceMatch::InterfaceClass {
+        class: CLASS_HID,
+        subclass: Some(SUBCLASS_BOOT),
+        protocol: Some(PROTOCOL_KEYBOARD),
+    },
+    DeviceMatch::InterfaceClass {
+        class: CLASS_HID,
+        subclass: Some(SUBCLASS_BOOT),
+        protocol: Some(PROTOCOL_MOUSE),
+    },
+];
+
+/// The kind of a boot protocol device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HidKind {
+    Keyboard,
+    Mouse,
+}
+
+/// A relative mouse movement, together with the buttons held down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// Button states, bit 0 is the left button, bit 1 the right one and bit 2
+    /// the middle one.
+    pub buttons: u8,
+    pub dx: i8,
+    pub dy: i8,
+    pub wheel: i8,
+}
+
+/// An input event decoded from a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HidEvent {
+    /// A key press, translated to ASCII (US layout).
+    Key(u8),
+    Mouse(MouseEvent),
+}
+
+/// A boot protocol keyboard or mouse bound to an interface of a device.
+pub struct HidDevice {
+    kind: HidKind,
+    slot: u8,
+    /// Address of the interrupt IN endpoint.
+    endpoint: u8,
+    last_report: [u8; REPORT_SIZE],
+    pending: bool,
+}
+
+impl HidDevice {
+    /// Binds to `dev` if it has a boot keyboard or mouse interface: sets up
+    /// its interrupt IN endpoint and switches it to the boot protocol.
+    pub fn probe(host: &mut dyn USBHostDriverOps, dev: &UsbDevice) -> Option<Self> {
+        let interface = MATCHES.iter().find_map(|m| m.find(dev))?;
+        let kind = match interface.descriptor.protocol {
+            PROTOCOL_KEYBOARD => HidKind::Keyboard,
+            _ => HidKind::Mouse,
+        };
+        let endpoint = *interface
+            .endpoints
+            .iter()
+            .find(|ep| ep.is_in() && ep.transfer_type() == EndpointTransferType::Interrupt)?;
+
+        let number = interface.descriptor.interface_number as u16;
+        let res = host
+            .configure_endpoints(dev.slot, &[endpoint])
+            .and_then(|_| class_request(host, dev.slot, REQ_SET_PROTOCOL, BOOT_PROTOCOL, number))
+            // Only report on changes.
+            .and_then(|_| class_request(host, dev.slot, REQ_SET_IDLE, 0, number));
+        if let Err(e) = res {
+            warn!("usb hid slot {}: probe failed: {:?}", dev.slot, e);
+            return None;
+        }
+        debug!("usb hid slot {}: {:?} bound", dev.slot, kind);
+
+        Some(Self {
+            kind,
+            slot: dev.slot,
+            endpoint: endpoint.address,
+            last_report: [0; REPORT_SIZE],
+            pending: false,
+        })
+    }
+
+    /// The slot of the device the driver is bound to.
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    pub fn kind(&self) -> HidKind {
+        self.kind
+    }
+
+    /// Collects the latest report, if any, and passes the decoded events to
+    /// `f`. Never blocks.
+    pub fn poll(
+        &mut self,
+        host: &mut dyn USBHostDriverOps,
+        mut f: impl FnMut(HidEvent),
+    ) -> DevResult {
+        if !self.pending {
+            host.submit_transfer(self.slot, self.endpoint, REPORT_SIZE)?;
+            self.pending = true;
+        }
+
+        let mut report = [0u8; REPORT_SIZE];
+        let len = match host.poll_transfer(self.slot, self.endpoint, &mut report) {
+            None => return Ok(()),
+            Some(res) => {
+                self.pending = false;
+                res?
+            }
+        };
+        match self.kind {
+            HidKind::Keyboard if len >= REPORT_SIZE => self.keyboard_report(&report, &mut f),
+            HidKind::Mouse if len >= 3 => f(HidEvent::Mouse(MouseEvent {
+                buttons: report[0] & 0x7,
+                dx: report[1] as i8,
+                dy: report[2] as i8,
+                wheel: if len > 3 { report[3] as i8 } else { 0 },
+            })),
+            _ => return Err(DevError::Io),
+        }
+        Ok(())
+    }
+
+    /// Reports the keys present in `report` that were not held down in the
+    /// previous one.
+    fn keyboard_report(&mut self, report: &[u8; REPORT_SIZE], f: &mut impl FnMut(HidEvent)) {
+        let modifiers = report[0];
+        for &code in &report[2..] {
+            // 0x01 means too many keys are held down (ErrorRollOver).
+            if code <= 0x03 || self.last_report[2..].contains(&code) {
+                continue;
+            }
+            if let Some(c) = keycode_to_ascii(code, modifiers) {
+                f(HidEvent::Key(c));
+            }
+        }
+        self.last_report = *report;
+    }
+}
+
+fn class_request(
+    host: &mut dyn USBHostDriverOps,
+    slot: u8,
+    request: u8,
+    value: u16,
+    interface: u16,
+) -> DevResult {
+    host.control_transfer(
+        slot,
+        SetupPacket {
+            request_type: REQ_TYPE_CLASS_INTERFACE_OUT,
+            request,
+            value,
+            index: interface,
+        },
+        &mut [],
+    )
+    .map(|_| ())
+}
+
+/// Translates a usage ID of the keyboard page into ASCII.
+fn keycode_to_ascii(code: u8, modifiers: u8) -> Option<u8> {
+    const MOD_CTRL: u8 = 0x11;
+    const MOD_SHIFT: u8 = 0x22;
+    const SYMBOLS: &[u8] = b"-=[]\\#;'`,./";
+    const SYMBOLS_SHIFT: &[u8] = b"_+{}|~:\"~<>?";
+
+    let shift = modifiers & MOD_SHIFT != 0;
+    let c = match code {
+        0x04..=0x1d => {
+            let c = b'a' + code - 0x04;
+            if modifiers & MOD_CTRL != 0 {
+                return Some(c - b'a' + 1);
+            }
+            if shift {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        }
+        0x1e..=0x27 => {
+            let i = (code - 0x1e) as usize;
+            if shift {
+                b"!@#$%^&*()"[i]
+            } else {
+                b"1234567890"[i]
+            }
+        }
+        0x28 | 0x58 => b'\n',
+        0x29 => 0x1b,
+        0x2a => 0x7f,
+        0x2b => b'\t',
+        0x2c => b' ',
+        0x2d..=0x38 => {
+            let i = (code - 0x2d) as usize;
+            if shift {
+                SYMBOLS_SHIFT[i]
+            } else {
+                SYMBOLS[i]
+            }
+        }
+        _ => return None,
+    };
+    Some(c)
+}
@@ -0,0 +1,334 @@
+//! Hub class driver (USB 2.0 spec, chapter 11 and USB 3.2 spec, chapter 10).
+
+use core::time::Duration;
+use driver_common::{DevError, DevResult};
+use log::{debug, warn};
+
+use crate::host::{DeviceRoute, PortSpeed, SetupPacket, USBHostDriverOps};
+use crate::usb::{
+    descriptors::{EndpointDescriptor, EndpointTransferType},
+    DeviceMatch, UsbDevice,
+};
+
+const CLASS_HUB: u8 = 0x09;
+
+const DESC_HUB: u8 = 0x29;
+const DESC_SS_HUB: u8 = 0x2a;
+
+const REQ_GET_STATUS: u8 = 0x00;
+const REQ_CLEAR_FEATURE: u8 = 0x01;
+const REQ_SET_FEATURE: u8 = 0x03;
+const REQ_GET_DESCRIPTOR: u8 = 0x06;
+const REQ_SET_HUB_DEPTH: u8 = 0x0c;
+/// `bmRequestType`: class request to the hub, device to host.
+const REQ_TYPE_CLASS_DEVICE_IN: u8 = 0xa0;
+/// `bmRequestType`: class request to the hub, host to device.
+const REQ_TYPE_CLASS_DEVICE_OUT: u8 = 0x20;
+/// `bmRequestType`: class request to a port, device to host.
+const REQ_TYPE_CLASS_PORT_IN: u8 = 0xa3;
+/// `bmRequestType`: class request to a port, host to device.
+const REQ_TYPE_CLASS_PORT_OUT: u8 = 0x23;
+
+const PORT_RESET: u16 = 4;
+const PORT_POWER: u16 = 8;
+const C_PORT_CONNECTION: u16 = 16;
+const C_PORT_ENABLE: u16 = 17;
+const C_PORT_SUSPEND: u16 = 18;
+const C_PORT_OVER_CURRENT: u16 = 19;
+const C_PORT_RESET: u16 = 20;
+const C_PORT_LINK_STATE: u16 = 25;
+const C_PORT_CONFIG_ERROR: u16 = 26;
+const C_BH_PORT_RESET: u16 = 29;
+
+const PORT_STAT_CONNECTION: u16 = 1 << 0;
+const PORT_STAT_ENABLE: u16 = 1 << 1;
+const PORT_STAT_RESET: u16 = 1 << 4;
+const PORT_STAT_LOW_SPEED: u16 = 1 << 9;
+const PORT_STAT_HIGH_SPEED: u16 = 1 << 10;
+
+const RESET_TIMEOUT: Duration = Duration::from_millis(500);
+/// Time for a device to recover from a port reset (TRSTRCY).
+const RESET_RECOVERY: Duration = Duration::from_millis(10);
+
+/// Devices handled by this driver.
+pub const MATCHES: [DeviceMatch; 1] = [DeviceMatch::InterfaceClass {
+    class: CLASS_HUB,
+    subclass: None,
+    protocol: None,
+}];
+
+/// The status of a downstream port, as returned by GET_STATUS.
+#[derive(Debug, Clone, Copy)]
+pub struct PortStatus {
+    pub status: u16,
+    pub change: u16,
+}
+
+impl PortStatus {
+    pub fn connected(&self) -> bool {
+        self.status & PORT_STAT_CONNECTION != 0
+    }
+
+    /// Whether a device has been attached or detached.
+    pub fn connection_changed(&self) -> bool {
+        self.change & PORT_STAT_CONNECTION != 0
+    }
+}
+
+/// A hub bound to a device, with its downstream ports powered on.
+pub struct Hub {
+    slot: u8,
+    route: DeviceRoute,
+    speed: PortSpeed,
+    num_ports: u8,
+    /// Address of the status change endpoint.
+    endpoint: u8,
+    pending: bool,
+}
+
+impl Hub {
+    /// Binds to `dev` if it is a hub: reads its descriptor, tells the host
+    /// controller about it and powers its ports on.
+    pub fn probe(host: &mut dyn USBHostDriverOps, dev: &UsbDevice) -> Option<Self> {
+        let interface = MATCHES.iter().find_map(|m| m.find(dev))?;
+        let endpoint = *interface
+            .endpoints
+            .iter()
+            .find(|ep| ep.is_in() && ep.transfer_type() == EndpointTransferType::Interrupt)?;
+        let hub = Self {
+            slot: dev.slot,
+            route: dev.route,
+            speed: dev.speed,
+            num_ports: 0,
+            endpoint: endpoint.address,
+            pending: false,
+        };
+        match hub.init(host, dev, &endpoint) {
+            Ok(hub) => Some(hub),
+            Err(e) => {
+                warn!("usb hub slot {}: probe failed: {:?}", dev.slot, e);
+                None
+            }
+        }
+    }
+
+    fn init(
+        mut self,
+        host: &mut dyn USBHostDriverOps,
+        dev: &UsbDevice,
+        endpoint: &EndpointDescriptor,
+    ) -> DevResult<Self> {
+        let superspeed = self.is_superspeed();
+        let ty = if superspeed { DESC_SS_HUB } else { DESC_HUB };
+        let mut desc = [0u8; 12];
+        let len = host.control_transfer(
+            self.slot,
+            SetupPacket {
+                request_type: REQ_TYPE_CLASS_DEVICE_IN,
+                request: REQ_GET_DESCRIPTOR,
+                value: (ty as u16) << 8,
+                index: 0,
+            },
+            &mut desc,
+        )?;
+        if len < 7 || desc[1] != ty {
+            return Err(DevError::Io);
+        }
+        self.num_ports = desc[2];
+        let characteristics = u16::from_le_bytes([desc[3], desc[4]]);
+        // In 2ms units.
+        let power_on_delay = Duration::from_millis(desc[5] as u64 * 2);
+        let tt_think_time = ((characteristics >> 5) & 0x3) as u8;
+        let multi_tt = dev.descriptor.protocol == 2;
+
+        host.configure_hub(self.slot, self.num_ports, tt_think_time, multi_tt)?;
+        host.configure_endpoints(self.slot, &[*endpoint])?;
+        if superspeed {
+            host.control_transfer(
+                self.slot,
+                SetupPacket {
+                    request_type: REQ_TYPE_CLASS_DEVICE_OUT,
+                    request: REQ_SET_HUB_DEPTH,
+                    value: self.route.depth() as u16,
+                    index: 0,
+                },
+                &mut [],
+            )?;
+        }
+
+        for port in 1..=self.num_ports {
+            self.port_request(host, REQ_SET_FEATURE, PORT_POWER, port)?;
+        }
+        axtask::sleep(power_on_delay);
+        debug!(
+            "usb hub slot {}: {} ports powered on",
+            self.slot, self.num_ports
+        );
+        Ok(self)
+    }
+
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    pub fn route(&self) -> DeviceRoute {
+        self.route
+    }
+
+    pub fn speed(&self) -> PortSpeed {
+        self.speed
+    }
+
+    pub fn num_ports(&self) -> u8 {
+        self.num_ports
+    }
+
+    fn is_superspeed(&self) -> bool {
+        matches!(self.speed, PortSpeed::Super | PortSpeed::SuperPlus)
+    }
+
+    fn port_request(
+        &self,
+        host: &mut dyn USBHostDriverOps,
+        request: u8,
+        feature: u16,
+        port: u8,
+    ) -> DevResult {
+        host.control_transfer(
+            self.slot,
+            SetupPacket {
+                request_type: REQ_TYPE_CLASS_PORT_OUT,
+                request,
+                value: feature,
+                index: port as u16,
+            },
+            &mut [],
+        )
+        .map(|_| ())
+    }
+
+    /// Reads the status of a downstream port.
+    pub fn port_status(&self, host: &mut dyn USBHostDriverOps, port: u8) -> DevResult<PortStatus> {
+        let mut buf = [0u8; 4];
+        let len = host.control_transfer(
+            self.slot,
+            SetupPacket {
+                request_type: REQ_TYPE_CLASS_PORT_IN,
+                request: REQ_GET_STATUS,
+                value: 0,
+                index: port as u16,
+            },
+            &mut buf,
+        )?;
+        if len < 4 {
+            return Err(DevError::Io);
+        }
+        Ok(PortStatus {
+            status: u16::from_le_bytes([buf[0], buf[1]]),
+            change: u16::from_le_bytes([buf[2], buf[3]]),
+        })
+    }
+
+    /// Acknowledges every change reported in `status`.
+    pub fn clear_changes(
+        &self,
+        host: &mut dyn USBHostDriverOps,
+        port: u8,
+        status: &PortStatus,
+    ) -> DevResult {
+        // (bit in wPortChange, feature clearing it)
+        let changes: &[(u16, u16)] = if self.is_superspeed() {
+            &[
+                (0, C_PORT_CONNECTION),
+                (3, C_PORT_OVER_CURRENT),
+                (4, C_PORT_RESET),
+                (5, C_BH_PORT_RESET),
+                (6, C_PORT_LINK_STATE),
+                (7, C_PORT_CONFIG_ERROR),
+            ]
+        } else {
+            &[
+                (0, C_PORT_CONNECTION),
+                (1, C_PORT_ENABLE),
+                (2, C_PORT_SUSPEND),
+                (3, C_PORT_OVER_CURRENT),
+                (4, C_PORT_RESET),
+            ]
+        };
+        for &(bit, feature) in changes {
+            if status.change & (1 << bit) != 0 {
+                self.port_request(host, REQ_CLEAR_FEATURE, feature, port)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resets a downstream port and returns the speed of the device
+    /// connected to it.
+    pub fn reset_port(&self, host: &mut dyn USBHostDriverOps, port: u8) -> DevResult<PortSpeed> {
+        self.port_request(host, REQ_SET_FEATURE, PORT_RESET, port)?;
+        let deadline = axhal::time::current_time() + RESET_TIMEOUT;
+        let status = loop {
+            let status = self.port_status(host, port)?;
+            if status.status & PORT_STAT_RESET == 0 && status.status & PORT_STAT_ENABLE != 0 {
+                break status;
+            }
+            if axhal::time::current_time() > deadline {
+                return Err(DevError::Io);
+            }
+            axtask::sleep(Duration::from_millis(1));
+        };
+        self.clear_changes(host, port, &status)?;
+        axtask::sleep(RESET_RECOVERY);
+
+        Ok(if self.is_superspeed() {
+            PortSpeed::Super
+        } else if status.status & PORT_STAT_LOW_SPEED != 0 {
+            PortSpeed::Low
+        } else if status.status & PORT_STAT_HIGH_SPEED != 0 {
+            PortSpeed::High
+        } else {
+            PortSpeed::Full
+        })
+    }
+
+    /// Checks the status change endpoint without blocking.
+    ///
+    /// Returns a bitmap with bit 0 set for a change of the hub itself and
+    /// bit `n` set for a change of port `n` (up to 255), or all zeros if
+    /// nothing changed.
+    pub fn poll_changes(&mut self, host: &mut dyn USBHostDriverOps) -> DevResult<PortBitmap> {
+        let len = (self.num_ports as usize + 8) / 8;
+        if !self.pending {
+            host.submit_transfer(self.slot, self.endpoint, len)?;
+            self.pending = true;
+        }
+
+        let mut bitmap = PortBitmap::default();
+        match host.poll_transfer(self.slot, self.endpoint, &mut bitmap.0[..len]) {
+            None => Ok(bitmap),
+            Some(res) => {
+                self.pending = false;
+                res?;
+                Ok(bitmap)
+            }
+        }
+    }
+}
+
+/// The status change bitmap of a hub, one bit per port plus bit 0 for the
+/// hub itself.
+#[derive(Clone, Copy, Default)]
+pub struct PortBitmap([u8; 32]);
+
+impl PortBitmap {
+    /// Whether any change is reported.
+    pub fn any(&self) -> bool {
+        self.0.iter().any(|&b| b != 0)
+    }
+
+    /// Whether bit `n` is set.
+    pub fn is_set(&self, n: u8) -> bool {
+        self.0[n as usize / 8] & (1 << (n % 8)) != 0
+    }
+}
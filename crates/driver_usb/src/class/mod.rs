@@ -0,0 +1,5 @@
+//! USB class drivers, bound to the devices found by [`crate::usb::enumerate`].
+
+pub mod hid;
+pub mod hub;
+pub mod storage;
@@ -0,0 +1,329 @@
+//! Mass storage class driver: Bulk-Only Transport (USB MSC BOT 1.0) carrying
+//! SCSI commands (SBC-3).
+
+use alloc::vec;
+use driver_block::BlockDriverOps;
+use driver_common::{BaseDriverOps, DevError, DevResult, DeviceType};
+use log::{debug, warn};
+
+use crate::host::{SetupPacket, USBHostDriverOps, MAX_TRANSFER_SIZE};
+use crate::usb::{descriptors::EndpointTransferType, DeviceMatch, UsbDevice};
+
+const CLASS_MASS_STORAGE: u8 = 0x08;
+const SUBCLASS_SCSI: u8 = 0x06;
+const PROTOCOL_BOT: u8 = 0x50;
+
+/// Bulk-Only Mass Storage Reset.
+const REQ_BOT_RESET: u8 = 0xff;
+const REQ_TYPE_CLASS_INTERFACE_OUT: u8 = 0x21;
+
+/// Devices handled by this driver.
+pub const MATCHES: [DeviceMatch; 1] = [DeviceMatch::InterfaceClass {
+    class: CLASS_MASS_STORAGE,
+    subclass: Some(SUBCLASS_SCSI),
+    protocol: Some(PROTOCOL_BOT),
+}];
+
+const CBW_SIGNATURE: u32 = 0x4342_5355;
+const CBW_SIZE: usize = 31;
+const CBW_FLAG_IN: u8 = 0x80;
+const CSW_SIGNATURE: u32 = 0x5342_5355;
+const CSW_SIZE: usize = 13;
+const CSW_STATUS_PASSED: u8 = 0;
+const CSW_STATUS_PHASE_ERROR: u8 = 2;
+
+const SCSI_TEST_UNIT_READY: u8 = 0x00;
+const SCSI_REQUEST_SENSE: u8 = 0x03;
+const SCSI_INQUIRY: u8 = 0x12;
+const SCSI_READ_CAPACITY_10: u8 = 0x25;
+const SCSI_READ_10: u8 = 0x28;
+const SCSI_WRITE_10: u8 = 0x2a;
+const SCSI_SYNCHRONIZE_CACHE_10: u8 = 0x35;
+
+/// Attempts of TEST UNIT READY while the medium spins up.
+const READY_RETRIES: usize = 10;
+/// Most blocks moved by a single READ(10)/WRITE(10) command.
+const MAX_BLOCKS_PER_COMMAND: usize = 128;
+
+/// Direction of the data phase of a command.
+enum Data<'a> {
+    None,
+    In(&'a mut [u8]),
+    Out(&'a [u8]),
+}
+
+/// A SCSI disk behind a Bulk-Only Transport interface, such as a USB stick.
+///
+/// Only LUN 0 is used.
+pub struct MassStorage<H: USBHostDriverOps> {
+    host: H,
+    slot: u8,
+    interface: u8,
+    bulk_in: u8,
+    bulk_out: u8,
+    tag: u32,
+    block_size: usize,
+    num_blocks: u64,
+}
+
+impl<H: USBHostDriverOps> MassStorage<H> {
+    /// Binds to `dev` if it has a BOT SCSI interface, and reads the capacity
+    /// of the medium.
+    pub fn probe(mut host: H, dev: &UsbDevice) -> Option<Self> {
+        let interface = MATCHES.iter().find_map(|m| m.find(dev))?;
+        let bulk = |is_in: bool| {
+            interface
+                .endpoints
+                .iter()
+                .find(|ep| ep.transfer_type() == EndpointTransferType::Bulk && ep.is_in() == is_in)
+                .copied()
+        };
+        let (bulk_in, bulk_out) = (bulk(true)?, bulk(false)?);
+        if let Err(e) = host.configure_endpoints(dev.slot, &[bulk_in, bulk_out]) {
+            warn!("usb storage slot {}: {:?}", dev.slot, e);
+            return None;
+        }
+
+        let mut disk = Self {
+            host,
+            slot: dev.slot,
+            interface: interface.descriptor.interface_number,
+            bulk_in: bulk_in.address,
+            bulk_out: bulk_out.address,
+            tag: 0,
+            block_size: 0,
+            num_blocks: 0,
+        };
+        if let Err(e) = disk.init() {
+            warn!("usb storage slot {}: init failed: {:?}", dev.slot, e);
+            return None;
+        }
+        debug!(
+            "usb storage slot {}: {} blocks of {} bytes",
+            dev.slot, disk.num_blocks, disk.block_size
+        );
+        Some(disk)
+    }
+
+    fn init(&mut self) -> DevResult {
+        let mut inquiry = [0u8; 36];
+        self.command(&[SCSI_INQUIRY, 0, 0, 0, 36, 0], Data::In(&mut inquiry))?;
+
+        let mut retries = READY_RETRIES;
+        while self
+            .command(&[SCSI_TEST_UNIT_READY, 0, 0, 0, 0, 0], Data::None)
+            .is_err()
+        {
+            // Clears the unit attention reported after power on.
+            let mut sense = [0u8; 18];
+            self.command(&[SCSI_REQUEST_SENSE, 0, 0, 0, 18, 0], Data::In(&mut sense))?;
+            retries -= 1;
+            if retries == 0 {
+                return Err(DevError::Io);
+            }
+        }
+
+        let mut capacity = [0u8; 8];
+        let mut cb = [0u8; 10];
+        cb[0] = SCSI_READ_CAPACITY_10;
+        self.command(&cb, Data::In(&mut capacity))?;
+        let last_block = u32::from_be_bytes(capacity[0..4].try_into().unwrap());
+        self.block_size = u32::from_be_bytes(capacity[4..8].try_into().unwrap()) as usize;
+        self.num_blocks = last_block as u64 + 1;
+        if self.block_size == 0 || self.block_size > MAX_TRANSFER_SIZE {
+            return Err(DevError::Unsupported);
+        }
+        Ok(())
+    }
+
+    /// Runs a SCSI command, and recovers the transport if it fails without a
+    /// valid status.
+    fn command(&mut self, cb: &[u8], data: Data) -> DevResult {
+        let status = match self.transport(cb, data) {
+            Ok(status) => status,
+            Err(e) => {
+                self.reset_recovery();
+                return Err(e);
+            }
+        };
+        if status != CSW_STATUS_PASSED {
+            debug!(
+                "usb storage slot {}: command {:#x} failed: {}",
+                self.slot, cb[0], status
+            );
+            if status == CSW_STATUS_PHASE_ERROR {
+                self.reset_recovery();
+            }
+            return Err(DevError::Io);
+        }
+        Ok(())
+    }
+
+    /// Sends the CBW, moves the data, then reads the CSW and returns its
+    /// status.
+    ///
+    /// A stall in the data phase is cleared before reading the CSW (BOT 1.0,
+    /// 6.7.2), as is one when reading the CSW, which is then read again.
+    fn transport(&mut self, cb: &[u8], data: Data) -> DevResult<u8> {
+        self.tag = self.tag.wrapping_add(1);
+        let (len, flags) = match &data {
+            Data::None => (0, 0),
+            Data::In(buf) => (buf.len(), CBW_FLAG_IN),
+            Data::Out(buf) => (buf.len(), 0),
+        };
+
+        let mut cbw = [0u8; CBW_SIZE];
+        cbw[0..4].copy_from_slice(&CBW_SIGNATURE.to_le_bytes());
+        cbw[4..8].copy_from_slice(&self.tag.to_le_bytes());
+        cbw[8..12].copy_from_slice(&(len as u32).to_le_bytes());
+        cbw[12] = flags;
+        cbw[13] = 0; // LUN
+        cbw[14] = cb.len() as u8;
+        cbw[15..15 + cb.len()].copy_from_slice(cb);
+        self.host.transfer(self.slot, self.bulk_out, &mut cbw)?;
+
+        let res = match data {
+            Data::None => Ok(0),
+            Data::In(buf) => self
+                .host
+                .transfer(self.slot, self.bulk_in, buf)
+                .map_err(|e| (e, self.bulk_in)),
+            Data::Out(buf) => {
+                // The host controller interface takes a mutable buffer for
+                // both directions.
+                let mut out = vec![0u8; buf.len()];
+                out.copy_from_slice(buf);
+                self.host
+                    .transfer(self.slot, self.bulk_out, &mut out)
+                    .map_err(|e| (e, self.bulk_out))
+            }
+        };
+        if let Err((e, endpoint)) = res {
+            debug!("usb storage slot {}: data phase: {:?}", self.slot, e);
+            self.host.clear_halt(self.slot, endpoint)?;
+        }
+
+        let mut csw = [0u8; CSW_SIZE];
+        let n = match self.host.transfer(self.slot, self.bulk_in, &mut csw) {
+            Ok(n) => n,
+            Err(_) => {
+                self.host.clear_halt(self.slot, self.bulk_in)?;
+                self.host.transfer(self.slot, self.bulk_in, &mut csw)?
+            }
+        };
+        if n != CSW_SIZE
+            || csw[0..4] != CSW_SIGNATURE.to_le_bytes()
+            || csw[4..8] != self.tag.to_le_bytes()
+        {
+            warn!(
+                "usb storage slot {}: invalid CSW {:x?}",
+                self.slot,
+                &csw[..n]
+            );
+            return Err(DevError::Io);
+        }
+        Ok(csw[12])
+    }
+
+    /// Reset recovery (BOT 1.0, 5.3.4), after the device and the host have
+    /// lost track of the state of the transport: Bulk-Only Mass Storage
+    /// Reset, then clears the halt of both bulk endpoints.
+    fn reset_recovery(&mut self) {
+        warn!("usb storage slot {}: reset recovery", self.slot);
+        let setup = SetupPacket {
+            request_type: REQ_TYPE_CLASS_INTERFACE_OUT,
+            request: REQ_BOT_RESET,
+            value: 0,
+            index: self.interface as u16,
+        };
+        let res = self
+            .host
+            .control_transfer(self.slot, setup, &mut [])
+            .and_then(|_| self.host.clear_halt(self.slot, self.bulk_in))
+            .and_then(|_| self.host.clear_halt(self.slot, self.bulk_out));
+        if let Err(e) = res {
+            warn!(
+                "usb storage slot {}: reset recovery failed: {:?}",
+                self.slot, e
+            );
+        }
+    }
+
+    /// Blocks moved by a single READ(10)/WRITE(10) command, within what the
+    /// host controller can transfer at once.
+    fn blocks_per_command(&self) -> usize {
+        (MAX_TRANSFER_SIZE / self.block_size).min(MAX_BLOCKS_PER_COMMAND)
+    }
+
+    /// Builds a READ(10)/WRITE(10) command block.
+    fn rw10(op: u8, block_id: u64, blocks: usize) -> [u8; 10] {
+        let mut cb = [0u8; 10];
+        cb[0] = op;
+        cb[2..6].copy_from_slice(&(block_id as u32).to_be_bytes());
+        cb[7..9].copy_from_slice(&(blocks as u16).to_be_bytes());
+        cb
+    }
+
+    fn check_range(&self, block_id: u64, len: usize) -> DevResult<usize> {
+        if len % self.block_size != 0 {
+            return Err(DevError::InvalidParam);
+        }
+        let blocks = len / self.block_size;
+        if block_id + blocks as u64 > self.num_blocks {
+            return Err(DevError::InvalidParam);
+        }
+        Ok(blocks)
+    }
+}
+
+impl<H: USBHostDriverOps> BaseDriverOps for MassStorage<H> {
+    fn device_name(&self) -> &str {
+        "usb-storage"
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Block
+    }
+}
+
+impl<H: USBHostDriverOps> BlockDriverOps for MassStorage<H> {
+    fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn read_block(&mut self, block_id: u64, buf: &mut [u8]) -> DevResult {
+        self.check_range(block_id, buf.len())?;
+        let blocks_per_command = self.blocks_per_command();
+        let chunk_size = blocks_per_command * self.block_size;
+        for (i, chunk) in buf.chunks_mut(chunk_size).enumerate() {
+            let blocks = chunk.len() / self.block_size;
+            let start = block_id + (i * blocks_per_command) as u64;
+            let cb = Self::rw10(SCSI_READ_10, start, blocks);
+            self.command(&cb, Data::In(chunk))?;
+        }
+        Ok(())
+    }
+
+    fn write_block(&mut self, block_id: u64, buf: &[u8]) -> DevResult {
+        self.check_range(block_id, buf.len())?;
+        let blocks_per_command = self.blocks_per_command();
+        let chunk_size = blocks_per_command * self.block_size;
+        for (i, chunk) in buf.chunks(chunk_size).enumerate() {
+            let blocks = chunk.len() / self.block_size;
+            let start = block_id + (i * blocks_per_command) as u64;
+            let cb = Self::rw10(SCSI_WRITE_10, start, blocks);
+            self.command(&cb, Data::Out(chunk))?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> DevResult {
+        let mut cb = [0u8; 10];
+        cb[0] = SCSI_SYNCHRONIZE_CACHE_10;
+        self.command(&cb, Data::None)
+    }
+}
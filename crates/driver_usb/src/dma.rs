@@ -5,6 +5,7 @@ use core::{
     ptr::{slice_from_raw_parts, NonNull},
 };
 
+use axhal::mem::virt_to_phys;
 use log::debug;
 
 pub struct DMAVec<A: Allocator, T> {
@@ -14,12 +15,14 @@ pub struct DMAVec<A: Allocator, T> {
 }
 
 impl<A: Allocator, T> DMAVec<A, T> {
+    /// Allocates a zeroed buffer of `size` elements aligned to `align` bytes.
     pub fn new(size: usize, align: usize, allocator: A) -> Self {
         let buff_size = size * size_of::<T>();
         let layout = Layout::from_size_align(buff_size, align).unwrap();
         let buff = allocator.allocate(layout).unwrap();
         let ptr;
         unsafe {
+            (buff.as_ptr() as *mut u8).write_bytes(0, buff_size);
             let s = &*slice_from_raw_parts(buff.as_ptr() as *const T, size);
             ptr = NonNull::from(s);
         }
@@ -29,8 +32,28 @@ impl<A: Allocator, T> DMAVec<A, T> {
             allocator,
         }
     }
+
+    /// The address of the buffer as seen by the device.
+    pub fn bus_addr(&self) -> u64 {
+        virt_to_bus(self.ptr.as_ptr() as *const T as usize) as u64
+    }
+}
+
+/// Translates a kernel virtual address into an address usable for DMA.
+///
+/// The no-cache region is identity mapped (see `remap_kernel_memory` in
+/// axruntime), everything else lives in the linear mapping.
+pub(crate) fn virt_to_bus(vaddr: usize) -> usize {
+    if vaddr >= axconfig::PHYS_VIRT_OFFSET {
+        virt_to_phys(vaddr.into()).as_usize()
+    } else {
+        vaddr
+    }
 }
 
+unsafe impl<A: Allocator + Send, T: Send> Send for DMAVec<A, T> {}
+unsafe impl<A: Allocator + Sync, T: Sync> Sync for DMAVec<A, T> {}
+
 impl<A: Allocator, T> Deref for DMAVec<A, T> {
     type Target = [T];
 
@@ -1,15 +1,226 @@
 pub mod xhci;
-use driver_common::BaseDriverOps;
 
-/// The information of the graphics device.
-#[derive(Debug, Clone, Copy)]
+mod shared;
+
+use alloc::vec::Vec;
+use driver_common::{BaseDriverOps, DevResult};
+
+pub use self::shared::SharedHost;
+use crate::usb::{descriptors::EndpointDescriptor, HotplugEvent, UsbDevice};
+
+/// The largest buffer accepted by [`USBHostDriverOps::transfer`] and
+/// [`USBHostDriverOps::submit_transfer`].
+pub const MAX_TRANSFER_SIZE: usize = 1024 * 1024;
+
+/// The information of the usb host controller.
+#[derive(Debug, Clone)]
 pub struct USBHostInfo {
+    /// Number of device slots enabled on the controller.
+    pub max_slots: u8,
+    /// Number of root hub ports.
+    pub max_ports: u8,
+    /// Devices currently attached, including those behind hubs. See
+    /// [`crate::usb::UsbBus`].
+    pub devices: Vec<UsbDevice>,
+}
+
+/// Speed of a device attached to a port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortSpeed {
+    Full,
+    Low,
+    High,
+    Super,
+    SuperPlus,
+}
+
+impl PortSpeed {
+    /// Decodes the default Protocol Speed ID Value reported in PORTSC.
+    pub fn from_psiv(psiv: u8) -> Option<Self> {
+        match psiv {
+            1 => Some(Self::Full),
+            2 => Some(Self::Low),
+            3 => Some(Self::High),
+            4 => Some(Self::Super),
+            5 => Some(Self::SuperPlus),
+            _ => None,
+        }
+    }
+
+    /// The Protocol Speed ID Value to put in a slot context.
+    pub fn psiv(&self) -> u8 {
+        match self {
+            Self::Full => 1,
+            Self::Low => 2,
+            Self::High => 3,
+            Self::Super => 4,
+            Self::SuperPlus => 5,
+        }
+    }
+
+    /// Max packet size of the default control endpoint before the device
+    /// descriptor has been read.
+    pub fn default_max_packet_size(&self) -> u16 {
+        match self {
+            Self::Low | Self::Full => 8,
+            Self::High => 64,
+            Self::Super | Self::SuperPlus => 512,
+        }
+    }
+}
+
+/// The 8-byte SETUP packet of a control transfer.
+///
+/// The direction of the data stage is given by bit 7 of `request_type`, and
+/// its length by the buffer passed to [`USBHostDriverOps::control_transfer`].
+#[derive(Debug, Clone, Copy)]
+pub struct SetupPacket {
+    pub request_type: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+}
+
+impl SetupPacket {
+    /// Whether the data stage goes from the device to the host.
+    pub fn is_device_to_host(&self) -> bool {
+        self.request_type & 0x80 != 0
+    }
+}
 
+/// Where a device sits in the bus topology.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeviceRoute {
+    /// The root hub port the device is reached through.
+    pub root_port: u8,
+    /// The downstream port of each hub between the root hub and the device,
+    /// 4 bits per tier starting from the least significant ones (USB 3.2
+    /// spec, 8.9). Zero for a device attached to the root hub.
+    pub route_string: u32,
+    /// The high-speed hub doing the transaction translation for a low or
+    /// full speed device, as its slot and the port the device is reached
+    /// through.
+    pub tt: Option<(u8, u8)>,
 }
 
+impl DeviceRoute {
+    /// The route of a device attached to a root hub port.
+    pub fn root(port: u8) -> Self {
+        Self {
+            root_port: port,
+            ..Default::default()
+        }
+    }
 
+    /// Number of hubs between the root hub and the device.
+    pub fn depth(&self) -> u8 {
+        ((32 - self.route_string.leading_zeros() + 3) / 4) as u8
+    }
 
-/// Operations that require a graphics device driver to implement.
+    /// The route of a device of speed `speed` attached to `port` of the hub
+    /// `hub_slot`, whose route is `self`.
+    pub fn child(&self, hub_slot: u8, hub_speed: PortSpeed, port: u8, speed: PortSpeed) -> Self {
+        let tt = match (hub_speed, speed) {
+            (PortSpeed::High, PortSpeed::Low | PortSpeed::Full) => Some((hub_slot, port)),
+            _ => self.tt,
+        };
+        Self {
+            root_port: self.root_port,
+            route_string: self.route_string | ((port.min(15) as u32) << (self.depth() * 4)),
+            tt,
+        }
+    }
+}
+
+/// Operations that require a usb host controller driver to implement.
 pub trait USBHostDriverOps: BaseDriverOps {
+    /// Get the host controller information.
+    fn info(&self) -> USBHostInfo;
+
+    /// Returns the speed of the device connected to the root hub port
+    /// (numbered from 1), or `None` if nothing is connected.
+    fn port_speed(&self, port: u8) -> Option<PortSpeed>;
+
+    /// Resets the root hub port and returns the speed of the connected device
+    /// once the port is enabled.
+    fn reset_port(&mut self, port: u8) -> DevResult<PortSpeed>;
+
+    /// Allocates a device slot, returns the slot ID.
+    fn enable_slot(&mut self) -> DevResult<u8>;
+
+    /// Sets up the default control endpoint of `slot` for the device at
+    /// `route`, and assigns it a USB address.
+    fn address_device(&mut self, slot: u8, route: DeviceRoute, speed: PortSpeed) -> DevResult;
+
+    /// Releases `slot` once its device has been detached.
+    fn disable_slot(&mut self, slot: u8) -> DevResult;
+
+    /// Updates the max packet size of the default control endpoint of
+    /// `slot`, once it has been read from the device descriptor.
+    fn update_max_packet_size(&mut self, slot: u8, max_packet_size: u16) -> DevResult;
+
+    /// Runs a control transfer on the default control endpoint of `slot`.
+    ///
+    /// Returns the number of bytes actually transferred in the data stage.
+    fn control_transfer(
+        &mut self,
+        slot: u8,
+        setup: SetupPacket,
+        data: &mut [u8],
+    ) -> DevResult<usize>;
 
-}
\ No newline at end of file
+    /// Sets up the transfer rings of the given (non-control) endpoints of
+    /// `slot`, after the device has been configured.
+    fn configure_endpoints(&mut self, slot: u8, endpoints: &[EndpointDescriptor]) -> DevResult;
+
+    /// Runs a bulk or interrupt transfer of up to [`MAX_TRANSFER_SIZE`] bytes
+    /// on `endpoint` (an endpoint address, with the direction in bit 7) and
+    /// waits for its completion.
+    ///
+    /// Returns the number of bytes actually transferred. The endpoint can be
+    /// used again after an error, but a stalled one must be cleared by
+    /// [`clear_halt`](USBHostDriverOps::clear_halt) first.
+    fn transfer(&mut self, slot: u8, endpoint: u8, data: &mut [u8]) -> DevResult<usize>;
+
+    /// Queues an IN transfer of up to `len` bytes on `endpoint` without
+    /// waiting for it. Only one transfer can be pending per endpoint.
+    fn submit_transfer(&mut self, slot: u8, endpoint: u8, len: usize) -> DevResult;
+
+    /// Checks the transfer queued by [`submit_transfer`] on `endpoint`.
+    ///
+    /// Returns `None` if it is still pending, otherwise copies the received
+    /// data into `data` and returns the number of bytes transferred.
+    ///
+    /// [`submit_transfer`]: USBHostDriverOps::submit_transfer
+    fn poll_transfer(
+        &mut self,
+        slot: u8,
+        endpoint: u8,
+        data: &mut [u8],
+    ) -> Option<DevResult<usize>>;
+
+    /// Clears the halt condition of `endpoint` of `slot` (e.g., after it
+    /// stalled) on the device with CLEAR_FEATURE(ENDPOINT_HALT), and resets
+    /// the endpoint in the host controller.
+    fn clear_halt(&mut self, slot: u8, endpoint: u8) -> DevResult;
+
+    /// Marks the device in `slot` as a hub with `num_ports` downstream ports.
+    ///
+    /// `tt_think_time` and `multi_tt` describe the transaction translator of
+    /// a high-speed hub, they are ignored for other hubs.
+    fn configure_hub(
+        &mut self,
+        slot: u8,
+        num_ports: u8,
+        tt_think_time: u8,
+        multi_tt: bool,
+    ) -> DevResult;
+
+    /// Returns a root hub port whose connection status has changed since
+    /// the last call, if any.
+    fn poll_port_change(&mut self) -> Option<u8>;
+
+    /// Handles the devices attached or detached since the last call,
+    /// including those behind hubs, and reports them.
+    fn poll_hotplug(&mut self) -> Vec<HotplugEvent>;
+}
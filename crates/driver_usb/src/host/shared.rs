@@ -0,0 +1,148 @@
+use alloc::{string::String, sync::Arc, vec::Vec};
+use axsync::Mutex;
+use driver_common::{BaseDriverOps, DevResult, DeviceType};
+
+use super::{DeviceRoute, PortSpeed, SetupPacket, USBHostDriverOps, USBHostInfo};
+use crate::usb::{descriptors::EndpointDescriptor, HotplugEvent};
+
+/// A host controller shared by several class drivers, e.g. the block device
+/// of a USB stick and the keyboard plugged next to it.
+///
+/// Every operation locks the controller for its whole duration, which can
+/// be long (e.g., resetting a port), so the lock sleeps instead of spinning
+/// with IRQs disabled.
+pub struct SharedHost<H: ?Sized> {
+    name: String,
+    host: Arc<Mutex<H>>,
+}
+
+impl<H: USBHostDriverOps> SharedHost<H> {
+    pub fn new(host: H) -> Self {
+        Self {
+            name: host.device_name().into(),
+            host: Arc::new(Mutex::new(host)),
+        }
+    }
+}
+
+impl<H: ?Sized> SharedHost<H> {
+    /// Whether both handles refer to the same host controller.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.host, &other.host)
+    }
+}
+
+impl<H: USBHostDriverOps + 'static> SharedHost<H> {
+    /// Erases the type of the host controller.
+    pub fn into_dyn(self) -> SharedHost<dyn USBHostDriverOps> {
+        SharedHost {
+            name: self.name,
+            host: self.host,
+        }
+    }
+}
+
+impl<H: ?Sized> Clone for SharedHost<H> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            host: self.host.clone(),
+        }
+    }
+}
+
+impl<H: USBHostDriverOps + ?Sized> BaseDriverOps for SharedHost<H> {
+    fn device_name(&self) -> &str {
+        &self.name
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::USBHost
+    }
+}
+
+impl<H: USBHostDriverOps + ?Sized> USBHostDriverOps for SharedHost<H> {
+    fn info(&self) -> USBHostInfo {
+        self.host.lock().info()
+    }
+
+    fn port_speed(&self, port: u8) -> Option<PortSpeed> {
+        self.host.lock().port_speed(port)
+    }
+
+    fn reset_port(&mut self, port: u8) -> DevResult<PortSpeed> {
+        self.host.lock().reset_port(port)
+    }
+
+    fn enable_slot(&mut self) -> DevResult<u8> {
+        self.host.lock().enable_slot()
+    }
+
+    fn address_device(&mut self, slot: u8, route: DeviceRoute, speed: PortSpeed) -> DevResult {
+        self.host.lock().address_device(slot, route, speed)
+    }
+
+    fn disable_slot(&mut self, slot: u8) -> DevResult {
+        self.host.lock().disable_slot(slot)
+    }
+
+    fn update_max_packet_size(&mut self, slot: u8, max_packet_size: u16) -> DevResult {
+        self.host
+            .lock()
+            .update_max_packet_size(slot, max_packet_size)
+    }
+
+    fn control_transfer(
+        &mut self,
+        slot: u8,
+        setup: SetupPacket,
+        data: &mut [u8],
+    ) -> DevResult<usize> {
+        self.host.lock().control_transfer(slot, setup, data)
+    }
+
+    fn configure_endpoints(&mut self, slot: u8, endpoints: &[EndpointDescriptor]) -> DevResult {
+        self.host.lock().configure_endpoints(slot, endpoints)
+    }
+
+    fn transfer(&mut self, slot: u8, endpoint: u8, data: &mut [u8]) -> DevResult<usize> {
+        self.host.lock().transfer(slot, endpoint, data)
+    }
+
+    fn submit_transfer(&mut self, slot: u8, endpoint: u8, len: usize) -> DevResult {
+        self.host.lock().submit_transfer(slot, endpoint, len)
+    }
+
+    fn poll_transfer(
+        &mut self,
+        slot: u8,
+        endpoint: u8,
+        data: &mut [u8],
+    ) -> Option<DevResult<usize>> {
+        self.host.lock().poll_transfer(slot, endpoint, data)
+    }
+
+    fn clear_halt(&mut self, slot: u8, endpoint: u8) -> DevResult {
+        self.host.lock().clear_halt(slot, endpoint)
+    }
+
+    fn configure_hub(
+        &mut self,
+        slot: u8,
+        num_ports: u8,
+        tt_think_time: u8,
+        multi_tt: bool,
+    ) -> DevResult {
+        self.host
+            .lock()
+            .configure_hub(slot, num_ports, tt_think_time, multi_tt)
+    }
+
+    fn poll_port_change(&mut self) -> Option<u8> {
+        self.host.lock().poll_port_change()
+    }
+
+    fn poll_hotplug(&mut self) -> Vec<HotplugEvent> {
+        self.host.lock().poll_hotplug()
+    }
+}
@@ -0,0 +1,75 @@
+use core::alloc::Allocator;
+
+use xhci::context::{
+    Device32Byte, Device64Byte, DeviceHandler, Input32Byte, Input64Byte, InputHandler,
+};
+
+use crate::dma::DMAVec;
+
+/// An Input Context, sized according to `HCCPARAMS1.CSZ`.
+pub enum InputContext<A: Allocator> {
+    Byte32(DMAVec<A, Input32Byte>),
+    Byte64(DMAVec<A, Input64Byte>),
+}
+
+impl<A: Allocator> InputContext<A> {
+    pub fn new(csz: bool, alloc: A) -> Self {
+        if csz {
+            let mut ctx = DMAVec::new(1, 64, alloc);
+            ctx[0] = Input64Byte::new_64byte();
+            Self::Byte64(ctx)
+        } else {
+            let mut ctx = DMAVec::new(1, 64, alloc);
+            ctx[0] = Input32Byte::new_32byte();
+            Self::Byte32(ctx)
+        }
+    }
+
+    pub fn handler_mut(&mut self) -> &mut dyn InputHandler {
+        match self {
+            Self::Byte32(ctx) => &mut ctx[0],
+            Self::Byte64(ctx) => &mut ctx[0],
+        }
+    }
+
+    pub fn bus_addr(&self) -> u64 {
+        match self {
+            Self::Byte32(ctx) => ctx.bus_addr(),
+            Self::Byte64(ctx) => ctx.bus_addr(),
+        }
+    }
+}
+
+/// An Output Device Context, owned by the controller once the slot is enabled.
+pub enum DeviceContext<A: Allocator> {
+    Byte32(DMAVec<A, Device32Byte>),
+    Byte64(DMAVec<A, Device64Byte>),
+}
+
+impl<A: Allocator> DeviceContext<A> {
+    pub fn new(csz: bool, alloc: A) -> Self {
+        if csz {
+            let mut ctx = DMAVec::new(1, 64, alloc);
+            ctx[0] = Device64Byte::new_64byte();
+            Self::Byte64(ctx)
+        } else {
+            let mut ctx = DMAVec::new(1, 64, alloc);
+            ctx[0] = Device32Byte::new_32byte();
+            Self::Byte32(ctx)
+        }
+    }
+
+    pub fn handler(&self) -> &dyn DeviceHandler {
+        match self {
+            Self::Byte32(ctx) => &ctx[0],
+            Self::Byte64(ctx) => &ctx[0],
+        }
+    }
+
+    pub fn bus_addr(&self) -> u64 {
+        match self {
+            Self::Byte32(ctx) => ctx.bus_addr(),
+            Self::Byte64(ctx) => ctx.bus_addr(),
+        }
+    }
+}
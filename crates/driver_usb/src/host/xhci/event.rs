@@ -0,0 +1,80 @@
+use core::{alloc::Allocator, mem::size_of};
+
+use super::ring::TrbData;
+use crate::dma::DMAVec;
+
+/// One entry of the Event Ring Segment Table.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct EventRingSte {
+    addr: u64,
+    size: u16,
+    _reserved: [u16; 3],
+}
+
+/// A consumer ring with a single segment, owned by interrupter 0.
+pub struct EventRing<A: Allocator> {
+    ring: DMAVec<A, TrbData>,
+    ste: DMAVec<A, EventRingSte>,
+    i: usize,
+    cycle: bool,
+}
+
+impl<A: Allocator + Clone> EventRing<A> {
+    pub fn new(len: usize, alloc: A) -> Self {
+        let ring = DMAVec::new(len, 64, alloc.clone());
+        let mut ste = DMAVec::new(1, 64, alloc);
+        ste[0] = EventRingSte {
+            addr: ring.bus_addr(),
+            size: len as u16,
+            _reserved: [0; 3],
+        };
+        Self {
+            ring,
+            ste,
+            i: 0,
+            cycle: true,
+        }
+    }
+}
+
+impl<A: Allocator> EventRing<A> {
+    /// Number of entries in the segment table (ERSTSZ).
+    pub fn table_len(&self) -> u16 {
+        self.ste.len() as u16
+    }
+
+    /// Bus address of the segment table (ERSTBA).
+    pub fn table_addr(&self) -> u64 {
+        self.ste.bus_addr()
+    }
+
+    /// Bus address of the next TRB to be consumed (ERDP).
+    pub fn erdp(&self) -> u64 {
+        self.ring.bus_addr() + (self.i * size_of::<TrbData>()) as u64
+    }
+
+    /// Whether the controller has written an event not consumed yet.
+    pub fn has_next(&self) -> bool {
+        self.peek()[3] & 1 == self.cycle as u32
+    }
+
+    /// Pops the next event written by the controller, if any.
+    pub fn next(&mut self) -> Option<TrbData> {
+        if !self.has_next() {
+            return None;
+        }
+        let trb = self.peek();
+
+        self.i += 1;
+        if self.i == self.ring.len() {
+            self.i = 0;
+            self.cycle = !self.cycle;
+        }
+        Some(trb)
+    }
+
+    fn peek(&self) -> TrbData {
+        unsafe { (&self.ring[self.i] as *const TrbData).read_volatile() }
+    }
+}
@@ -1,10 +1,56 @@
 #[cfg(feature = "vl805")]
 pub mod vl805;
-use axhal::mem::{phys_to_virt, VirtAddr, PhysAddr};
-use log::info;
-use xhci::accessor::Mapper;
-use core::{num::NonZeroUsize, alloc::Layout, ptr::NonNull};
 
+mod context;
+mod event;
+mod ring;
+
+use alloc::vec::Vec;
+use axhal::mem::phys_to_virt;
+use core::{alloc::Allocator, num::NonZeroUsize, time::Duration};
+use driver_common::*;
+use driver_pci::types::{Bar, ConfigCommand, ConfigKind, ConfigSpace};
+use log::{debug, info, warn};
+use xhci::{
+    accessor::Mapper,
+    context::{EndpointState, EndpointType},
+    ring::trb::{
+        command,
+        event::{self, CompletionCode},
+        transfer::{self, Direction, TransferType},
+    },
+    Registers,
+};
+
+use self::{
+    context::{DeviceContext, InputContext},
+    event::EventRing,
+    ring::Ring,
+};
+pub use crate::host::{DeviceRoute, PortSpeed, SetupPacket, USBHostDriverOps, USBHostInfo};
+use crate::{
+    dma::DMAVec,
+    host::MAX_TRANSFER_SIZE,
+    usb::{
+        descriptors::{EndpointDescriptor, EndpointTransferType},
+        HotplugEvent, UsbBus, FEATURE_ENDPOINT_HALT, REQ_CLEAR_FEATURE,
+        REQ_TYPE_STANDARD_ENDPOINT_OUT,
+    },
+};
+
+const XHCI_CLASS: u8 = 0x0c;
+const XHCI_SUBCLASS: u8 = 0x03;
+const XHCI_PROG_IF: u8 = 0x30;
+
+const RING_SIZE: usize = 256;
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Direction bit of a Status Stage TRB, set when the status stage is IN.
+const STATUS_STAGE_DIR_IN: u32 = 1 << 16;
+
+/// The data buffer of a TRB must not cross a 64 KiB boundary (xHCI spec,
+/// 6.4.1), which also bounds its 17-bit transfer length.
+const TRB_BUFFER_BOUNDARY: usize = 0x10000;
 
 #[derive(Clone, Copy)]
 struct MemoryMapper;
@@ -17,4 +63,966 @@ impl Mapper for MemoryMapper {
     }
 
     fn unmap(&mut self, virt_base: usize, bytes: usize) {}
-}
\ No newline at end of file
+}
+
+/// Per-slot state: the contexts shared with the controller and the
+/// endpoints, indexed by Device Context Index (DCI 1 is the control endpoint).
+struct Slot<A: Allocator> {
+    speed: PortSpeed,
+    input: InputContext<A>,
+    output: DeviceContext<A>,
+    endpoints: Vec<Option<Endpoint<A>>>,
+}
+
+struct Endpoint<A: Allocator> {
+    ring: Ring<A>,
+    transfer: Option<Transfer<A>>,
+}
+
+/// A transfer queued on an endpoint, completed by the events of its TRBs.
+struct Transfer<A: Allocator> {
+    buffer: DMAVec<A, u8>,
+    /// The TRBs moving data, whose events report short packets.
+    data_trbs: Vec<DataTrb>,
+    /// Whether a short packet ends the transfer, i.e., there is no status
+    /// stage after the data.
+    ends_on_short: bool,
+    /// The TRB whose event completes the transfer.
+    last_trb: u64,
+    transferred: usize,
+    result: Option<DevResult<usize>>,
+}
+
+/// A TRB moving a part of the buffer of a transfer.
+#[derive(Clone, Copy)]
+struct DataTrb {
+    addr: u64,
+    offset: usize,
+    len: usize,
+}
+
+/// A generic xHCI host controller driver.
+pub struct Xhci<A: Allocator + Clone> {
+    regs: Registers<MemoryMapper>,
+    alloc: A,
+    info: USBHostInfo,
+    csz: bool,
+    dcbaa: DMAVec<A, u64>,
+    cmd: Ring<A>,
+    event: EventRing<A>,
+    scratchpad: Option<(DMAVec<A, u64>, Vec<DMAVec<A, u8>>)>,
+    slots: Vec<Option<Slot<A>>>,
+    /// Root hub ports reported by Port Status Change events.
+    port_changes: Vec<u8>,
+    bus: UsbBus,
+}
+
+unsafe impl<A: Allocator + Clone + Send> Send for Xhci<A> {}
+unsafe impl<A: Allocator + Clone + Sync> Sync for Xhci<A> {}
+
+impl<A: Allocator + Clone + Sync + Send> BaseDriverOps for Xhci<A> {
+    fn device_name(&self) -> &str {
+        "xHCI USB Host Controller"
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::USBHost
+    }
+}
+
+impl<A: Allocator + Clone + Sync + Send> Xhci<A> {
+    /// Resets and starts the controller whose registers are at the physical
+    /// address `mmio_base`.
+    pub fn new(mmio_base: usize, alloc: A) -> DevResult<Self> {
+        let mut regs = unsafe { Registers::new(mmio_base, MemoryMapper) };
+        let version = regs.capability.hciversion.read_volatile();
+        debug!("xhci version: {:x}", version.get());
+
+        Self::reset(&mut regs)?;
+        info!("XHCI reset HC");
+
+        let hcsparams1 = regs.capability.hcsparams1.read_volatile();
+        let info = USBHostInfo {
+            max_slots: hcsparams1.number_of_device_slots(),
+            max_ports: hcsparams1.number_of_ports(),
+            devices: Vec::new(),
+        };
+        let csz = regs.capability.hccparams1.read_volatile().context_size();
+        debug!("xhci {:?}, 64-byte contexts: {}", info, csz);
+
+        let mut slots = Vec::new();
+        slots.resize_with(info.max_slots as usize + 1, || None);
+        let mut xhci = Self {
+            dcbaa: DMAVec::new(info.max_slots as usize + 1, 64, alloc.clone()),
+            cmd: Ring::new(RING_SIZE, alloc.clone()),
+            event: EventRing::new(RING_SIZE, alloc.clone()),
+            scratchpad: None,
+            regs,
+            alloc,
+            info,
+            csz,
+            slots,
+            port_changes: Vec::new(),
+            bus: UsbBus::new(),
+        };
+        xhci.setup_scratchpad();
+        xhci.start()?;
+        Ok(xhci)
+    }
+
+    /// Probes a PCI function with the xHCI class code.
+    pub fn probe_pci(config: &ConfigSpace, dma_alloc: A) -> Option<Self> {
+        let (_, class, subclass, prog_if) = config.header.revision_and_class();
+        if !(class == XHCI_CLASS && subclass == XHCI_SUBCLASS && prog_if == XHCI_PROG_IF) {
+            return None;
+        }
+
+        let ConfigKind::Endpoint { inner } = &config.kind else {
+            return None;
+        };
+        let address = match inner.bar(0)? {
+            Bar::Memory64 { address, .. } => address as usize,
+            Bar::Memory32 { address, .. } => address as usize,
+            Bar::Io { .. } => return None,
+        };
+
+        debug!("xHCI @0x{:X}", address);
+        config.header.set_command([
+            ConfigCommand::MemorySpaceEnable,
+            ConfigCommand::BusMasterEnable,
+            ConfigCommand::ParityErrorResponse,
+            ConfigCommand::SERREnable,
+        ]);
+        let mut xhci = Self::new(address, dma_alloc)
+            .map_err(|e| warn!("xhci init failed: {:?}", e))
+            .ok()?;
+        xhci.enumerate();
+        Some(xhci)
+    }
+
+    /// Enumerates the devices attached to the root hub and the hubs behind
+    /// it, the result is available in [`USBHostInfo::devices`].
+    pub fn enumerate(&mut self) {
+        let mut bus = core::mem::take(&mut self.bus);
+        bus.enumerate(self);
+        self.info.devices = bus.devices().to_vec();
+        self.bus = bus;
+    }
+
+    fn reset(regs: &mut Registers<MemoryMapper>) -> DevResult {
+        let o = &mut regs.operational;
+        debug!("xhci stat: {:?}", o.usbsts.read_volatile());
+
+        o.usbcmd.update_volatile(|f| {
+            f.clear_run_stop();
+        });
+        wait_until(|| o.usbsts.read_volatile().hc_halted())?;
+
+        debug!("xhci wait for ready...");
+        wait_until(|| !o.usbsts.read_volatile().controller_not_ready())?;
+        o.usbcmd.update_volatile(|f| {
+            f.set_host_controller_reset();
+        });
+        wait_until(|| !o.usbcmd.read_volatile().host_controller_reset())?;
+        wait_until(|| !o.usbsts.read_volatile().controller_not_ready())
+    }
+
+    fn setup_scratchpad(&mut self) {
+        let count = self
+            .regs
+            .capability
+            .hcsparams2
+            .read_volatile()
+            .max_scratchpad_buffers() as usize;
+        if count == 0 {
+            return;
+        }
+
+        let page_size = (self.regs.operational.pagesize.read_volatile().get() as usize) << 12;
+        let mut array = DMAVec::new(count, 64, self.alloc.clone());
+        let mut buffers = Vec::with_capacity(count);
+        for entry in array.iter_mut() {
+            let buffer = DMAVec::new(page_size, page_size, self.alloc.clone());
+            *entry = buffer.bus_addr();
+            buffers.push(buffer);
+        }
+        self.dcbaa[0] = array.bus_addr();
+        debug!("xhci scratchpad: {} pages", count);
+        self.scratchpad = Some((array, buffers));
+    }
+
+    fn start(&mut self) -> DevResult {
+        let max_slots = self.info.max_slots;
+        let dcbaa = self.dcbaa.bus_addr();
+        let crcr = self.cmd.register();
+        let cycle = self.cmd.cycle();
+        let erstsz = self.event.table_len();
+        let erstba = self.event.table_addr();
+        let erdp = self.event.erdp();
+
+        let o = &mut self.regs.operational;
+        o.config.update_volatile(|r| {
+            r.set_max_device_slots_enabled(max_slots);
+        });
+        o.dcbaap.update_volatile(|r| {
+            r.set(dcbaa);
+        });
+        o.crcr.update_volatile(|r| {
+            r.set_command_ring_pointer(crcr);
+            if cycle {
+                r.set_ring_cycle_state();
+            } else {
+                r.clear_ring_cycle_state();
+            }
+        });
+
+        let mut ir = self.regs.interrupter_register_set.interrupter_mut(0);
+        ir.erstsz.update_volatile(|r| {
+            r.set(erstsz);
+        });
+        ir.erdp.update_volatile(|r| {
+            r.set_event_ring_dequeue_pointer(erdp);
+        });
+        // ERSTBA must be written last, it enables the event ring.
+        ir.erstba.update_volatile(|r| {
+            r.set(erstba);
+        });
+        ir.imod.update_volatile(|r| {
+            r.set_interrupt_moderation_interval(4000);
+        });
+        ir.iman.update_volatile(|r| {
+            r.set_interrupt_enable();
+            r.clear_interrupt_pending();
+        });
+
+        let o = &mut self.regs.operational;
+        o.usbcmd.update_volatile(|r| {
+            r.set_interrupter_enable();
+            r.set_run_stop();
+        });
+        wait_until(|| !o.usbsts.read_volatile().hc_halted())?;
+        info!("xhci running");
+
+        // Make sure the command ring works before handing the controller out.
+        self.post_command(command::Allowed::Noop(command::Noop::new()))?;
+        Ok(())
+    }
+
+    fn ring_doorbell(&mut self, slot: u8, target: u8) {
+        self.regs.doorbell.update_volatile_at(slot as usize, |r| {
+            r.set_doorbell_target(target);
+            r.set_doorbell_stream_id(0);
+        });
+    }
+
+    fn post_command(&mut self, trb: command::Allowed) -> DevResult<event::CommandCompletion> {
+        let addr = self.cmd.enqueue(trb.into_raw());
+        self.ring_doorbell(0, 0);
+
+        let deadline = axhal::time::current_time() + TIMEOUT;
+        loop {
+            if let Some(event::Allowed::CommandCompletion(c)) = self.handle_event() {
+                if c.command_trb_pointer() == addr {
+                    check_completion(c.completion_code())?;
+                    return Ok(c);
+                }
+            } else if axhal::time::current_time() > deadline {
+                warn!("xhci command timeout");
+                return Err(DevError::Io);
+            } else {
+                axtask::yield_now();
+            }
+        }
+    }
+
+    /// Consumes one event from interrupter 0, if any.
+    ///
+    /// Transfer events are recorded on the transfer they belong to, other
+    /// events are returned to the caller.
+    fn handle_event(&mut self) -> Option<event::Allowed> {
+        let trb = self.event.next()?;
+        let erdp = self.event.erdp();
+        self.regs
+            .interrupter_register_set
+            .interrupter_mut(0)
+            .erdp
+            .update_volatile(|r| {
+                r.set_event_ring_dequeue_pointer(erdp);
+                r.clear_event_handler_busy();
+            });
+
+        match event::Allowed::try_from(trb) {
+            Ok(event::Allowed::TransferEvent(e)) => {
+                self.complete_transfer(&e);
+                None
+            }
+            Ok(event::Allowed::PortStatusChange(e)) => {
+                debug!("xhci port {} status changed", e.port_id());
+                if !self.port_changes.contains(&e.port_id()) {
+                    self.port_changes.push(e.port_id());
+                }
+                Some(event::Allowed::PortStatusChange(e))
+            }
+            Ok(e) => Some(e),
+            Err(raw) => {
+                warn!("xhci unknown event TRB {:x?}", raw);
+                None
+            }
+        }
+    }
+
+    fn complete_transfer(&mut self, e: &event::TransferEvent) {
+        let Some(t) = self
+            .endpoint_mut(e.slot_id(), e.endpoint_id())
+            .ok()
+            .and_then(|ep| ep.transfer.as_mut())
+        else {
+            debug!(
+                "xhci stray transfer event: slot {} dci {}",
+                e.slot_id(),
+                e.endpoint_id()
+            );
+            return;
+        };
+        if t.result.is_some() {
+            return;
+        }
+
+        let code = e.completion_code();
+        if let Some(d) = t.data_trbs.iter().find(|d| d.addr == e.trb_pointer()) {
+            t.transferred = d.offset + d.len.saturating_sub(e.trb_transfer_length() as usize);
+        }
+        if let Err(err) = check_completion(code) {
+            t.result = Some(Err(err));
+        } else if e.trb_pointer() == t.last_trb
+            || (t.ends_on_short && matches!(code, Ok(CompletionCode::ShortPacket)))
+        {
+            // the controller skips the rest of the TD after a short packet
+            t.result = Some(Ok(t.transferred));
+        }
+    }
+
+    /// Queues the TRBs of a transfer on endpoint `dci` and rings its doorbell.
+    ///
+    /// The TRBs moving data come with the part of the buffer they cover. The
+    /// last TRB must have its IOC flag set.
+    fn submit(
+        &mut self,
+        slot: u8,
+        dci: u8,
+        buffer: DMAVec<A, u8>,
+        trbs: &[(ring::TrbData, Option<(usize, usize)>)],
+        ends_on_short: bool,
+    ) -> DevResult {
+        let ep = self.endpoint_mut(slot, dci)?;
+        if ep.transfer.is_some() {
+            return Err(DevError::ResourceBusy);
+        }
+
+        let mut data_trbs = Vec::new();
+        let mut last_trb = 0;
+        for (trb, data) in trbs {
+            last_trb = ep.ring.enqueue(*trb);
+            if let Some((offset, len)) = *data {
+                data_trbs.push(DataTrb {
+                    addr: last_trb,
+                    offset,
+                    len,
+                });
+            }
+        }
+        let transferred = data_trbs.iter().map(|d| d.len).sum();
+        ep.transfer = Some(Transfer {
+            buffer,
+            data_trbs,
+            ends_on_short,
+            last_trb,
+            transferred,
+            result: None,
+        });
+        self.ring_doorbell(slot, dci);
+        Ok(())
+    }
+
+    /// Takes the transfer queued on endpoint `dci` out if it has completed.
+    ///
+    /// The endpoint is recovered if the transfer failed, so it can take new
+    /// transfers.
+    fn take_completed(&mut self, slot: u8, dci: u8) -> DevResult<Option<Transfer<A>>> {
+        let ep = self.endpoint_mut(slot, dci)?;
+        let t = match &ep.transfer {
+            Some(t) if t.result.is_some() => ep.transfer.take().unwrap(),
+            Some(_) => return Ok(None),
+            None => return Err(DevError::BadState),
+        };
+        if matches!(t.result, Some(Err(_))) {
+            if let Err(e) = self.recover_endpoint(slot, dci) {
+                warn!("xhci slot {} dci {}: recovery failed: {:?}", slot, dci, e);
+            }
+        }
+        Ok(Some(t))
+    }
+
+    /// Polls the events until the transfer queued on endpoint `dci`
+    /// completes, letting the other tasks run in between.
+    ///
+    /// The transfer is cancelled if it times out.
+    fn wait_transfer(&mut self, slot: u8, dci: u8, data: &mut [u8]) -> DevResult<usize> {
+        let deadline = axhal::time::current_time() + TIMEOUT;
+        loop {
+            if let Some(t) = self.take_completed(slot, dci)? {
+                return finish_transfer(t, data);
+            }
+            if self.event.has_next() {
+                self.handle_event();
+                continue;
+            }
+            if axhal::time::current_time() > deadline {
+                warn!("xhci transfer timeout: slot {} dci {}", slot, dci);
+                let t = self.endpoint_mut(slot, dci)?.transfer.take();
+                if let Err(e) = self.recover_endpoint(slot, dci) {
+                    warn!("xhci slot {} dci {}: recovery failed: {:?}", slot, dci, e);
+                    // the controller may still write into the buffer
+                    core::mem::forget(t);
+                }
+                return Err(DevError::Io);
+            }
+            axtask::yield_now();
+        }
+    }
+
+    /// Makes endpoint `dci` usable again after a transfer on it failed or was
+    /// abandoned: a halted endpoint (e.g., after a STALL) is reset, a running
+    /// one is stopped, then its dequeue pointer is moved past the TRBs of the
+    /// transfer (xHCI spec, 4.6.8 and 4.6.9).
+    fn recover_endpoint(&mut self, slot: u8, dci: u8) -> DevResult {
+        if matches!(self.endpoint_state(slot, dci)?, EndpointState::Running) {
+            let mut trb = command::StopEndpoint::new();
+            trb.set_endpoint_id(dci).set_slot_id(slot);
+            // fails if the endpoint has halted in the meantime
+            if let Err(e) = self.post_command(command::Allowed::StopEndpoint(trb)) {
+                debug!("xhci slot {} dci {}: stop failed: {:?}", slot, dci, e);
+            }
+        }
+        if matches!(self.endpoint_state(slot, dci)?, EndpointState::Halted) {
+            let mut trb = command::ResetEndpoint::new();
+            trb.set_endpoint_id(dci).set_slot_id(slot);
+            self.post_command(command::Allowed::ResetEndpoint(trb))?;
+        }
+
+        let ring = &self.endpoint_mut(slot, dci)?.ring;
+        let mut trb = command::SetTrDequeuePointer::new();
+        trb.set_new_tr_dequeue_pointer(ring.enqueue_ptr())
+            .set_endpoint_id(dci)
+            .set_slot_id(slot);
+        if ring.cycle() {
+            trb.set_dequeue_cycle_state();
+        }
+        self.post_command(command::Allowed::SetTrDequeuePointer(trb))?;
+        debug!("xhci slot {} dci {} recovered", slot, dci);
+        Ok(())
+    }
+
+    fn endpoint_state(&mut self, slot: u8, dci: u8) -> DevResult<EndpointState> {
+        let s = self.slot_mut(slot)?;
+        Ok(s.output.handler().endpoint(dci as usize).endpoint_state())
+    }
+
+    fn endpoint_mut(&mut self, slot: u8, dci: u8) -> DevResult<&mut Endpoint<A>> {
+        self.slot_mut(slot)?
+            .endpoints
+            .get_mut(dci as usize)
+            .and_then(|ep| ep.as_mut())
+            .ok_or(DevError::InvalidParam)
+    }
+
+    fn slot_mut(&mut self, slot: u8) -> DevResult<&mut Slot<A>> {
+        self.slots
+            .get_mut(slot as usize)
+            .and_then(|s| s.as_mut())
+            .ok_or(DevError::InvalidParam)
+    }
+}
+
+impl<A: Allocator + Clone + Sync + Send> USBHostDriverOps for Xhci<A> {
+    fn info(&self) -> USBHostInfo {
+        self.info.clone()
+    }
+
+    fn port_speed(&self, port: u8) -> Option<PortSpeed> {
+        if port == 0 || port > self.info.max_ports {
+            return None;
+        }
+        let portsc = self
+            .regs
+            .port_register_set
+            .read_volatile_at(port as usize - 1)
+            .portsc;
+        if !portsc.current_connect_status() {
+            return None;
+        }
+        PortSpeed::from_psiv(portsc.port_speed())
+    }
+
+    fn reset_port(&mut self, port: u8) -> DevResult<PortSpeed> {
+        if port == 0 || port > self.info.max_ports {
+            return Err(DevError::InvalidParam);
+        }
+        let i = port as usize - 1;
+        let ports = &mut self.regs.port_register_set;
+        ports.update_volatile_at(i, |p| {
+            p.portsc.set_port_reset();
+        });
+        wait_until(|| {
+            let portsc = ports.read_volatile_at(i).portsc;
+            !portsc.port_reset() && portsc.port_enabled_disabled()
+        })?;
+        ports.update_volatile_at(i, |p| {
+            p.portsc.clear_port_reset_change();
+            p.portsc.clear_connect_status_change();
+        });
+
+        let speed = ports.read_volatile_at(i).portsc.port_speed();
+        debug!("xhci port {} reset, speed {}", port, speed);
+        PortSpeed::from_psiv(speed).ok_or(DevError::Unsupported)
+    }
+
+    fn enable_slot(&mut self) -> DevResult<u8> {
+        let c = self.post_command(command::Allowed::EnableSlot(command::EnableSlot::new()))?;
+        let slot = c.slot_id();
+        debug!("xhci slot {} enabled", slot);
+        Ok(slot)
+    }
+
+    fn address_device(&mut self, slot: u8, route: DeviceRoute, speed: PortSpeed) -> DevResult {
+        if slot == 0 || slot > self.info.max_slots {
+            return Err(DevError::InvalidParam);
+        }
+        let mut input = InputContext::new(self.csz, self.alloc.clone());
+        let output = DeviceContext::new(self.csz, self.alloc.clone());
+        let ring = Ring::new(RING_SIZE, self.alloc.clone());
+
+        let ctx = input.handler_mut();
+        let control = ctx.control_mut();
+        control.set_add_context_flag(0);
+        control.set_add_context_flag(1);
+
+        let slot_ctx = ctx.device_mut().slot_mut();
+        slot_ctx.set_root_hub_port_number(route.root_port);
+        slot_ctx.set_route_string(route.route_string);
+        slot_ctx.set_context_entries(1);
+        slot_ctx.set_speed(speed.psiv());
+        if let Some((hub_slot, hub_port)) = route.tt {
+            slot_ctx.set_parent_hub_slot_id(hub_slot);
+            slot_ctx.set_parent_port_number(hub_port);
+        }
+
+        let ep0 = ctx.device_mut().endpoint_mut(1);
+        ep0.set_endpoint_type(EndpointType::Control);
+        ep0.set_max_packet_size(speed.default_max_packet_size());
+        ep0.set_max_burst_size(0);
+        ep0.set_error_count(3);
+        ep0.set_average_trb_length(8);
+        ep0.set_tr_dequeue_pointer(ring.register());
+        if ring.cycle() {
+            ep0.set_dequeue_cycle_state();
+        }
+
+        unsafe { (&mut self.dcbaa[slot as usize] as *mut u64).write_volatile(output.bus_addr()) };
+
+        let mut trb = command::AddressDevice::new();
+        trb.set_input_context_pointer(input.bus_addr())
+            .set_slot_id(slot);
+        let mut endpoints = Vec::new();
+        endpoints.resize_with(32, || None);
+        endpoints[1] = Some(Endpoint {
+            ring,
+            transfer: None,
+        });
+        self.slots[slot as usize] = Some(Slot {
+            speed,
+            input,
+            output,
+            endpoints,
+        });
+
+        if let Err(e) = self.post_command(command::Allowed::AddressDevice(trb)) {
+            self.slots[slot as usize] = None;
+            return Err(e);
+        }
+        let address = self
+            .slot_mut(slot)?
+            .output
+            .handler()
+            .slot()
+            .usb_device_address();
+        debug!("xhci slot {} addressed: {}", slot, address);
+        Ok(())
+    }
+
+    fn disable_slot(&mut self, slot: u8) -> DevResult {
+        self.slot_mut(slot)?;
+        let mut trb = command::DisableSlot::new();
+        trb.set_slot_id(slot);
+        let res = self.post_command(command::Allowed::DisableSlot(trb));
+
+        unsafe { (&mut self.dcbaa[slot as usize] as *mut u64).write_volatile(0) };
+        self.slots[slot as usize] = None;
+        debug!("xhci slot {} disabled", slot);
+        res.map(|_| ())
+    }
+
+    fn update_max_packet_size(&mut self, slot: u8, max_packet_size: u16) -> DevResult {
+        let s = self.slot_mut(slot)?;
+        let ctx = s.input.handler_mut();
+        let control = ctx.control_mut();
+        control.clear_add_context_flag(0);
+        control.set_add_context_flag(1);
+        ctx.device_mut()
+            .endpoint_mut(1)
+            .set_max_packet_size(max_packet_size);
+
+        let mut trb = command::EvaluateContext::new();
+        trb.set_input_context_pointer(s.input.bus_addr())
+            .set_slot_id(slot);
+        self.post_command(command::Allowed::EvaluateContext(trb))?;
+        debug!("xhci slot {} max packet size: {}", slot, max_packet_size);
+        Ok(())
+    }
+
+    fn control_transfer(
+        &mut self,
+        slot: u8,
+        setup: SetupPacket,
+        data: &mut [u8],
+    ) -> DevResult<usize> {
+        let len = data.len();
+        let dir_in = setup.is_device_to_host();
+        // aligned to its size, so the single data stage TRB does not cross a
+        // 64 KiB boundary (wLength is at most 64 KiB)
+        let align = len.next_power_of_two().clamp(64, TRB_BUFFER_BOUNDARY);
+        let mut buffer: DMAVec<A, u8> = DMAVec::new(len.max(1), align, self.alloc.clone());
+        if !dir_in {
+            buffer[..len].copy_from_slice(data);
+        }
+
+        let mut setup_trb = transfer::SetupStage::new();
+        setup_trb
+            .set_request_type(setup.request_type)
+            .set_request(setup.request)
+            .set_value(setup.value)
+            .set_index(setup.index)
+            .set_length(len as u16)
+            .set_transfer_type(if len == 0 {
+                TransferType::No
+            } else if dir_in {
+                TransferType::In
+            } else {
+                TransferType::Out
+            });
+        let mut trbs = Vec::with_capacity(3);
+        trbs.push((setup_trb.into_raw(), None));
+
+        if len > 0 {
+            let mut data_trb = transfer::DataStage::new();
+            data_trb
+                .set_data_buffer_pointer(buffer.bus_addr())
+                .set_trb_transfer_length(len as u32)
+                .set_direction(if dir_in {
+                    Direction::In
+                } else {
+                    Direction::Out
+                })
+                .set_interrupt_on_short_packet();
+            trbs.push((data_trb.into_raw(), Some((0, len))));
+        }
+
+        // The status stage runs in the opposite direction of the data stage.
+        let mut status_trb = transfer::StatusStage::new();
+        status_trb.set_interrupt_on_completion();
+        let mut status_trb = status_trb.into_raw();
+        if len == 0 || !dir_in {
+            status_trb[3] |= STATUS_STAGE_DIR_IN;
+        }
+        trbs.push((status_trb, None));
+
+        self.submit(slot, 1, buffer, &trbs, false)?;
+        self.wait_transfer(slot, 1, data)
+    }
+
+    fn configure_endpoints(&mut self, slot: u8, endpoints: &[EndpointDescriptor]) -> DevResult {
+        let alloc = self.alloc.clone();
+        let s = self.slot_mut(slot)?;
+        let speed = s.speed;
+        let ctx = s.input.handler_mut();
+        let control = ctx.control_mut();
+        for i in 0..32 {
+            control.clear_add_context_flag(i);
+        }
+        control.set_add_context_flag(0);
+
+        let mut context_entries = ctx.device_mut().slot_mut().context_entries();
+        for desc in endpoints {
+            let dci = desc.dci();
+            let ring = Ring::new(RING_SIZE, alloc.clone());
+            ctx.control_mut().set_add_context_flag(dci as usize);
+            context_entries = context_entries.max(dci);
+
+            let ep = ctx.device_mut().endpoint_mut(dci as usize);
+            ep.set_endpoint_type(endpoint_type(desc));
+            ep.set_max_packet_size(desc.max_packet_size & 0x7ff);
+            ep.set_max_burst_size(((desc.max_packet_size >> 11) & 0x3) as u8);
+            ep.set_interval(endpoint_interval(speed, desc));
+            ep.set_error_count(
+                if desc.transfer_type() == EndpointTransferType::Isochronous {
+                    0
+                } else {
+                    3
+                },
+            );
+            ep.set_average_trb_length(if desc.transfer_type() == EndpointTransferType::Bulk {
+                3072
+            } else {
+                desc.max_packet_size & 0x7ff
+            });
+            ep.set_tr_dequeue_pointer(ring.register());
+            if ring.cycle() {
+                ep.set_dequeue_cycle_state();
+            } else {
+                ep.clear_dequeue_cycle_state();
+            }
+
+            s.endpoints[dci as usize] = Some(Endpoint {
+                ring,
+                transfer: None,
+            });
+        }
+        ctx.device_mut()
+            .slot_mut()
+            .set_context_entries(context_entries);
+
+        let mut trb = command::ConfigureEndpoint::new();
+        trb.set_input_context_pointer(s.input.bus_addr())
+            .set_slot_id(slot);
+        self.post_command(command::Allowed::ConfigureEndpoint(trb))?;
+        debug!(
+            "xhci slot {}: {} endpoints configured",
+            slot,
+            endpoints.len()
+        );
+        Ok(())
+    }
+
+    fn submit_transfer(&mut self, slot: u8, endpoint: u8, len: usize) -> DevResult {
+        if len > MAX_TRANSFER_SIZE {
+            return Err(DevError::InvalidParam);
+        }
+        let buffer = DMAVec::new(len.max(1), 64, self.alloc.clone());
+        let trbs = normal_trbs(&buffer, len);
+        self.submit(slot, endpoint_dci(endpoint), buffer, &trbs, true)
+    }
+
+    fn poll_transfer(
+        &mut self,
+        slot: u8,
+        endpoint: u8,
+        data: &mut [u8],
+    ) -> Option<DevResult<usize>> {
+        while self.event.has_next() {
+            self.handle_event();
+        }
+        match self.take_completed(slot, endpoint_dci(endpoint)) {
+            Ok(Some(t)) => Some(finish_transfer(t, data)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    fn transfer(&mut self, slot: u8, endpoint: u8, data: &mut [u8]) -> DevResult<usize> {
+        let len = data.len();
+        if len > MAX_TRANSFER_SIZE {
+            return Err(DevError::InvalidParam);
+        }
+        let mut buffer: DMAVec<A, u8> = DMAVec::new(len.max(1), 64, self.alloc.clone());
+        if endpoint & 0x80 == 0 {
+            buffer[..len].copy_from_slice(data);
+        }
+        let trbs = normal_trbs(&buffer, len);
+        let dci = endpoint_dci(endpoint);
+        self.submit(slot, dci, buffer, &trbs, true)?;
+        self.wait_transfer(slot, dci, data)
+    }
+
+    fn clear_halt(&mut self, slot: u8, endpoint: u8) -> DevResult {
+        let setup = SetupPacket {
+            request_type: REQ_TYPE_STANDARD_ENDPOINT_OUT,
+            request: REQ_CLEAR_FEATURE,
+            value: FEATURE_ENDPOINT_HALT,
+            index: endpoint as u16,
+        };
+        self.control_transfer(slot, setup, &mut [])?;
+        self.recover_endpoint(slot, endpoint_dci(endpoint))
+    }
+
+    fn configure_hub(
+        &mut self,
+        slot: u8,
+        num_ports: u8,
+        tt_think_time: u8,
+        multi_tt: bool,
+    ) -> DevResult {
+        let s = self.slot_mut(slot)?;
+        let speed = s.speed;
+        let ctx = s.input.handler_mut();
+        let control = ctx.control_mut();
+        for i in 0..32 {
+            control.clear_add_context_flag(i);
+        }
+        control.set_add_context_flag(0);
+
+        let slot_ctx = ctx.device_mut().slot_mut();
+        slot_ctx.set_hub();
+        slot_ctx.set_number_of_ports(num_ports);
+        if speed == PortSpeed::High {
+            slot_ctx.set_tt_think_time(tt_think_time);
+            if multi_tt {
+                slot_ctx.set_multi_tt();
+            }
+        }
+
+        let mut trb = command::ConfigureEndpoint::new();
+        trb.set_input_context_pointer(s.input.bus_addr())
+            .set_slot_id(slot);
+        self.post_command(command::Allowed::ConfigureEndpoint(trb))?;
+        debug!("xhci slot {}: hub with {} ports", slot, num_ports);
+        Ok(())
+    }
+
+    fn poll_port_change(&mut self) -> Option<u8> {
+        while self.event.has_next() {
+            self.handle_event();
+        }
+        while !self.port_changes.is_empty() {
+            let port = self.port_changes.remove(0);
+            if port == 0 || port > self.info.max_ports {
+                continue;
+            }
+            // Changes caused by resets during enumeration have already been
+            // acknowledged.
+            let i = port as usize - 1;
+            let ports = &mut self.regs.port_register_set;
+            if ports.read_volatile_at(i).portsc.connect_status_change() {
+                ports.update_volatile_at(i, |p| {
+                    p.portsc.clear_connect_status_change();
+                });
+                return Some(port);
+            }
+        }
+        None
+    }
+
+    fn poll_hotplug(&mut self) -> Vec<HotplugEvent> {
+        let mut events = Vec::new();
+        let mut bus = core::mem::take(&mut self.bus);
+        bus.poll(self, &mut |e| events.push(e));
+        if !events.is_empty() {
+            self.info.devices = bus.devices().to_vec();
+        }
+        self.bus = bus;
+        events
+    }
+}
+
+/// Copies the data of a completed IN transfer into `data`.
+fn finish_transfer<A: Allocator>(t: Transfer<A>, data: &mut [u8]) -> DevResult<usize> {
+    let transferred = t.result.unwrap_or(Err(DevError::BadState))?;
+    let n = transferred.min(data.len());
+    data[..n].copy_from_slice(&t.buffer[..n]);
+    Ok(transferred)
+}
+
+/// Builds a TD of chained Normal TRBs for the first `len` bytes of `buffer`,
+/// split at the 64 KiB boundaries. Each TRB comes with the part of the
+/// buffer it covers.
+fn normal_trbs<A: Allocator>(
+    buffer: &DMAVec<A, u8>,
+    len: usize,
+) -> Vec<(ring::TrbData, Option<(usize, usize)>)> {
+    let base = buffer.bus_addr() as usize;
+    let mut trbs = Vec::new();
+    let mut offset = 0;
+    loop {
+        let addr = base + offset;
+        let boundary = (addr / TRB_BUFFER_BOUNDARY + 1) * TRB_BUFFER_BOUNDARY;
+        let n = (len - offset).min(boundary - addr);
+        let mut trb = transfer::Normal::new();
+        trb.set_data_buffer_pointer(addr as u64)
+            .set_trb_transfer_length(n as u32)
+            .set_interrupt_on_short_packet();
+        offset += n;
+        if offset == len {
+            trb.set_interrupt_on_completion();
+            trbs.push((trb.into_raw(), Some((offset - n, n))));
+            return trbs;
+        }
+        trb.set_chain_bit();
+        trbs.push((trb.into_raw(), Some((offset - n, n))));
+    }
+}
+
+/// Converts an endpoint address into its Device Context Index.
+fn endpoint_dci(endpoint: u8) -> u8 {
+    (endpoint & 0x0f) * 2 + (endpoint >> 7)
+}
+
+fn endpoint_type(desc: &EndpointDescriptor) -> EndpointType {
+    match (desc.transfer_type(), desc.is_in()) {
+        (EndpointTransferType::Control, _) => EndpointType::Control,
+        (EndpointTransferType::Isochronous, true) => EndpointType::IsochIn,
+        (EndpointTransferType::Isochronous, false) => EndpointType::IsochOut,
+        (EndpointTransferType::Bulk, true) => EndpointType::BulkIn,
+        (EndpointTransferType::Bulk, false) => EndpointType::BulkOut,
+        (EndpointTransferType::Interrupt, true) => EndpointType::InterruptIn,
+        (EndpointTransferType::Interrupt, false) => EndpointType::InterruptOut,
+    }
+}
+
+/// Converts `bInterval` into the xHCI interval, an exponent of 125us.
+fn endpoint_interval(speed: PortSpeed, desc: &EndpointDescriptor) -> u8 {
+    match (speed, desc.transfer_type()) {
+        (_, EndpointTransferType::Control | EndpointTransferType::Bulk) => 0,
+        // Full/low speed interrupt endpoints give the interval in frames (1ms).
+        (PortSpeed::Full | PortSpeed::Low, EndpointTransferType::Interrupt) => {
+            let microframes = (desc.interval.max(1) as u32) * 8;
+            (31 - microframes.leading_zeros()) as u8
+        }
+        (PortSpeed::Full, EndpointTransferType::Isochronous) => desc.interval.clamp(1, 16) + 2,
+        _ => desc.interval.clamp(1, 16) - 1,
+    }
+}
+
+fn check_completion(code: Result<CompletionCode, u8>) -> DevResult {
+    match code {
+        Ok(CompletionCode::Success) | Ok(CompletionCode::ShortPacket) => Ok(()),
+        Ok(CompletionCode::NoSlotsAvailableError) => Err(DevError::NoMemory),
+        Ok(code) => {
+            warn!("xhci completion: {:?}", code);
+            Err(DevError::Io)
+        }
+        Err(code) => {
+            warn!("xhci unknown completion code: {}", code);
+            Err(DevError::Io)
+        }
+    }
+}
+
+fn wait_until(mut cond: impl FnMut() -> bool) -> DevResult {
+    let deadline = axhal::time::current_time() + TIMEOUT;
+    while !cond() {
+        if axhal::time::current_time() > deadline {
+            return Err(DevError::Io);
+        }
+        axtask::yield_now();
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use core::{alloc::Allocator, mem::size_of};
+
+use xhci::ring::trb::Link;
+
+use crate::dma::DMAVec;
+
+/// Raw representation of a Transfer Request Block.
+pub type TrbData = [u32; 4];
+
+const TRB_SIZE: usize = size_of::<TrbData>();
+
+/// A producer ring (command ring or transfer ring).
+///
+/// The last slot is reserved for a Link TRB which points back to the start of
+/// the segment and toggles the cycle bit, so the ring can be reused forever.
+pub struct Ring<A: Allocator> {
+    trbs: DMAVec<A, TrbData>,
+    i: usize,
+    cycle: bool,
+}
+
+impl<A: Allocator> Ring<A> {
+    pub fn new(len: usize, alloc: A) -> Self {
+        Self {
+            trbs: DMAVec::new(len, 64, alloc),
+            i: 0,
+            cycle: true,
+        }
+    }
+
+    /// The bus address of the first TRB, used to program CRCR or the TR
+    /// dequeue pointer of an endpoint context.
+    pub fn register(&self) -> u64 {
+        self.trbs.bus_addr()
+    }
+
+    /// The producer cycle state the consumer should start with.
+    pub fn cycle(&self) -> bool {
+        self.cycle
+    }
+
+    /// The bus address where the next TRB will be placed, used to move the
+    /// dequeue pointer of an endpoint past abandoned TRBs.
+    pub fn enqueue_ptr(&self) -> u64 {
+        self.trb_addr(self.i)
+    }
+
+    /// Places a TRB on the ring and returns its bus address, which is used to
+    /// match the completion event.
+    pub fn enqueue(&mut self, trb: TrbData) -> u64 {
+        let addr = self.trb_addr(self.i);
+        self.write(self.i, trb);
+        self.i += 1;
+
+        if self.i == self.trbs.len() - 1 {
+            let mut link = Link::new();
+            link.set_ring_segment_pointer(self.register())
+                .set_toggle_cycle();
+            self.write(self.i, link.into_raw());
+            self.i = 0;
+            self.cycle = !self.cycle;
+        }
+        addr
+    }
+
+    fn write(&mut self, i: usize, mut trb: TrbData) {
+        if self.cycle {
+            trb[3] |= 1;
+        } else {
+            trb[3] &= !1;
+        }
+        // Write the cycle bit last, the controller may be reading the ring.
+        let dst = &mut self.trbs[i];
+        unsafe {
+            for (j, dw) in trb.iter().enumerate().take(3) {
+                (&mut dst[j] as *mut u32).write_volatile(*dw);
+            }
+            (&mut dst[3] as *mut u32).write_volatile(trb[3]);
+        }
+    }
+
+    fn trb_addr(&self, i: usize) -> u64 {
+        self.register() + (i * TRB_SIZE) as u64
+    }
+}
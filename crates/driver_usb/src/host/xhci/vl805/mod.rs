@@ -1,24 +1,25 @@
-use core::{
-    alloc::{Allocator, Layout},
-    ptr::slice_from_raw_parts_mut,
-};
+use core::alloc::Allocator;
 mod mailbox;
 use self::mailbox::*;
-use super::MemoryMapper;
-use crate::dma::DMAVec;
+use super::{DeviceRoute, PortSpeed, SetupPacket, USBHostInfo, Xhci};
 pub use crate::host::USBHostDriverOps;
+use crate::{
+    dma::DMAVec,
+    usb::{descriptors::EndpointDescriptor, HotplugEvent},
+};
+use alloc::vec::Vec;
 use driver_common::*;
 use driver_pci::{
     types::{Bar, ConfigCommand, ConfigKind, ConfigSpace},
     PciAddress,
 };
-use log::{debug, info};
+use log::{debug, info, warn};
 
 const VL805_VENDOR_ID: u16 = 0x1106;
 const VL805_DEVICE_ID: u16 = 0x3483;
 
 pub struct VL805<A: Allocator + Clone> {
-    alloc: A,
+    xhci: Xhci<A>,
 }
 
 impl<A: Allocator + Clone + Sync + Send> BaseDriverOps for VL805<A> {
@@ -31,30 +32,90 @@ impl<A: Allocator + Clone + Sync + Send> BaseDriverOps for VL805<A> {
     }
 }
 
-impl<A: Allocator + Clone> VL805<A> {
-    fn new(mmio_base: usize, alloc: A) -> Self {
-        let mapper = MemoryMapper;
-        let regs: xhci::Registers<MemoryMapper> =
-            unsafe { xhci::Registers::new(mmio_base, mapper) };
-        let version = regs.capability.hciversion.read_volatile();
-        debug!("xhci version: {:x}", version.get());
-        let mut o = regs.operational;
-        debug!("xhci stat: {:?}", o.usbsts.read_volatile());
+impl<A: Allocator + Clone + Sync + Send> USBHostDriverOps for VL805<A> {
+    fn info(&self) -> USBHostInfo {
+        self.xhci.info()
+    }
+
+    fn port_speed(&self, port: u8) -> Option<PortSpeed> {
+        self.xhci.port_speed(port)
+    }
 
-        debug!("xhci wait for ready...");
-        while o.usbsts.read_volatile().controller_not_ready() {}
-        info!("xhci ok");
+    fn reset_port(&mut self, port: u8) -> DevResult<PortSpeed> {
+        self.xhci.reset_port(port)
+    }
 
-        o.usbcmd.update_volatile(|f| {
-            f.set_host_controller_reset();
-        });
+    fn enable_slot(&mut self) -> DevResult<u8> {
+        self.xhci.enable_slot()
+    }
 
-        while o.usbcmd.read_volatile().host_controller_reset() {}
+    fn address_device(&mut self, slot: u8, route: DeviceRoute, speed: PortSpeed) -> DevResult {
+        self.xhci.address_device(slot, route, speed)
+    }
 
-        info!("XHCI reset HC");
+    fn disable_slot(&mut self, slot: u8) -> DevResult {
+        self.xhci.disable_slot(slot)
+    }
+
+    fn update_max_packet_size(&mut self, slot: u8, max_packet_size: u16) -> DevResult {
+        self.xhci.update_max_packet_size(slot, max_packet_size)
+    }
+
+    fn control_transfer(
+        &mut self,
+        slot: u8,
+        setup: SetupPacket,
+        data: &mut [u8],
+    ) -> DevResult<usize> {
+        self.xhci.control_transfer(slot, setup, data)
+    }
+
+    fn configure_endpoints(&mut self, slot: u8, endpoints: &[EndpointDescriptor]) -> DevResult {
+        self.xhci.configure_endpoints(slot, endpoints)
+    }
 
-        VL805 { alloc }
+    fn transfer(&mut self, slot: u8, endpoint: u8, data: &mut [u8]) -> DevResult<usize> {
+        self.xhci.transfer(slot, endpoint, data)
     }
+
+    fn submit_transfer(&mut self, slot: u8, endpoint: u8, len: usize) -> DevResult {
+        self.xhci.submit_transfer(slot, endpoint, len)
+    }
+
+    fn poll_transfer(
+        &mut self,
+        slot: u8,
+        endpoint: u8,
+        data: &mut [u8],
+    ) -> Option<DevResult<usize>> {
+        self.xhci.poll_transfer(slot, endpoint, data)
+    }
+
+    fn clear_halt(&mut self, slot: u8, endpoint: u8) -> DevResult {
+        self.xhci.clear_halt(slot, endpoint)
+    }
+
+    fn configure_hub(
+        &mut self,
+        slot: u8,
+        num_ports: u8,
+        tt_think_time: u8,
+        multi_tt: bool,
+    ) -> DevResult {
+        self.xhci
+            .configure_hub(slot, num_ports, tt_think_time, multi_tt)
+    }
+
+    fn poll_port_change(&mut self) -> Option<u8> {
+        self.xhci.poll_port_change()
+    }
+
+    fn poll_hotplug(&mut self) -> Vec<HotplugEvent> {
+        self.xhci.poll_hotplug()
+    }
+}
+
+impl<A: Allocator + Clone + Sync + Send> VL805<A> {
     pub fn probe_pci(config: &ConfigSpace, dma_alloc: A) -> Option<Self> {
         let (vendor_id, device_id) = config.header.vendor_id_and_device_id();
         if !(vendor_id == VL805_VENDOR_ID && device_id == VL805_DEVICE_ID) {
@@ -81,8 +142,16 @@ impl<A: Allocator + Clone> VL805<A> {
                     ConfigCommand::ParityErrorResponse,
                     ConfigCommand::SERREnable,
                 ]);
-                let vl805 = VL805::new(address as _, dma_alloc);
-                return Some(vl805);
+                return match Xhci::new(address as _, dma_alloc) {
+                    Ok(mut xhci) => {
+                        xhci.enumerate();
+                        Some(VL805 { xhci })
+                    }
+                    Err(e) => {
+                        warn!("VL805 init failed: {:?}", e);
+                        None
+                    }
+                };
             }
         }
 
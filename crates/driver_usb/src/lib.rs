@@ -6,7 +6,9 @@
 
 extern crate alloc;
 pub(crate) mod dma;
+pub mod class;
 pub mod host;
+pub mod usb;
 use core::alloc::Allocator;
 
 #[doc(no_inline)]
@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+use driver_common::DevResult;
+use log::{debug, info, warn};
+
+use super::{attach_device, UsbDevice};
+use crate::class::hub::Hub;
+use crate::host::{DeviceRoute, USBHostDriverOps};
+
+/// A change of the devices attached to a host controller.
+#[derive(Debug, Clone)]
+pub enum HotplugEvent {
+    Attached(UsbDevice),
+    Detached(UsbDevice),
+}
+
+/// The devices attached to a host controller, and the hubs they are reached
+/// through.
+#[derive(Default)]
+pub struct UsbBus {
+    devices: Vec<UsbDevice>,
+    hubs: Vec<Hub>,
+}
+
+impl UsbBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The devices currently attached, in attachment order.
+    pub fn devices(&self) -> &[UsbDevice] {
+        &self.devices
+    }
+
+    /// Resets every connected root hub port, then addresses and configures
+    /// the device behind it, and the devices behind it if it is a hub.
+    ///
+    /// The USB address itself is assigned by the host controller (on xHCI the
+    /// SET_ADDRESS request is issued by the Address Device command).
+    pub fn enumerate(&mut self, host: &mut dyn USBHostDriverOps) {
+        for port in 1..=host.info().max_ports {
+            if host.port_speed(port).is_some() {
+                self.attach(host, None, port, &mut |_| {});
+            }
+        }
+    }
+
+    /// Handles the connection changes of the root hub ports and of the hub
+    /// ports, passing the attached and detached devices to `f`.
+    pub fn poll(&mut self, host: &mut dyn USBHostDriverOps, f: &mut dyn FnMut(HotplugEvent)) {
+        while let Some(port) = host.poll_port_change() {
+            self.port_changed(host, None, port, f);
+        }
+
+        let mut changes = Vec::new();
+        for hub in self.hubs.iter_mut() {
+            match hub.poll_changes(host) {
+                Ok(bitmap) if bitmap.any() => changes.push((hub.slot(), hub.num_ports(), bitmap)),
+                Ok(_) => {}
+                Err(e) => warn!("usb hub slot {}: {:?}", hub.slot(), e),
+            }
+        }
+        for (hub, num_ports, bitmap) in changes {
+            for port in 1..=num_ports {
+                // The hub may have been detached by a previous change.
+                if bitmap.is_set(port) && self.hub(hub).is_some() {
+                    self.port_changed(host, Some(hub), port, f);
+                }
+            }
+        }
+    }
+
+    fn hub(&self, slot: u8) -> Option<&Hub> {
+        self.hubs.iter().find(|h| h.slot() == slot)
+    }
+
+    /// Handles a change of `port` of the hub `parent` (`None` for the root
+    /// hub).
+    fn port_changed(
+        &mut self,
+        host: &mut dyn USBHostDriverOps,
+        parent: Option<u8>,
+        port: u8,
+        f: &mut dyn FnMut(HotplugEvent),
+    ) {
+        let connected = match parent {
+            None => host.port_speed(port).is_some(),
+            Some(slot) => {
+                let Some(hub) = self.hub(slot) else {
+                    return;
+                };
+                match Self::hub_port_status(hub, host, port) {
+                    Ok(Some(connected)) => connected,
+                    Ok(None) => return,
+                    Err(e) => {
+                        warn!("usb hub slot {} port {}: {:?}", slot, port, e);
+                        return;
+                    }
+                }
+            }
+        };
+
+        let old = self
+            .devices
+            .iter()
+            .find(|d| d.parent == parent && d.port == port)
+            .map(|d| d.slot);
+        if let Some(slot) = old {
+            self.detach(host, slot, f);
+        }
+        if connected {
+            self.attach(host, parent, port, f);
+        }
+    }
+
+    /// Reads and acknowledges the status of a hub port, returns whether a
+    /// device is connected if the connection has changed.
+    fn hub_port_status(
+        hub: &Hub,
+        host: &mut dyn USBHostDriverOps,
+        port: u8,
+    ) -> DevResult<Option<bool>> {
+        let status = hub.port_status(host, port)?;
+        hub.clear_changes(host, port, &status)?;
+        Ok(status.connection_changed().then_some(status.connected()))
+    }
+
+    fn attach(
+        &mut self,
+        host: &mut dyn USBHostDriverOps,
+        parent: Option<u8>,
+        port: u8,
+        f: &mut dyn FnMut(HotplugEvent),
+    ) {
+        let res = match parent {
+            None => host
+                .reset_port(port)
+                .map(|speed| (DeviceRoute::root(port), speed)),
+            Some(slot) => {
+                let Some(hub) = self.hub(slot) else {
+                    return;
+                };
+                hub.reset_port(host, port).map(|speed| {
+                    let route = hub.route().child(slot, hub.speed(), port, speed);
+                    (route, speed)
+                })
+            }
+        };
+        let dev =
+            match res.and_then(|(route, speed)| attach_device(host, route, parent, port, speed)) {
+                Ok(dev) => dev,
+                Err(e) => {
+                    warn!(
+                        "usb port {:?}.{}: enumeration failed: {:?}",
+                        parent, port, e
+                    );
+                    return;
+                }
+            };
+        info!("usb device attached: {:?}", dev);
+        self.devices.push(dev.clone());
+        f(HotplugEvent::Attached(dev.clone()));
+
+        if let Some(hub) = Hub::probe(host, &dev) {
+            let (slot, num_ports) = (hub.slot(), hub.num_ports());
+            self.hubs.push(hub);
+            for port in 1..=num_ports {
+                let hub = self.hub(slot).unwrap();
+                match Self::hub_port_status(hub, host, port) {
+                    Ok(Some(true)) => self.attach(host, Some(slot), port, f),
+                    Ok(_) => {}
+                    Err(e) => debug!("usb hub slot {} port {}: {:?}", slot, port, e),
+                }
+            }
+        }
+    }
+
+    /// Removes the device in `slot`, and every device behind it if it is a
+    /// hub.
+    fn detach(
+        &mut self,
+        host: &mut dyn USBHostDriverOps,
+        slot: u8,
+        f: &mut dyn FnMut(HotplugEvent),
+    ) {
+        let children: Vec<u8> = self
+            .devices
+            .iter()
+            .filter(|d| d.parent == Some(slot))
+            .map(|d| d.slot)
+            .collect();
+        for child in children {
+            self.detach(host, child, f);
+        }
+        self.hubs.retain(|h| h.slot() != slot);
+
+        let Some(i) = self.devices.iter().position(|d| d.slot == slot) else {
+            return;
+        };
+        let dev = self.devices.remove(i);
+        if let Err(e) = host.disable_slot(slot) {
+            warn!("usb slot {}: disable failed: {:?}", slot, e);
+        }
+        info!("usb device detached: {:?}", dev);
+        f(HotplugEvent::Detached(dev));
+    }
+}
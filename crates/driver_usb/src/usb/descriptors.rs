@@ -0,0 +1,242 @@
+//! Standard USB descriptors (USB 2.0 spec, chapter 9.6).
+
+use alloc::vec::Vec;
+
+pub const DESC_DEVICE: u8 = 0x01;
+pub const DESC_CONFIGURATION: u8 = 0x02;
+pub const DESC_STRING: u8 = 0x03;
+pub const DESC_INTERFACE: u8 = 0x04;
+pub const DESC_ENDPOINT: u8 = 0x05;
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Checks that `buf` starts with a descriptor of type `ty` at least `len`
+/// bytes long.
+fn check_header(buf: &[u8], ty: u8, len: usize) -> Option<()> {
+    if buf.len() < len || (buf[0] as usize) < len || buf[1] != ty {
+        return None;
+    }
+    Some(())
+}
+
+/// The device descriptor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceDescriptor {
+    /// USB specification release number in BCD (e.g. 0x0200).
+    pub usb_version: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub protocol: u8,
+    /// Max packet size of endpoint 0. For SuperSpeed devices this is the
+    /// exponent of a power of two.
+    pub max_packet_size0: u8,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Device release number in BCD.
+    pub device_version: u16,
+    pub manufacturer_index: u8,
+    pub product_index: u8,
+    pub serial_number_index: u8,
+    pub num_configurations: u8,
+}
+
+impl DeviceDescriptor {
+    pub const SIZE: usize = 18;
+
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        check_header(buf, DESC_DEVICE, Self::SIZE)?;
+        Some(Self {
+            usb_version: read_u16(buf, 2),
+            class: buf[4],
+            subclass: buf[5],
+            protocol: buf[6],
+            max_packet_size0: buf[7],
+            vendor_id: read_u16(buf, 8),
+            product_id: read_u16(buf, 10),
+            device_version: read_u16(buf, 12),
+            manufacturer_index: buf[14],
+            product_index: buf[15],
+            serial_number_index: buf[16],
+            num_configurations: buf[17],
+        })
+    }
+}
+
+/// The configuration descriptor, without the descriptors that follow it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfigurationDescriptor {
+    /// Total length of the configuration, including all the interface and
+    /// endpoint descriptors.
+    pub total_length: u16,
+    pub num_interfaces: u8,
+    /// The value passed to SET_CONFIGURATION to select this configuration.
+    pub configuration_value: u8,
+    pub configuration_index: u8,
+    pub attributes: u8,
+    /// Maximum power consumption in 2mA units.
+    pub max_power: u8,
+}
+
+impl ConfigurationDescriptor {
+    pub const SIZE: usize = 9;
+
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        check_header(buf, DESC_CONFIGURATION, Self::SIZE)?;
+        Some(Self {
+            total_length: read_u16(buf, 2),
+            num_interfaces: buf[4],
+            configuration_value: buf[5],
+            configuration_index: buf[6],
+            attributes: buf[7],
+            max_power: buf[8],
+        })
+    }
+}
+
+/// The interface descriptor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InterfaceDescriptor {
+    pub interface_number: u8,
+    pub alternate_setting: u8,
+    pub num_endpoints: u8,
+    pub class: u8,
+    pub subclass: u8,
+    pub protocol: u8,
+    pub interface_index: u8,
+}
+
+impl InterfaceDescriptor {
+    pub const SIZE: usize = 9;
+
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        check_header(buf, DESC_INTERFACE, Self::SIZE)?;
+        Some(Self {
+            interface_number: buf[2],
+            alternate_setting: buf[3],
+            num_endpoints: buf[4],
+            class: buf[5],
+            subclass: buf[6],
+            protocol: buf[7],
+            interface_index: buf[8],
+        })
+    }
+}
+
+/// Transfer type of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndpointTransferType {
+    #[default]
+    Control,
+    Isochronous,
+    Bulk,
+    Interrupt,
+}
+
+/// The endpoint descriptor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EndpointDescriptor {
+    /// Endpoint number in bits 0..4, direction in bit 7 (1 = IN).
+    pub address: u8,
+    pub attributes: u8,
+    pub max_packet_size: u16,
+    /// Polling interval, encoded according to the device speed.
+    pub interval: u8,
+}
+
+impl EndpointDescriptor {
+    pub const SIZE: usize = 7;
+
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        check_header(buf, DESC_ENDPOINT, Self::SIZE)?;
+        Some(Self {
+            address: buf[2],
+            attributes: buf[3],
+            max_packet_size: read_u16(buf, 4),
+            interval: buf[6],
+        })
+    }
+
+    /// The endpoint number, without the direction bit.
+    pub fn number(&self) -> u8 {
+        self.address & 0x0f
+    }
+
+    /// Whether data flows from the device to the host.
+    pub fn is_in(&self) -> bool {
+        self.address & 0x80 != 0
+    }
+
+    pub fn transfer_type(&self) -> EndpointTransferType {
+        match self.attributes & 0x3 {
+            0 => EndpointTransferType::Control,
+            1 => EndpointTransferType::Isochronous,
+            2 => EndpointTransferType::Bulk,
+            _ => EndpointTransferType::Interrupt,
+        }
+    }
+
+    /// The xHCI Device Context Index of this endpoint.
+    pub fn dci(&self) -> u8 {
+        self.number() * 2 + self.is_in() as u8
+    }
+}
+
+/// An interface together with its endpoints.
+#[derive(Debug, Clone, Default)]
+pub struct Interface {
+    pub descriptor: InterfaceDescriptor,
+    pub endpoints: Vec<EndpointDescriptor>,
+    /// Class-specific descriptors (e.g. the HID descriptor) that follow the
+    /// interface descriptor, as raw bytes.
+    pub class_descriptors: Vec<Vec<u8>>,
+}
+
+/// A full configuration: the configuration descriptor and every interface
+/// and endpoint descriptor returned with it.
+#[derive(Debug, Clone, Default)]
+pub struct Configuration {
+    pub descriptor: ConfigurationDescriptor,
+    pub interfaces: Vec<Interface>,
+}
+
+impl Configuration {
+    /// Parses the whole buffer returned by GET_DESCRIPTOR(CONFIGURATION).
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        let descriptor = ConfigurationDescriptor::parse(buf)?;
+        let buf = &buf[..buf.len().min(descriptor.total_length as usize)];
+        let mut config = Self {
+            descriptor,
+            interfaces: Vec::new(),
+        };
+
+        let mut offset = buf[0] as usize;
+        while offset + 2 <= buf.len() {
+            let len = buf[offset] as usize;
+            if len < 2 || offset + len > buf.len() {
+                break;
+            }
+            let desc = &buf[offset..offset + len];
+            match desc[1] {
+                DESC_INTERFACE => config.interfaces.push(Interface {
+                    descriptor: InterfaceDescriptor::parse(desc)?,
+                    endpoints: Vec::new(),
+                    class_descriptors: Vec::new(),
+                }),
+                DESC_ENDPOINT => {
+                    if let Some(interface) = config.interfaces.last_mut() {
+                        interface.endpoints.push(EndpointDescriptor::parse(desc)?);
+                    }
+                }
+                _ => {
+                    if let Some(interface) = config.interfaces.last_mut() {
+                        interface.class_descriptors.push(desc.to_vec());
+                    }
+                }
+            }
+            offset += len;
+        }
+        Some(config)
+    }
+}
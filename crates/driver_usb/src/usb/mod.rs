@@ -0,0 +1,216 @@
+//! USB core: device enumeration on top of [`USBHostDriverOps`].
+
+mod bus;
+pub mod descriptors;
+
+use core::fmt;
+use driver_common::{DevError, DevResult};
+
+pub use self::bus::{HotplugEvent, UsbBus};
+use self::descriptors::*;
+use crate::host::{DeviceRoute, PortSpeed, SetupPacket, USBHostDriverOps};
+
+pub const REQ_GET_STATUS: u8 = 0x00;
+pub const REQ_CLEAR_FEATURE: u8 = 0x01;
+pub const REQ_SET_FEATURE: u8 = 0x03;
+pub const REQ_SET_ADDRESS: u8 = 0x05;
+pub const REQ_GET_DESCRIPTOR: u8 = 0x06;
+pub const REQ_GET_CONFIGURATION: u8 = 0x08;
+pub const REQ_SET_CONFIGURATION: u8 = 0x09;
+pub const REQ_SET_INTERFACE: u8 = 0x0b;
+
+/// `bmRequestType`: standard request to the device, host to device.
+pub const REQ_TYPE_STANDARD_OUT: u8 = 0x00;
+/// `bmRequestType`: standard request to the device, device to host.
+pub const REQ_TYPE_STANDARD_IN: u8 = 0x80;
+/// `bmRequestType`: standard request to an endpoint, host to device.
+pub const REQ_TYPE_STANDARD_ENDPOINT_OUT: u8 = 0x02;
+
+/// Feature selector of CLEAR_FEATURE for an endpoint.
+pub const FEATURE_ENDPOINT_HALT: u16 = 0;
+
+/// A device attached to the bus, after it has been addressed and configured.
+#[derive(Clone)]
+pub struct UsbDevice {
+    /// The host controller slot assigned to the device.
+    pub slot: u8,
+    pub route: DeviceRoute,
+    /// The slot of the hub the device is attached to, `None` for the root
+    /// hub.
+    pub parent: Option<u8>,
+    /// The port of the parent hub the device is attached to.
+    pub port: u8,
+    pub speed: PortSpeed,
+    pub descriptor: DeviceDescriptor,
+    /// The active configuration.
+    pub configuration: Configuration,
+}
+
+impl UsbDevice {
+    /// Iterates over the interfaces of the active configuration.
+    pub fn interfaces(&self) -> impl Iterator<Item = &Interface> {
+        self.configuration.interfaces.iter()
+    }
+}
+
+impl fmt::Debug for UsbDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "port {}.{:x} slot {}: {:04x}:{:04x} {:?} speed, class {:02x}.{:02x}",
+            self.route.root_port,
+            self.route.route_string,
+            self.slot,
+            self.descriptor.vendor_id,
+            self.descriptor.product_id,
+            self.speed,
+            self.descriptor.class,
+            self.descriptor.subclass,
+        )
+    }
+}
+
+/// What a class driver binds to.
+#[derive(Debug, Clone, Copy)]
+pub enum DeviceMatch {
+    /// A specific product.
+    VendorProduct { vendor_id: u16, product_id: u16 },
+    /// Any interface with the given class, optionally narrowed down by
+    /// subclass and protocol.
+    InterfaceClass {
+        class: u8,
+        subclass: Option<u8>,
+        protocol: Option<u8>,
+    },
+}
+
+impl DeviceMatch {
+    /// Returns the first interface of `dev` matched by this rule.
+    pub fn find<'a>(&self, dev: &'a UsbDevice) -> Option<&'a Interface> {
+        match *self {
+            Self::VendorProduct {
+                vendor_id,
+                product_id,
+            } => {
+                if dev.descriptor.vendor_id == vendor_id && dev.descriptor.product_id == product_id
+                {
+                    dev.interfaces().next()
+                } else {
+                    None
+                }
+            }
+            Self::InterfaceClass {
+                class,
+                subclass,
+                protocol,
+            } => dev.interfaces().find(|i| {
+                let d = &i.descriptor;
+                d.class == class
+                    && subclass.map_or(true, |s| d.subclass == s)
+                    && protocol.map_or(true, |p| d.protocol == p)
+            }),
+        }
+    }
+}
+
+/// Issues GET_DESCRIPTOR on the default control endpoint.
+pub fn get_descriptor<H: USBHostDriverOps + ?Sized>(
+    host: &mut H,
+    slot: u8,
+    ty: u8,
+    index: u8,
+    buf: &mut [u8],
+) -> DevResult<usize> {
+    host.control_transfer(
+        slot,
+        SetupPacket {
+            request_type: REQ_TYPE_STANDARD_IN,
+            request: REQ_GET_DESCRIPTOR,
+            value: ((ty as u16) << 8) | index as u16,
+            index: 0,
+        },
+        buf,
+    )
+}
+
+/// Issues SET_CONFIGURATION on the default control endpoint.
+pub fn set_configuration<H: USBHostDriverOps + ?Sized>(
+    host: &mut H,
+    slot: u8,
+    value: u8,
+) -> DevResult {
+    host.control_transfer(
+        slot,
+        SetupPacket {
+            request_type: REQ_TYPE_STANDARD_OUT,
+            request: REQ_SET_CONFIGURATION,
+            value: value as u16,
+            index: 0,
+        },
+        &mut [],
+    )
+    .map(|_| ())
+}
+
+/// Addresses and configures a device whose port has just been reset.
+fn attach_device(
+    host: &mut dyn USBHostDriverOps,
+    route: DeviceRoute,
+    parent: Option<u8>,
+    port: u8,
+    speed: PortSpeed,
+) -> DevResult<UsbDevice> {
+    let slot = host.enable_slot()?;
+    let (descriptor, configuration) = match configure_device(host, slot, route, speed) {
+        Ok(res) => res,
+        Err(e) => {
+            host.disable_slot(slot).ok();
+            return Err(e);
+        }
+    };
+    Ok(UsbDevice {
+        slot,
+        route,
+        parent,
+        port,
+        speed,
+        descriptor,
+        configuration,
+    })
+}
+
+fn configure_device(
+    host: &mut dyn USBHostDriverOps,
+    slot: u8,
+    route: DeviceRoute,
+    speed: PortSpeed,
+) -> DevResult<(DeviceDescriptor, Configuration)> {
+    host.address_device(slot, route, speed)?;
+
+    // Only the first 8 bytes can be read before the real max packet size of
+    // the control endpoint is known.
+    let mut buf = [0u8; DeviceDescriptor::SIZE];
+    get_descriptor(host, slot, DESC_DEVICE, 0, &mut buf[..8])?;
+    let max_packet_size = match speed {
+        PortSpeed::Super | PortSpeed::SuperPlus => 1u16 << buf[7],
+        _ => buf[7] as u16,
+    };
+    if max_packet_size != speed.default_max_packet_size() {
+        host.update_max_packet_size(slot, max_packet_size)?;
+    }
+
+    get_descriptor(host, slot, DESC_DEVICE, 0, &mut buf)?;
+    let descriptor = DeviceDescriptor::parse(&buf).ok_or(DevError::Io)?;
+
+    let mut header = [0u8; ConfigurationDescriptor::SIZE];
+    get_descriptor(host, slot, DESC_CONFIGURATION, 0, &mut header)?;
+    let total_length = ConfigurationDescriptor::parse(&header)
+        .ok_or(DevError::Io)?
+        .total_length;
+    let mut buf = alloc::vec![0u8; total_length as usize];
+    let len = get_descriptor(host, slot, DESC_CONFIGURATION, 0, &mut buf)?;
+    let configuration = Configuration::parse(&buf[..len]).ok_or(DevError::Io)?;
+
+    set_configuration(host, slot, configuration.descriptor.configuration_value)?;
+    Ok((descriptor, configuration))
+}
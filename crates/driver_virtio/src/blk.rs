@@ -1,11 +1,16 @@
 use crate::as_dev_err;
 use driver_block::BlockDriverOps;
-use driver_common::{BaseDriverOps, DevResult, DeviceType};
-use virtio_drivers::{device::blk::VirtIOBlk as InnerDev, transport::Transport, Hal};
+use driver_common::{BaseDriverOps, DevResult, DeviceIrq, DeviceType};
+use virtio_drivers::device::blk::{BlkReq, BlkResp, VirtIOBlk as InnerDev};
+use virtio_drivers::{transport::Transport, Hal};
 
 /// The VirtIO block device driver.
+///
+/// If the interrupt line is given, the current task sleeps until the request
+/// completes. Otherwise, it busy-waits.
 pub struct VirtIoBlkDev<H: Hal, T: Transport> {
     inner: InnerDev<H, T>,
+    irq: Option<DeviceIrq>,
 }
 
 unsafe impl<H: Hal, T: Transport> Send for VirtIoBlkDev<H, T> {}
@@ -14,11 +19,20 @@ unsafe impl<H: Hal, T: Transport> Sync for VirtIoBlkDev<H, T> {}
 impl<H: Hal, T: Transport> VirtIoBlkDev<H, T> {
     /// Creates a new driver instance and initializes the device, or returns
     /// an error if any step fails.
-    pub fn try_new(transport: T) -> DevResult<Self> {
+    pub fn try_new(transport: T, irq: Option<DeviceIrq>) -> DevResult<Self> {
         Ok(Self {
             inner: InnerDev::new(transport).map_err(as_dev_err)?,
+            irq,
         })
     }
+
+    /// Sleeps until the request identified by `token` completes.
+    fn wait_for(&mut self, irq: DeviceIrq, token: u16) {
+        irq.wait_until(|| {
+            self.inner.ack_interrupt();
+            self.inner.peek_used() == Some(token)
+        });
+    }
 }
 
 impl<H: Hal, T: Transport> const BaseDriverOps for VirtIoBlkDev<H, T> {
@@ -29,6 +43,10 @@ impl<H: Hal, T: Transport> const BaseDriverOps for VirtIoBlkDev<H, T> {
     fn device_type(&self) -> DeviceType {
         DeviceType::Block
     }
+
+    fn irq(&self) -> Option<DeviceIrq> {
+        self.irq
+    }
 }
 
 impl<H: Hal, T: Transport> BlockDriverOps for VirtIoBlkDev<H, T> {
@@ -43,15 +61,47 @@ impl<H: Hal, T: Transport> BlockDriverOps for VirtIoBlkDev<H, T> {
     }
 
     fn read_block(&mut self, block_id: u64, buf: &mut [u8]) -> DevResult {
-        self.inner
-            .read_blocks(block_id as _, buf)
-            .map_err(as_dev_err)
+        let Some(irq) = self.irq else {
+            return self
+                .inner
+                .read_blocks(block_id as _, buf)
+                .map_err(as_dev_err);
+        };
+        let mut req = BlkReq::default();
+        let mut resp = BlkResp::default();
+        // Safe because `req`, `buf` and `resp` live until the request completes.
+        unsafe {
+            let token = self
+                .inner
+                .read_blocks_nb(block_id as _, &mut req, buf, &mut resp)
+                .map_err(as_dev_err)?;
+            self.wait_for(irq, token);
+            self.inner
+                .complete_read_blocks(token, &req, buf, &mut resp)
+                .map_err(as_dev_err)
+        }
     }
 
     fn write_block(&mut self, block_id: u64, buf: &[u8]) -> DevResult {
-        self.inner
-            .write_blocks(block_id as _, buf)
-            .map_err(as_dev_err)
+        let Some(irq) = self.irq else {
+            return self
+                .inner
+                .write_blocks(block_id as _, buf)
+                .map_err(as_dev_err);
+        };
+        let mut req = BlkReq::default();
+        let mut resp = BlkResp::default();
+        // Safe because `req`, `buf` and `resp` live until the request completes.
+        unsafe {
+            let token = self
+                .inner
+                .write_blocks_nb(block_id as _, &mut req, buf, &mut resp)
+                .map_err(as_dev_err)?;
+            self.wait_for(irq, token);
+            self.inner
+                .complete_write_blocks(token, &req, buf, &mut resp)
+                .map_err(as_dev_err)
+        }
     }
 
     fn flush(&mut self) -> DevResult {
@@ -1,6 +1,6 @@
 use crate::as_dev_err;
 use alloc::{sync::Arc, vec::Vec};
-use driver_common::{BaseDriverOps, DevError, DevResult, DeviceType};
+use driver_common::{BaseDriverOps, DevError, DevResult, DeviceIrq, DeviceType};
 use driver_net::{EthernetAddress, NetBuf, NetBufBox, NetBufPool, NetBufPtr, NetDriverOps};
 use virtio_drivers::{device::net::VirtIONetRaw as InnerDev, transport::Transport, Hal};
 
@@ -17,6 +17,7 @@ pub struct VirtIoNetDev<H: Hal, T: Transport, const QS: usize> {
     free_tx_bufs: Vec<NetBufBox>,
     buf_pool: Arc<NetBufPool>,
     inner: InnerDev<H, T, QS>,
+    irq: Option<DeviceIrq>,
 }
 
 unsafe impl<H: Hal, T: Transport, const QS: usize> Send for VirtIoNetDev<H, T, QS> {}
@@ -25,7 +26,12 @@ unsafe impl<H: Hal, T: Transport, const QS: usize> Sync for VirtIoNetDev<H, T, Q
 impl<H: Hal, T: Transport, const QS: usize> VirtIoNetDev<H, T, QS> {
     /// Creates a new driver instance and initializes the device, or returns
     /// an error if any step fails.
-    pub fn try_new(transport: T) -> DevResult<Self> {
+    ///
+    /// If the interrupt line is given, the device raises interrupts on packet
+    /// reception and transmission completion, which are acknowledged in
+    /// [`NetDriverOps::recycle_tx_buffers`]. The caller should call it before
+    /// [`NetDriverOps::receive`].
+    pub fn try_new(transport: T, irq: Option<DeviceIrq>) -> DevResult<Self> {
         // 0. Create a new driver instance.
         const NONE_BUF: Option<NetBufBox> = None;
         let inner = InnerDev::new(transport).map_err(as_dev_err)?;
@@ -40,6 +46,7 @@ impl<H: Hal, T: Transport, const QS: usize> VirtIoNetDev<H, T, QS> {
             tx_buffers,
             free_tx_bufs,
             buf_pool,
+            irq,
         };
 
         // 1. Fill all rx buffers.
@@ -80,6 +87,10 @@ impl<H: Hal, T: Transport, const QS: usize> const BaseDriverOps for VirtIoNetDev
     fn device_type(&self) -> DeviceType {
         DeviceType::Net
     }
+
+    fn irq(&self) -> Option<DeviceIrq> {
+        self.irq
+    }
 }
 
 impl<H: Hal, T: Transport, const QS: usize> NetDriverOps for VirtIoNetDev<H, T, QS> {
@@ -127,6 +138,10 @@ impl<H: Hal, T: Transport, const QS: usize> NetDriverOps for VirtIoNetDev<H, T,
     }
 
     fn recycle_tx_buffers(&mut self) -> DevResult {
+        if self.irq.is_some() {
+            // acknowledge before checking the queues, so no event is missed
+            self.inner.ack_interrupt();
+        }
         while let Some(token) = self.inner.poll_transmit() {
             let tx_buf = self.tx_buffers[token as usize]
                 .take()
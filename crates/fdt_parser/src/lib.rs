@@ -0,0 +1,518 @@
+//! A minimal, allocation-free parser of flattened device trees (FDT), the
+//! device tree blob (DTB) passed by the bootloader.
+//!
+//! It only supports what a kernel needs at boot: walking the nodes, reading
+//! properties, and decoding the `reg` property. Addresses are not translated
+//! through the `ranges` of parent buses, i.e., identity mappings are assumed.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use fdt_parser::Fdt;
+//!
+//! # let dtb: usize = 0;
+//! let fdt = unsafe { Fdt::from_ptr(dtb as *const u8) }.unwrap();
+//! for bank in fdt.memory() {
+//!     println!("memory: {:#x} ({:#x} bytes)", bank.address, bank.size);
+//! }
+//! for node in fdt.find_compatible(&["virtio,mmio"]) {
+//!     println!("{}: {:x?}", node.name(), node.reg().next());
+//! }
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+
+use core::fmt;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_HEADER_SIZE: usize = 40;
+const FDT_LAST_COMP_VERSION: u32 = 16;
+
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+/// The maximum node depth whose `#address-cells` and `#size-cells` are
+/// tracked. Deeper nodes use the values of this depth.
+const MAX_DEPTH: usize = 16;
+
+/// Default `#address-cells` and `#size-cells`, as defined by the
+/// specification.
+const DEFAULT_CELLS: (u32, u32) = (2, 1);
+
+/// The error type of FDT parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdtError {
+    /// The blob does not start with the FDT magic number.
+    BadMagic,
+    /// The blob version is not compatible with version 17.
+    BadVersion,
+    /// The blob is shorter than its header claims, or the header offsets
+    /// point outside of the blob.
+    Truncated,
+}
+
+impl fmt::Display for FdtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "bad FDT magic"),
+            Self::BadVersion => write!(f, "unsupported FDT version"),
+            Self::Truncated => write!(f, "truncated FDT"),
+        }
+    }
+}
+
+/// A range of physical addresses decoded from a `reg` property or the
+/// memory reservation block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// The start address.
+    pub address: usize,
+    /// The size in bytes. It is 0 if `#size-cells` is 0.
+    pub size: usize,
+}
+
+/// A parsed flattened device tree.
+#[derive(Clone, Copy)]
+pub struct Fdt<'a> {
+    data: &'a [u8],
+    struct_block: &'a [u8],
+    strings_block: &'a [u8],
+    mem_rsvmap: &'a [u8],
+}
+
+impl<'a> Fdt<'a> {
+    /// Parses the device tree in the given byte slice.
+    ///
+    /// The slice may be longer than the blob.
+    pub fn new(data: &'a [u8]) -> Result<Self, FdtError> {
+        let header = |index: usize| read_u32(data, index * 4).ok_or(FdtError::Truncated);
+        if header(0)? != FDT_MAGIC {
+            return Err(FdtError::BadMagic);
+        }
+        let total_size = header(1)? as usize;
+        if total_size < FDT_HEADER_SIZE || total_size > data.len() {
+            return Err(FdtError::Truncated);
+        }
+        if header(6)? > FDT_LAST_COMP_VERSION {
+            return Err(FdtError::BadVersion);
+        }
+        let data = &data[..total_size];
+        let block = |offset: u32, size: Option<u32>| {
+            let offset = offset as usize;
+            let end = match size {
+                Some(size) => offset.checked_add(size as usize),
+                None => Some(total_size),
+            };
+            match end {
+                Some(end) if offset <= end && end <= total_size => Ok(&data[offset..end]),
+                _ => Err(FdtError::Truncated),
+            }
+        };
+        Ok(Self {
+            data,
+            struct_block: block(header(2)?, Some(header(9)?))?,
+            strings_block: block(header(3)?, Some(header(8)?))?,
+            mem_rsvmap: block(header(4)?, None)?,
+        })
+    }
+
+    /// Parses the device tree at the given address.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be readable for at least [`Fdt::HEADER_SIZE`] bytes, and for
+    /// the total size in the header if the magic number matches. The memory
+    /// must stay valid and unmodified for the lifetime `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Result<Self, FdtError> {
+        let header = core::slice::from_raw_parts(ptr, FDT_HEADER_SIZE);
+        if read_u32(header, 0) != Some(FDT_MAGIC) {
+            return Err(FdtError::BadMagic);
+        }
+        let total_size = read_u32(header, 4).unwrap() as usize;
+        Self::new(core::slice::from_raw_parts(
+            ptr,
+            total_size.max(FDT_HEADER_SIZE),
+        ))
+    }
+
+    /// The size of the FDT header, the minimum number of readable bytes
+    /// required by [`Fdt::from_ptr`].
+    pub const HEADER_SIZE: usize = FDT_HEADER_SIZE;
+
+    /// Returns the total size in bytes of the blob.
+    pub fn total_size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the physical address of the boot CPU.
+    pub fn boot_cpuid(&self) -> u32 {
+        read_u32(self.data, 28).unwrap_or(0)
+    }
+
+    /// Returns an iterator over the entries of the memory reservation block.
+    pub fn memory_reservations(&self) -> impl Iterator<Item = Region> + 'a {
+        let rsvmap = self.mem_rsvmap;
+        (0..)
+            .map(move |i| {
+                let address = read_u64(rsvmap, i * 16)?;
+                let size = read_u64(rsvmap, i * 16 + 8)?;
+                Some(Region {
+                    address: address as usize,
+                    size: size as usize,
+                })
+            })
+            .take_while(|r| matches!(r, Some(r) if r.address != 0 || r.size != 0))
+            .flatten()
+    }
+
+    /// Returns an iterator over all nodes, in depth-first order.
+    pub fn nodes(&self) -> NodeIter<'a> {
+        NodeIter {
+            fdt: *self,
+            offset: 0,
+            depth: 0,
+            cells: [DEFAULT_CELLS; MAX_DEPTH + 1],
+        }
+    }
+
+    /// Returns the root node.
+    pub fn root(&self) -> Option<Node<'a>> {
+        self.nodes().next()
+    }
+
+    /// Returns the node at the given absolute path (e.g., `/chosen`). Unit
+    /// addresses may be omitted from the path.
+    pub fn find_node(&self, path: &str) -> Option<Node<'a>> {
+        let mut nodes = self.nodes();
+        let mut node = nodes.next()?;
+        for want in path.split('/').filter(|c| !c.is_empty()) {
+            let depth = node.depth();
+            node = nodes
+                .by_ref()
+                .take_while(|n| n.depth() > depth)
+                .find(|n| n.depth() == depth + 1 && node_name_matches(n.name(), want))?;
+        }
+        Some(node)
+    }
+
+    /// Returns an iterator over the nodes that are compatible with any of the
+    /// given strings.
+    pub fn find_compatible<'b>(
+        &self,
+        compatible: &'b [&'b str],
+    ) -> impl Iterator<Item = Node<'a>> + 'b
+    where
+        'a: 'b,
+    {
+        self.nodes()
+            .filter(move |node| compatible.iter().any(|c| node.is_compatible(c)))
+    }
+
+    /// Returns an iterator over the memory banks, i.e., the `reg` entries of
+    /// all nodes with `device_type = "memory"`.
+    pub fn memory(&self) -> impl Iterator<Item = Region> + 'a {
+        self.nodes()
+            .filter(|node| node.property_str("device_type") == Some("memory"))
+            .filter(Node::is_available)
+            .flat_map(|node| node.reg())
+    }
+
+    /// Returns an iterator over the static reserved memory regions, i.e., the
+    /// `reg` entries of the children of `/reserved-memory`.
+    pub fn reserved_memory(&self) -> impl Iterator<Item = Region> + 'a {
+        let mut inside = false;
+        self.nodes()
+            .filter(move |node| match node.depth() {
+                1 => {
+                    inside = node.name() == "reserved-memory";
+                    false
+                }
+                2 => inside,
+                _ => false,
+            })
+            .flat_map(|node| node.reg())
+    }
+
+    /// Returns an iterator over the interrupt controllers, i.e., the nodes
+    /// with the `interrupt-controller` property.
+    pub fn interrupt_controllers(&self) -> impl Iterator<Item = Node<'a>> + 'a {
+        self.nodes()
+            .filter(|node| node.property("interrupt-controller").is_some())
+    }
+
+    /// Returns the `bootargs` property of `/chosen`, if any.
+    pub fn bootargs(&self) -> Option<&'a str> {
+        self.find_node("/chosen")?.property_str("bootargs")
+    }
+
+    fn string_at(&self, offset: usize) -> Option<&'a str> {
+        read_cstr(self.strings_block, offset)
+    }
+}
+
+impl fmt::Debug for Fdt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fdt")
+            .field("total_size", &self.total_size())
+            .field("model", &self.root().and_then(|r| r.property_str("model")))
+            .finish()
+    }
+}
+
+/// A device tree node.
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    fdt: Fdt<'a>,
+    name: &'a str,
+    depth: usize,
+    /// Offset of the first token after the node name.
+    props_offset: usize,
+    /// `#address-cells` and `#size-cells` of the parent node.
+    cells: (u32, u32),
+}
+
+impl<'a> Node<'a> {
+    /// Returns the node name, including the unit address (e.g.,
+    /// `virtio_mmio@a000000`). The root node has an empty name.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the depth of the node. The root node has depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns an iterator over the properties of the node.
+    pub fn properties(&self) -> PropIter<'a> {
+        PropIter {
+            fdt: self.fdt,
+            offset: self.props_offset,
+        }
+    }
+
+    /// Returns the value of the given property, if present.
+    pub fn property(&self, name: &str) -> Option<&'a [u8]> {
+        self.properties().find(|p| p.name == name).map(|p| p.value)
+    }
+
+    /// Returns the value of the given property as a string.
+    pub fn property_str(&self, name: &str) -> Option<&'a str> {
+        read_cstr(self.property(name)?, 0)
+    }
+
+    /// Returns the value of the given property as a `u32`.
+    pub fn property_u32(&self, name: &str) -> Option<u32> {
+        read_u32(self.property(name)?, 0)
+    }
+
+    /// Returns an iterator over the strings of the `compatible` property.
+    pub fn compatible(&self) -> impl Iterator<Item = &'a str> {
+        self.property("compatible")
+            .unwrap_or_default()
+            .split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| core::str::from_utf8(s).ok())
+    }
+
+    /// Whether the `compatible` property contains the given string.
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible().any(|c| c == compatible)
+    }
+
+    /// Whether the device is available, i.e., its `status` property is
+    /// absent, `"okay"` or `"ok"`.
+    pub fn is_available(&self) -> bool {
+        matches!(
+            self.property_str("status"),
+            None | Some("okay") | Some("ok")
+        )
+    }
+
+    /// Returns an iterator over the entries of the `reg` property, decoded
+    /// with the `#address-cells` and `#size-cells` of the parent node.
+    pub fn reg(&self) -> RegIter<'a> {
+        RegIter {
+            data: self.property("reg").unwrap_or_default(),
+            address_cells: self.cells.0 as usize,
+            size_cells: self.cells.1 as usize,
+        }
+    }
+
+    /// Returns an iterator over the cells of the `interrupts` property.
+    ///
+    /// The number of cells per interrupt depends on the interrupt parent, see
+    /// its `#interrupt-cells` property.
+    pub fn interrupts(&self) -> impl Iterator<Item = u32> + 'a {
+        let data = self.property("interrupts").unwrap_or_default();
+        (0..data.len() / 4).filter_map(move |i| read_u32(data, i * 4))
+    }
+}
+
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("name", &self.name)
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+/// A property of a device tree node.
+#[derive(Debug, Clone, Copy)]
+pub struct Property<'a> {
+    /// The property name.
+    pub name: &'a str,
+    /// The raw (big-endian) property value.
+    pub value: &'a [u8],
+}
+
+/// An iterator over all nodes of a device tree, returned by [`Fdt::nodes`].
+pub struct NodeIter<'a> {
+    fdt: Fdt<'a>,
+    offset: usize,
+    depth: usize,
+    /// `cells[d]` is the `#address-cells` and `#size-cells` that apply to the
+    /// nodes at depth `d`.
+    cells: [(u32, u32); MAX_DEPTH + 1],
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let block = self.fdt.struct_block;
+        loop {
+            let token = read_u32(block, self.offset)?;
+            self.offset += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let name = read_cstr(block, self.offset)?;
+                    self.offset = align4(self.offset + name.len() + 1);
+                    let depth = self.depth;
+                    self.depth += 1;
+                    self.cells[self.depth.min(MAX_DEPTH)] = DEFAULT_CELLS;
+                    return Some(Node {
+                        fdt: self.fdt,
+                        name,
+                        depth,
+                        props_offset: self.offset,
+                        cells: self.cells[depth.min(MAX_DEPTH)],
+                    });
+                }
+                FDT_PROP => {
+                    let len = read_u32(block, self.offset)? as usize;
+                    let name = self
+                        .fdt
+                        .string_at(read_u32(block, self.offset + 4)? as usize)?;
+                    let value = block.get(self.offset + 8..self.offset + 8 + len)?;
+                    self.offset = align4(self.offset + 8 + len);
+                    let cells = &mut self.cells[self.depth.min(MAX_DEPTH)];
+                    match name {
+                        "#address-cells" => cells.0 = read_u32(value, 0)?,
+                        "#size-cells" => cells.1 = read_u32(value, 0)?,
+                        _ => {}
+                    }
+                }
+                FDT_END_NODE => self.depth = self.depth.checked_sub(1)?,
+                FDT_NOP => {}
+                FDT_END => return None,
+                _ => return None, // malformed
+            }
+        }
+    }
+}
+
+/// An iterator over the properties of a node, returned by
+/// [`Node::properties`].
+pub struct PropIter<'a> {
+    fdt: Fdt<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for PropIter<'a> {
+    type Item = Property<'a>;
+
+    fn next(&mut self) -> Option<Property<'a>> {
+        let block = self.fdt.struct_block;
+        loop {
+            match read_u32(block, self.offset)? {
+                FDT_PROP => {
+                    let len = read_u32(block, self.offset + 4)? as usize;
+                    let name = self
+                        .fdt
+                        .string_at(read_u32(block, self.offset + 8)? as usize)?;
+                    let value = block.get(self.offset + 12..self.offset + 12 + len)?;
+                    self.offset = align4(self.offset + 12 + len);
+                    return Some(Property { name, value });
+                }
+                FDT_NOP => self.offset += 4,
+                _ => return None, // properties always precede child nodes
+            }
+        }
+    }
+}
+
+/// An iterator over the entries of a `reg` property, returned by
+/// [`Node::reg`].
+pub struct RegIter<'a> {
+    data: &'a [u8],
+    address_cells: usize,
+    size_cells: usize,
+}
+
+impl Iterator for RegIter<'_> {
+    type Item = Region;
+
+    fn next(&mut self) -> Option<Region> {
+        let entry_len = (self.address_cells + self.size_cells) * 4;
+        if entry_len == 0 || self.data.len() < entry_len {
+            return None;
+        }
+        let (entry, rest) = self.data.split_at(entry_len);
+        self.data = rest;
+        let (address, size) = entry.split_at(self.address_cells * 4);
+        Some(Region {
+            address: read_cells(address) as usize,
+            size: read_cells(size) as usize,
+        })
+    }
+}
+
+fn node_name_matches(name: &str, want: &str) -> bool {
+    name == want || (!want.contains('@') && name.split('@').next() == Some(want))
+}
+
+const fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?;
+    Some(u64::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a big-endian number of one or more cells. Only the lowest two cells
+/// are kept.
+fn read_cells(data: &[u8]) -> u64 {
+    data.chunks_exact(4).fold(0, |acc, c| {
+        (acc << 32) | u32::from_be_bytes(c.try_into().unwrap()) as u64
+    })
+}
+
+fn read_cstr(data: &[u8], offset: usize) -> Option<&str> {
+    let bytes = data.get(offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    core::str::from_utf8(&bytes[..len]).ok()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,223 @@
+use super::*;
+
+/// Builds a device tree blob from a sequence of structure tokens.
+struct Builder {
+    structs: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl Builder {
+    fn new() -> Self {
+        Self {
+            structs: Vec::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    fn token(&mut self, token: u32) -> &mut Self {
+        self.structs.extend_from_slice(&token.to_be_bytes());
+        self
+    }
+
+    fn begin(&mut self, name: &str) -> &mut Self {
+        self.token(FDT_BEGIN_NODE);
+        self.structs.extend_from_slice(name.as_bytes());
+        self.structs.push(0);
+        self.structs.resize(align4(self.structs.len()), 0);
+        self
+    }
+
+    fn end(&mut self) -> &mut Self {
+        self.token(FDT_END_NODE)
+    }
+
+    fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        let nameoff = self.strings.len() as u32;
+        self.strings.extend_from_slice(name.as_bytes());
+        self.strings.push(0);
+        self.token(FDT_PROP);
+        self.structs
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.structs.extend_from_slice(&nameoff.to_be_bytes());
+        self.structs.extend_from_slice(value);
+        self.structs.resize(align4(self.structs.len()), 0);
+        self
+    }
+
+    fn prop_cells(&mut self, name: &str, cells: &[u32]) -> &mut Self {
+        let value: Vec<u8> = cells.iter().flat_map(|c| c.to_be_bytes()).collect();
+        self.prop(name, &value)
+    }
+
+    fn build(&mut self, reservations: &[(u64, u64)]) -> Vec<u8> {
+        self.token(FDT_END);
+        let rsvmap_off = FDT_HEADER_SIZE;
+        let rsvmap_len = (reservations.len() + 1) * 16;
+        let struct_off = rsvmap_off + rsvmap_len;
+        let strings_off = struct_off + self.structs.len();
+        let total = strings_off + self.strings.len();
+
+        let mut blob = Vec::new();
+        for field in [
+            FDT_MAGIC,
+            total as u32,
+            struct_off as u32,
+            strings_off as u32,
+            rsvmap_off as u32,
+            17,
+            16,
+            0,
+            self.strings.len() as u32,
+            self.structs.len() as u32,
+        ] {
+            blob.extend_from_slice(&field.to_be_bytes());
+        }
+        for &(address, size) in reservations.iter().chain([(0, 0)].iter()) {
+            blob.extend_from_slice(&address.to_be_bytes());
+            blob.extend_from_slice(&size.to_be_bytes());
+        }
+        blob.extend_from_slice(&self.structs);
+        blob.extend_from_slice(&self.strings);
+        blob
+    }
+}
+
+fn qemu_like_blob() -> Vec<u8> {
+    Builder::new()
+        .begin("")
+        .prop_cells("#address-cells", &[2])
+        .prop_cells("#size-cells", &[2])
+        .prop("model", b"linux,dummy-virt\0")
+        .begin("memory@40000000")
+        .prop("device_type", b"memory\0")
+        .prop_cells(
+            "reg",
+            &[0, 0x4000_0000, 0, 0x800_0000, 1, 0, 0, 0x1000_0000],
+        )
+        .end()
+        .begin("reserved-memory")
+        .prop_cells("#address-cells", &[2])
+        .prop_cells("#size-cells", &[2])
+        .begin("mmode_resv0@80000000")
+        .prop_cells("reg", &[0, 0x8000_0000, 0, 0x4_0000])
+        .end()
+        .end()
+        .begin("chosen")
+        .prop("bootargs", b"console=ttyS0\0")
+        .end()
+        .begin("intc@8000000")
+        .prop("compatible", b"arm,cortex-a15-gic\0")
+        .prop("interrupt-controller", b"")
+        .prop_cells(
+            "reg",
+            &[0, 0x800_0000, 0, 0x1_0000, 0, 0x801_0000, 0, 0x1_0000],
+        )
+        .end()
+        .begin("soc")
+        .prop_cells("#address-cells", &[1])
+        .prop_cells("#size-cells", &[1])
+        .begin("virtio_mmio@a000000")
+        .prop("compatible", b"virtio,mmio\0")
+        .prop_cells("reg", &[0xa00_0000, 0x200])
+        .prop_cells("interrupts", &[0, 0x10, 1])
+        .end()
+        .begin("virtio_mmio@a000200")
+        .prop("compatible", b"virtio,mmio\0")
+        .prop("status", b"disabled\0")
+        .prop_cells("reg", &[0xa00_0200, 0x200])
+        .end()
+        .end()
+        .end()
+        .build(&[(0x4000_0000, 0x1_0000)])
+}
+
+#[test]
+fn test_header() {
+    let blob = qemu_like_blob();
+    let fdt = Fdt::new(&blob).unwrap();
+    assert_eq!(fdt.total_size(), blob.len());
+    assert_eq!(
+        fdt.root().unwrap().property_str("model"),
+        Some("linux,dummy-virt")
+    );
+
+    let mut bad = blob.clone();
+    bad[0] = 0;
+    assert_eq!(Fdt::new(&bad).err(), Some(FdtError::BadMagic));
+    assert_eq!(
+        Fdt::new(&blob[..blob.len() - 1]).err(),
+        Some(FdtError::Truncated)
+    );
+}
+
+#[test]
+fn test_memory() {
+    let blob = qemu_like_blob();
+    let fdt = Fdt::new(&blob).unwrap();
+    let banks: Vec<_> = fdt.memory().collect();
+    assert_eq!(
+        banks,
+        [
+            Region {
+                address: 0x4000_0000,
+                size: 0x800_0000
+            },
+            Region {
+                address: 0x1_0000_0000,
+                size: 0x1000_0000
+            },
+        ]
+    );
+    let reserved: Vec<_> = fdt.reserved_memory().collect();
+    assert_eq!(
+        reserved,
+        [Region {
+            address: 0x8000_0000,
+            size: 0x4_0000
+        }]
+    );
+    let rsvmap: Vec<_> = fdt.memory_reservations().collect();
+    assert_eq!(
+        rsvmap,
+        [Region {
+            address: 0x4000_0000,
+            size: 0x1_0000
+        }]
+    );
+}
+
+#[test]
+fn test_find() {
+    let blob = qemu_like_blob();
+    let fdt = Fdt::new(&blob).unwrap();
+    assert_eq!(fdt.bootargs(), Some("console=ttyS0"));
+    assert_eq!(
+        fdt.find_node("/soc/virtio_mmio").unwrap().name(),
+        "virtio_mmio@a000000"
+    );
+    assert_eq!(
+        fdt.find_node("/soc/virtio_mmio@a000200").unwrap().name(),
+        "virtio_mmio@a000200"
+    );
+    assert!(fdt.find_node("/virtio_mmio").is_none());
+    assert!(fdt.find_node("/chosen/soc").is_none());
+
+    let virtio: Vec<_> = fdt
+        .find_compatible(&["virtio,mmio"])
+        .filter(Node::is_available)
+        .collect();
+    assert_eq!(virtio.len(), 1);
+    assert_eq!(
+        virtio[0].reg().collect::<Vec<_>>(),
+        [Region {
+            address: 0xa00_0000,
+            size: 0x200
+        }]
+    );
+    assert_eq!(virtio[0].interrupts().collect::<Vec<_>>(), [0, 0x10, 1]);
+
+    let intc: Vec<_> = fdt.interrupt_controllers().collect();
+    assert_eq!(intc.len(), 1);
+    assert!(intc[0].is_compatible("arm,cortex-a15-gic"));
+    assert_eq!(intc[0].reg().nth(1).unwrap().address, 0x801_0000);
+}
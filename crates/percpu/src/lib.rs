@@ -47,6 +47,9 @@
 
 extern crate percpu_macros;
 
+// make `percpu::` paths in the generated code resolve in this crate too
+extern crate self as percpu;
+
 #[cfg_attr(feature = "sp-naive", path = "naive.rs")]
 mod imp;
 
@@ -99,4 +99,28 @@ fn test_percpu() {
         assert_eq!(s.foo, 0x2333);
         assert_eq!(s.bar, 100);
     });
+    #[cfg(not(feature = "sp-naive"))]
+    test_remote_access();
+}
+
+#[cfg(all(target_os = "linux", not(feature = "sp-naive")))]
+fn test_remote_access() {
+    unsafe {
+        assert_eq!(USIZE.remote_ptr(0), USIZE.current_ptr());
+        assert_eq!(
+            USIZE.remote_ptr(1) as usize - USIZE.remote_ptr(0) as usize,
+            percpu_area_base(1) - percpu_area_base(0)
+        );
+
+        *USIZE.remote_ref_mut_raw(1) = 0xdead;
+        STRUCT.remote_ref_mut_raw(1).foo = 0xbeef;
+        assert_eq!(*USIZE.remote_ref_raw(1), 0xdead);
+        assert_eq!(STRUCT.remote_ref_raw(1).foo, 0xbeef);
+    }
+    assert_eq!(USIZE.read_current(), 0xffff_0000);
+
+    set_local_thread_pointer(1);
+    assert_eq!(USIZE.read_current(), 0xdead);
+    STRUCT.with_current(|s| assert_eq!(s.foo, 0xbeef));
+    set_local_thread_pointer(0);
 }
@@ -15,6 +15,13 @@ pub fn gen_current_ptr(symbol: &Ident, _ty: &Type) -> proc_macro2::TokenStream {
     }
 }
 
+pub fn gen_remote_ptr(symbol: &Ident, _ty: &Type) -> proc_macro2::TokenStream {
+    quote! {
+        let _ = cpu_id;
+        unsafe { ::core::ptr::addr_of!(#symbol) }
+    }
+}
+
 pub fn gen_read_current_raw(_symbol: &Ident, _ty: &Type) -> proc_macro2::TokenStream {
     quote! {
         *self.current_ptr()
@@ -4,9 +4,8 @@ use core::sync::atomic::{AtomicIsize, Ordering};
 
 use crate::BaseScheduler;
 
-/// task for CFS
-pub struct CFSTask<T> {
-    inner: T,
+/// The states of a task in CFS.
+pub(crate) struct CFSEntity {
     init_vruntime: AtomicIsize,
     delta: AtomicIsize,
     nice: AtomicIsize,
@@ -28,11 +27,10 @@ const NICE2WEIGHT_NEG: [isize; NICE_RANGE_NEG + 1] = [
     29154, 36291, 46273, 56483, 71755, 88761,
 ];
 
-impl<T> CFSTask<T> {
+impl CFSEntity {
     /// new with default values
-    pub const fn new(inner: T) -> Self {
+    pub(crate) const fn new() -> Self {
         Self {
-            inner,
             init_vruntime: AtomicIsize::new(0_isize),
             delta: AtomicIsize::new(0_isize),
             nice: AtomicIsize::new(0_isize),
@@ -40,6 +38,10 @@ impl<T> CFSTask<T> {
         }
     }
 
+    pub(crate) fn nice(&self) -> isize {
+        self.nice.load(Ordering::Acquire)
+    }
+
     fn get_weight(&self) -> isize {
         let nice = self.nice.load(Ordering::Acquire);
         if nice >= 0 {
@@ -68,7 +70,7 @@ impl<T> CFSTask<T> {
 
     // Simple Implementation: no change in vruntime.
     // Only modifying priority of current process is supported currently.
-    fn set_priority(&self, nice: isize) {
+    pub(crate) fn set_priority(&self, nice: isize) {
         let current_init_vruntime = self.get_vruntime();
         self.init_vruntime
             .store(current_init_vruntime, Ordering::Release);
@@ -76,6 +78,13 @@ impl<T> CFSTask<T> {
         self.nice.store(nice, Ordering::Release);
     }
 
+    /// Restarts the vruntime from `vruntime`, e.g., when the task comes back
+    /// from other scheduling classes.
+    pub(crate) fn reset_vruntime(&self, vruntime: isize) {
+        self.init_vruntime.store(vruntime, Ordering::Release);
+        self.delta.store(0, Ordering::Release);
+    }
+
     fn set_id(&self, id: isize) {
         self.id.store(id, Ordering::Release);
     }
@@ -83,11 +92,43 @@ impl<T> CFSTask<T> {
     fn task_tick(&self) {
         self.delta.fetch_add(1, Ordering::Release);
     }
+}
+
+/// Tasks that can be scheduled by CFS.
+pub(crate) trait CFSItem {
+    fn cfs_entity(&self) -> &CFSEntity;
+}
+
+/// task for CFS
+pub struct CFSTask<T> {
+    inner: T,
+    entity: CFSEntity,
+}
+
+impl<T> CFSTask<T> {
+    /// new with default values
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            entity: CFSEntity::new(),
+        }
+    }
 
     /// Returns a reference to the inner task struct.
     pub const fn inner(&self) -> &T {
         &self.inner
     }
+
+    /// Returns the priority of the task, i.e., the nice value.
+    pub fn priority(&self) -> isize {
+        self.entity.nice()
+    }
+}
+
+impl<T> CFSItem for CFSTask<T> {
+    fn cfs_entity(&self) -> &CFSEntity {
+        &self.entity
+    }
 }
 
 impl<T> Deref for CFSTask<T> {
@@ -97,43 +138,37 @@ impl<T> Deref for CFSTask<T> {
     }
 }
 
-/// A simple [Completely Fair Scheduler][1] (CFS).
-///
-/// [1]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
-pub struct CFScheduler<T> {
-    ready_queue: BTreeMap<(isize, isize), Arc<CFSTask<T>>>, // (vruntime, taskid)
+/// The ready queue of CFS, ordered by the vruntime of tasks.
+pub(crate) struct CFSQueue<X> {
+    ready_queue: BTreeMap<(isize, isize), Arc<X>>, // (vruntime, taskid)
     min_vruntime: Option<AtomicIsize>,
     id_pool: AtomicIsize,
 }
 
-impl<T> CFScheduler<T> {
-    /// Creates a new empty [`CFScheduler`].
-    pub const fn new() -> Self {
+impl<X: CFSItem> CFSQueue<X> {
+    pub(crate) const fn new() -> Self {
         Self {
             ready_queue: BTreeMap::new(),
             min_vruntime: None,
             id_pool: AtomicIsize::new(0_isize),
         }
     }
-    /// get the name of scheduler
-    pub fn scheduler_name() -> &'static str {
-        "Completely Fair"
-    }
-}
-
-impl<T> BaseScheduler for CFScheduler<T> {
-    type SchedItem = Arc<CFSTask<T>>;
 
-    fn init(&mut self) {}
+    /// The minimum vruntime of the ready tasks, or 0 if there is none.
+    pub(crate) fn min_vruntime(&self) -> isize {
+        self.min_vruntime
+            .as_ref()
+            .map_or(0, |v| v.load(Ordering::Acquire))
+    }
 
-    fn add_task(&mut self, task: Self::SchedItem) {
+    pub(crate) fn add_task(&mut self, task: Arc<X>) {
         if self.min_vruntime.is_none() {
             self.min_vruntime = Some(AtomicIsize::new(0_isize));
         }
         let vruntime = self.min_vruntime.as_mut().unwrap().load(Ordering::Acquire);
         let taskid = self.id_pool.fetch_add(1, Ordering::Release);
-        task.set_vruntime(vruntime);
-        task.set_id(taskid);
+        task.cfs_entity().set_vruntime(vruntime);
+        task.cfs_entity().set_id(taskid);
         self.ready_queue.insert((vruntime, taskid), task);
         if let Some(((min_vruntime, _), _)) = self.ready_queue.first_key_value() {
             self.min_vruntime = Some(AtomicIsize::new(*min_vruntime));
@@ -142,10 +177,11 @@ impl<T> BaseScheduler for CFScheduler<T> {
         }
     }
 
-    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+    pub(crate) fn remove_task(&mut self, task: &Arc<X>) -> Option<Arc<X>> {
+        let entity = task.cfs_entity();
         if let Some((_, tmp)) = self
             .ready_queue
-            .remove_entry(&(task.clone().get_vruntime(), task.clone().get_id()))
+            .remove_entry(&(entity.get_vruntime(), entity.get_id()))
         {
             if let Some(((min_vruntime, _), _)) = self.ready_queue.first_key_value() {
                 self.min_vruntime = Some(AtomicIsize::new(*min_vruntime));
@@ -158,7 +194,7 @@ impl<T> BaseScheduler for CFScheduler<T> {
         }
     }
 
-    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+    pub(crate) fn pick_next_task(&mut self) -> Option<Arc<X>> {
         if let Some((_, v)) = self.ready_queue.pop_first() {
             Some(v)
         } else {
@@ -166,25 +202,76 @@ impl<T> BaseScheduler for CFScheduler<T> {
         }
     }
 
-    fn put_prev_task(&mut self, prev: Self::SchedItem, _preempt: bool) {
+    pub(crate) fn put_prev_task(&mut self, prev: Arc<X>, _preempt: bool) {
         let taskid = self.id_pool.fetch_add(1, Ordering::Release);
-        prev.set_id(taskid);
+        prev.cfs_entity().set_id(taskid);
         self.ready_queue
-            .insert((prev.clone().get_vruntime(), taskid), prev);
+            .insert((prev.cfs_entity().get_vruntime(), taskid), prev);
     }
 
-    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
-        current.task_tick();
+    pub(crate) fn task_tick(&mut self, current: &Arc<X>) -> bool {
+        current.cfs_entity().task_tick();
         self.min_vruntime.is_none()
-            || current.get_vruntime() > self.min_vruntime.as_mut().unwrap().load(Ordering::Acquire)
+            || current.cfs_entity().get_vruntime()
+                > self.min_vruntime.as_mut().unwrap().load(Ordering::Acquire)
     }
 
-    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+    pub(crate) fn set_priority(&mut self, task: &Arc<X>, prio: isize) -> bool {
         if (-20..=19).contains(&prio) {
-            task.set_priority(prio);
+            task.cfs_entity().set_priority(prio);
             true
         } else {
             false
         }
     }
 }
+
+/// A simple [Completely Fair Scheduler][1] (CFS).
+///
+/// [1]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
+pub struct CFScheduler<T> {
+    queue: CFSQueue<CFSTask<T>>,
+}
+
+impl<T> CFScheduler<T> {
+    /// Creates a new empty [`CFScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            queue: CFSQueue::new(),
+        }
+    }
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Completely Fair"
+    }
+}
+
+impl<T> BaseScheduler for CFScheduler<T> {
+    type SchedItem = Arc<CFSTask<T>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        self.queue.add_task(task)
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        self.queue.remove_task(task)
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        self.queue.pick_next_task()
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        self.queue.put_prev_task(prev, preempt)
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        self.queue.task_tick(current)
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        self.queue.set_priority(task, prio)
+    }
+}
@@ -0,0 +1,359 @@
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::cfs::{CFSEntity, CFSItem, CFSQueue};
+use crate::BaseScheduler;
+
+/// Bandwidths are fixed-point numbers, where `1 << BW_SHIFT` means the whole
+/// CPU.
+const BW_SHIFT: u32 = 20;
+const BW_UNIT: u64 = 1 << BW_SHIFT;
+
+/// Reservation parameters of a deadline task, in timer ticks.
+///
+/// In every `period`, the task is guaranteed to run for `runtime` before
+/// `deadline` (relative to the start of the period), as long as it is admitted
+/// by the [`EDFScheduler`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EDFParams {
+    /// The CPU time reserved in each period.
+    pub runtime: u64,
+    /// The relative deadline.
+    pub deadline: u64,
+    /// The period of the reservation.
+    pub period: u64,
+}
+
+impl EDFParams {
+    /// Whether `0 < runtime <= deadline <= period`.
+    pub const fn is_valid(&self) -> bool {
+        0 < self.runtime && self.runtime <= self.deadline && self.deadline <= self.period
+    }
+
+    /// Returns the fraction of the CPU reserved by the task, i.e.,
+    /// `runtime / period`, in units of `1 / (1 << 20)`.
+    const fn bandwidth(&self) -> u64 {
+        (self.runtime << BW_SHIFT) / self.period
+    }
+}
+
+/// A task wrapper for the [`EDFScheduler`].
+///
+/// It holds the reservation parameters and the server states (the remaining
+/// budget and the absolute deadline) of deadline tasks, and the CFS states of
+/// normal tasks.
+pub struct EDFTask<T> {
+    inner: T,
+    /// `runtime`, `deadline` and `period` of the parameters, all zeros if it
+    /// is a normal task.
+    runtime: AtomicU64,
+    rel_deadline: AtomicU64,
+    period: AtomicU64,
+    /// The remaining budget in the current server period.
+    budget: AtomicU64,
+    /// The absolute deadline of the server.
+    deadline: AtomicU64,
+    /// The sequence number to order tasks with the same deadline.
+    seq: AtomicUsize,
+    /// Whether the task is in the ready queue.
+    on_rq: AtomicBool,
+    cfs: CFSEntity,
+}
+
+impl<T> EDFTask<T> {
+    /// Creates a new [`EDFTask`] from the inner task struct. It is a normal
+    /// task.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            runtime: AtomicU64::new(0),
+            rel_deadline: AtomicU64::new(0),
+            period: AtomicU64::new(0),
+            budget: AtomicU64::new(0),
+            deadline: AtomicU64::new(0),
+            seq: AtomicUsize::new(0),
+            on_rq: AtomicBool::new(false),
+            cfs: CFSEntity::new(),
+        }
+    }
+
+    /// Returns the reservation parameters, or [`None`] if it is a normal task.
+    pub fn params(&self) -> Option<EDFParams> {
+        let params = EDFParams {
+            runtime: self.runtime.load(Ordering::Acquire),
+            deadline: self.rel_deadline.load(Ordering::Acquire),
+            period: self.period.load(Ordering::Acquire),
+        };
+        if params.runtime == 0 {
+            None
+        } else {
+            Some(params)
+        }
+    }
+
+    /// Returns the current absolute deadline of a deadline task, or [`None`]
+    /// if it is a normal task.
+    pub fn deadline(&self) -> Option<u64> {
+        self.params().map(|_| self.abs_deadline())
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the priority of the task, i.e., the nice value that takes
+    /// effect when it is a normal task.
+    pub fn priority(&self) -> isize {
+        self.cfs.nice()
+    }
+
+    fn is_deadline_task(&self) -> bool {
+        self.runtime.load(Ordering::Acquire) != 0
+    }
+
+    fn abs_deadline(&self) -> u64 {
+        self.deadline.load(Ordering::Acquire)
+    }
+
+    fn budget(&self) -> u64 {
+        self.budget.load(Ordering::Acquire)
+    }
+
+    fn key(&self) -> (u64, usize) {
+        (self.abs_deadline(), self.seq.load(Ordering::Acquire))
+    }
+
+    fn store_params(&self, params: Option<EDFParams>) {
+        let params = params.unwrap_or(EDFParams {
+            runtime: 0,
+            deadline: 0,
+            period: 0,
+        });
+        self.runtime.store(params.runtime, Ordering::Release);
+        self.rel_deadline.store(params.deadline, Ordering::Release);
+        self.period.store(params.period, Ordering::Release);
+    }
+
+    /// Starts a new server period with a full budget at `now`.
+    fn replenish(&self, params: &EDFParams, now: u64) {
+        self.budget.store(params.runtime, Ordering::Release);
+        self.deadline
+            .store(now + params.deadline, Ordering::Release);
+    }
+}
+
+impl<T> Deref for EDFTask<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> CFSItem for EDFTask<T> {
+    fn cfs_entity(&self) -> &CFSEntity {
+        &self.cfs
+    }
+}
+
+/// An [Earliest Deadline First][1] (EDF) scheduler with [Constant Bandwidth
+/// Servers][2] (CBS), like `SCHED_DEADLINE` in Linux.
+///
+/// Deadline tasks declare their [`EDFParams`], and the one with the earliest
+/// absolute deadline is always picked first. A task is admitted only if the
+/// total bandwidth (`runtime / period`) of the deadline tasks does not exceed
+/// the CPU, so all admitted tasks meet their deadlines as long as they do not
+/// run for more than their runtime in each period.
+///
+/// Each deadline task runs in a server, which gives it a budget of `runtime`
+/// ticks. Once the budget is exhausted, the server deadline is postponed by a
+/// period and the budget is refilled, so a task that overruns only delays
+/// itself, not the others. When a task wakes up, it gets a new server period
+/// unless its remaining budget can still be consumed before the current
+/// deadline without exceeding the reserved bandwidth.
+///
+/// Time is measured in timer ticks, and is provided by [`update_clock`].
+/// Normal tasks are scheduled by CFS (see [`CFScheduler`]) when no deadline
+/// task is ready.
+///
+/// [1]: https://en.wikipedia.org/wiki/Earliest_deadline_first_scheduling
+/// [2]: https://doi.org/10.1109/REAL.1998.739726
+/// [`update_clock`]: EDFScheduler::update_clock
+/// [`CFScheduler`]: crate::CFScheduler
+pub struct EDFScheduler<T> {
+    ready_queue: BTreeMap<(u64, usize), Arc<EDFTask<T>>>, // (deadline, seq)
+    fair: CFSQueue<EDFTask<T>>,
+    /// The current time in ticks.
+    clock: u64,
+    /// The total bandwidth of the admitted deadline tasks.
+    total_bw: u64,
+    seq_pool: usize,
+}
+
+impl<T> EDFScheduler<T> {
+    /// Creates a new empty [`EDFScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queue: BTreeMap::new(),
+            fair: CFSQueue::new(),
+            clock: 0,
+            total_bw: 0,
+            seq_pool: 0,
+        }
+    }
+
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Earliest deadline first"
+    }
+
+    /// Returns the current time in ticks.
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// Advances the current time to `now` ticks. It never goes backwards.
+    pub fn update_clock(&mut self, now: u64) {
+        self.clock = self.clock.max(now);
+    }
+
+    /// Sets the reservation parameters of a task, which can be in the ready
+    /// queue or not. It becomes a normal task if `params` is [`None`], and
+    /// its bandwidth is released.
+    ///
+    /// Returns `false` if the parameters are invalid, or the task is rejected
+    /// by the admission control as the total bandwidth would exceed the CPU.
+    pub fn set_params(&mut self, task: &Arc<EDFTask<T>>, params: Option<EDFParams>) -> bool {
+        let old = task.params();
+        let old_bw = old.map_or(0, |p| p.bandwidth());
+        let new_bw = match params {
+            Some(p) if !p.is_valid() => return false,
+            Some(p) => p.bandwidth(),
+            None => 0,
+        };
+        if self.total_bw - old_bw + new_bw > BW_UNIT {
+            return false;
+        }
+        self.total_bw = self.total_bw - old_bw + new_bw;
+
+        let queued = self.remove_task(task);
+        task.store_params(params);
+        match params {
+            Some(p) => task.replenish(&p, self.clock),
+            // come back to the fair class
+            None if old.is_some() => task.cfs.reset_vruntime(self.fair.min_vruntime()),
+            None => {}
+        }
+        if let Some(task) = queued {
+            self.add_task(task);
+        }
+        true
+    }
+
+    fn enqueue_deadline_task(&mut self, task: Arc<EDFTask<T>>) {
+        self.seq_pool += 1;
+        task.seq.store(self.seq_pool, Ordering::Release);
+        self.ready_queue.insert(task.key(), task);
+    }
+
+    /// Whether a ready deadline task has an earlier deadline than `deadline`.
+    fn earlier_ready(&self, deadline: u64) -> bool {
+        self.ready_queue
+            .first_key_value()
+            .is_some_and(|(&(d, _), _)| d < deadline)
+    }
+}
+
+impl<T> BaseScheduler for EDFScheduler<T> {
+    type SchedItem = Arc<EDFTask<T>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        task.on_rq.store(true, Ordering::Release);
+        if let Some(params) = task.params() {
+            // the CBS wakeup rule: keep the current server period only if
+            // `budget / (deadline - now) <= runtime / period`
+            let now = self.clock;
+            let deadline = task.abs_deadline();
+            if deadline <= now
+                || task.budget() as u128 * params.period as u128
+                    > (deadline - now) as u128 * params.runtime as u128
+            {
+                task.replenish(&params, now);
+            }
+            self.enqueue_deadline_task(task);
+        } else {
+            self.fair.add_task(task);
+        }
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        if !task.on_rq.load(Ordering::Acquire) {
+            return None;
+        }
+        let removed = if task.is_deadline_task() {
+            self.ready_queue.remove(&task.key())
+        } else {
+            self.fair.remove_task(task)
+        };
+        if removed.is_some() {
+            task.on_rq.store(false, Ordering::Release);
+        }
+        removed
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        let task = if let Some((_, task)) = self.ready_queue.pop_first() {
+            Some(task)
+        } else {
+            self.fair.pick_next_task()
+        }?;
+        task.on_rq.store(false, Ordering::Release);
+        Some(task)
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        prev.on_rq.store(true, Ordering::Release);
+        if prev.is_deadline_task() {
+            self.enqueue_deadline_task(prev);
+        } else {
+            self.fair.put_prev_task(prev, preempt);
+        }
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        if let Some(params) = current.params() {
+            let budget = current.budget().saturating_sub(1);
+            if budget == 0 {
+                // the budget is exhausted, postpone the deadline
+                current.budget.store(params.runtime, Ordering::Release);
+                current.deadline.fetch_add(params.period, Ordering::Release);
+            } else {
+                current.budget.store(budget, Ordering::Release);
+            }
+            self.earlier_ready(current.abs_deadline())
+        } else {
+            self.fair.task_tick(current) || !self.ready_queue.is_empty()
+        }
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        if !(-20..=19).contains(&prio) {
+            return false;
+        }
+        if task.is_deadline_task() {
+            // the nice value takes effect when it becomes a normal task
+            task.cfs.set_priority(prio);
+        } else {
+            let queued = self.fair.remove_task(task);
+            task.cfs.set_priority(prio);
+            if let Some(task) = queued {
+                self.fair.add_task(task);
+            }
+        }
+        true
+    }
+}
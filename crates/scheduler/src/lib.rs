@@ -5,13 +5,19 @@
 //! - [`FifoScheduler`]: FIFO (First-In-First-Out) scheduler (cooperative).
 //! - [`RRScheduler`]: Round-robin scheduler (preemptive).
 //! - [`CFScheduler`]: Completely Fair Scheduler (preemptive).
+//! - [`RTScheduler`]: Real-time scheduler with priorities (preemptive), with
+//!   CFS for normal tasks.
+//! - [`EDFScheduler`]: Earliest Deadline First scheduler with bandwidth servers
+//!   and admission control (preemptive), with CFS for normal tasks.
 
 #![cfg_attr(not(test), no_std)]
 #![feature(const_mut_refs)]
 
 mod cfs;
+mod edf;
 mod fifo;
 mod round_robin;
+mod rt;
 
 #[cfg(test)]
 mod tests;
@@ -19,8 +25,10 @@ mod tests;
 extern crate alloc;
 
 pub use cfs::{CFSTask, CFScheduler};
+pub use edf::{EDFParams, EDFScheduler, EDFTask};
 pub use fifo::{FifoScheduler, FifoTask};
 pub use round_robin::{RRScheduler, RRTask};
+pub use rt::{RTScheduler, RTTask, SchedPolicy, MAX_RT_PRIO};
 
 /// The base scheduler trait that all schedulers should implement.
 ///
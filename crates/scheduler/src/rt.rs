@@ -0,0 +1,307 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, AtomicIsize, AtomicU8, Ordering};
+
+use crate::cfs::{CFSEntity, CFSItem, CFSQueue};
+use crate::BaseScheduler;
+
+/// The number of real-time priorities. Real-time priorities range from 0 to
+/// `MAX_RT_PRIO - 1`, and a larger value means a higher priority.
+pub const MAX_RT_PRIO: usize = 100;
+
+/// Scheduling policies of the [`RTScheduler`].
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// Normal tasks, scheduled by CFS with their nice values. They only run
+    /// when no real-time task is ready.
+    Normal = 0,
+    /// Real-time tasks that run until they block, yield, or are preempted by
+    /// tasks with higher priorities.
+    Fifo = 1,
+    /// Real-time tasks like [`SchedPolicy::Fifo`], but tasks with the same
+    /// priority share the CPU in time slices.
+    RoundRobin = 2,
+}
+
+impl SchedPolicy {
+    fn from_u8(policy: u8) -> Self {
+        match policy {
+            1 => Self::Fifo,
+            2 => Self::RoundRobin,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Whether it is a real-time policy.
+    pub const fn is_rt(&self) -> bool {
+        !matches!(self, Self::Normal)
+    }
+
+    /// Whether `prio` is a valid priority of the policy, i.e., a nice value in
+    /// `-20..=19` for [`SchedPolicy::Normal`], or a real-time priority in
+    /// `0..MAX_RT_PRIO` for others.
+    pub const fn is_valid_priority(&self, prio: isize) -> bool {
+        match self {
+            Self::Normal => -20 <= prio && prio <= 19,
+            _ => 0 <= prio && prio < MAX_RT_PRIO as isize,
+        }
+    }
+}
+
+/// A task wrapper for the [`RTScheduler`].
+///
+/// It holds the scheduling policy and priority of the task, with the states
+/// for both real-time and normal scheduling.
+pub struct RTTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    policy: AtomicU8,
+    /// The real-time priority, or the nice value of normal tasks.
+    prio: AtomicIsize,
+    time_slice: AtomicIsize,
+    /// Whether the task is in the ready queue.
+    on_rq: AtomicBool,
+    cfs: CFSEntity,
+}
+
+impl<T, const S: usize> RTTask<T, S> {
+    /// Creates a new [`RTTask`] from the inner task struct. It is a normal
+    /// task with nice value 0.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            policy: AtomicU8::new(SchedPolicy::Normal as u8),
+            prio: AtomicIsize::new(0),
+            time_slice: AtomicIsize::new(S as isize),
+            on_rq: AtomicBool::new(false),
+            cfs: CFSEntity::new(),
+        }
+    }
+
+    /// Returns the scheduling policy of the task.
+    pub fn policy(&self) -> SchedPolicy {
+        SchedPolicy::from_u8(self.policy.load(Ordering::Acquire))
+    }
+
+    /// Returns the priority of the task, i.e., the real-time priority, or the
+    /// nice value if it is a normal task.
+    pub fn priority(&self) -> isize {
+        self.prio.load(Ordering::Acquire)
+    }
+
+    fn rt_prio(&self) -> usize {
+        self.priority() as usize
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for RTTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> CFSItem for RTTask<T, S> {
+    fn cfs_entity(&self) -> &CFSEntity {
+        &self.cfs
+    }
+}
+
+/// A real-time scheduler with the [`SchedPolicy::Fifo`] and
+/// [`SchedPolicy::RoundRobin`] policies, like `SCHED_FIFO` and `SCHED_RR` in
+/// Linux.
+///
+/// Real-time tasks are always picked before normal tasks, and a task is
+/// preempted as soon as a task with a higher priority becomes ready (at the
+/// next timer tick at the latest). Each real-time priority has its own FIFO
+/// queue, and a bitmap of the non-empty queues is kept to find the highest
+/// priority in constant time.
+///
+/// Normal tasks are scheduled by CFS (see [`CFScheduler`]) as a lower
+/// scheduling class.
+///
+/// [`CFScheduler`]: crate::CFScheduler
+pub struct RTScheduler<T, const MAX_TIME_SLICE: usize> {
+    rt_queues: [VecDeque<Arc<RTTask<T, MAX_TIME_SLICE>>>; MAX_RT_PRIO],
+    /// Bit `i` is set if the queue of priority `i` is not empty.
+    bitmap: u128,
+    fair: CFSQueue<RTTask<T, MAX_TIME_SLICE>>,
+}
+
+impl<T, const S: usize> RTScheduler<T, S> {
+    const EMPTY_QUEUE: VecDeque<Arc<RTTask<T, S>>> = VecDeque::new();
+
+    /// Creates a new empty [`RTScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            rt_queues: [Self::EMPTY_QUEUE; MAX_RT_PRIO],
+            bitmap: 0,
+            fair: CFSQueue::new(),
+        }
+    }
+
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Real-time"
+    }
+
+    /// Returns the highest priority of the ready real-time tasks.
+    fn highest_rt_prio(&self) -> Option<usize> {
+        if self.bitmap == 0 {
+            None
+        } else {
+            Some(127 - self.bitmap.leading_zeros() as usize)
+        }
+    }
+
+    fn push_rt(&mut self, task: Arc<RTTask<T, S>>, front: bool) {
+        let prio = task.rt_prio();
+        if front {
+            self.rt_queues[prio].push_front(task);
+        } else {
+            self.rt_queues[prio].push_back(task);
+        }
+        self.bitmap |= 1 << prio;
+    }
+
+    /// Whether `task`, which has just become ready, should preempt the running
+    /// `current`, i.e., it is a real-time task of a higher priority, or
+    /// `current` is a normal task.
+    pub fn preempts(task: &Arc<RTTask<T, S>>, current: &Arc<RTTask<T, S>>) -> bool {
+        task.policy().is_rt() && (!current.policy().is_rt() || task.rt_prio() > current.rt_prio())
+    }
+
+    fn update_bitmap(&mut self, prio: usize) {
+        if self.rt_queues[prio].is_empty() {
+            self.bitmap &= !(1 << prio);
+        }
+    }
+
+    /// Sets the scheduling policy and the priority of a task, which can be in
+    /// the ready queue or not.
+    ///
+    /// `prio` is a nice value for [`SchedPolicy::Normal`], otherwise it is a
+    /// real-time priority. Returns `false` if it is not valid for the policy.
+    pub fn set_policy(
+        &mut self,
+        task: &Arc<RTTask<T, S>>,
+        policy: SchedPolicy,
+        prio: isize,
+    ) -> bool {
+        if !policy.is_valid_priority(prio) {
+            return false;
+        }
+        let queued = self.remove_task(task);
+        if policy.is_rt() {
+            if task.policy() != policy {
+                task.reset_time_slice();
+            }
+        } else if task.policy().is_rt() {
+            // come back to the fair class
+            task.cfs.reset_vruntime(self.fair.min_vruntime());
+        }
+        task.policy.store(policy as u8, Ordering::Release);
+        task.prio.store(prio, Ordering::Release);
+        if !policy.is_rt() {
+            task.cfs.set_priority(prio);
+        }
+        if let Some(task) = queued {
+            self.add_task(task);
+        }
+        true
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for RTScheduler<T, S> {
+    type SchedItem = Arc<RTTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        task.on_rq.store(true, Ordering::Release);
+        if task.policy().is_rt() {
+            self.push_rt(task, false);
+        } else {
+            self.fair.add_task(task);
+        }
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        if !task.on_rq.load(Ordering::Acquire) {
+            return None;
+        }
+        let removed = if task.policy().is_rt() {
+            let prio = task.rt_prio();
+            let removed = self.rt_queues[prio]
+                .iter()
+                .position(|t| Arc::ptr_eq(t, task))
+                .and_then(|idx| self.rt_queues[prio].remove(idx));
+            self.update_bitmap(prio);
+            removed
+        } else {
+            self.fair.remove_task(task)
+        };
+        if removed.is_some() {
+            task.on_rq.store(false, Ordering::Release);
+        }
+        removed
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        let task = if let Some(prio) = self.highest_rt_prio() {
+            let task = self.rt_queues[prio].pop_front();
+            self.update_bitmap(prio);
+            task
+        } else {
+            self.fair.pick_next_task()
+        }?;
+        task.on_rq.store(false, Ordering::Release);
+        Some(task)
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        prev.on_rq.store(true, Ordering::Release);
+        match prev.policy() {
+            SchedPolicy::Fifo => self.push_rt(prev, preempt),
+            SchedPolicy::RoundRobin => {
+                if prev.time_slice() > 0 && preempt {
+                    self.push_rt(prev, true)
+                } else {
+                    prev.reset_time_slice();
+                    self.push_rt(prev, false)
+                }
+            }
+            SchedPolicy::Normal => self.fair.put_prev_task(prev, preempt),
+        }
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        let higher_ready = |prio: usize| self.highest_rt_prio().is_some_and(|p| p > prio);
+        match current.policy() {
+            SchedPolicy::Fifo => higher_ready(current.rt_prio()),
+            SchedPolicy::RoundRobin => {
+                let old_slice = current.time_slice.fetch_sub(1, Ordering::Release);
+                old_slice <= 1 || higher_ready(current.rt_prio())
+            }
+            SchedPolicy::Normal => self.fair.task_tick(current) || self.bitmap != 0,
+        }
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        self.set_policy(task, task.policy(), prio)
+    }
+}
@@ -82,3 +82,304 @@ macro_rules! def_test_sched {
 def_test_sched!(fifo, FifoScheduler::<usize>, FifoTask::<usize>);
 def_test_sched!(rr, RRScheduler::<usize, 5>, RRTask::<usize, 5>);
 def_test_sched!(cfs, CFScheduler::<usize>, CFSTask::<usize>);
+def_test_sched!(rt, RTScheduler::<usize, 5>, RTTask::<usize, 5>);
+def_test_sched!(edf, EDFScheduler::<usize>, EDFTask::<usize>);
+
+mod rt_policy {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<RTTask<usize, 5>>;
+
+    fn new_task(
+        scheduler: &mut RTScheduler<usize, 5>,
+        id: usize,
+        policy: SchedPolicy,
+        prio: isize,
+    ) -> Task {
+        let task = Arc::new(RTTask::new(id));
+        assert!(scheduler.set_policy(&task, policy, prio));
+        scheduler.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        new_task(&mut scheduler, 0, SchedPolicy::Normal, -20);
+        new_task(&mut scheduler, 1, SchedPolicy::Fifo, 10);
+        new_task(&mut scheduler, 2, SchedPolicy::RoundRobin, 99);
+        new_task(&mut scheduler, 3, SchedPolicy::Fifo, 0);
+        new_task(&mut scheduler, 4, SchedPolicy::Fifo, 10);
+
+        let order: Vec<usize> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [2, 1, 4, 3, 0]);
+    }
+
+    #[test]
+    fn test_preemption() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let normal = new_task(&mut scheduler, 0, SchedPolicy::Normal, 0);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &normal));
+
+        // a real-time task preempts the normal one
+        let low = new_task(&mut scheduler, 1, SchedPolicy::Fifo, 1);
+        assert!(scheduler.task_tick(&normal));
+        scheduler.put_prev_task(normal.clone(), true);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &low));
+
+        // FIFO tasks are not preempted by ticks, only by higher priorities
+        for _ in 0..10 {
+            assert!(!scheduler.task_tick(&low));
+        }
+        let high = new_task(&mut scheduler, 2, SchedPolicy::Fifo, 2);
+        assert!(scheduler.task_tick(&low));
+        scheduler.put_prev_task(low.clone(), true);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &high));
+
+        // the preempted task is still at the front of its queue
+        let same = new_task(&mut scheduler, 3, SchedPolicy::Fifo, 1);
+        scheduler.put_prev_task(high.clone(), false);
+        assert!(scheduler.remove_task(&same).is_some());
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 2);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &low));
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &normal));
+
+        // woken up on another CPU
+        type Sched = RTScheduler<usize, 5>;
+        assert!(Sched::preempts(&low, &normal));
+        assert!(Sched::preempts(&high, &low));
+        assert!(!Sched::preempts(&same, &low));
+        assert!(!Sched::preempts(&normal, &low));
+        assert!(!Sched::preempts(&normal, &normal));
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        for i in 0..3 {
+            new_task(&mut scheduler, i, SchedPolicy::RoundRobin, 50);
+        }
+        for i in 0..30 {
+            let next = scheduler.pick_next_task().unwrap();
+            assert_eq!(*next.inner(), i % 3);
+            for _ in 0..4 {
+                assert!(!scheduler.task_tick(&next));
+            }
+            assert!(scheduler.task_tick(&next));
+            scheduler.put_prev_task(next, true);
+        }
+    }
+
+    #[test]
+    fn test_set_policy() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let a = new_task(&mut scheduler, 0, SchedPolicy::Normal, 0);
+        let b = new_task(&mut scheduler, 1, SchedPolicy::Normal, 0);
+        assert!(!scheduler.set_policy(&b, SchedPolicy::Fifo, MAX_RT_PRIO as isize));
+        assert!(!scheduler.set_policy(&b, SchedPolicy::Normal, 20));
+
+        // move a ready task between the classes
+        assert!(scheduler.set_policy(&b, SchedPolicy::RoundRobin, 5));
+        assert_eq!(b.policy(), SchedPolicy::RoundRobin);
+        assert_eq!(b.priority(), 5);
+        assert!(scheduler.set_priority(&b, 6));
+        assert_eq!(b.priority(), 6);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &b));
+
+        assert!(scheduler.set_policy(&b, SchedPolicy::Normal, 0));
+        scheduler.put_prev_task(b.clone(), false);
+        assert!(scheduler.remove_task(&a).is_some());
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &b));
+        assert!(scheduler.pick_next_task().is_none());
+    }
+}
+
+mod edf_deadline {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<EDFTask<usize>>;
+
+    const fn params(runtime: u64, deadline: u64, period: u64) -> EDFParams {
+        EDFParams {
+            runtime,
+            deadline,
+            period,
+        }
+    }
+
+    /// A periodic task that releases a job of `work` ticks every period.
+    struct Periodic {
+        task: Task,
+        params: EDFParams,
+        work: u64,
+        remaining: u64,
+        job_deadline: u64,
+        misses: usize,
+    }
+
+    impl Periodic {
+        fn new(id: usize, params: EDFParams, work: u64) -> Self {
+            Self {
+                task: Arc::new(EDFTask::new(id)),
+                params,
+                work,
+                remaining: 0,
+                job_deadline: 0,
+                misses: 0,
+            }
+        }
+    }
+
+    /// Simulates the periodic tasks (and a background normal task that is
+    /// always ready) for `ticks` ticks, and returns the ticks that the
+    /// background task has run. Deadline misses are counted in each task.
+    fn simulate(tasks: &mut [Periodic], ticks: u64) -> u64 {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        for t in tasks.iter() {
+            assert!(scheduler.set_params(&t.task, Some(t.params)));
+        }
+        let background = Arc::new(EDFTask::new(usize::MAX));
+        scheduler.add_task(background.clone());
+        let mut background_ticks = 0;
+
+        let mut current: Option<Task> = None;
+        for now in 0..ticks {
+            scheduler.update_clock(now);
+            for t in tasks.iter_mut() {
+                if now % t.params.period == 0 {
+                    if t.remaining > 0 {
+                        // the previous job is still running, abandon it
+                        t.misses += 1;
+                        t.remaining = 0;
+                    } else {
+                        scheduler.add_task(t.task.clone());
+                    }
+                    t.remaining = t.work;
+                    t.job_deadline = now + t.params.deadline;
+                }
+            }
+            // reschedule at every tick, so that new jobs preempt immediately
+            if let Some(curr) = current.take() {
+                scheduler.put_prev_task(curr, true);
+            }
+            let curr = scheduler.pick_next_task().unwrap();
+            scheduler.task_tick(&curr);
+            if Arc::ptr_eq(&curr, &background) {
+                background_ticks += 1;
+                current = Some(curr);
+                continue;
+            }
+            let t = tasks
+                .iter_mut()
+                .find(|t| Arc::ptr_eq(&t.task, &curr))
+                .unwrap();
+            t.remaining -= 1;
+            if t.remaining == 0 {
+                // the job is done, block until the next period
+                if now + 1 > t.job_deadline {
+                    t.misses += 1;
+                }
+            } else {
+                current = Some(curr);
+            }
+        }
+        background_ticks
+    }
+
+    #[test]
+    fn test_no_deadline_miss() {
+        // utilization = 1/4 + 2/6 + 4/12 + 1/12 = 1
+        let mut tasks = [
+            Periodic::new(0, params(1, 4, 4), 1),
+            Periodic::new(1, params(2, 6, 6), 2),
+            Periodic::new(2, params(4, 12, 12), 4),
+            Periodic::new(3, params(1, 12, 12), 1),
+        ];
+        let background = simulate(&mut tasks, 1200);
+        assert!(tasks.iter().all(|t| t.misses == 0));
+        assert_eq!(background, 0);
+
+        // constrained deadlines with spare time for normal tasks
+        let mut tasks = [
+            Periodic::new(0, params(2, 3, 5), 2),
+            Periodic::new(1, params(3, 7, 10), 3),
+            Periodic::new(2, params(1, 4, 20), 1),
+        ];
+        let background = simulate(&mut tasks, 1000);
+        assert!(tasks.iter().all(|t| t.misses == 0));
+        // utilization = 2/5 + 3/10 + 1/20 = 0.75
+        assert_eq!(background, 250);
+    }
+
+    #[test]
+    fn test_overrun_isolation() {
+        // task 0 overruns its reservation, but others are not affected
+        let mut tasks = [
+            Periodic::new(0, params(2, 6, 6), 5),
+            Periodic::new(1, params(1, 3, 3), 1),
+            Periodic::new(2, params(2, 6, 6), 2),
+        ];
+        simulate(&mut tasks, 600);
+        assert!(tasks[0].misses > 0);
+        assert_eq!(tasks[1].misses, 0);
+        assert_eq!(tasks[2].misses, 0);
+    }
+
+    #[test]
+    fn test_admission_control() {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        let tasks: Vec<Task> = (0..4).map(|i| Arc::new(EDFTask::new(i))).collect();
+        assert!(!scheduler.set_params(&tasks[0], Some(params(0, 1, 1))));
+        assert!(!scheduler.set_params(&tasks[0], Some(params(2, 1, 3))));
+        assert!(!scheduler.set_params(&tasks[0], Some(params(1, 4, 3))));
+
+        assert!(scheduler.set_params(&tasks[0], Some(params(1, 3, 3))));
+        assert!(scheduler.set_params(&tasks[1], Some(params(1, 3, 3))));
+        assert!(scheduler.set_params(&tasks[2], Some(params(1, 3, 3))));
+        assert!(!scheduler.set_params(&tasks[3], Some(params(1, 100, 100))));
+        assert_eq!(tasks[3].params(), None);
+
+        // shrink a reservation, or release it
+        assert!(scheduler.set_params(&tasks[0], Some(params(1, 4, 4))));
+        assert!(!scheduler.set_params(&tasks[3], Some(params(1, 6, 6))));
+        assert!(scheduler.set_params(&tasks[3], Some(params(1, 12, 12))));
+        assert!(scheduler.set_params(&tasks[1], None));
+        assert!(scheduler.set_params(&tasks[3], Some(params(1, 3, 3))));
+    }
+
+    #[test]
+    fn test_deadline_order() {
+        let mut scheduler = EDFScheduler::<usize>::new();
+        let normal = Arc::new(EDFTask::new(0));
+        scheduler.add_task(normal.clone());
+        let tasks: Vec<Task> = (1..4).map(|i| Arc::new(EDFTask::new(i))).collect();
+        assert!(scheduler.set_params(&tasks[0], Some(params(1, 8, 10))));
+        assert!(scheduler.set_params(&tasks[1], Some(params(1, 4, 10))));
+        assert!(scheduler.set_params(&tasks[2], Some(params(1, 6, 10))));
+        for t in &tasks {
+            scheduler.add_task(t.clone());
+        }
+        assert_eq!(tasks[1].deadline(), Some(4));
+        assert_eq!(normal.deadline(), None);
+
+        let order: Vec<usize> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [2, 3, 1, 0]);
+
+        // a ready deadline task preempts the normal one
+        scheduler.add_task(tasks[0].clone());
+        assert!(scheduler.task_tick(&normal));
+
+        // a normal task comes back to CFS
+        assert!(scheduler.set_params(&tasks[0], None));
+        assert_eq!(tasks[0].deadline(), None);
+        scheduler.put_prev_task(normal.clone(), true);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 1);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+    }
+}
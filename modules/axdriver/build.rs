@@ -1,7 +1,7 @@
 const NET_DEV_FEATURES: &[&str] = &["ixgbe", "virtio-net"];
-const BLOCK_DEV_FEATURES: &[&str] = &["ramdisk", "bcm2835-sdhci", "virtio-blk"];
+const BLOCK_DEV_FEATURES: &[&str] = &["ramdisk", "bcm2835-sdhci", "usb-storage", "virtio-blk"];
 const DISPLAY_DEV_FEATURES: &[&str] = &["virtio-gpu"];
-const USB_HOST_DEV_FEATURES: &[&str] = &["vl805"];
+const USB_HOST_DEV_FEATURES: &[&str] = &["vl805", "xhci"];
 
 
 fn has_feature(feature: &str) -> bool {
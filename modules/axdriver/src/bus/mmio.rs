@@ -3,21 +3,38 @@ use crate::{prelude::*, AllDevices};
 
 impl AllDevices {
     pub(crate) fn probe_bus_devices(&mut self) {
-        // TODO: parse device tree
         #[cfg(feature = "virtio")]
-        for reg in axconfig::VIRTIO_MMIO_REGIONS {
-            for_each_drivers!(type Driver, {
-                if let Some(dev) = Driver::probe_mmio(reg.0, reg.1) {
-                    info!(
-                        "registered a new {:?} device at [PA:{:#x}, PA:{:#x}): {:?}",
-                        dev.device_type(),
-                        reg.0, reg.0 + reg.1,
-                        dev.device_name(),
-                    );
-                    self.add_device(dev);
-                    continue; // skip to the next device
+        if axhal::dtb::get().is_some() {
+            for node in axhal::dtb::find_compatible(&["virtio,mmio"]) {
+                #[cfg(feature = "irq")]
+                let irq = axhal::dtb::irq_num(&node).map(crate::irq::device_irq);
+                #[cfg(not(feature = "irq"))]
+                let irq = None;
+                if let Some(reg) = node.reg().next() {
+                    self.probe_virtio_mmio(reg.address, reg.size, irq);
                 }
-            });
+            }
+        } else {
+            for reg in axconfig::VIRTIO_MMIO_REGIONS {
+                self.probe_virtio_mmio(reg.0, reg.1, None);
+            }
         }
     }
+
+    #[cfg(feature = "virtio")]
+    fn probe_virtio_mmio(&mut self, paddr: usize, size: usize, irq: Option<DeviceIrq>) {
+        for_each_drivers!(type Driver, {
+            if let Some(dev) = Driver::probe_mmio(paddr, size, irq) {
+                info!(
+                    "registered a new {:?} device at [PA:{:#x}, PA:{:#x}): {:?}, {:?}",
+                    dev.device_type(),
+                    paddr, paddr + size,
+                    dev.device_name(),
+                    dev.irq(),
+                );
+                self.add_device(dev);
+                return;
+            }
+        });
+    }
 }
@@ -1,4 +1,6 @@
 #[cfg(bus = "mmio")]
 mod mmio;
 #[cfg(bus = "pci")]
-mod pci;
+pub(crate) mod pci;
+#[cfg(feature = "usb_host")]
+mod usb;
@@ -2,7 +2,73 @@ use crate::{prelude::*, AllDevices};
 use axhal::mem::phys_to_virt;
 use driver_pci::*;
 
+#[cfg(feature = "irq")]
+use {
+    alloc::vec::Vec,
+    driver_pci::capability::MsixTable,
+    driver_pci::types::{Bar, ConfigSpace},
+};
 
+/// Allocates up to `count` interrupt vectors for a PCI function, and programs
+/// them into its MSI-X table, in order. If the function only supports MSI, a
+/// single vector is allocated.
+///
+/// The legacy INTx interrupt is disabled if any vector is allocated. It
+/// returns an empty vector if the function supports neither, or if the
+/// platform has no free MSI vectors, in which case the driver should poll.
+#[cfg(feature = "irq")]
+#[allow(dead_code)]
+pub(crate) fn alloc_irqs(
+    root: &PciRoot,
+    bdf: DeviceFunction,
+    cfg: &ConfigSpace,
+    count: usize,
+) -> Vec<DeviceIrq> {
+    let mut irqs = Vec::new();
+    if let Some(msix) = cfg.msix() {
+        let (slot, offset) = msix.table_location();
+        let bar_paddr = match root.bar_info(bdf, slot) {
+            Some(Bar::Memory32 { address, .. }) => address as usize,
+            Some(Bar::Memory64 { address, .. }) => address as usize,
+            _ => {
+                warn!("PCI {}: invalid BAR {} for the MSI-X table", bdf, slot);
+                return irqs;
+            }
+        };
+        let table_vaddr = phys_to_virt((bar_paddr + offset).into()).as_usize();
+        let table = unsafe { MsixTable::new(table_vaddr, msix.table_size()) };
+        msix.set_function_mask(true);
+        msix.set_enabled(true);
+        for index in 0..count.min(table.size()) {
+            let Some((irq, msi)) = crate::irq::alloc_msi_irq() else {
+                break;
+            };
+            table.set_message(index, msi.address, msi.data).unwrap();
+            table.set_masked(index, false).unwrap();
+            irqs.push(irq);
+        }
+        if irqs.is_empty() {
+            msix.set_enabled(false);
+        }
+        msix.set_function_mask(false);
+        debug!("PCI {}: {} MSI-X vectors allocated", bdf, irqs.len());
+    } else if let Some(msi) = cfg.msi() {
+        if let Some((irq, vector)) = crate::irq::alloc_msi_irq() {
+            match msi.set_message(vector.address, vector.data as u16) {
+                Ok(_) => {
+                    msi.set_enabled(true);
+                    irqs.push(irq);
+                    debug!("PCI {}: MSI vector allocated", bdf);
+                }
+                Err(e) => warn!("PCI {}: failed to program MSI: {}", bdf, e),
+            }
+        }
+    }
+    if !irqs.is_empty() {
+        cfg.header.set_interrupt_disable(true);
+    }
+    irqs
+}
 
 impl AllDevices {
     pub(crate) fn probe_bus_devices(&mut self) {
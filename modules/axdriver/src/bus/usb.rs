@@ -0,0 +1,27 @@
+use crate::{prelude::*, AllDevices, AxDeviceEnum};
+
+impl AllDevices {
+    /// Binds the class drivers to the devices found on the usb host
+    /// controllers, and registers the resulting devices.
+    pub(crate) fn probe_usb_devices(&mut self) {
+        #[cfg(block_dev = "usb-storage")]
+        {
+            use driver_usb::class::storage::MassStorage;
+
+            let hosts: alloc::vec::Vec<AxUSBHostDevice> = self.usb_host.iter().cloned().collect();
+            for host in hosts {
+                for usb_dev in host.info().devices {
+                    if let Some(disk) = MassStorage::probe(host.clone(), &usb_dev) {
+                        info!(
+                            "registered a new {:?} device on usb slot {}: {:?}",
+                            disk.device_type(),
+                            usb_dev.slot,
+                            disk.device_name(),
+                        );
+                        self.add_device(AxDeviceEnum::from_block(disk));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 #![allow(unused_imports)]
 
+use crate::prelude::*;
 use crate::AxDeviceEnum;
 use axalloc::{global_allocator, global_no_cache_allocator};
 use driver_common::DeviceType;
@@ -21,7 +22,11 @@ pub trait DriverProbe {
     }
 
     #[cfg(bus = "mmio")]
-    fn probe_mmio(_mmio_base: usize, _mmio_size: usize) -> Option<AxDeviceEnum> {
+    fn probe_mmio(
+        _mmio_base: usize,
+        _mmio_size: usize,
+        _irq: Option<driver_common::DeviceIrq>,
+    ) -> Option<AxDeviceEnum> {
         None
     }
 
@@ -101,13 +106,46 @@ cfg_if::cfg_if! {
                     dev_info: &DeviceFunctionInfo,
                     cfg: &ConfigSpace,
                 ) -> Option<AxDeviceEnum> {
-              
-                VL805::probe_pci(cfg, global_no_cache_allocator()).map(|d| AxDeviceEnum::from_usb_host(d))
+
+                VL805::probe_pci(cfg, global_no_cache_allocator())
+                    .map(|d| AxDeviceEnum::from_usb_host(SharedHost::new(d)))
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(usb_host_dev = "xhci")] {
+        use alloc::alloc::Global;
+        use driver_usb::host::xhci::Xhci;
+        pub struct XhciDriver;
+        register_usb_host_driver!(XhciDriver, Xhci<Global>);
+
+        impl DriverProbe for XhciDriver {
+            fn probe_pci(
+                    _root: &mut PciRoot,
+                    _bdf: DeviceFunction,
+                    _dev_info: &DeviceFunctionInfo,
+                    cfg: &ConfigSpace,
+                ) -> Option<AxDeviceEnum> {
+                Xhci::probe_pci(cfg, Global).map(|d| AxDeviceEnum::from_usb_host(SharedHost::new(d)))
             }
         }
     }
 }
 
+cfg_if::cfg_if! {
+    if #[cfg(block_dev = "usb-storage")] {
+        use driver_usb::class::storage::MassStorage;
+        pub struct UsbStorageDriver;
+        register_block_driver!(UsbStorageDriver, MassStorage<AxUSBHostDevice>);
+
+        /// Probed on the usb host controllers once the buses have been
+        /// scanned, see `AllDevices::probe_usb_devices`.
+        impl DriverProbe for UsbStorageDriver {}
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(net_dev = "ixgbe")] {
         use crate::ixgbe::IxgbeHalImpl;
@@ -0,0 +1,104 @@
+//! Interrupt routing of devices.
+//!
+//! The interrupt line of every device is registered to [`axhal::irq`] with
+//! the same handler, which counts the interrupts and wakes up the tasks waiting
+//! for any device. The woken tasks then check their own devices.
+//!
+//! PCI devices get their vectors allocated by [`alloc_msi_irq`] if they
+//! support MSI or MSI-X.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use driver_common::{DeviceIrq, IrqWaiter};
+
+/// Upper bound of the IRQ numbers on all platforms.
+const MAX_IRQS: usize = 1024;
+
+static IRQ_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Bitmap of the registered IRQs, as devices may share an interrupt line or
+/// give one of their allocated vectors.
+static REGISTERED: [AtomicUsize; MAX_IRQS / usize::BITS as usize] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicUsize = AtomicUsize::new(0);
+    [EMPTY; MAX_IRQS / usize::BITS as usize]
+};
+
+#[cfg(feature = "multitask")]
+static IRQ_WAIT_QUEUE: axtask::WaitQueue = axtask::WaitQueue::new();
+
+struct Waiter;
+
+static WAITER: Waiter = Waiter;
+
+impl IrqWaiter for Waiter {
+    fn irq_count(&self) -> usize {
+        IRQ_COUNT.load(Ordering::Acquire)
+    }
+
+    fn wait_irq(&self, count: usize, timeout: Option<Duration>) {
+        if !axhal::arch::irqs_enabled() {
+            // e.g., during initialization, the caller has to poll the device
+            core::hint::spin_loop();
+            return;
+        }
+        let arrived = || self.irq_count() != count;
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "multitask")] {
+                match timeout {
+                    Some(dur) => {
+                        IRQ_WAIT_QUEUE.wait_timeout_until(dur, arrived);
+                    }
+                    None => IRQ_WAIT_QUEUE.wait_until(arrived),
+                }
+            } else {
+                let deadline = timeout.map(|dur| axhal::time::current_time() + dur);
+                while !arrived() && deadline.map_or(true, |d| axhal::time::current_time() < d) {
+                    axhal::arch::wait_for_irqs();
+                }
+            }
+        }
+    }
+}
+
+fn device_irq_handler() {
+    IRQ_COUNT.fetch_add(1, Ordering::Release);
+    #[cfg(feature = "multitask")]
+    IRQ_WAIT_QUEUE.notify_all(true);
+}
+
+/// Returns the interrupt line to be given to the driver of a device.
+pub(crate) fn device_irq(irq_num: usize) -> DeviceIrq {
+    DeviceIrq {
+        irq_num,
+        waiter: &WAITER,
+    }
+}
+
+/// Allocates an MSI vector, and returns it with the interrupt to be given to
+/// the driver. The interrupt is enabled.
+#[allow(dead_code)]
+pub(crate) fn alloc_msi_irq() -> Option<(DeviceIrq, axhal::irq::MsiVector)> {
+    let msi = axhal::irq::alloc_msi()?;
+    let irq = device_irq(msi.irq_num);
+    enable_device_irq(&irq);
+    Some((irq, msi))
+}
+
+/// Registers the interrupt handler of a device and enables its interrupt.
+///
+/// It does nothing if the interrupt has been enabled.
+pub(crate) fn enable_device_irq(irq: &DeviceIrq) {
+    let bits = usize::BITS as usize;
+    let (idx, bit) = (irq.irq_num / bits, 1 << (irq.irq_num % bits));
+    if REGISTERED
+        .get(idx)
+        .is_some_and(|r| r.fetch_or(bit, Ordering::AcqRel) & bit != 0)
+    {
+        return;
+    }
+    if axhal::irq::register_handler(irq.irq_num, device_irq_handler) {
+        debug!("registered IRQ {} for device", irq.irq_num);
+    }
+}
@@ -34,6 +34,9 @@
 //! | Block | `virtio-blk` | VirtIO block device |
 //! | Network | `virtio-net` | VirtIO network device |
 //! | Display | `virtio-gpu` | VirtIO graphics device |
+//! | USB Host | `vl805` | VIA VL805 xHCI controller on the Raspberry Pi 4 |
+//! | USB Host | `xhci` | Generic xHCI controller on the PCI bus (e.g., QEMU `qemu-xhci`) |
+//! | Block | `usb-storage` | USB mass storage device (Bulk-Only Transport), requires a USB host driver |
 //!
 //! # Other Cargo Features
 //!
@@ -41,6 +44,10 @@
 //! - `bus-mmio`: use device tree to probe all MMIO devices. This feature is
 //!    enabeld by default.
 //! - `bus-pci`: use PCI bus to probe all PCI devices.
+//! - `irq`: register the interrupt lines of devices (currently the VirtIO MMIO
+//!   devices in the device tree), so their drivers complete I/O on interrupts
+//!   instead of busy-polling. With `multitask`, the waiting tasks sleep. PCI
+//!   drivers can also allocate MSI/MSI-X vectors for their devices.
 //! - `virtio`: use VirtIO devices. This is enabled if any of `virtio-blk`,
 //!   `virtio-net` or `virtio-gpu` is enabled.
 //! - `net`: use network devices. This is enabled if any feature of network
@@ -57,11 +64,12 @@
 #![no_std]
 #![feature(doc_auto_cfg)]
 #![feature(associated_type_defaults)]
+#![feature(allocator_api)]
 
 #[macro_use]
 extern crate log;
 
-#[cfg(feature = "dyn")]
+#[cfg(any(feature = "dyn", feature = "usb_host"))]
 extern crate alloc;
 
 #[macro_use]
@@ -72,6 +80,8 @@ mod drivers;
 mod dummy;
 mod structs;
 
+#[cfg(feature = "irq")]
+mod irq;
 #[cfg(feature = "virtio")]
 mod virtio;
 
@@ -93,7 +103,6 @@ pub use self::structs::AxNetDevice;
 #[cfg(feature = "usb_host")]
 pub use self::structs::AxUSBHostDevice;
 
-
 /// A structure that contains all device drivers, organized by their category.
 #[derive(Default)]
 pub struct AllDevices {
@@ -106,6 +115,7 @@ pub struct AllDevices {
     /// All graphics device drivers.
     #[cfg(feature = "display")]
     pub display: AxDeviceContainer<AxDisplayDevice>,
+    /// All usb host controller drivers.
     #[cfg(feature = "usb_host")]
     pub usb_host: AxDeviceContainer<AxUSBHostDevice>,
 }
@@ -136,11 +146,18 @@ impl AllDevices {
         });
 
         self.probe_bus_devices();
+
+        #[cfg(feature = "usb_host")]
+        self.probe_usb_devices();
     }
 
     /// Adds one device into the corresponding container, according to its device category.
     #[allow(dead_code)]
     fn add_device(&mut self, dev: AxDeviceEnum) {
+        #[cfg(feature = "irq")]
+        if let Some(irq) = dev.irq() {
+            irq::enable_device_irq(&irq);
+        }
         match dev {
             #[cfg(feature = "net")]
             AxDeviceEnum::Net(dev) => self.net.push(dev),
@@ -148,7 +165,7 @@ impl AllDevices {
             AxDeviceEnum::Block(dev) => self.block.push(dev),
             #[cfg(feature = "display")]
             AxDeviceEnum::Display(dev) => self.display.push(dev),
-          #[cfg(feature = "usb_host")]
+            #[cfg(feature = "usb_host")]
             AxDeviceEnum::USBHost(dev) => self.usb_host.push(dev),
         }
     }
@@ -192,8 +209,22 @@ pub fn init_drivers() -> AllDevices {
         for (i, dev) in all_devs.usb_host.iter().enumerate() {
             assert_eq!(dev.device_type(), DeviceType::USBHost);
             debug!("  usb host controller {}: {:?}", i, dev.device_name());
+            for usb_dev in dev.info().devices.iter() {
+                debug!("    {:?}", usb_dev);
+                for interface in usb_dev.interfaces() {
+                    let d = &interface.descriptor;
+                    debug!(
+                        "      interface {}: class {:02x}.{:02x}.{:02x}, {} endpoints",
+                        d.interface_number,
+                        d.class,
+                        d.subclass,
+                        d.protocol,
+                        interface.endpoints.len(),
+                    );
+                }
+            }
         }
     }
-    
+
     all_devs
 }
@@ -28,9 +28,10 @@ macro_rules! register_display_driver {
 
 macro_rules! register_usb_host_driver {
     ($driver_type:ty, $device_type:ty) => {
-        /// The unified type of the NIC devices.
+        /// The unified type of the usb host controllers, shared by the class
+        /// drivers bound to their devices.
         #[cfg(not(feature = "dyn"))]
-        pub type AxUSBHostDevice = $device_type;
+        pub type AxUSBHostDevice = driver_usb::host::SharedHost<$device_type>;
     };
 }
 
@@ -77,5 +78,15 @@ macro_rules! for_each_drivers {
             type $drv_type = crate::drivers::VL805Driver;
             $code
         }
+        #[cfg(usb_host_dev = "xhci")]
+        {
+            type $drv_type = crate::drivers::XhciDriver;
+            $code
+        }
+        #[cfg(block_dev = "usb-storage")]
+        {
+            type $drv_type = crate::drivers::UsbStorageDriver;
+            $code
+        }
     }};
 }
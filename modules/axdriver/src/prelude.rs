@@ -1,6 +1,6 @@
 //! Device driver prelude that includes some traits and types.
 
-pub use driver_common::{BaseDriverOps, DevError, DevResult, DeviceType};
+pub use driver_common::{BaseDriverOps, DevError, DevResult, DeviceIrq, DeviceType};
 
 #[cfg(feature = "block")]
 pub use {crate::structs::AxBlockDevice, driver_block::BlockDriverOps};
@@ -8,3 +8,8 @@ pub use {crate::structs::AxBlockDevice, driver_block::BlockDriverOps};
 pub use {crate::structs::AxDisplayDevice, driver_display::DisplayDriverOps};
 #[cfg(feature = "net")]
 pub use {crate::structs::AxNetDevice, driver_net::NetDriverOps};
+#[cfg(feature = "usb_host")]
+pub use {
+    crate::structs::AxUSBHostDevice,
+    driver_usb::host::{SharedHost, USBHostDriverOps},
+};
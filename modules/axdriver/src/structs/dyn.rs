@@ -14,7 +14,7 @@ pub type AxBlockDevice = Box<dyn BlockDriverOps>;
 pub type AxDisplayDevice = Box<dyn DisplayDriverOps>;
 #[cfg(feature = "usb_host")]
 /// The unified type of the usb host devices.
-pub type AxUSBHostDevice = Box<dyn USBHostDriverOps>;
+pub type AxUSBHostDevice = SharedHost<dyn USBHostDriverOps>;
 
 impl super::AxDeviceEnum {
     /// Constructs a network device.
@@ -34,10 +34,10 @@ impl super::AxDeviceEnum {
     pub fn from_display(dev: impl DisplayDriverOps + 'static) -> Self {
         Self::Display(Box::new(dev))
     }    
-    /// Constructs a display device.
+    /// Constructs a usb host device.
     #[cfg(feature = "usb_host")]
-    pub fn from_display(dev: impl XhciDriverOps + 'static) -> Self {
-        Self::USBHost(Box::new(dev))
+    pub fn from_usb_host(dev: SharedHost<impl USBHostDriverOps + 'static>) -> Self {
+        Self::USBHost(dev.into_dyn())
     }
 }
 
@@ -2,7 +2,7 @@
 #[cfg_attr(not(feature = "dyn"), path = "static.rs")]
 mod imp;
 
-use driver_common::{BaseDriverOps, DeviceType};
+use driver_common::{BaseDriverOps, DeviceIrq, DeviceType};
 
 pub use imp::*;
 
@@ -17,7 +17,7 @@ pub enum AxDeviceEnum {
     Block(AxBlockDevice),
     /// Graphic display device.
     #[cfg(feature = "display")]
-    Display(AxDisplayDevice),    
+    Display(AxDisplayDevice),
     /// USB host controller.
     #[cfg(feature = "usb_host")]
     USBHost(AxUSBHostDevice),
@@ -55,4 +55,20 @@ impl BaseDriverOps for AxDeviceEnum {
             _ => unreachable!(),
         }
     }
+
+    #[inline]
+    #[allow(unreachable_patterns)]
+    fn irq(&self) -> Option<DeviceIrq> {
+        match self {
+            #[cfg(feature = "net")]
+            Self::Net(dev) => dev.irq(),
+            #[cfg(feature = "block")]
+            Self::Block(dev) => dev.irq(),
+            #[cfg(feature = "display")]
+            Self::Display(dev) => dev.irq(),
+            #[cfg(feature = "usb_host")]
+            Self::USBHost(dev) => dev.irq(),
+            _ => unreachable!(),
+        }
+    }
 }
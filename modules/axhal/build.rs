@@ -9,6 +9,7 @@ fn main() {
 
     println!("cargo:rustc-cfg=platform=\"{}\"", platform);
     println!("cargo:rustc-cfg=platform_family=\"{}\"", axconfig::FAMILY);
+    println!("cargo:rustc-cfg=gic_version=\"{}\"", axconfig::GIC_VERSION);
 }
 
 fn gen_linker_script(arch: &str, platform: &str) -> Result<()> {
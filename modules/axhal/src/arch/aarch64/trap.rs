@@ -4,8 +4,12 @@ use aarch64_cpu::registers::{ESR_EL1, FAR_EL1};
 use tock_registers::interfaces::Readable;
 
 use super::TrapFrame;
+use crate::trap::{ExceptionKind, MappingFlags};
 
-global_asm!(include_str!("trap.S"));
+global_asm!(
+    include_str!("trap.S"),
+    overflow_stack_top = sym crate::arch::__PERCPU_OVERFLOW_STACK_TOP,
+);
 
 #[repr(u8)]
 #[derive(Debug)]
@@ -35,46 +39,87 @@ fn invalid_exception(tf: &TrapFrame, kind: TrapKind, source: TrapSource) {
     );
 }
 
+/// Fault status codes (`DFSC` / `IFSC` in ISS) of translation faults, access
+/// flag faults and permission faults, ignoring the level bits.
+const FSC_PAGE_FAULTS: [u64; 3] = [0b000100, 0b001000, 0b001100];
+const FSC_ALIGNMENT_FAULT: u64 = 0b100001;
+
+fn handle_page_fault(tf: &TrapFrame, iss: u64, is_instr: bool, is_user: bool) {
+    let vaddr = FAR_EL1.get() as usize;
+    let access_flags = if is_instr {
+        MappingFlags::EXECUTE
+    } else if iss & (1 << 6) != 0 {
+        // WnR: the abort is caused by a write
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    };
+    if !is_user {
+        crate::trap::check_stack_overflow(vaddr, tf.elr as _, tf.r[29] as _);
+    }
+    if crate::trap::handle_page_fault(vaddr, access_flags, is_user) {
+        return;
+    }
+    if is_user {
+        warn!(
+            "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x} ({:?})",
+            tf.elr, vaddr, iss, access_flags
+        );
+    } else {
+        panic!(
+            "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x} ({:?}):\n{:#x?}",
+            tf.elr, vaddr, iss, access_flags, tf,
+        );
+    }
+}
+
 #[no_mangle]
 fn handle_sync_exception(tf: &mut TrapFrame) {
+    use ESR_EL1::EC::Value as EC;
+
     let esr = ESR_EL1.extract();
-    match esr.read_as_enum(ESR_EL1::EC) {
-        Some(ESR_EL1::EC::Value::Brk64) => {
-            let iss = esr.read(ESR_EL1::ISS);
+    let iss = esr.read(ESR_EL1::ISS);
+    let ec = esr.read_as_enum(ESR_EL1::EC);
+    // SPSR_EL1.M[3:2] is the exception level that the exception is taken from
+    let is_user = tf.spsr & 0b1100 == 0;
+    let kind = match ec {
+        Some(EC::DataAbortLowerEL | EC::DataAbortCurrentEL)
+        | Some(EC::InstrAbortLowerEL | EC::InstrAbortCurrentEL) => {
+            let fsc = iss & 0b111111;
+            if FSC_PAGE_FAULTS.contains(&(fsc & !0b11)) {
+                let is_instr = matches!(ec, Some(EC::InstrAbortLowerEL | EC::InstrAbortCurrentEL));
+                return handle_page_fault(tf, iss, is_instr, is_user);
+            } else if fsc == FSC_ALIGNMENT_FAULT {
+                ExceptionKind::Misaligned
+            } else {
+                ExceptionKind::Other(esr.read(ESR_EL1::EC) as _)
+            }
+        }
+        Some(EC::Brk64) => ExceptionKind::Breakpoint,
+        Some(EC::SVC64) => ExceptionKind::Syscall,
+        Some(EC::Unknown) => ExceptionKind::IllegalInstruction,
+        Some(EC::PCAlignmentFault | EC::SPAlignmentFault) => ExceptionKind::Misaligned,
+        _ => ExceptionKind::Other(esr.read(ESR_EL1::EC) as _),
+    };
+    if crate::trap::handle_exception(kind, tf, is_user) {
+        return;
+    }
+    match kind {
+        ExceptionKind::Breakpoint => {
             debug!("BRK #{:#x} @ {:#x} ", iss, tf.elr);
             tf.elr += 4;
         }
-        Some(ESR_EL1::EC::Value::SVC64) => {
+        ExceptionKind::Syscall => {
             warn!("No supervisor call is supported currently!");
         }
-        Some(ESR_EL1::EC::Value::DataAbortLowerEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            warn!(
-                "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss
-            );
-        }
-        Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            panic!(
-                "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss,
-                tf,
-            );
-        }
         _ => {
             panic!(
-                "Unhandled synchronous exception @ {:#x}: ESR={:#x} (EC {:#08b}, ISS {:#x})",
+                "Unhandled synchronous exception @ {:#x}: ESR={:#x} (EC {:#08b}, ISS {:#x}), FAR={:#x}",
                 tf.elr,
                 esr.get(),
                 esr.read(ESR_EL1::EC),
-                esr.read(ESR_EL1::ISS),
+                iss,
+                FAR_EL1.get(),
             );
         }
     }
@@ -12,3 +12,87 @@ cfg_if::cfg_if! {
         pub use self::aarch64::*;
     }
 }
+
+use core::ops::Range;
+
+/// Size of the emergency stack of each CPU.
+const OVERFLOW_STACK_SIZE: usize = 0x4000; // 16K
+
+#[repr(C, align(16))]
+struct OverflowStack([u8; OVERFLOW_STACK_SIZE]);
+
+/// Emergency stacks that trap handlers switch to when the kernel stack
+/// overflows, as the overflowed stack cannot hold the trap frame.
+static mut OVERFLOW_STACKS: [OverflowStack; axconfig::SMP] = {
+    const EMPTY: OverflowStack = OverflowStack([0; OVERFLOW_STACK_SIZE]);
+    [EMPTY; axconfig::SMP]
+};
+
+/// The top of the emergency stack of each CPU.
+#[percpu::def_percpu]
+static OVERFLOW_STACK_TOP: usize = 0;
+
+/// The lowest address of the kernel stack that each CPU is running on. The page
+/// below it is an unmapped guard page, or it is 0 if the stack is not guarded.
+#[percpu::def_percpu]
+static KERNEL_STACK_BOTTOM: usize = 0;
+
+/// The top of the kernel stack that each CPU is running on.
+#[percpu::def_percpu]
+static KERNEL_STACK_TOP: usize = 0;
+
+/// Sets up the emergency stack of the current CPU.
+pub(crate) fn init_overflow_stack(cpu_id: usize) {
+    unsafe {
+        let stack = core::ptr::addr_of!(OVERFLOW_STACKS[cpu_id]);
+        OVERFLOW_STACK_TOP.write_current_raw(stack as usize + OVERFLOW_STACK_SIZE);
+    }
+}
+
+/// Returns the top of the emergency stack of the current CPU.
+#[cfg(target_arch = "x86_64")]
+#[allow(dead_code)]
+pub(crate) fn overflow_stack_top() -> usize {
+    unsafe { OVERFLOW_STACK_TOP.read_current_raw() }
+}
+
+/// Sets the bounds of the kernel stack that the current CPU is running on, or
+/// is switching to.
+///
+/// The page below `bottom` must be an unmapped guard page, then a page fault on
+/// it is reported as a stack overflow (see [`TrapHandler::handle_stack_overflow`]).
+/// Set `bottom` to 0 for stacks without guard pages.
+///
+/// It must be called with IRQs disabled.
+///
+/// [`TrapHandler::handle_stack_overflow`]: crate::trap::TrapHandler::handle_stack_overflow
+pub fn set_kernel_stack(bottom: usize, top: usize) {
+    unsafe {
+        KERNEL_STACK_BOTTOM.write_current_raw(bottom);
+        KERNEL_STACK_TOP.write_current_raw(top);
+    }
+}
+
+/// Returns the bounds of the kernel stack of the current CPU, or [`None`] if
+/// the stack is not guarded.
+pub(crate) fn kernel_stack() -> Option<Range<usize>> {
+    let (bottom, top) = unsafe {
+        (
+            KERNEL_STACK_BOTTOM.read_current_raw(),
+            KERNEL_STACK_TOP.read_current_raw(),
+        )
+    };
+    if bottom == 0 {
+        None
+    } else {
+        Some(bottom..top)
+    }
+}
+
+/// Whether a kernel page fault at `vaddr` is on the guard page below the
+/// current kernel stack.
+pub(crate) fn is_stack_overflow(vaddr: usize) -> bool {
+    kernel_stack().is_some_and(|stack| {
+        (stack.start.saturating_sub(crate::mem::PAGE_SIZE_4K)..stack.start).contains(&vaddr)
+    })
+}
@@ -36,6 +36,17 @@ pub fn wait_for_irqs() {
     unsafe { riscv::asm::wfi() }
 }
 
+/// Enables interrupts and waits for them atomically, so that the interrupts
+/// arriving after it is called are not missed.
+///
+/// It must be called with interrupts disabled.
+#[inline]
+pub fn enable_irqs_and_wait() {
+    // `wfi` wakes up on pending interrupts even if `sstatus.SIE` is clear
+    unsafe { riscv::asm::wfi() }
+    enable_irqs();
+}
+
 /// Halt the current CPU.
 #[inline]
 pub fn halt() {
@@ -1,12 +1,16 @@
 use riscv::register::scause::{self, Exception as E, Trap};
+use riscv::register::stval;
 
 use super::TrapFrame;
+use crate::trap::{ExceptionKind, MappingFlags};
 
 include_asm_marcos!();
 
 core::arch::global_asm!(
     include_str!("trap.S"),
     trapframe_size = const core::mem::size_of::<TrapFrame>(),
+    kstack_bottom = sym crate::arch::__PERCPU_KERNEL_STACK_BOTTOM,
+    overflow_stack_top = sym crate::arch::__PERCPU_OVERFLOW_STACK_TOP,
 );
 
 fn handle_breakpoint(sepc: &mut usize) {
@@ -14,19 +18,64 @@ fn handle_breakpoint(sepc: &mut usize) {
     *sepc += 2
 }
 
-#[no_mangle]
-fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
-    let scause = scause::read();
-    match scause.cause() {
-        Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
-        Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
+fn handle_page_fault(tf: &TrapFrame, access_flags: MappingFlags, is_user: bool) {
+    let vaddr = stval::read();
+    if !is_user {
+        crate::trap::check_stack_overflow(vaddr, tf.sepc, tf.regs.s0);
+    }
+    if crate::trap::handle_page_fault(vaddr, access_flags, is_user) {
+        return;
+    }
+    if is_user {
+        warn!(
+            "User Page Fault @ {:#x}, fault_vaddr={:#x} ({:?})",
+            tf.sepc, vaddr, access_flags
+        );
+    } else {
+        panic!(
+            "Supervisor Page Fault @ {:#x}, fault_vaddr={:#x} ({:?}):\n{:#x?}",
+            tf.sepc, vaddr, access_flags, tf
+        );
+    }
+}
+
+fn handle_exception(tf: &mut TrapFrame, cause: E, code: usize, is_user: bool) {
+    let kind = match cause {
+        E::Breakpoint => ExceptionKind::Breakpoint,
+        E::IllegalInstruction => ExceptionKind::IllegalInstruction,
+        E::InstructionMisaligned | E::LoadMisaligned | E::StoreMisaligned => {
+            ExceptionKind::Misaligned
+        }
+        E::UserEnvCall => ExceptionKind::Syscall,
+        _ => ExceptionKind::Other(code),
+    };
+    if crate::trap::handle_exception(kind, tf, is_user) {
+        return;
+    }
+    match cause {
+        E::Breakpoint => handle_breakpoint(&mut tf.sepc),
         _ => {
             panic!(
-                "Unhandled trap {:?} @ {:#x}:\n{:#x?}",
-                scause.cause(),
+                "Unhandled trap {:?} @ {:#x}, stval={:#x}:\n{:#x?}",
+                cause,
                 tf.sepc,
+                stval::read(),
                 tf
             );
         }
     }
 }
+
+#[no_mangle]
+fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
+    let scause = scause::read();
+    match scause.cause() {
+        Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
+        Trap::Exception(E::LoadPageFault) => handle_page_fault(tf, MappingFlags::READ, from_user),
+        Trap::Exception(E::StorePageFault) => handle_page_fault(tf, MappingFlags::WRITE, from_user),
+        Trap::Exception(E::InstructionPageFault) => {
+            handle_page_fault(tf, MappingFlags::EXECUTE, from_user)
+        }
+        Trap::Exception(e) => handle_exception(tf, e, scause.code(), from_user),
+    }
+}
@@ -1,11 +1,16 @@
 use core::fmt;
 
+use x86::irq::DOUBLE_FAULT_VECTOR;
 use x86_64::addr::VirtAddr;
 use x86_64::structures::idt::{Entry, HandlerFunc, InterruptDescriptorTable};
 use x86_64::structures::DescriptorTablePointer;
 
 const NUM_INT: usize = 256;
 
+/// The index in the Interrupt Stack Table (IST) of the emergency stack, which
+/// the CPU switches to on double faults, e.g., by kernel stack overflows.
+pub(crate) const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
 /// A wrapper of the Interrupt Descriptor Table (IDT).
 #[repr(transparent)]
 pub struct IdtStruct {
@@ -32,7 +37,10 @@ impl IdtStruct {
             )
         };
         for i in 0..NUM_INT {
-            entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            let opts = entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            if i == DOUBLE_FAULT_VECTOR as usize {
+                unsafe { opts.set_stack_index(DOUBLE_FAULT_IST_INDEX) };
+            }
         }
         idt
     }
@@ -14,6 +14,7 @@ use x86_64::instructions::interrupts;
 pub use self::context::{ExtendedState, FxsaveArea, TaskContext, TrapFrame};
 pub use self::gdt::GdtStruct;
 pub use self::idt::IdtStruct;
+pub(crate) use self::idt::DOUBLE_FAULT_IST_INDEX;
 pub use x86_64::structures::tss::TaskStateSegment;
 
 /// Allows the current CPU to respond to interrupts.
@@ -48,6 +49,20 @@ pub fn wait_for_irqs() {
     }
 }
 
+/// Enables interrupts and waits for them atomically, so that the interrupts
+/// arriving after it is called are not missed.
+///
+/// It must be called with interrupts disabled.
+#[inline]
+pub fn enable_irqs_and_wait() {
+    if cfg!(target_os = "none") {
+        // interrupts are not recognized until the instruction after `sti`
+        unsafe { asm!("sti; hlt") }
+    } else {
+        core::hint::spin_loop()
+    }
+}
+
 /// Halt the current CPU.
 #[inline]
 pub fn halt() {
@@ -1,33 +1,56 @@
 use x86::{controlregs::cr2, irq::*};
+use x86_64::structures::idt::PageFaultErrorCode;
 
 use super::context::TrapFrame;
+use crate::trap::{ExceptionKind, MappingFlags};
 
 core::arch::global_asm!(include_str!("trap.S"));
 
 const IRQ_VECTOR_START: u8 = 0x20;
 const IRQ_VECTOR_END: u8 = 0xff;
 
-#[no_mangle]
-fn x86_trap_handler(tf: &TrapFrame) {
+fn handle_page_fault(tf: &TrapFrame) {
+    let vaddr = unsafe { cr2() };
+    let err = PageFaultErrorCode::from_bits_truncate(tf.error_code);
+    let access_flags = if err.contains(PageFaultErrorCode::INSTRUCTION_FETCH) {
+        MappingFlags::EXECUTE
+    } else if err.contains(PageFaultErrorCode::CAUSED_BY_WRITE) {
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    };
+    let is_user = tf.is_user();
+    if !is_user {
+        crate::trap::check_stack_overflow(vaddr, tf.rip as _, tf.rbp as _);
+    }
+    if crate::trap::handle_page_fault(vaddr, access_flags, is_user) {
+        return;
+    }
+    if is_user {
+        warn!(
+            "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x} ({:?})",
+            tf.rip, vaddr, tf.error_code, err,
+        );
+    } else {
+        panic!(
+            "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x} ({:?}):\n{:#x?}",
+            tf.rip, vaddr, tf.error_code, err, tf,
+        );
+    }
+}
+
+fn handle_exception(tf: &mut TrapFrame) {
+    let kind = match tf.vector as u8 {
+        BREAKPOINT_VECTOR => ExceptionKind::Breakpoint,
+        INVALID_OPCODE_VECTOR => ExceptionKind::IllegalInstruction,
+        ALIGNMENT_CHECK_VECTOR => ExceptionKind::Misaligned,
+        _ => ExceptionKind::Other(tf.vector as _),
+    };
+    let is_user = tf.is_user();
+    if crate::trap::handle_exception(kind, tf, is_user) {
+        return;
+    }
     match tf.vector as u8 {
-        PAGE_FAULT_VECTOR => {
-            if tf.is_user() {
-                warn!(
-                    "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                );
-            } else {
-                panic!(
-                    "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                    tf,
-                );
-            }
-        }
         BREAKPOINT_VECTOR => debug!("#BP @ {:#x} ", tf.rip),
         GENERAL_PROTECTION_FAULT_VECTOR => {
             panic!(
@@ -35,7 +58,6 @@ fn x86_trap_handler(tf: &TrapFrame) {
                 tf.rip, tf.error_code, tf
             );
         }
-        IRQ_VECTOR_START..=IRQ_VECTOR_END => crate::trap::handle_irq_extern(tf.vector as _),
         _ => {
             panic!(
                 "Unhandled exception {} (error_code = {:#x}) @ {:#x}:\n{:#x?}",
@@ -44,3 +66,19 @@ fn x86_trap_handler(tf: &TrapFrame) {
         }
     }
 }
+
+#[no_mangle]
+fn x86_trap_handler(tf: &mut TrapFrame) {
+    match tf.vector as u8 {
+        PAGE_FAULT_VECTOR => handle_page_fault(tf),
+        DOUBLE_FAULT_VECTOR => {
+            // the page fault on the guard page cannot be delivered, as the
+            // CPU fails to push the trap frame onto the overflowed stack
+            let vaddr = unsafe { cr2() };
+            crate::trap::check_stack_overflow(vaddr, tf.rip as _, tf.rbp as _);
+            panic!("#DF @ {:#x}, fault_vaddr={:#x}:\n{:#x?}", tf.rip, vaddr, tf);
+        }
+        IRQ_VECTOR_START..=IRQ_VECTOR_END => crate::trap::handle_irq_extern(tf.vector as _),
+        _ => handle_exception(tf),
+    }
+}
@@ -0,0 +1,80 @@
+//! Stack backtraces by walking the frame pointers.
+//!
+//! It requires the kernel to be built with frame pointers (the
+//! `-C force-frame-pointers=yes` flag of `rustc`), otherwise the backtrace may
+//! be incomplete.
+
+use core::fmt;
+use core::ops::Range;
+
+/// The maximum number of frames to print.
+const MAX_FRAMES: usize = 64;
+
+/// The offset of the frame record `[previous frame pointer, return address]`
+/// from the frame pointer.
+///
+/// On RISC-V, the frame pointer points to the top of the frame, right above
+/// the record.
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const FRAME_RECORD_OFFSET: isize = -2 * core::mem::size_of::<usize>() as isize;
+#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+const FRAME_RECORD_OFFSET: isize = 0;
+
+/// A backtrace of a kernel stack.
+///
+/// It is formatted as the program counter where it starts, followed by the
+/// return addresses of the call frames.
+pub struct Backtrace {
+    pc: usize,
+    fp: usize,
+    stack: Range<usize>,
+}
+
+impl Backtrace {
+    /// Creates a backtrace that starts from the given program counter and
+    /// frame pointer.
+    ///
+    /// Only the frame records within `stack` are read, so an invalid frame
+    /// pointer stops the backtrace instead of causing a fault.
+    pub const fn new(pc: usize, fp: usize, stack: Range<usize>) -> Self {
+        Self { pc, fp, stack }
+    }
+
+    /// Returns an iterator over the return addresses of the call frames,
+    /// starting from the innermost one.
+    pub fn return_addrs(&self) -> impl Iterator<Item = usize> + '_ {
+        const RECORD_SIZE: usize = 2 * core::mem::size_of::<usize>();
+        let mut fp = self.fp;
+        core::iter::from_fn(move || {
+            let record = fp.wrapping_add_signed(FRAME_RECORD_OFFSET);
+            if record % core::mem::align_of::<usize>() != 0
+                || record < self.stack.start
+                || record.checked_add(RECORD_SIZE)? > self.stack.end
+            {
+                return None;
+            }
+            // Safety: the frame record is within the stack.
+            let [prev_fp, ra] = unsafe { (record as *const [usize; 2]).read() };
+            if ra == 0 || prev_fp <= fp {
+                // the outermost frame, or a corrupted frame pointer, as the
+                // stack grows downwards
+                fp = usize::MAX;
+            } else {
+                fp = prev_fp;
+            }
+            (ra != 0).then_some(ra)
+        })
+        .take(MAX_FRAMES)
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "backtrace:")?;
+        writeln!(f, "  #0  {:#x}", self.pc)?;
+        for (i, ra) in self.return_addrs().enumerate() {
+            writeln!(f, "  #{:<2} {:#x}", i + 1, ra)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Device tree (FDT) passed by the bootloader.
+//!
+//! The platform boot code hands the physical address of the device tree blob
+//! to this module before calling `rust_main`. If there is none (e.g., on
+//! x86 or on platforms that do not parse it yet), [`get`] returns `None` and
+//! the callers fall back to the hard-coded values in [`axconfig`].
+
+use core::ops::Range;
+
+use lazy_init::LazyInit;
+
+use crate::mem::{phys_to_virt, PhysAddr};
+
+#[doc(no_inline)]
+pub use fdt_parser::{Fdt, FdtError, Node, Region};
+
+struct BootFdt {
+    paddr: PhysAddr,
+    fdt: Fdt<'static>,
+    /// Physical memory mapped by the boot page table.
+    mapped: Range<usize>,
+}
+
+static BOOT_FDT: LazyInit<BootFdt> = LazyInit::new();
+
+/// Parses the device tree at `dtb_paddr`.
+///
+/// `mapped` is the physical memory mapped by the boot page table. The blob
+/// is ignored if it is not entirely inside of it.
+#[allow(dead_code)]
+pub(crate) fn init(dtb_paddr: usize, mapped: Range<usize>) {
+    let in_mapped =
+        |size: usize| dtb_paddr >= mapped.start && dtb_paddr.saturating_add(size) <= mapped.end;
+    if !in_mapped(Fdt::HEADER_SIZE) {
+        return;
+    }
+    let ptr = phys_to_virt(dtb_paddr.into()).as_ptr();
+    if let Ok(fdt) = unsafe { Fdt::from_ptr(ptr) } {
+        if in_mapped(fdt.total_size()) {
+            BOOT_FDT.init_by(BootFdt {
+                paddr: dtb_paddr.into(),
+                fdt,
+                mapped,
+            });
+        }
+    }
+}
+
+/// Returns the device tree passed by the bootloader, if any.
+pub fn get() -> Option<&'static Fdt<'static>> {
+    BOOT_FDT.try_get().map(|b| &b.fdt)
+}
+
+/// Returns the physical address range of the device tree blob itself.
+pub fn blob_range() -> Option<Range<usize>> {
+    BOOT_FDT.try_get().map(|b| {
+        let start = b.paddr.as_usize();
+        start..start + b.fdt.total_size()
+    })
+}
+
+/// Returns the physical memory that can be accessed before the kernel page
+/// table is set up, i.e., that is mapped by the boot page table.
+pub(crate) fn boot_mapped_memory() -> Option<Range<usize>> {
+    BOOT_FDT.try_get().map(|b| b.mapped.clone())
+}
+
+/// Returns an iterator over the memory regions that must not be used for
+/// allocation: the memory reservation block, the children of
+/// `/reserved-memory`, and the blob itself.
+pub fn reserved_regions() -> impl Iterator<Item = Range<usize>> {
+    let fdt = get();
+    let reservations = fdt.into_iter().flat_map(|fdt| fdt.memory_reservations());
+    let reserved_memory = fdt.into_iter().flat_map(|fdt| fdt.reserved_memory());
+    reservations
+        .chain(reserved_memory)
+        .map(|r| r.address..r.address.saturating_add(r.size))
+        .chain(blob_range())
+}
+
+/// Returns the IRQ number of the first interrupt of the given node.
+///
+/// It returns `None` if the node has no interrupts, or if the interrupt
+/// controller of the platform is not supported yet (only the GIC is).
+#[cfg(feature = "irq")]
+pub fn irq_num(node: &Node) -> Option<usize> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "aarch64")] {
+            crate::platform::irq::translate_fdt_irq(node.interrupts())
+        } else {
+            let _ = node;
+            None
+        }
+    }
+}
+
+/// Returns an iterator over the available nodes compatible with any of the
+/// given strings.
+pub fn find_compatible<'a>(compatible: &'a [&'a str]) -> impl Iterator<Item = Node<'static>> + 'a {
+    get()
+        .into_iter()
+        .flat_map(move |fdt| fdt.find_compatible(compatible))
+        .filter(Node::is_available)
+}
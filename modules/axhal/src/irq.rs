@@ -6,11 +6,47 @@ use crate::platform::irq::MAX_IRQ_COUNT;
 
 pub use crate::platform::irq::{dispatch_irq, register_handler, set_enable};
 
+/// Inter-processor interrupts (IPIs).
+///
+/// [`IPI_IRQ_NUM`] is enabled on all CPUs, and its handler is registered like
+/// the others. It is delivered by the local APIC on x86_64, by SGIs on
+/// aarch64, and by the SBI IPI extension (as software interrupts) on RISC-V.
+#[cfg(feature = "smp")]
+pub use crate::platform::irq::{send_ipi, send_ipi_all_others, IPI_IRQ_NUM};
+
 /// The type if an IRQ handler.
 pub type IrqHandler = handler_table::Handler;
 
 static IRQ_HANDLER_TABLE: HandlerTable<MAX_IRQ_COUNT> = HandlerTable::new();
 
+/// A message signaled interrupt (MSI) vector.
+///
+/// A device raises the interrupt by writing `data` to `address`.
+#[derive(Debug, Clone, Copy)]
+pub struct MsiVector {
+    /// The IRQ number to register the handler for.
+    pub irq_num: usize,
+    /// The message address.
+    pub address: u64,
+    /// The message data.
+    pub data: u32,
+}
+
+/// Allocates an MSI vector, which is routed to the current CPU.
+///
+/// It returns `None` if the vectors are exhausted, or if the platform does not
+/// support MSIs. Currently, they are supported by the local APIC on x86_64,
+/// and by the GICv2m frame (e.g., on QEMU `virt`) on aarch64.
+pub fn alloc_msi() -> Option<MsiVector> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(all(target_arch = "x86_64", platform_family = "x86-pc"), target_arch = "aarch64"))] {
+            crate::platform::irq::alloc_msi()
+        } else {
+            None
+        }
+    }
+}
+
 /// Platform-independent IRQ dispatching.
 #[allow(dead_code)]
 pub(crate) fn dispatch_irq_common(irq_num: usize) {
@@ -37,7 +37,9 @@ extern crate log;
 mod platform;
 
 pub mod arch;
+pub mod backtrace;
 pub mod cpu;
+pub mod dtb;
 pub mod mem;
 pub mod time;
 pub mod trap;
@@ -1,11 +1,15 @@
 //! Physical memory management.
 
-use core::fmt;
+use core::{fmt, ops::Range};
 
 use axconfig::PHYS_MEMORY_END;
+use memory_addr::{align_down_4k, align_up_4k};
 #[doc(no_inline)]
 pub use memory_addr::{PhysAddr, VirtAddr, PAGE_SIZE_4K};
 
+/// The maximum number of disjoint ranges in a [`RangeSet`].
+const MAX_RANGES: usize = 32;
+
 bitflags::bitflags! {
     /// The flags of a physical memory region.
     pub struct MemRegionFlags: usize {
@@ -114,50 +118,177 @@ fn kernel_image_regions() -> impl Iterator<Item = MemRegion> {
 }
 
 /// Returns the default MMIO memory regions (from [`axconfig::MMIO_REGIONS`]).
+///
+/// The VirtIO MMIO devices found in the device tree but not covered by
+/// [`axconfig::MMIO_REGIONS`] are also included.
 #[allow(dead_code)]
 pub(crate) fn default_mmio_regions() -> impl Iterator<Item = MemRegion> {
-    axconfig::MMIO_REGIONS.iter().map(|reg| MemRegion {
-        paddr: reg.0.into(),
-        size: reg.1,
-        flags: MemRegionFlags::RESERVED
-            | MemRegionFlags::DEVICE
-            | MemRegionFlags::READ
-            | MemRegionFlags::WRITE,
-        name: "mmio",
-    })
+    let mut extra = RangeSet::new();
+    for node in crate::dtb::find_compatible(&["virtio,mmio"]) {
+        for reg in node.reg() {
+            let range = align_down_4k(reg.address)..align_up_4k(reg.address + reg.size);
+            let configured = axconfig::MMIO_REGIONS
+                .iter()
+                .any(|&(base, size)| range.start < base + size && base < range.end);
+            if !configured && !extra.overlaps(&range) {
+                extra.insert(range);
+            }
+        }
+    }
+    axconfig::MMIO_REGIONS
+        .iter()
+        .map(|&(base, size)| base..base + size)
+        .chain(extra)
+        .map(|r| MemRegion {
+            paddr: r.start.into(),
+            size: r.end - r.start,
+            flags: MemRegionFlags::RESERVED
+                | MemRegionFlags::DEVICE
+                | MemRegionFlags::READ
+                | MemRegionFlags::WRITE,
+            name: "mmio",
+        })
 }
 
 /// Returns the default free memory regions (kernel image end to physical memory end).
+///
+/// If the device tree is available, the memory banks in it are used instead of
+/// [`axconfig::PHYS_MEMORY_END`], excluding the reserved regions.
 #[allow(dead_code)]
 pub(crate) fn default_free_regions() -> impl Iterator<Item = MemRegion> {
     let start = VirtAddr::from(_ekernel as usize + axconfig::NOCACHE_MEMORY_SIZE).align_up_4k();
-    let start = virt_to_phys( start);
-    let end = PhysAddr::from(axconfig::PHYS_MEMORY_END);
-    core::iter::once(MemRegion {
-        paddr: start,
-        size: end.as_usize() - start.as_usize(),
+    let start = virt_to_phys(start).as_usize();
+
+    let mut free = RangeSet::new();
+    if let Some(fdt) = crate::dtb::get() {
+        for bank in fdt.memory() {
+            free.insert(bank.address..bank.address.saturating_add(bank.size));
+        }
+    }
+    if free.is_empty() {
+        free.insert(start..PHYS_MEMORY_END);
+    } else {
+        free.remove(0..start);
+        for r in crate::dtb::reserved_regions() {
+            free.remove(align_down_4k(r.start)..align_up_4k(r.end));
+        }
+        if let Some(mapped) = crate::dtb::boot_mapped_memory() {
+            // the memory beyond can not be accessed by the allocator
+            free.remove(0..mapped.start);
+            free.remove(mapped.end..usize::MAX);
+        }
+    }
+    free.into_iter().map(|r| MemRegion {
+        paddr: r.start.into(),
+        size: r.end - r.start,
         flags: MemRegionFlags::FREE | MemRegionFlags::READ | MemRegionFlags::WRITE,
         name: "free memory",
     })
 }
 
+/// Returns the memory region of the device tree blob, which is accessed after
+/// the kernel page table is set up.
+#[allow(dead_code)]
+pub(crate) fn dtb_regions() -> impl Iterator<Item = MemRegion> {
+    crate::dtb::blob_range().into_iter().map(|r| {
+        let start = align_down_4k(r.start);
+        MemRegion {
+            paddr: start.into(),
+            size: align_up_4k(r.end) - start,
+            flags: MemRegionFlags::RESERVED | MemRegionFlags::READ,
+            name: "dtb",
+        }
+    })
+}
+
 /// Returns the default free memory regions (kernel image end to physical memory end).
 #[allow(dead_code)]
 pub(crate) fn default_nocache_regions() -> impl Iterator<Item = MemRegion> {
-
     let start = VirtAddr::from(_ekernel as usize).align_up_4k();
-    let start = virt_to_phys( start);
+    let start = virt_to_phys(start);
 
     core::iter::once(MemRegion {
         paddr: start,
         size: axconfig::NOCACHE_MEMORY_SIZE,
-        flags: MemRegionFlags::DEVICE
-            | MemRegionFlags::READ
-            | MemRegionFlags::WRITE,
+        flags: MemRegionFlags::DEVICE | MemRegionFlags::READ | MemRegionFlags::WRITE,
         name: "nocache memory",
     })
 }
 
+/// A set of disjoint physical address ranges with a fixed capacity, used to
+/// carve the memory regions out of the device tree without allocation.
+struct RangeSet {
+    ranges: [Range<usize>; MAX_RANGES],
+    len: usize,
+}
+
+impl RangeSet {
+    const fn new() -> Self {
+        const EMPTY: Range<usize> = 0..0;
+        Self {
+            ranges: [EMPTY; MAX_RANGES],
+            len: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.ranges[..self.len]
+            .iter()
+            .any(|r| range.start < r.end && r.start < range.end)
+    }
+
+    /// Adds a range that does not overlap the existing ones. It is dropped if
+    /// the set is full.
+    fn insert(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        if self.len == MAX_RANGES {
+            warn!("too many memory regions, dropping {:#x?}", range);
+            return;
+        }
+        self.ranges[self.len] = range;
+        self.len += 1;
+    }
+
+    /// Removes the addresses in `range` from the set, splitting the ranges
+    /// that contain it.
+    fn remove(&mut self, range: Range<usize>) {
+        let mut i = 0;
+        while i < self.len {
+            let r = self.ranges[i].clone();
+            if range.end <= r.start || r.end <= range.start {
+                i += 1;
+                continue;
+            }
+            if r.start < range.start {
+                self.ranges[i] = r.start..range.start;
+                self.insert(range.end..r.end.max(range.end));
+                i += 1;
+            } else if range.end < r.end {
+                self.ranges[i] = range.end..r.end;
+                i += 1;
+            } else {
+                self.len -= 1;
+                self.ranges.swap(i, self.len);
+            }
+        }
+    }
+}
+
+impl IntoIterator for RangeSet {
+    type Item = Range<usize>;
+    type IntoIter = core::iter::Take<core::array::IntoIter<Range<usize>, MAX_RANGES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.into_iter().take(self.len)
+    }
+}
+
 /// Fills the `.bss` section with zeros.
 #[allow(dead_code)]
 pub(crate) fn clear_bss() {
@@ -1,7 +1,9 @@
 //! Page table manipulation.
 
 use axalloc::global_allocator;
+use lazy_init::LazyInit;
 use page_table::PagingIf;
+use spinlock::SpinNoIrq;
 
 use crate::mem::{phys_to_virt, virt_to_phys, MemRegionFlags, PhysAddr, VirtAddr, PAGE_SIZE_4K};
 
@@ -64,3 +66,26 @@ cfg_if::cfg_if! {
         pub type PageTable = page_table::aarch64::A64PageTable<PagingIfImpl>;
     }
 }
+
+static KERNEL_PAGE_TABLE: LazyInit<SpinNoIrq<PageTable>> = LazyInit::new();
+
+/// Sets the kernel page table, which is shared by all CPUs.
+///
+/// # Panics
+///
+/// Panics if it has already been set.
+pub fn set_kernel_page_table(page_table: PageTable) {
+    KERNEL_PAGE_TABLE.init_by(SpinNoIrq::new(page_table));
+}
+
+/// Returns the kernel page table, e.g., to map memory regions that are not in
+/// the linear mapping.
+///
+/// Changes to existing mappings should be followed by TLB flushes on all CPUs.
+///
+/// # Panics
+///
+/// Panics if it has not been set by [`set_kernel_page_table`].
+pub fn kernel_page_table() -> &'static SpinNoIrq<PageTable> {
+    &KERNEL_PAGE_TABLE
+}
@@ -1,30 +1,207 @@
+//! ARM Generic Interrupt Controller (GIC).
+//!
+//! The version is selected by `gic-version` in the platform configuration.
+
 use crate::{irq::IrqHandler, mem::phys_to_virt};
-use arm_gic::gic_v2::{GicCpuInterface, GicDistributor};
+#[cfg(feature = "smp")]
+use arm_gic::SgiTarget;
 use arm_gic::{translate_irq, InterruptType};
+use lazy_init::LazyInit;
 use memory_addr::PhysAddr;
 use spinlock::SpinNoIrq;
 
+cfg_if::cfg_if! {
+    if #[cfg(gic_version = "3")] {
+        #[cfg(feature = "smp")]
+        use core::sync::atomic::{AtomicU64, Ordering};
+        use arm_gic::gic_v3::{GicCpuInterface, GicDistributor, GicRedistributor};
+    } else {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use arm_gic::gic_v2::{GicCpuInterface, GicDistributor};
+    }
+}
+
 /// The maximum number of IRQs.
 pub const MAX_IRQ_COUNT: usize = 1024;
 
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = translate_irq(14, InterruptType::PPI).unwrap();
 
+/// The SGI used for IPIs.
+const IPI_SGI: usize = 1;
+
+/// The IPI IRQ number.
+pub const IPI_IRQ_NUM: usize = translate_irq(IPI_SGI, InterruptType::SGI).unwrap();
+
 /// The UART IRQ number.
 pub const UART_IRQ_NUM: usize = translate_irq(axconfig::UART_IRQ, InterruptType::SPI).unwrap();
 
-const GICD_BASE: PhysAddr = PhysAddr::from(axconfig::GICD_PADDR);
-const GICC_BASE: PhysAddr = PhysAddr::from(axconfig::GICC_PADDR);
+/// Compatible strings of the GIC nodes in the device tree.
+#[cfg(gic_version = "3")]
+const GIC_COMPATIBLE: &[&str] = &["arm,gic-v3"];
+#[cfg(not(gic_version = "3"))]
+const GIC_COMPATIBLE: &[&str] = &["arm,cortex-a15-gic", "arm,gic-400"];
 
-static GICD: SpinNoIrq<GicDistributor> =
-    SpinNoIrq::new(GicDistributor::new(phys_to_virt(GICD_BASE).as_mut_ptr()));
+/// Compatible strings of the GICv2m MSI frames in the device tree.
+#[cfg(not(gic_version = "3"))]
+const GICV2M_COMPATIBLE: &[&str] = &["arm,gic-v2m-frame"];
+
+/// Offset of the `MSI_TYPER` register in a GICv2m frame.
+#[cfg(not(gic_version = "3"))]
+const GICV2M_MSI_TYPER: usize = 0x008;
+/// Offset of the `MSI_SETSPI_NS` register in a GICv2m frame, i.e., the MSI
+/// address.
+#[cfg(not(gic_version = "3"))]
+const GICV2M_MSI_SETSPI_NS: u64 = 0x040;
+
+/// A GICv2m frame, which turns MSI writes into a range of SPIs.
+#[cfg(not(gic_version = "3"))]
+struct MsiFrame {
+    base: PhysAddr,
+    spi_base: usize,
+    spi_count: usize,
+    next: AtomicUsize,
+}
+
+#[cfg(not(gic_version = "3"))]
+static GICV2M: LazyInit<MsiFrame> = LazyInit::new();
+
+static GICD: LazyInit<SpinNoIrq<GicDistributor>> = LazyInit::new();
 
 // per-CPU, no lock
-static GICC: GicCpuInterface = GicCpuInterface::new(phys_to_virt(GICC_BASE).as_mut_ptr());
+static GICC: LazyInit<GicCpuInterface> = LazyInit::new();
+
+/// Virtual address of the redistributor region.
+#[cfg(gic_version = "3")]
+static GICR_REGION: LazyInit<usize> = LazyInit::new();
+
+/// Virtual address of the redistributor of the current CPU.
+#[cfg(gic_version = "3")]
+#[percpu::def_percpu]
+static GICR_BASE: usize = 0;
+
+/// The affinities (`MPIDR_EL1`) of the CPUs indexed by CPU ID, to send IPIs
+/// to, or [`u64::MAX`] if the CPU is not up yet.
+#[cfg(all(gic_version = "3", feature = "smp"))]
+static CPU_AFFINITIES: [AtomicU64; axconfig::SMP] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const UNKNOWN: AtomicU64 = AtomicU64::new(u64::MAX);
+    [UNKNOWN; axconfig::SMP]
+};
+
+/// Returns the physical base addresses of GICD and GICC (GICv2), or GICD and
+/// the redistributor region (GICv3), from the device tree if available, or
+/// from the platform configuration otherwise.
+fn gic_base() -> (PhysAddr, PhysAddr) {
+    #[cfg(gic_version = "3")]
+    let default = (axconfig::GICD_PADDR.into(), axconfig::GICR_PADDR.into());
+    #[cfg(not(gic_version = "3"))]
+    let default = (axconfig::GICD_PADDR.into(), axconfig::GICC_PADDR.into());
+    crate::dtb::find_compatible(GIC_COMPATIBLE)
+        .find_map(|node| {
+            let mut reg = node.reg();
+            Some((reg.next()?.address.into(), reg.next()?.address.into()))
+        })
+        .unwrap_or(default)
+}
+
+/// Returns the redistributor of the current CPU.
+#[cfg(gic_version = "3")]
+fn gicr() -> GicRedistributor {
+    GicRedistributor::new(GICR_BASE.read_current() as *mut u8)
+}
+
+/// Locates and initializes the redistributor of the current CPU.
+#[cfg(gic_version = "3")]
+fn init_gicr() {
+    let gicr = unsafe { GicRedistributor::for_current_cpu(*GICR_REGION as *mut u8) }
+        .expect("no GICv3 redistributor for the current CPU");
+    GICR_BASE.write_current(gicr.base() as usize);
+    gicr.init();
+    #[cfg(feature = "smp")]
+    if let Some(affinity) = CPU_AFFINITIES.get(crate::cpu::this_cpu_id()) {
+        affinity.store(arm_gic::gic_v3::current_affinity(), Ordering::Release);
+    }
+}
+
+/// Translates an interrupt specifier in the device tree (`<type num flags>`)
+/// to the IRQ number.
+pub(crate) fn translate_fdt_irq(mut specifier: impl Iterator<Item = u32>) -> Option<usize> {
+    let int_type = match specifier.next()? {
+        0 => InterruptType::SPI,
+        1 => InterruptType::PPI,
+        _ => return None,
+    };
+    translate_irq(specifier.next()? as usize, int_type)
+}
+
+/// Looks for a GICv2m frame in the device tree.
+///
+/// The SPI range is read from the node if present, or from the frame itself.
+#[cfg(not(gic_version = "3"))]
+fn probe_gicv2m() -> Option<MsiFrame> {
+    let node = crate::dtb::find_compatible(GICV2M_COMPATIBLE).next()?;
+    let base = PhysAddr::from(node.reg().next()?.address);
+    let (spi_base, spi_count) = match (
+        node.property_u32("arm,msi-base-spi"),
+        node.property_u32("arm,msi-num-spis"),
+    ) {
+        (Some(spi_base), Some(spi_count)) => (spi_base as usize, spi_count as usize),
+        _ => {
+            let typer = unsafe {
+                (phys_to_virt(base + GICV2M_MSI_TYPER).as_ptr() as *const u32).read_volatile()
+            };
+            (((typer >> 16) & 0x3ff) as usize, (typer & 0x3ff) as usize)
+        }
+    };
+    Some(MsiFrame {
+        base,
+        spi_base,
+        spi_count,
+        next: AtomicUsize::new(0),
+    })
+}
+
+/// Allocates an MSI vector from the GICv2m frame.
+///
+/// The SPIs of the frame are edge-triggered and routed to the primary CPU,
+/// like the others.
+#[cfg(not(gic_version = "3"))]
+pub fn alloc_msi() -> Option<crate::irq::MsiVector> {
+    let frame = GICV2M.try_get()?;
+    let idx = frame
+        .next
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |i| {
+            (i < frame.spi_count).then_some(i + 1)
+        })
+        .ok()?;
+    // `spi_base` is the interrupt ID, not the SPI index
+    let irq_num = frame.spi_base + idx;
+    Some(crate::irq::MsiVector {
+        irq_num,
+        address: frame.base.as_usize() as u64 + GICV2M_MSI_SETSPI_NS,
+        data: irq_num as u32,
+    })
+}
+
+/// Allocates an MSI vector.
+///
+/// GICv3 ITS is not supported yet, so it always returns `None`.
+#[cfg(gic_version = "3")]
+pub fn alloc_msi() -> Option<crate::irq::MsiVector> {
+    None
+}
 
 /// Enables or disables the given IRQ.
+///
+/// On GICv3, SGIs and PPIs are enabled on the current CPU only.
 pub fn set_enable(irq_num: usize, enabled: bool) {
     trace!("GICD set enable: {} {}", irq_num, enabled);
+    #[cfg(gic_version = "3")]
+    if irq_num < arm_gic::SPI_RANGE.start {
+        gicr().set_enable(irq_num, enabled);
+        return;
+    }
     GICD.lock().set_enable(irq_num as _, enabled);
 }
 
@@ -37,6 +214,36 @@ pub fn register_handler(irq_num: usize, handler: IrqHandler) -> bool {
     crate::irq::register_handler_common(irq_num, handler)
 }
 
+/// Sends an IPI to the given CPU.
+///
+/// On GICv3, it is sent to the affinity of the CPU, which is recorded when the
+/// CPU initializes its redistributor. On GICv2, the CPU ID is the number of
+/// the CPU interface.
+#[cfg(feature = "smp")]
+pub fn send_ipi(cpu_id: usize) {
+    #[cfg(gic_version = "3")]
+    match CPU_AFFINITIES
+        .get(cpu_id)
+        .map(|a| a.load(Ordering::Acquire))
+    {
+        Some(affinity) if affinity != u64::MAX => {
+            GICC.send_sgi(IPI_SGI, SgiTarget::Cpu(affinity));
+        }
+        _ => warn!("IPI to CPU {} which is not up", cpu_id),
+    }
+    #[cfg(not(gic_version = "3"))]
+    GICD.lock().send_sgi(IPI_SGI, SgiTarget::Cpu(cpu_id as u64));
+}
+
+/// Sends an IPI to all CPUs except the current one.
+#[cfg(feature = "smp")]
+pub fn send_ipi_all_others() {
+    #[cfg(gic_version = "3")]
+    GICC.send_sgi(IPI_SGI, SgiTarget::AllExceptSelf);
+    #[cfg(not(gic_version = "3"))]
+    GICD.lock().send_sgi(IPI_SGI, SgiTarget::AllExceptSelf);
+}
+
 /// Dispatches the IRQ.
 ///
 /// This function is called by the common interrupt handler. It looks
@@ -46,15 +253,60 @@ pub fn dispatch_irq(_unused: usize) {
     GICC.handle_irq(|irq_num| crate::irq::dispatch_irq_common(irq_num as _));
 }
 
-/// Initializes GICD, GICC on the primary CPU.
+/// Initializes GICD, GICC (and GICR on GICv3) on the primary CPU.
+#[cfg(gic_version = "3")]
 pub(crate) fn init_primary() {
-    info!("Initialize GICv2...");
+    let (gicd_base, gicr_base) = gic_base();
+    info!(
+        "Initialize GICv3 (GICD: {:#x}, GICR: {:#x})...",
+        gicd_base, gicr_base
+    );
+    GICD.init_by(SpinNoIrq::new(GicDistributor::new(
+        phys_to_virt(gicd_base).as_mut_ptr(),
+    )));
+    GICR_REGION.init_by(phys_to_virt(gicr_base).as_usize());
+    GICC.init_by(GicCpuInterface::new());
     GICD.lock().init();
+    init_gicr();
     GICC.init();
+    #[cfg(feature = "smp")]
+    set_enable(IPI_IRQ_NUM, true);
+}
+
+/// Initializes GICD, GICC (and GICR on GICv3) on the primary CPU.
+#[cfg(not(gic_version = "3"))]
+pub(crate) fn init_primary() {
+    let (gicd_base, gicc_base) = gic_base();
+    info!(
+        "Initialize GICv2 (GICD: {:#x}, GICC: {:#x})...",
+        gicd_base, gicc_base
+    );
+    GICD.init_by(SpinNoIrq::new(GicDistributor::new(
+        phys_to_virt(gicd_base).as_mut_ptr(),
+    )));
+    GICC.init_by(GicCpuInterface::new(phys_to_virt(gicc_base).as_mut_ptr()));
+    GICD.lock().init();
+    GICC.init();
+    #[cfg(feature = "smp")]
+    set_enable(IPI_IRQ_NUM, true);
+
+    if let Some(frame) = probe_gicv2m() {
+        info!(
+            "Found GICv2m frame at {:#x}, SPIs: {}..{}",
+            frame.base,
+            frame.spi_base,
+            frame.spi_base + frame.spi_count
+        );
+        GICV2M.init_by(frame);
+    }
 }
 
-/// Initializes GICC on secondary CPUs.
+/// Initializes GICC (and GICR on GICv3) on secondary CPUs.
 #[cfg(feature = "smp")]
 pub(crate) fn init_secondary() {
+    #[cfg(gic_version = "3")]
+    init_gicr();
     GICC.init();
+    // SGIs are banked per CPU
+    set_enable(IPI_IRQ_NUM, true);
 }
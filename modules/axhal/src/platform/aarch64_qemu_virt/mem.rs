@@ -1,9 +1,15 @@
 use crate::mem::{MemRegion, PhysAddr};
 use page_table_entry::{aarch64::A64PTE, GenericPTE, MappingFlags};
 
+/// Size of the normal memory mapped by the boot page table, starting from
+/// [`axconfig::PHYS_MEMORY_BASE`].
+pub(crate) const BOOT_MAPPED_MEMORY_SIZE: usize = 0x1_0000_0000; // 4G
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
-    crate::mem::default_free_regions().chain(crate::mem::default_mmio_regions())
+    crate::mem::default_free_regions()
+        .chain(crate::mem::dtb_regions())
+        .chain(crate::mem::default_mmio_regions())
 }
 
 pub(crate) unsafe fn init_boot_page_table(
@@ -18,10 +24,13 @@ pub(crate) unsafe fn init_boot_page_table(
         MappingFlags::READ | MappingFlags::WRITE | MappingFlags::DEVICE,
         true,
     );
-    // 0x0000_4000_0000..0x0000_8000_0000, 1G block, normal memory
-    boot_pt_l1[1] = A64PTE::new_page(
-        PhysAddr::from(0x4000_0000),
-        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::EXECUTE,
-        true,
-    );
+    // 0x0000_4000_0000..0x0001_4000_0000, 1G blocks, normal memory
+    let blocks = BOOT_MAPPED_MEMORY_SIZE >> 30;
+    for (i, pte) in boot_pt_l1.iter_mut().enumerate().take(1 + blocks).skip(1) {
+        *pte = A64PTE::new_page(
+            PhysAddr::from(i << 30),
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::EXECUTE,
+            true,
+        );
+    }
 }
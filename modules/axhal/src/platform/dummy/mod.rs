@@ -63,6 +63,9 @@ pub mod irq {
     /// The timer IRQ number.
     pub const TIMER_IRQ_NUM: usize = 0;
 
+    /// The IPI IRQ number.
+    pub const IPI_IRQ_NUM: usize = 1;
+
     /// Enables or disables the given IRQ.
     pub fn set_enable(irq_num: usize, enabled: bool) {}
 
@@ -77,6 +80,14 @@ pub mod irq {
     /// up in the IRQ handler table and calls the corresponding handler. If
     /// necessary, it also acknowledges the interrupt controller after handling.
     pub fn dispatch_irq(irq_num: usize) {}
+
+    /// Sends an IPI to the given CPU.
+    #[cfg(feature = "smp")]
+    pub fn send_ipi(cpu_id: usize) {}
+
+    /// Sends an IPI to all CPUs except the current one.
+    #[cfg(feature = "smp")]
+    pub fn send_ipi_all_others() {}
 }
 
 /// Initializes the platform devices for the primary CPU.
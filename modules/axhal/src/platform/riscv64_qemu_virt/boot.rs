@@ -9,10 +9,13 @@ static mut BOOT_STACK: [u8; TASK_STACK_SIZE] = [0; TASK_STACK_SIZE];
 static mut BOOT_PT_SV39: [u64; 512] = [0; 512];
 
 unsafe fn init_boot_page_table() {
-    // 0x8000_0000..0xc000_0000, VRWX_GAD, 1G block
-    BOOT_PT_SV39[2] = (0x80000 << 10) | 0xef;
-    // 0xffff_ffc0_8000_0000..0xffff_ffc0_c000_0000, VRWX_GAD, 1G block
-    BOOT_PT_SV39[0x102] = (0x80000 << 10) | 0xef;
+    for i in 2..2 + (super::mem::BOOT_MAPPED_MEMORY_SIZE >> 30) {
+        let pte = ((i << 18) << 10) as u64 | 0xef;
+        // 0x8000_0000..0x1_8000_0000, VRWX_GAD, 1G blocks
+        BOOT_PT_SV39[i] = pte;
+        // 0xffff_ffc0_8000_0000..0xffff_ffc1_8000_0000, VRWX_GAD, 1G blocks
+        BOOT_PT_SV39[0x100 + i] = pte;
+    }
 }
 
 unsafe fn init_mmu() {
@@ -2,13 +2,12 @@
 
 use crate::irq::IrqHandler;
 use lazy_init::LazyInit;
-use riscv::register::sie;
+use riscv::register::{sie, sip};
 
 /// `Interrupt` bit in `scause`
 pub(super) const INTC_IRQ_BASE: usize = 1 << (usize::BITS - 1);
 
 /// Supervisor software interrupt in `scause`
-#[allow(unused)]
 pub(super) const S_SOFT: usize = INTC_IRQ_BASE + 1;
 
 /// Supervisor timer interrupt in `scause`
@@ -19,16 +18,27 @@ pub(super) const S_EXT: usize = INTC_IRQ_BASE + 9;
 
 static TIMER_HANDLER: LazyInit<IrqHandler> = LazyInit::new();
 
+static IPI_HANDLER: LazyInit<IrqHandler> = LazyInit::new();
+
 /// The maximum number of IRQs.
 pub const MAX_IRQ_COUNT: usize = 1024;
 
 /// The timer IRQ number (supervisor timer interrupt in `scause`).
 pub const TIMER_IRQ_NUM: usize = S_TIMER;
 
+/// The IPI IRQ number (supervisor software interrupt in `scause`).
+pub const IPI_IRQ_NUM: usize = S_SOFT;
+
 macro_rules! with_cause {
-    ($cause: expr, @TIMER => $timer_op: expr, @EXT => $ext_op: expr $(,)?) => {
+    (
+        $cause: expr,
+        @TIMER => $timer_op: expr,
+        @SOFT => $soft_op: expr,
+        @EXT => $ext_op: expr $(,)?
+    ) => {
         match $cause {
             S_TIMER => $timer_op,
+            S_SOFT => $soft_op,
             S_EXT => $ext_op,
             _ => panic!("invalid trap cause: {:#x}", $cause),
         }
@@ -55,6 +65,12 @@ pub fn register_handler(scause: usize, handler: IrqHandler) -> bool {
         } else {
             false
         },
+        @SOFT => if !IPI_HANDLER.is_init() {
+            IPI_HANDLER.init_by(handler);
+            true
+        } else {
+            false
+        },
         @EXT => crate::irq::register_handler_common(scause & !INTC_IRQ_BASE, handler),
     )
 }
@@ -71,10 +87,32 @@ pub fn dispatch_irq(scause: usize) {
             trace!("IRQ: timer");
             TIMER_HANDLER();
         },
+        @SOFT => {
+            trace!("IRQ: IPI");
+            unsafe { sip::clear_ssoft() };
+            if let Some(handler) = IPI_HANDLER.try_get() {
+                handler();
+            }
+        },
         @EXT => crate::irq::dispatch_irq_common(0), // TODO: get IRQ number from PLIC
     );
 }
 
+/// Sends an IPI to the given CPU (hart) via the SBI IPI extension.
+#[cfg(feature = "smp")]
+pub fn send_ipi(cpu_id: usize) {
+    sbi_rt::send_ipi(1, cpu_id);
+}
+
+/// Sends an IPI to all CPUs except the current one.
+#[cfg(feature = "smp")]
+pub fn send_ipi_all_others() {
+    let this_cpu = crate::cpu::this_cpu_id();
+    for cpu_id in (0..axconfig::SMP).filter(|&id| id != this_cpu) {
+        send_ipi(cpu_id);
+    }
+}
+
 pub(super) fn init_percpu() {
     // enable soft interrupts, timer interrupts, and external interrupts
     unsafe {
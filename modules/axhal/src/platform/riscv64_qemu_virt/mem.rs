@@ -1,6 +1,12 @@
 use crate::mem::MemRegion;
 
+/// Size of the memory mapped by the boot page table, starting from
+/// [`axconfig::PHYS_MEMORY_BASE`].
+pub(crate) const BOOT_MAPPED_MEMORY_SIZE: usize = 0x1_0000_0000; // 4G
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
-    crate::mem::default_free_regions().chain(crate::mem::default_mmio_regions())
+    crate::mem::default_free_regions()
+        .chain(crate::mem::dtb_regions())
+        .chain(crate::mem::default_mmio_regions())
 }
@@ -22,6 +22,10 @@ unsafe extern "C" fn rust_entry(cpu_id: usize, dtb: usize) {
     crate::mem::clear_bss();
     crate::cpu::init_primary(cpu_id);
     crate::arch::set_trap_vector_base(trap_vector_base as usize);
+    crate::dtb::init(
+        dtb,
+        axconfig::PHYS_MEMORY_BASE..axconfig::PHYS_MEMORY_BASE + self::mem::BOOT_MAPPED_MEMORY_SIZE,
+    );
     rust_main(cpu_id, dtb);
 }
 
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use core::sync::atomic::{AtomicU8, Ordering};
+
 use lazy_init::LazyInit;
 use memory_addr::PhysAddr;
 use spinlock::SpinNoIrq;
@@ -11,9 +13,12 @@ use self::vectors::*;
 use crate::mem::phys_to_virt;
 
 pub(super) mod vectors {
+    /// Vectors in `MSI_VECTOR_START..APIC_TIMER_VECTOR` are allocated for MSIs.
+    pub const MSI_VECTOR_START: u8 = 0x80;
     pub const APIC_TIMER_VECTOR: u8 = 0xf0;
     pub const APIC_SPURIOUS_VECTOR: u8 = 0xf1;
     pub const APIC_ERROR_VECTOR: u8 = 0xf2;
+    pub const APIC_IPI_VECTOR: u8 = 0xf3;
 }
 
 /// The maximum number of IRQs.
@@ -22,17 +27,24 @@ pub const MAX_IRQ_COUNT: usize = 256;
 /// The timer IRQ number.
 pub const TIMER_IRQ_NUM: usize = APIC_TIMER_VECTOR as usize;
 
+/// The IPI IRQ number.
+pub const IPI_IRQ_NUM: usize = APIC_IPI_VECTOR as usize;
+
 const IO_APIC_BASE: PhysAddr = PhysAddr::from(0xFEC0_0000);
 
+/// Base of the MSI address, i.e., the local APIC in the physical address space.
+const MSI_ADDRESS_BASE: u64 = 0xFEE0_0000;
+
 static mut LOCAL_APIC: Option<LocalApic> = None;
 static mut IS_X2APIC: bool = false;
 static IO_APIC: LazyInit<SpinNoIrq<IoApic>> = LazyInit::new();
+static NEXT_MSI_VECTOR: AtomicU8 = AtomicU8::new(MSI_VECTOR_START);
 
 /// Enables or disables the given IRQ.
 #[cfg(feature = "irq")]
 pub fn set_enable(vector: usize, enabled: bool) {
-    // should not affect LAPIC interrupts
-    if vector < APIC_TIMER_VECTOR as _ {
+    // should not affect LAPIC interrupts and MSIs, which are masked by devices
+    if vector < MSI_VECTOR_START as _ {
         unsafe {
             if enabled {
                 IO_APIC.lock().enable_irq(vector as u8);
@@ -52,6 +64,26 @@ pub fn register_handler(vector: usize, handler: crate::irq::IrqHandler) -> bool
     crate::irq::register_handler_common(vector, handler)
 }
 
+/// Allocates an MSI vector, which is delivered to the local APIC of the
+/// current CPU in fixed, edge-triggered mode.
+#[cfg(feature = "irq")]
+pub fn alloc_msi() -> Option<crate::irq::MsiVector> {
+    let vector = NEXT_MSI_VECTOR
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |v| {
+            (v < APIC_TIMER_VECTOR).then_some(v + 1)
+        })
+        .ok()?;
+    // the destination field is 8 bits without interrupt remapping
+    let apic_id = raw_cpuid::CpuId::new()
+        .get_feature_info()
+        .map_or(0, |finfo| finfo.initial_local_apic_id());
+    Some(crate::irq::MsiVector {
+        irq_num: vector as usize,
+        address: MSI_ADDRESS_BASE | ((apic_id as u64) << 12),
+        data: vector as u32,
+    })
+}
+
 /// Dispatches the IRQ.
 ///
 /// This function is called by the common interrupt handler. It looks
@@ -63,6 +95,19 @@ pub fn dispatch_irq(vector: usize) {
     unsafe { local_apic().end_of_interrupt() };
 }
 
+/// Sends an IPI to the given CPU.
+#[cfg(all(feature = "irq", feature = "smp"))]
+pub fn send_ipi(cpu_id: usize) {
+    unsafe { local_apic().send_ipi(APIC_IPI_VECTOR, raw_apic_id(cpu_id as u8)) };
+}
+
+/// Sends an IPI to all CPUs except the current one.
+#[cfg(all(feature = "irq", feature = "smp"))]
+pub fn send_ipi_all_others() {
+    use x2apic::lapic::IpiAllShorthand;
+    unsafe { local_apic().send_ipi_all(APIC_IPI_VECTOR, IpiAllShorthand::AllExcludingSelf) };
+}
+
 pub(super) fn local_apic<'a>() -> &'a mut LocalApic {
     // It's safe as LAPIC is per-cpu.
     unsafe { LOCAL_APIC.as_mut().unwrap() }
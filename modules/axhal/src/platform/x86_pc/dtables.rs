@@ -1,7 +1,8 @@
 //! Description tables (per-CPU GDT, per-CPU ISS, IDT)
 
-use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment};
+use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment, DOUBLE_FAULT_IST_INDEX};
 use lazy_init::LazyInit;
+use x86_64::VirtAddr;
 
 static IDT: LazyInit<IdtStruct> = LazyInit::new();
 
@@ -16,7 +17,10 @@ fn init_percpu() {
         IDT.load();
         let tss = TSS.current_ref_mut_raw();
         let gdt = GDT.current_ref_mut_raw();
-        tss.init_by(TaskStateSegment::new());
+        let mut new_tss = TaskStateSegment::new();
+        new_tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] =
+            VirtAddr::new(crate::arch::overflow_stack_top() as u64);
+        tss.init_by(new_tss);
         gdt.init_by(GdtStruct::new(tss));
         gdt.load();
         gdt.load_tss();
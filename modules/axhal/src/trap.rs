@@ -1,6 +1,32 @@
 //! Trap handling.
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use crate_interface::{call_interface, def_interface};
+use memory_addr::VirtAddr;
+
+use crate::arch::TrapFrame;
+use crate::backtrace::Backtrace;
+
+#[doc(no_inline)]
+pub use page_table_entry::MappingFlags;
+
+/// Synchronous exceptions other than page faults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionKind {
+    /// A breakpoint instruction (`int3`, `brk` or `ebreak`).
+    Breakpoint,
+    /// An illegal or undefined instruction.
+    IllegalInstruction,
+    /// A misaligned memory access or instruction fetch.
+    Misaligned,
+    /// A system call (`svc` or `ecall`).
+    Syscall,
+    /// Other exceptions, with the architecture-specific code: the vector
+    /// number on x86_64, `ESR_EL1.EC` on AArch64, or the exception code in
+    /// `scause` on RISC-V.
+    Other(usize),
+}
 
 /// Trap handler interface.
 ///
@@ -13,7 +39,85 @@ use crate_interface::{call_interface, def_interface};
 pub trait TrapHandler {
     /// Handles interrupt requests for the given IRQ number.
     fn handle_irq(irq_num: usize);
-    // more e.g.: handle_page_fault();
+    /// Handles a page fault at `vaddr`, e.g., for demand paging.
+    ///
+    /// `access_flags` is the access that caused the fault, which is one of
+    /// [`MappingFlags::READ`], [`MappingFlags::WRITE`] and
+    /// [`MappingFlags::EXECUTE`]. `is_user` is whether the fault is from user
+    /// space.
+    ///
+    /// Returns `true` if the fault is handled, then the faulting instruction is
+    /// executed again. Otherwise, kernel faults cause a panic.
+    fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool;
+    /// Handles a synchronous exception other than page faults. `tf` can be
+    /// modified, e.g., to skip the faulting instruction.
+    ///
+    /// Returns `true` if the exception is handled. Otherwise, breakpoints are
+    /// skipped, and other exceptions from the kernel cause a panic.
+    fn handle_exception(kind: ExceptionKind, tf: &mut TrapFrame, is_user: bool) -> bool;
+    /// Handles a kernel stack overflow, i.e., a page fault at `vaddr` on the
+    /// guard page below the current kernel stack (see
+    /// [`set_kernel_stack`](crate::arch::set_kernel_stack)).
+    ///
+    /// `backtrace` is that of the overflowed stack. It runs on the emergency
+    /// stack of the current CPU, and should not return.
+    fn handle_stack_overflow(vaddr: usize, backtrace: &Backtrace);
+}
+
+/// The type of a page fault handler, see [`TrapHandler::handle_page_fault`].
+pub type PageFaultHandler = fn(VirtAddr, MappingFlags, bool) -> bool;
+
+/// The type of an exception handler, see [`TrapHandler::handle_exception`].
+pub type ExceptionHandler = fn(ExceptionKind, &mut TrapFrame, bool) -> bool;
+
+static PAGE_FAULT_HANDLER: AtomicUsize = AtomicUsize::new(0);
+static EXCEPTION_HANDLER: AtomicUsize = AtomicUsize::new(0);
+
+/// Registers the page fault handler, e.g., for demand paging.
+///
+/// It is called by [`dispatch_page_fault`], which the implementations of
+/// [`TrapHandler::handle_page_fault`] (e.g., the one of `axruntime`) forward
+/// the faults to. Returns `false` if a handler has been registered.
+pub fn register_page_fault_handler(handler: PageFaultHandler) -> bool {
+    PAGE_FAULT_HANDLER
+        .compare_exchange(0, handler as usize, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+}
+
+/// Registers the handler of the exceptions other than page faults.
+///
+/// It is called by [`dispatch_exception`], which the implementations of
+/// [`TrapHandler::handle_exception`] (e.g., the one of `axruntime`) forward
+/// the exceptions to. Returns `false` if a handler has been registered.
+pub fn register_exception_handler(handler: ExceptionHandler) -> bool {
+    EXCEPTION_HANDLER
+        .compare_exchange(0, handler as usize, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+}
+
+/// Calls the page fault handler registered by
+/// [`register_page_fault_handler`]. Returns `false` if there is none, or the
+/// fault is not handled.
+pub fn dispatch_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+    match PAGE_FAULT_HANDLER.load(Ordering::Acquire) {
+        0 => false,
+        handler => {
+            let handler: PageFaultHandler = unsafe { core::mem::transmute(handler) };
+            handler(vaddr, access_flags, is_user)
+        }
+    }
+}
+
+/// Calls the exception handler registered by [`register_exception_handler`].
+/// Returns `false` if there is none, or the exception is not handled.
+pub fn dispatch_exception(kind: ExceptionKind, tf: &mut TrapFrame, is_user: bool) -> bool {
+    match EXCEPTION_HANDLER.load(Ordering::Acquire) {
+        0 => false,
+        handler => {
+            let handler: ExceptionHandler = unsafe { core::mem::transmute(handler) };
+            handler(kind, tf, is_user)
+        }
+    }
 }
 
 /// Call the external IRQ handler.
@@ -21,3 +125,33 @@ pub trait TrapHandler {
 pub(crate) fn handle_irq_extern(irq_num: usize) {
     call_interface!(TrapHandler::handle_irq, irq_num);
 }
+
+/// Call the external page fault handler.
+#[allow(dead_code)]
+pub(crate) fn handle_page_fault(vaddr: usize, access_flags: MappingFlags, is_user: bool) -> bool {
+    call_interface!(
+        TrapHandler::handle_page_fault,
+        VirtAddr::from(vaddr),
+        access_flags,
+        is_user
+    )
+}
+
+/// Call the external exception handler.
+#[allow(dead_code)]
+pub(crate) fn handle_exception(kind: ExceptionKind, tf: &mut TrapFrame, is_user: bool) -> bool {
+    call_interface!(TrapHandler::handle_exception, kind, tf, is_user)
+}
+
+/// Reports a kernel page fault at `vaddr` as a stack overflow if it is on the
+/// guard page below the current kernel stack. `pc` and `fp` are the program
+/// counter and the frame pointer where the fault occurred.
+#[allow(dead_code)]
+pub(crate) fn check_stack_overflow(vaddr: usize, pc: usize, fp: usize) {
+    if crate::arch::is_stack_overflow(vaddr) {
+        let stack = crate::arch::kernel_stack().unwrap();
+        let backtrace = Backtrace::new(pc, fp, stack);
+        call_interface!(TrapHandler::handle_stack_overflow, vaddr, &backtrace);
+        panic!("kernel stack overflow @ {:#x}\n{}", pc, backtrace);
+    }
+}
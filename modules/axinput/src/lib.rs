@@ -0,0 +1,108 @@
+//! [ArceOS](https://github.com/rcore-os/arceos) input module.
+//!
+//! Currently only supports USB boot protocol keyboards and mice. Devices are
+//! polled when the input is read, and bound or released as they are plugged
+//! in or out.
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+#[doc(no_inline)]
+pub use driver_usb::class::hid::MouseEvent;
+
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use axdriver::{prelude::*, AxDeviceContainer};
+use axsync::Mutex;
+use axusb::HotplugEvent;
+use driver_usb::class::hid::{HidDevice, HidEvent};
+use lazy_init::LazyInit;
+
+/// Events not consumed yet are dropped beyond this limit.
+const QUEUE_LEN: usize = 256;
+
+struct InputDevices {
+    devices: Vec<(AxUSBHostDevice, HidDevice)>,
+    keys: VecDeque<u8>,
+    mouse_events: VecDeque<MouseEvent>,
+}
+
+impl InputDevices {
+    fn bind(&mut self, host: &AxUSBHostDevice, dev: &axusb::UsbDevice) {
+        let mut host = host.clone();
+        if let Some(hid) = HidDevice::probe(&mut host, dev) {
+            info!("  use {:?} on {:?}", hid.kind(), host.device_name());
+            self.devices.push((host, hid));
+        }
+    }
+
+    fn poll(&mut self) {
+        let Self {
+            devices,
+            keys,
+            mouse_events,
+        } = self;
+        for (host, dev) in devices.iter_mut() {
+            let res = dev.poll(host, |event| match event {
+                HidEvent::Key(c) => push_bounded(keys, c),
+                HidEvent::Mouse(e) => push_bounded(mouse_events, e),
+            });
+            if let Err(e) = res {
+                warn!("input device {:?}: {:?}", dev.kind(), e);
+            }
+        }
+    }
+}
+
+static INPUT: LazyInit<Mutex<InputDevices>> = LazyInit::new();
+
+/// Initializes the input subsystem by binding the keyboards and mice found
+/// on the given USB host controllers.
+pub fn init_input(mut usb_hosts: AxDeviceContainer<AxUSBHostDevice>) {
+    info!("Initialize input subsystem...");
+
+    let mut input = InputDevices {
+        devices: Vec::new(),
+        keys: VecDeque::new(),
+        mouse_events: VecDeque::new(),
+    };
+    while let Some(host) = usb_hosts.take_one() {
+        for dev in host.info().devices.iter() {
+            input.bind(&host, dev);
+        }
+    }
+    INPUT.init_by(Mutex::new(input));
+
+    axusb::register_hotplug_callback(Box::new(|host: &AxUSBHostDevice, event: &HotplugEvent| {
+        let mut input = INPUT.lock();
+        match event {
+            HotplugEvent::Attached(dev) => input.bind(host, dev),
+            HotplugEvent::Detached(dev) => input
+                .devices
+                .retain(|(h, hid)| !(h.ptr_eq(host) && hid.slot() == dev.slot)),
+        }
+    }));
+}
+
+/// Reads a byte typed on a keyboard, if any.
+pub fn read_byte() -> Option<u8> {
+    let mut input = INPUT.try_get()?.lock();
+    input.poll();
+    input.keys.pop_front()
+}
+
+/// Reads the oldest pending mouse event, if any.
+pub fn read_mouse_event() -> Option<MouseEvent> {
+    let mut input = INPUT.try_get()?.lock();
+    input.poll();
+    input.mouse_events.pop_front()
+}
+
+fn push_bounded<T>(queue: &mut VecDeque<T>, item: T) {
+    if queue.len() >= QUEUE_LEN {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
@@ -14,6 +14,10 @@
 //!
 //! - `smoltcp`: Use [smoltcp] as the underlying network stack. This is enabled
 //!   by default.
+//! - `async`: Enable the asynchronous versions of socket operations, such as
+//!   [`TcpSocket::recv_async`]. They register wakers to the sockets instead of
+//!   blocking the calling task, and a background task polls the network stack
+//!   to wake them. It also enables the `multitask` feature of `axtask`.
 //!
 //! [smoltcp]: https://github.com/smoltcp-rs/smoltcp
 
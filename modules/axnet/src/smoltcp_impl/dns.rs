@@ -51,27 +51,17 @@ impl DnsSocket {
                     ax_err_type!(InvalidInput, "socket query() failed: too long name")
                 }
             })?;
-        loop {
-            SOCKET_SET.poll_interfaces();
-            match SOCKET_SET.with_socket_mut::<dns::Socket, _, _>(handle, |socket| {
+        let addrs = super::block_on(|| {
+            SOCKET_SET.with_socket_mut::<dns::Socket, _, _>(handle, |socket| {
                 socket.get_query_result(query_handle).map_err(|e| match e {
                     GetQueryResultError::Pending => AxError::WouldBlock,
                     GetQueryResultError::Failed => {
                         ax_err_type!(ConnectionRefused, "socket query() failed")
                     }
                 })
-            }) {
-                Ok(n) => {
-                    let mut res = Vec::with_capacity(n.capacity());
-                    for ip in n {
-                        res.push(into_core_ipaddr(ip))
-                    }
-                    return Ok(res);
-                }
-                Err(AxError::WouldBlock) => axtask::yield_now(),
-                Err(e) => return Err(e),
-            }
-        }
+            })
+        })?;
+        Ok(addrs.into_iter().map(into_core_ipaddr).collect())
     }
 }
 
@@ -1,5 +1,7 @@
 use alloc::{boxed::Box, collections::VecDeque};
 use core::ops::{Deref, DerefMut};
+#[cfg(feature = "async")]
+use core::task::Waker;
 
 use axerrno::{ax_err, AxError, AxResult};
 use axsync::Mutex;
@@ -14,6 +16,9 @@ const PORT_NUM: usize = 65536;
 struct ListenTableEntry {
     listen_endpoint: IpListenEndpoint,
     syn_queue: VecDeque<SocketHandle>,
+    /// The waker of an asynchronous `accept`, registered to new connections.
+    #[cfg(feature = "async")]
+    waker: Option<Waker>,
 }
 
 impl ListenTableEntry {
@@ -21,6 +26,8 @@ impl ListenTableEntry {
         Self {
             listen_endpoint,
             syn_queue: VecDeque::with_capacity(LISTEN_QUEUE_SIZE),
+            #[cfg(feature = "async")]
+            waker: None,
         }
     }
 
@@ -110,6 +117,28 @@ impl ListenTable {
         }
     }
 
+    /// Like [`accept`](Self::accept), but registers the waker to all pending
+    /// connections (including the future ones) first, so that it is woken
+    /// when any of them is established.
+    ///
+    /// Only the last registered waker is woken.
+    #[cfg(feature = "async")]
+    pub fn poll_accept(
+        &self,
+        port: u16,
+        waker: &Waker,
+    ) -> AxResult<(SocketHandle, (IpEndpoint, IpEndpoint))> {
+        if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
+            for &handle in &entry.syn_queue {
+                SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                    socket.register_recv_waker(waker)
+                });
+            }
+            entry.waker = Some(waker.clone());
+        }
+        self.accept(port)
+    }
+
     pub fn incoming_tcp_packet(
         &self,
         src: IpEndpoint,
@@ -127,6 +156,10 @@ impl ListenTable {
                 return;
             }
             let mut socket = SocketSetWrapper::new_tcp_socket();
+            #[cfg(feature = "async")]
+            if let Some(waker) = &entry.waker {
+                socket.register_recv_waker(waker);
+            }
             if socket.listen(entry.listen_endpoint).is_ok() {
                 let handle = sockets.add(socket);
                 debug!(
@@ -8,8 +8,12 @@ mod udp;
 use alloc::vec;
 use core::cell::RefCell;
 use core::ops::DerefMut;
+#[cfg(feature = "async")]
+use core::task::{Poll, Waker};
+use core::time::Duration;
 
 use axdriver::prelude::*;
+use axerrno::{AxError, AxResult};
 use axhal::time::{current_time_nanos, NANOS_PER_MICROS};
 use axsync::Mutex;
 use driver_net::{DevError, NetBufPtr};
@@ -49,6 +53,11 @@ const TCP_TX_BUF_LEN: usize = 64 * 1024;
 const UDP_RX_BUF_LEN: usize = 64 * 1024;
 const UDP_TX_BUF_LEN: usize = 64 * 1024;
 const LISTEN_QUEUE_SIZE: usize = 512;
+#[cfg(feature = "async")]
+const POLLER_MAX_DELAY: Duration = Duration::from_millis(10);
+/// The longest sleep between two polls of a NIC without interrupts, which
+/// bounds the latency of the received packets.
+const NO_IRQ_POLL_DELAY: Duration = Duration::from_millis(1);
 
 static LISTEN_TABLE: LazyInit<ListenTable> = LazyInit::new();
 static SOCKET_SET: LazyInit<SocketSetWrapper> = LazyInit::new();
@@ -63,6 +72,7 @@ struct DeviceWrapper {
 struct InterfaceWrapper {
     name: &'static str,
     ether_addr: EthernetAddress,
+    irq: Option<DeviceIrq>,
     dev: Mutex<DeviceWrapper>,
     iface: Mutex<Interface>,
 }
@@ -123,6 +133,12 @@ impl<'a> SocketSetWrapper<'a> {
         ETH0.poll(&self.0);
     }
 
+    /// Returns how long the interfaces can wait before the next poll, or
+    /// `None` if they can wait for the next packet indefinitely.
+    pub fn poll_delay(&self) -> Option<Duration> {
+        ETH0.poll_delay(&self.0)
+    }
+
     pub fn remove(&self, handle: SocketHandle) {
         self.0.lock().remove(handle);
         debug!("socket {}: destroyed", handle);
@@ -134,11 +150,13 @@ impl InterfaceWrapper {
         let mut config = Config::new(HardwareAddress::Ethernet(ether_addr));
         config.random_seed = RANDOM_SEED;
 
+        let irq = dev.irq();
         let mut dev = DeviceWrapper::new(dev);
         let iface = Mutex::new(Interface::new(config, &mut dev, Self::current_time()));
         Self {
             name,
             ether_addr,
+            irq,
             dev: Mutex::new(dev),
             iface,
         }
@@ -177,6 +195,14 @@ impl InterfaceWrapper {
         let timestamp = Self::current_time();
         iface.poll(timestamp, dev.deref_mut(), &mut sockets);
     }
+
+    pub fn poll_delay(&self, sockets: &Mutex<SocketSet>) -> Option<Duration> {
+        let mut iface = self.iface.lock();
+        let sockets = sockets.lock();
+        iface
+            .poll_delay(Self::current_time(), &sockets)
+            .map(|d| Duration::from_micros(d.total_micros()))
+    }
 }
 
 impl DeviceWrapper {
@@ -292,6 +318,93 @@ fn snoop_tcp_packet(buf: &[u8], sockets: &mut SocketSet<'_>) -> Result<(), smolt
     Ok(())
 }
 
+/// Blocks the current task until `f` does not return
+/// [`Err(WouldBlock)`](AxError::WouldBlock), polling the network stack before
+/// every attempt.
+///
+/// If the NIC is interrupt-driven, the task sleeps until the next interrupt of
+/// the NIC or the next timer of the network stack. Otherwise, it sleeps until
+/// the next timer, but at most [`NO_IRQ_POLL_DELAY`].
+pub(crate) fn block_on<F, T>(mut f: F) -> AxResult<T>
+where
+    F: FnMut() -> AxResult<T>,
+{
+    loop {
+        // read the count before polling, so no interrupt is missed
+        let irq_count = ETH0.irq.map(|irq| irq.waiter.irq_count());
+        SOCKET_SET.poll_interfaces();
+        match f() {
+            Ok(t) => return Ok(t),
+            Err(AxError::WouldBlock) => match (ETH0.irq, irq_count) {
+                (Some(irq), Some(count)) => irq.waiter.wait_irq(count, SOCKET_SET.poll_delay()),
+                _ => axtask::sleep(poll_delay(NO_IRQ_POLL_DELAY)),
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Polls `f` asynchronously until it does not return
+/// [`Err(WouldBlock)`](AxError::WouldBlock), polling the network stack before
+/// every attempt.
+///
+/// Before returning `WouldBlock`, `f` must register the given waker to the
+/// sockets it waits for. The sockets are woken when the network stack is
+/// polled by the background task (see [`start_poller`]), so no task busy-polls
+/// the interfaces.
+#[cfg(feature = "async")]
+pub(crate) async fn poll_fn<F, T>(mut f: F) -> AxResult<T>
+where
+    F: FnMut(&Waker) -> AxResult<T>,
+{
+    start_poller();
+    let res = core::future::poll_fn(|cx| {
+        SOCKET_SET.poll_interfaces();
+        match f(cx.waker()) {
+            Err(AxError::WouldBlock) => Poll::Pending,
+            res => Poll::Ready(res),
+        }
+    })
+    .await;
+    // transmit the packets queued by `f` now, the poller may be sleeping
+    SOCKET_SET.poll_interfaces();
+    res
+}
+
+/// Spawns the task that polls the network stack in background, if not yet.
+///
+/// If the NIC is interrupt-driven, the task sleeps until the next interrupt of
+/// the NIC or the next timer of the network stack, but at most
+/// [`POLLER_MAX_DELAY`]. Otherwise, it sleeps until the next timer, but at
+/// most [`NO_IRQ_POLL_DELAY`].
+#[cfg(feature = "async")]
+fn start_poller() {
+    use core::sync::atomic::{AtomicBool, Ordering};
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    if STARTED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    axtask::spawn(|| loop {
+        let irq_count = ETH0.irq.map(|irq| irq.waiter.irq_count());
+        SOCKET_SET.poll_interfaces();
+        match (ETH0.irq, irq_count) {
+            (Some(irq), Some(count)) => {
+                // sockets added after the last poll may need an earlier poll
+                irq.waiter
+                    .wait_irq(count, Some(poll_delay(POLLER_MAX_DELAY)));
+            }
+            _ => axtask::sleep(poll_delay(NO_IRQ_POLL_DELAY)),
+        }
+    });
+}
+
+/// Returns the time until the network stack needs to be polled for its
+/// timers, but at most `max`.
+fn poll_delay(max: Duration) -> Duration {
+    SOCKET_SET.poll_delay().map_or(max, |d| d.min(max))
+}
+
 /// Poll the network stack.
 ///
 /// It may receive packets from the NIC and process them, and transmit queued
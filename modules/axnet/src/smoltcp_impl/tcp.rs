@@ -119,60 +119,37 @@ impl TcpSocket {
     ///
     /// The local port is generated automatically.
     pub fn connect(&self, remote_addr: SocketAddr) -> AxResult {
-        self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
-            // SAFETY: no other threads can read or write these fields.
-            let handle = unsafe { self.handle.get().read() }
-                .unwrap_or_else(|| SOCKET_SET.add(SocketSetWrapper::new_tcp_socket()));
-
-            // TODO: check remote addr unreachable
-            let remote_endpoint = from_core_sockaddr(remote_addr);
-            let bound_endpoint = self.bound_endpoint()?;
-            let iface = &ETH0.iface;
-            let (local_endpoint, remote_endpoint) = SOCKET_SET
-                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                    socket
-                        .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
-                        .or_else(|e| match e {
-                            ConnectError::InvalidState => {
-                                ax_err!(BadState, "socket connect() failed")
-                            }
-                            ConnectError::Unaddressable => {
-                                ax_err!(ConnectionRefused, "socket connect() failed")
-                            }
-                        })?;
-                    Ok((
-                        socket.local_endpoint().unwrap(),
-                        socket.remote_endpoint().unwrap(),
-                    ))
-                })?;
-            unsafe {
-                // SAFETY: no other threads can read or write these fields as we
-                // have changed the state to `BUSY`.
-                self.local_addr.get().write(local_endpoint);
-                self.peer_addr.get().write(remote_endpoint);
-                self.handle.get().write(Some(handle));
-            }
-            Ok(())
-        })
-        .unwrap_or_else(|_| ax_err!(AlreadyExists, "socket connect() failed: already connected"))?; // EISCONN
+        self.start_connect(remote_addr)?;
 
         // Here our state must be `CONNECTING`, and only one thread can run here.
         if self.is_nonblocking() {
             Err(AxError::WouldBlock)
         } else {
-            self.block_on(|| {
-                let PollState { writable, .. } = self.poll_connect()?;
-                if !writable {
-                    Err(AxError::WouldBlock)
-                } else if self.get_state() == STATE_CONNECTED {
-                    Ok(())
-                } else {
-                    ax_err!(ConnectionRefused, "socket connect() failed")
-                }
-            })
+            self.block_on(|| self.check_connected())
         }
     }
 
+    /// Connects to the given address and port asynchronously.
+    ///
+    /// It's the asynchronous version of [`connect`](Self::connect), which
+    /// waits for the connection without blocking the calling thread, even if
+    /// the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn connect_async(&self, remote_addr: SocketAddr) -> AxResult {
+        self.start_connect(remote_addr)?;
+
+        // SAFETY: `self.handle` should be initialized above.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        super::poll_fn(|waker| {
+            // register first, so the state change after the check is not missed
+            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                socket.register_send_waker(waker)
+            });
+            self.check_connected()
+        })
+        .await
+    }
+
     /// Binds an unbound socket to the given address and port.
     ///
     /// If the given port is 0, it generates one automatically.
@@ -236,6 +213,26 @@ impl TcpSocket {
         })
     }
 
+    /// Accepts a new connection asynchronously.
+    ///
+    /// It's the asynchronous version of [`accept`](Self::accept), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn accept_async(&self) -> AxResult<TcpSocket> {
+        if !self.is_listening() {
+            return ax_err!(InvalidInput, "socket accept() failed: not listen");
+        }
+
+        // SAFETY: `self.local_addr` should be initialized after `bind()`.
+        let local_port = unsafe { self.local_addr.get().read().port };
+        super::poll_fn(|waker| {
+            let (handle, (local_addr, peer_addr)) = LISTEN_TABLE.poll_accept(local_port, waker)?;
+            debug!("TCP socket accepted a new connection {}", peer_addr);
+            Ok(TcpSocket::new_connected(handle, local_addr, peer_addr))
+        })
+        .await
+    }
+
     /// Close the connection.
     pub fn shutdown(&self) -> AxResult {
         // stream
@@ -280,26 +277,36 @@ impl TcpSocket {
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
         self.block_on(|| {
+            SOCKET_SET
+                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| Self::recv_impl(socket, buf))
+        })
+    }
+
+    /// Receives data from the socket asynchronously, stores it in the given
+    /// buffer.
+    ///
+    /// It's the asynchronous version of [`recv`](Self::recv), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&self, buf: &mut [u8]) -> AxResult<usize> {
+        if self.is_connecting() {
+            return Err(AxError::WouldBlock);
+        } else if !self.is_connected() {
+            return ax_err!(NotConnected, "socket recv() failed");
+        }
+
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        super::poll_fn(|waker| {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                if !socket.is_active() {
-                    // not open
-                    ax_err!(ConnectionRefused, "socket recv() failed")
-                } else if !socket.may_recv() {
-                    // connection closed
-                    Ok(0)
-                } else if socket.recv_queue() > 0 {
-                    // data available
-                    // TODO: use socket.recv(|buf| {...})
-                    let len = socket
-                        .recv_slice(buf)
-                        .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
-                    Ok(len)
-                } else {
-                    // no more data
-                    Err(AxError::WouldBlock)
+                let res = Self::recv_impl(socket, buf);
+                if matches!(res, Err(AxError::WouldBlock)) {
+                    socket.register_recv_waker(waker);
                 }
+                res
             })
         })
+        .await
     }
 
     /// Transmits data in the given buffer.
@@ -313,23 +320,35 @@ impl TcpSocket {
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
         self.block_on(|| {
+            SOCKET_SET
+                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| Self::send_impl(socket, buf))
+        })
+    }
+
+    /// Transmits data in the given buffer asynchronously.
+    ///
+    /// It's the asynchronous version of [`send`](Self::send), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, buf: &[u8]) -> AxResult<usize> {
+        if self.is_connecting() {
+            return Err(AxError::WouldBlock);
+        } else if !self.is_connected() {
+            return ax_err!(NotConnected, "socket send() failed");
+        }
+
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        super::poll_fn(|waker| {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
-                if !socket.is_active() || !socket.may_send() {
-                    // closed by remote
-                    ax_err!(ConnectionReset, "socket send() failed")
-                } else if socket.can_send() {
-                    // connected, and the tx buffer is not full
-                    // TODO: use socket.send(|buf| {...})
-                    let len = socket
-                        .send_slice(buf)
-                        .map_err(|_| ax_err_type!(BadState, "socket send() failed"))?;
-                    Ok(len)
-                } else {
-                    // tx buffer is full
-                    Err(AxError::WouldBlock)
+                let res = Self::send_impl(socket, buf);
+                if matches!(res, Err(AxError::WouldBlock)) {
+                    socket.register_send_waker(waker);
                 }
+                res
             })
         })
+        .await
     }
 
     /// Whether the socket is readable or writable.
@@ -419,6 +438,98 @@ impl TcpSocket {
         Ok(IpListenEndpoint { addr, port })
     }
 
+    /// Starts connecting to the given address, changes the state to
+    /// `CONNECTING`.
+    fn start_connect(&self, remote_addr: SocketAddr) -> AxResult {
+        self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
+            // SAFETY: no other threads can read or write these fields.
+            let handle = unsafe { self.handle.get().read() }
+                .unwrap_or_else(|| SOCKET_SET.add(SocketSetWrapper::new_tcp_socket()));
+
+            // TODO: check remote addr unreachable
+            let remote_endpoint = from_core_sockaddr(remote_addr);
+            let bound_endpoint = self.bound_endpoint()?;
+            let iface = &ETH0.iface;
+            let (local_endpoint, remote_endpoint) = SOCKET_SET
+                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                    socket
+                        .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
+                        .or_else(|e| match e {
+                            ConnectError::InvalidState => {
+                                ax_err!(BadState, "socket connect() failed")
+                            }
+                            ConnectError::Unaddressable => {
+                                ax_err!(ConnectionRefused, "socket connect() failed")
+                            }
+                        })?;
+                    Ok((
+                        socket.local_endpoint().unwrap(),
+                        socket.remote_endpoint().unwrap(),
+                    ))
+                })?;
+            unsafe {
+                // SAFETY: no other threads can read or write these fields as we
+                // have changed the state to `BUSY`.
+                self.local_addr.get().write(local_endpoint);
+                self.peer_addr.get().write(remote_endpoint);
+                self.handle.get().write(Some(handle));
+            }
+            Ok(())
+        })
+        // EISCONN
+        .unwrap_or_else(|_| ax_err!(AlreadyExists, "socket connect() failed: already connected"))
+    }
+
+    /// Checks whether the connection is established, or returns
+    /// [`Err(WouldBlock)`](AxError::WouldBlock) if it's still connecting.
+    fn check_connected(&self) -> AxResult {
+        let PollState { writable, .. } = self.poll_connect()?;
+        if !writable {
+            Err(AxError::WouldBlock)
+        } else if self.get_state() == STATE_CONNECTED {
+            Ok(())
+        } else {
+            ax_err!(ConnectionRefused, "socket connect() failed")
+        }
+    }
+
+    fn recv_impl(socket: &mut tcp::Socket, buf: &mut [u8]) -> AxResult<usize> {
+        if !socket.is_active() {
+            // not open
+            ax_err!(ConnectionRefused, "socket recv() failed")
+        } else if !socket.may_recv() {
+            // connection closed
+            Ok(0)
+        } else if socket.recv_queue() > 0 {
+            // data available
+            // TODO: use socket.recv(|buf| {...})
+            let len = socket
+                .recv_slice(buf)
+                .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
+            Ok(len)
+        } else {
+            // no more data
+            Err(AxError::WouldBlock)
+        }
+    }
+
+    fn send_impl(socket: &mut tcp::Socket, buf: &[u8]) -> AxResult<usize> {
+        if !socket.is_active() || !socket.may_send() {
+            // closed by remote
+            ax_err!(ConnectionReset, "socket send() failed")
+        } else if socket.can_send() {
+            // connected, and the tx buffer is not full
+            // TODO: use socket.send(|buf| {...})
+            let len = socket
+                .send_slice(buf)
+                .map_err(|_| ax_err_type!(BadState, "socket send() failed"))?;
+            Ok(len)
+        } else {
+            // tx buffer is full
+            Err(AxError::WouldBlock)
+        }
+    }
+
     fn poll_connect(&self) -> AxResult<PollState> {
         // SAFETY: `self.handle` should be initialized above.
         let handle = unsafe { self.handle.get().read().unwrap() };
@@ -481,14 +592,7 @@ impl TcpSocket {
         if self.is_nonblocking() {
             f()
         } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
+            super::block_on(f)
         }
     }
 }
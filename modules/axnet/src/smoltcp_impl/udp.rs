@@ -118,6 +118,34 @@ impl UdpSocket {
         })
     }
 
+    /// Sends data on the socket to the given address asynchronously. On
+    /// success, returns the number of bytes written.
+    ///
+    /// It's the asynchronous version of [`send_to`](Self::send_to), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn send_to_async(&self, buf: &[u8], remote_addr: SocketAddr) -> AxResult<usize> {
+        if remote_addr.port() == 0 || remote_addr.ip().is_unspecified() {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        self.send_impl_async(buf, from_core_sockaddr(remote_addr))
+            .await
+    }
+
+    /// Receives a single datagram message on the socket asynchronously. On
+    /// success, returns the number of bytes read and the origin.
+    ///
+    /// It's the asynchronous version of [`recv_from`](Self::recv_from), which
+    /// never blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn recv_from_async(&self, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)> {
+        self.recv_impl_async(|socket| match socket.recv_slice(buf) {
+            Ok((len, meta)) => Ok((len, into_core_sockaddr(meta.endpoint))),
+            Err(_) => ax_err!(BadState, "socket recv_from() failed"),
+        })
+        .await
+    }
+
     /// Receives a single datagram message on the socket, without removing it from
     /// the queue. On success, returns the number of bytes read and the origin.
     pub fn peek_from(&self, buf: &mut [u8]) -> AxResult<(usize, SocketAddr)> {
@@ -156,18 +184,31 @@ impl UdpSocket {
     /// to which it is connected. On success, returns the number of bytes read.
     pub fn recv(&self, buf: &mut [u8]) -> AxResult<usize> {
         let remote_endpoint = self.remote_endpoint()?;
-        self.recv_impl(|socket| {
-            let (len, meta) = socket
-                .recv_slice(buf)
-                .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
-            if !is_unspecified(remote_endpoint.addr) && remote_endpoint.addr != meta.endpoint.addr {
-                return Err(AxError::WouldBlock);
-            }
-            if remote_endpoint.port != 0 && remote_endpoint.port != meta.endpoint.port {
-                return Err(AxError::WouldBlock);
-            }
-            Ok(len)
-        })
+        self.recv_impl(|socket| Self::recv_connected(socket, buf, remote_endpoint))
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected
+    /// asynchronously.
+    ///
+    /// It's the asynchronous version of [`send`](Self::send), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, buf: &[u8]) -> AxResult<usize> {
+        let remote_endpoint = self.remote_endpoint()?;
+        self.send_impl_async(buf, remote_endpoint).await
+    }
+
+    /// Receives a single datagram message on the socket from the remote address
+    /// to which it is connected asynchronously. On success, returns the number
+    /// of bytes read.
+    ///
+    /// It's the asynchronous version of [`recv`](Self::recv), which never
+    /// blocks the calling thread, even if the socket is in blocking mode.
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&self, buf: &mut [u8]) -> AxResult<usize> {
+        let remote_endpoint = self.remote_endpoint()?;
+        self.recv_impl_async(|socket| Self::recv_connected(socket, buf, remote_endpoint))
+            .await
     }
 
     /// Close the socket.
@@ -206,6 +247,25 @@ impl UdpSocket {
         }
     }
 
+    /// Receives a datagram from the connected remote address, the datagrams
+    /// from other addresses are dropped.
+    fn recv_connected(
+        socket: &mut udp::Socket,
+        buf: &mut [u8],
+        remote_endpoint: IpEndpoint,
+    ) -> AxResult<usize> {
+        let (len, meta) = socket
+            .recv_slice(buf)
+            .map_err(|_| ax_err_type!(BadState, "socket recv() failed"))?;
+        if !is_unspecified(remote_endpoint.addr) && remote_endpoint.addr != meta.endpoint.addr {
+            return Err(AxError::WouldBlock);
+        }
+        if remote_endpoint.port != 0 && remote_endpoint.port != meta.endpoint.port {
+            return Err(AxError::WouldBlock);
+        }
+        Ok(len)
+    }
+
     fn send_impl(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> AxResult<usize> {
         if self.local_addr.read().is_none() {
             return ax_err!(NotConnected, "socket send() failed");
@@ -213,22 +273,48 @@ impl UdpSocket {
 
         self.block_on(|| {
             SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-                if socket.can_send() {
-                    socket
-                        .send_slice(buf, remote_endpoint)
-                        .map_err(|e| match e {
-                            SendError::BufferFull => AxError::WouldBlock,
-                            SendError::Unaddressable => {
-                                ax_err_type!(ConnectionRefused, "socket send() failed")
-                            }
-                        })?;
-                    Ok(buf.len())
-                } else {
-                    // tx buffer is full
-                    Err(AxError::WouldBlock)
+                Self::send_socket(socket, buf, remote_endpoint)
+            })
+        })
+    }
+
+    #[cfg(feature = "async")]
+    async fn send_impl_async(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> AxResult<usize> {
+        if self.local_addr.read().is_none() {
+            return ax_err!(NotConnected, "socket send() failed");
+        }
+
+        super::poll_fn(|waker| {
+            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+                let res = Self::send_socket(socket, buf, remote_endpoint);
+                if matches!(res, Err(AxError::WouldBlock)) {
+                    socket.register_send_waker(waker);
                 }
+                res
             })
         })
+        .await
+    }
+
+    fn send_socket(
+        socket: &mut udp::Socket,
+        buf: &[u8],
+        remote_endpoint: IpEndpoint,
+    ) -> AxResult<usize> {
+        if socket.can_send() {
+            socket
+                .send_slice(buf, remote_endpoint)
+                .map_err(|e| match e {
+                    SendError::BufferFull => AxError::WouldBlock,
+                    SendError::Unaddressable => {
+                        ax_err_type!(ConnectionRefused, "socket send() failed")
+                    }
+                })?;
+            Ok(buf.len())
+        } else {
+            // tx buffer is full
+            Err(AxError::WouldBlock)
+        }
     }
 
     fn recv_impl<F, T>(&self, mut op: F) -> AxResult<T>
@@ -252,6 +338,35 @@ impl UdpSocket {
         })
     }
 
+    #[cfg(feature = "async")]
+    async fn recv_impl_async<F, T>(&self, mut op: F) -> AxResult<T>
+    where
+        F: FnMut(&mut udp::Socket) -> AxResult<T>,
+    {
+        if self.local_addr.read().is_none() {
+            return ax_err!(NotConnected, "socket send() failed");
+        }
+
+        super::poll_fn(|waker| {
+            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+                let res = if socket.can_recv() {
+                    op(socket)
+                } else {
+                    Err(AxError::WouldBlock)
+                };
+                if matches!(res, Err(AxError::WouldBlock)) {
+                    socket.register_recv_waker(waker);
+                    if socket.can_recv() {
+                        // a datagram is dropped by `op`, try the next one
+                        waker.wake_by_ref();
+                    }
+                }
+                res
+            })
+        })
+        .await
+    }
+
     fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
     where
         F: FnMut() -> AxResult<T>,
@@ -259,14 +374,7 @@ impl UdpSocket {
         if self.is_nonblocking() {
             f()
         } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
+            super::block_on(f)
         }
     }
 }
@@ -6,13 +6,18 @@
 //! # Cargo Features
 //!
 //! - `alloc`: Enable global memory allocator.
-//! - `paging`: Enable page table manipulation support.
+//! - `paging`: Enable page table manipulation support, and guard pages for task
+//!   stacks.
 //! - `irq`: Enable interrupt handling support.
+//! - `tickless`: Only raise timer interrupts when needed instead of
+//!   periodically, so idle CPUs sleep with the tick stopped.
 //! - `multitask`: Enable multi-threading support.
 //! - `smp`: Enable SMP (symmetric multiprocessing) support.
 //! - `fs`: Enable filesystem support.
 //! - `net`: Enable networking support.
 //! - `display`: Enable graphics support.
+//! - `usb`: Enable USB host controllers and hot-plug notifications.
+//! - `input`: Enable USB keyboards and mice.
 //!
 //! All the features are optional and disabled by default.
 
@@ -125,6 +130,10 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
     axlog::set_max_level(option_env!("AX_LOG").unwrap_or("")); // no effect if set `log-level-*` features
     info!("Logging is enabled.");
     info!("Primary CPU {} started, dtb = {:#x}.", cpu_id, dtb);
+    match axhal::dtb::get() {
+        Some(fdt) => info!("Found device tree: {:?}", fdt),
+        None => info!("No device tree found, use the platform configuration."),
+    }
 
     info!("Found physcial memory regions:");
     for r in axhal::mem::memory_regions() {
@@ -149,7 +158,6 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
     #[cfg(feature = "alloc")]
     init_allocator_no_cache();
 
-
     info!("Initialize platform devices...");
     axhal::platform_init();
 
@@ -169,6 +177,12 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
 
         #[cfg(feature = "display")]
         axdisplay::init_display(all_devices.display);
+
+        #[cfg(feature = "usb")]
+        axusb::init_usb(&all_devices.usb_host);
+
+        #[cfg(feature = "input")]
+        axinput::init_input(all_devices.usb_host);
     }
 
     #[cfg(feature = "smp")]
@@ -260,13 +274,10 @@ fn init_allocator_no_cache() {
 #[cfg(feature = "paging")]
 fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
     use axhal::mem::{memory_regions, phys_to_virt, VirtAddr};
-    use axhal::paging::PageTable;
-    use lazy_init::LazyInit;
-
-    static KERNEL_PAGE_TABLE: LazyInit<PageTable> = LazyInit::new();
+    use axhal::paging::{kernel_page_table, set_kernel_page_table, PageTable};
 
     if axhal::cpu::this_cpu_is_bsp() {
-        let mut kernel_page_table = PageTable::try_new()?;
+        let mut page_table = PageTable::try_new()?;
         for r in memory_regions() {
             // mailbox 需要物理地址和虚拟地址一致
             let vaddr = if r.name == "nocache memory" {
@@ -274,12 +285,13 @@ fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
             } else {
                 phys_to_virt(r.paddr)
             };
-            kernel_page_table.map_region(vaddr, r.paddr, r.size, r.flags.into(), true)?;
+            page_table.map_region(vaddr, r.paddr, r.size, r.flags.into(), true)?;
         }
-        KERNEL_PAGE_TABLE.init_by(kernel_page_table);
+        set_kernel_page_table(page_table);
     }
 
-    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.root_paddr()) };
+    let root_paddr = kernel_page_table().lock().root_paddr();
+    unsafe { axhal::arch::write_page_table_root(root_paddr) };
     Ok(())
 }
 
@@ -288,13 +300,15 @@ fn init_interrupt() {
     use axhal::time::TIMER_IRQ_NUM;
 
     // Setup timer interrupt handler
-    const PERIODIC_INTERVAL_NANOS: u64 =
-        axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
-
+    #[cfg(not(feature = "tickless"))]
     #[percpu::def_percpu]
     static NEXT_DEADLINE: u64 = 0;
 
+    #[cfg(not(feature = "tickless"))]
     fn update_timer() {
+        const PERIODIC_INTERVAL_NANOS: u64 =
+            axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+
         let now_ns = axhal::time::current_time_nanos();
         // Safety: we have disabled preemption in IRQ handler.
         let mut deadline = unsafe { NEXT_DEADLINE.read_current_raw() };
@@ -305,7 +319,9 @@ fn init_interrupt() {
         axhal::time::set_oneshot_timer(deadline);
     }
 
+    // In the tickless mode, the timer is programmed by axtask on demand.
     axhal::irq::register_handler(TIMER_IRQ_NUM, || {
+        #[cfg(not(feature = "tickless"))]
         update_timer();
         #[cfg(feature = "multitask")]
         axtask::on_timer_tick();
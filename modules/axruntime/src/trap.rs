@@ -1,3 +1,8 @@
+use axhal::arch::TrapFrame;
+use axhal::backtrace::Backtrace;
+use axhal::mem::VirtAddr;
+use axhal::trap::{ExceptionKind, MappingFlags};
+
 struct TrapHandlerImpl;
 
 #[crate_interface::impl_interface]
@@ -6,8 +11,37 @@ impl axhal::trap::TrapHandler for TrapHandlerImpl {
         #[cfg(feature = "irq")]
         {
             let guard = kernel_guard::NoPreempt::new();
+            #[cfg(feature = "multitask")]
+            let start = axhal::time::current_time_nanos();
             axhal::irq::dispatch_irq(_irq_num);
+            #[cfg(feature = "multitask")]
+            axtask::account_irq_time(axhal::time::current_time_nanos() - start);
             drop(guard); // rescheduling may occur when preemption is re-enabled.
         }
     }
+
+    fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+        // otherwise, the fault is reported by `axhal`
+        axhal::trap::dispatch_page_fault(vaddr, access_flags, is_user)
+    }
+
+    fn handle_exception(kind: ExceptionKind, tf: &mut TrapFrame, is_user: bool) -> bool {
+        // otherwise, use the default handling in `axhal`
+        axhal::trap::dispatch_exception(kind, tf, is_user)
+    }
+
+    fn handle_stack_overflow(vaddr: usize, backtrace: &Backtrace) {
+        // do not allocate memory, the allocator may be locked by the task
+        #[cfg(feature = "multitask")]
+        if let Some(curr) = axtask::current_may_uninit() {
+            panic!(
+                "stack overflow in task Task({}, {:?}), fault_vaddr={:#x}\n{}",
+                curr.id().as_u64(),
+                curr.name(),
+                vaddr,
+                backtrace
+            );
+        }
+        panic!("stack overflow, fault_vaddr={:#x}\n{}", vaddr, backtrace);
+    }
 }
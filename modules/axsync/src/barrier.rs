@@ -0,0 +1,85 @@
+//! A sleeping barrier.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axtask::WaitQueue;
+use spinlock::SpinNoIrq;
+
+/// A barrier enables multiple tasks to synchronize the beginning of some
+/// computation, similar to
+/// [`std::sync::Barrier`](https://doc.rust-lang.org/std/sync/struct.Barrier.html).
+pub struct Barrier {
+    wq: WaitQueue,
+    /// The number of tasks arrived in the current generation.
+    count: SpinNoIrq<usize>,
+    /// Increased when all tasks of the current generation arrive.
+    generation: AtomicUsize,
+    num_tasks: usize,
+}
+
+/// A `BarrierWaitResult` is returned by [`Barrier::wait()`] when all tasks
+/// in the [`Barrier`] have rendezvoused.
+#[derive(Debug)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns `true` if this task is the "leader task" for the call to
+    /// [`Barrier::wait()`].
+    ///
+    /// Only one task will have `true` returned from their result, all other
+    /// tasks will have `false` returned.
+    #[must_use]
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Creates a new barrier that can block a given number of tasks.
+    ///
+    /// A barrier will block `n`-1 tasks which call [`wait()`] and then wake
+    /// up all tasks at once when the `n`th task calls [`wait()`].
+    ///
+    /// [`wait()`]: Barrier::wait
+    pub const fn new(n: usize) -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            count: SpinNoIrq::new(0),
+            generation: AtomicUsize::new(0),
+            num_tasks: n,
+        }
+    }
+
+    /// Blocks the current task until all tasks have rendezvoused here.
+    ///
+    /// Barriers are re-usable after all tasks have rendezvoused once, and can
+    /// be used continuously.
+    ///
+    /// A single (arbitrary) task will receive a [`BarrierWaitResult`] that
+    /// returns `true` from [`BarrierWaitResult::is_leader()`] when returning
+    /// from this function, and all other tasks will receive a result that
+    /// will return `false` from [`BarrierWaitResult::is_leader()`].
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut count = self.count.lock();
+        let generation = self.generation.load(Ordering::Acquire);
+        *count += 1;
+        if *count < self.num_tasks {
+            drop(count);
+            self.wq
+                .wait_until(|| self.generation.load(Ordering::Acquire) != generation);
+            BarrierWaitResult(false)
+        } else {
+            *count = 0;
+            self.generation.fetch_add(1, Ordering::Release);
+            drop(count);
+            self.wq.notify_all(true);
+            BarrierWaitResult(true)
+        }
+    }
+}
+
+impl core::fmt::Debug for Barrier {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad("Barrier { .. }")
+    }
+}
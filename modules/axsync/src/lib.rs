@@ -3,25 +3,55 @@
 //! Currently supported primitives:
 //!
 //! - [`Mutex`]: A mutual exclusion primitive.
+//! - [`Condvar`]: A condition variable, used with [`Mutex`].
+//! - [`RwLock`]: A writer-preferring reader-writer lock.
+//! - [`Semaphore`]: A counting semaphore.
+//! - [`Barrier`]: A barrier for a number of tasks to rendezvous.
+//! - [`Once`] and [`LazyLock`]: One-time initialization.
 //! - mod [`spin`](spinlock): spin-locks.
 //!
+//! All primitives except [`Mutex`] block the tasks in wait queues, so they
+//! require the `multitask` feature.
+//!
 //! # Cargo Features
 //!
 //! - `multitask`: For use in the multi-threaded environments. If the feature is
 //!   not enabled, [`Mutex`] will be an alias of [`spin::SpinNoIrq`]. This
 //!   feature is enabled by default.
+//! - `irq`: Interrupts are enabled. If this feature is enabled, the timed waits
+//!   can be used, such as [`Condvar::wait_timeout`].
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
 
 pub use spinlock as spin;
 
+#[cfg(feature = "multitask")]
+mod barrier;
+#[cfg(feature = "multitask")]
+mod condvar;
 #[cfg(feature = "multitask")]
 mod mutex;
+#[cfg(feature = "multitask")]
+mod once;
+#[cfg(feature = "multitask")]
+mod rwlock;
+#[cfg(feature = "multitask")]
+mod semaphore;
+
+#[cfg(test)]
+mod tests;
 
 #[cfg(feature = "multitask")]
 #[doc(cfg(feature = "multitask"))]
-pub use self::mutex::{Mutex, MutexGuard};
+pub use self::{
+    barrier::{Barrier, BarrierWaitResult},
+    condvar::{Condvar, WaitTimeoutResult},
+    mutex::{Mutex, MutexGuard},
+    once::{LazyLock, Once},
+    rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    semaphore::Semaphore,
+};
 
 #[cfg(not(feature = "multitask"))]
 #[doc(cfg(not(feature = "multitask")))]
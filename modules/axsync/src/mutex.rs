@@ -11,11 +11,20 @@ use axtask::{current, WaitQueue};
 /// [`std::sync::Mutex`](https://doc.rust-lang.org/std/sync/struct.Mutex.html).
 ///
 /// When the mutex is locked, the current task will block and be put into the
-/// wait queue. When the mutex is unlocked, all tasks waiting on the queue
+/// wait queue. When the mutex is unlocked, one task waiting on the queue
 /// will be woken up.
+///
+/// A mutex created by [`Mutex::with_priority_inheritance`] lends the priority
+/// of a waiting task to the owner if it is more urgent, so a low-priority
+/// owner cannot block a high-priority waiter indefinitely. The lent priority
+/// is withdrawn when the owner unlocks the mutex, but the owner stays boosted
+/// by the waiters of the other such mutexes it still holds. On unlock, such a
+/// mutex is handed off to the most urgent waiter directly, instead of waking
+/// up one in order.
 pub struct Mutex<T: ?Sized> {
     wq: WaitQueue,
     owner_id: AtomicU64,
+    inherit: bool,
     data: UnsafeCell<T>,
 }
 
@@ -23,7 +32,7 @@ pub struct Mutex<T: ?Sized> {
 ///
 /// When the guard falls out of scope it will release the lock.
 pub struct MutexGuard<'a, T: ?Sized + 'a> {
-    lock: &'a Mutex<T>,
+    pub(crate) lock: &'a Mutex<T>,
     data: *mut T,
 }
 
@@ -38,6 +47,19 @@ impl<T> Mutex<T> {
         Self {
             wq: WaitQueue::new(),
             owner_id: AtomicU64::new(0),
+            inherit: false,
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new [`Mutex`] with priority inheritance, see
+    /// [`axtask::lend_priority`].
+    #[inline(always)]
+    pub const fn with_priority_inheritance(data: T) -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            owner_id: AtomicU64::new(0),
+            inherit: true,
             data: UnsafeCell::new(data),
         }
     }
@@ -64,6 +86,18 @@ impl<T: ?Sized> Mutex<T> {
         self.owner_id.load(Ordering::Relaxed) != 0
     }
 
+    /// Returns `true` if the mutex has priority inheritance.
+    #[inline(always)]
+    pub fn is_priority_inheritance(&self) -> bool {
+        self.inherit
+    }
+
+    /// Returns `true` if the lock is held by the current task.
+    #[inline(always)]
+    pub fn is_owned_by_current(&self) -> bool {
+        self.owner_id.load(Ordering::Relaxed) == current().id().as_u64()
+    }
+
     /// Locks the [`Mutex`] and returns a guard that permits access to the inner data.
     ///
     /// The returned value may be dereferenced for data access
@@ -87,8 +121,16 @@ impl<T: ?Sized> Mutex<T> {
                         "{} tried to acquire mutex it already owns.",
                         current().id_name()
                     );
-                    // Wait until the lock looks unlocked before retrying
-                    self.wq.wait_until(|| !self.is_locked());
+                    self.lend_priority(owner_id);
+                    // Wait until the lock looks unlocked before retrying, or
+                    // it is handed off to the current task
+                    self.wq.wait_until(|| {
+                        let owner_id = self.owner_id.load(Ordering::Acquire);
+                        owner_id == 0 || owner_id == current_id
+                    });
+                    if self.owner_id.load(Ordering::Acquire) == current_id {
+                        break;
+                    }
                 }
             }
         }
@@ -118,6 +160,21 @@ impl<T: ?Sized> Mutex<T> {
         }
     }
 
+    /// Creates a guard for the [`Mutex`] which is already locked by the
+    /// current task, without locking it again.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by the current task, and it is released when the
+    /// returned guard is dropped. This can be useful to turn a lock held for
+    /// FFI back into a guard, e.g., to wait on a [`Condvar`](crate::Condvar).
+    pub unsafe fn make_guard_unchecked(&self) -> MutexGuard<T> {
+        MutexGuard {
+            lock: self,
+            data: unsafe { &mut *self.data.get() },
+        }
+    }
+
     /// Force unlock the [`Mutex`].
     ///
     /// # Safety
@@ -126,14 +183,47 @@ impl<T: ?Sized> Mutex<T> {
     /// thread. However, this can be useful in some instances for exposing
     /// the lock to FFI that doesn’t know how to deal with RAII.
     pub unsafe fn force_unlock(&self) {
-        let owner_id = self.owner_id.swap(0, Ordering::Release);
+        let owner_id = self.owner_id.load(Ordering::Relaxed);
         assert_eq!(
             owner_id,
             current().id().as_u64(),
             "{} tried to release mutex it doesn't own",
             current().id_name()
         );
-        self.wq.notify_one(true);
+        if self.inherit {
+            // the new owner is more urgent than the other waiters, so they
+            // need not lend it their priorities
+            self.wq.notify_most_urgent(true, |task| {
+                let new_owner = task.map_or(0, |task| task.id().as_u64());
+                self.owner_id.store(new_owner, Ordering::Release);
+            });
+            axtask::restore_priority(self.lock_id());
+        } else {
+            self.owner_id.store(0, Ordering::Release);
+            self.wq.notify_one(true);
+        }
+    }
+
+    /// Lends the priority of the current task to the owner, which blocks it,
+    /// if the mutex has priority inheritance.
+    ///
+    /// It is lent only if `owner_id` still holds the lock, and the owner
+    /// restores its priority after it releases the lock, so a task is never
+    /// left boosted after it unlocks.
+    fn lend_priority(&self, owner_id: u64) {
+        if !self.inherit || owner_id == 0 {
+            return;
+        }
+        if let Some(owner) = axtask::find_task(owner_id) {
+            axtask::lend_priority(&owner, self.lock_id(), || {
+                self.owner_id.load(Ordering::Acquire) == owner_id
+            });
+        }
+    }
+
+    /// Identifies the mutex to the owner it lends priorities to.
+    fn lock_id(&self) -> usize {
+        self as *const Self as *const () as usize
     }
 
     /// Returns a mutable reference to the underlying data.
@@ -199,11 +289,9 @@ impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
 
 #[cfg(test)]
 mod tests {
+    use crate::tests::{INIT, SERIAL};
     use crate::Mutex;
     use axtask as thread;
-    use std::sync::Once;
-
-    static INIT: Once = Once::new();
 
     fn may_interrupt() {
         // simulate interrupts
@@ -214,6 +302,7 @@ mod tests {
 
     #[test]
     fn lots_and_lots() {
+        let _lock = SERIAL.lock();
         INIT.call_once(thread::init_scheduler);
 
         const NUM_TASKS: u32 = 10;
@@ -0,0 +1,153 @@
+//! One-time initialization that blocks the other tasks until it completes.
+
+use core::cell::{Cell, UnsafeCell};
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use axtask::WaitQueue;
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// A synchronization primitive which can be used to run a one-time global
+/// initialization, similar to
+/// [`std::sync::Once`](https://doc.rust-lang.org/std/sync/struct.Once.html).
+///
+/// The tasks calling [`call_once`](Self::call_once) while another task is
+/// running the initialization are blocked in the wait queue until it
+/// completes.
+pub struct Once {
+    wq: WaitQueue,
+    state: AtomicU8,
+}
+
+impl Once {
+    /// Creates a new `Once` value.
+    pub const fn new() -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            state: AtomicU8::new(INCOMPLETE),
+        }
+    }
+
+    /// Performs an initialization routine once and only once. The given
+    /// closure will be executed if this is the first time `call_once` has
+    /// been called, and otherwise the routine will *not* be invoked.
+    ///
+    /// This method will block the calling task if another initialization
+    /// routine is currently running.
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        if self.is_completed() {
+            return;
+        }
+        match self
+            .state
+            .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => {
+                f();
+                self.state.store(COMPLETE, Ordering::Release);
+                self.wq.notify_all(true);
+            }
+            Err(_) => self.wq.wait_until(|| self.is_completed()),
+        }
+    }
+
+    /// Returns `true` if some [`call_once()`](Self::call_once) call has
+    /// completed successfully.
+    #[inline]
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Once {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Once")
+            .field("completed", &self.is_completed())
+            .finish()
+    }
+}
+
+/// A value which is initialized on the first access, similar to
+/// [`std::sync::LazyLock`](https://doc.rust-lang.org/std/sync/struct.LazyLock.html).
+///
+/// The tasks accessing the value while it is being initialized are blocked
+/// until the initialization completes.
+pub struct LazyLock<T, F = fn() -> T> {
+    once: Once,
+    init: Cell<Option<F>>,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Same unsafe impls as `std::sync::LazyLock`
+unsafe impl<T: Sync + Send, F: Send> Sync for LazyLock<T, F> {}
+
+impl<T, F: FnOnce() -> T> LazyLock<T, F> {
+    /// Creates a new lazy value with the given initializing function.
+    pub const fn new(f: F) -> Self {
+        Self {
+            once: Once::new(),
+            init: Cell::new(Some(f)),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Forces the evaluation of this lazy value and returns a reference to
+    /// result. This is equivalent to the `Deref` impl, but is explicit.
+    pub fn force(this: &Self) -> &T {
+        this.once.call_once(|| {
+            // SAFETY: only one task can run here, and no one reads the data
+            // before it completes.
+            let f = this.init.take().unwrap();
+            unsafe { (*this.data.get()).write(f()) };
+        });
+        // SAFETY: the data has been initialized above.
+        unsafe { (*this.data.get()).assume_init_ref() }
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for LazyLock<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T: Default> Default for LazyLock<T> {
+    fn default() -> Self {
+        Self::new(T::default)
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for LazyLock<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("LazyLock");
+        if self.once.is_completed() {
+            // SAFETY: the data is initialized if the `Once` is completed.
+            d.field(unsafe { (*self.data.get()).assume_init_ref() });
+        } else {
+            d.field(&format_args!("<uninit>"));
+        }
+        d.finish()
+    }
+}
+
+impl<T, F> Drop for LazyLock<T, F> {
+    fn drop(&mut self) {
+        if self.once.is_completed() {
+            // SAFETY: the data is initialized if the `Once` is completed.
+            unsafe { self.data.get_mut().assume_init_drop() };
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex as StdMutex;
+
+use axtask as thread;
+
+use crate::{Barrier, Condvar, LazyLock, Mutex, Once, RwLock, Semaphore};
+
+pub(crate) static INIT: std::sync::Once = std::sync::Once::new();
+pub(crate) static SERIAL: StdMutex<()> = StdMutex::new(());
+
+const NUM_TASKS: usize = 10;
+
+#[test]
+fn test_condvar() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    static M: Mutex<usize> = Mutex::new(0);
+    static CV: Condvar = Condvar::new();
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            let mut val = M.lock();
+            *val += 1;
+            CV.notify_all();
+            val = CV.wait_while(val, |val| *val < NUM_TASKS * 2);
+            *val += 1;
+            CV.notify_all();
+        });
+    }
+
+    let mut val = CV.wait_while(M.lock(), |val| *val < NUM_TASKS);
+    *val += NUM_TASKS;
+    CV.notify_all();
+    let val = CV.wait_while(val, |val| *val < NUM_TASKS * 3);
+    assert_eq!(*val, NUM_TASKS * 3);
+}
+
+#[test]
+fn test_mutex_priority_inheritance() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_ITERS: usize = 100;
+    static M: Mutex<(usize, usize)> = Mutex::with_priority_inheritance((0, 0));
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            for _ in 0..NUM_ITERS {
+                let mut val = M.lock();
+                val.0 += 1;
+                // let the others wait and lend their priorities
+                thread::yield_now();
+                val.1 += 1;
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    while FINISHED.load(Ordering::Acquire) < NUM_TASKS {
+        thread::yield_now();
+    }
+    assert!(M.is_priority_inheritance());
+    assert_eq!(*M.lock(), (NUM_TASKS * NUM_ITERS, NUM_TASKS * NUM_ITERS));
+}
+
+#[test]
+fn test_mutex_nested_priority_inheritance() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    static A: Mutex<()> = Mutex::with_priority_inheritance(());
+    static B: Mutex<()> = Mutex::with_priority_inheritance(());
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    fn priority() -> isize {
+        thread::get_scheduler(thread::current().as_task_ref()).1
+    }
+
+    fn lock_with_priority(m: &'static Mutex<()>, prio: isize) {
+        thread::spawn(move || {
+            thread::set_priority(prio);
+            drop(m.lock());
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+        // wait until it lends its priority
+        while priority() != prio {
+            thread::yield_now();
+        }
+    }
+
+    for release_a_first in [true, false] {
+        if !thread::set_priority(10) {
+            return; // the scheduler has no priorities (e.g., FIFO)
+        }
+        FINISHED.store(0, Ordering::Release);
+        let a = A.lock();
+        let b = B.lock();
+        lock_with_priority(&A, 0);
+        lock_with_priority(&B, -10);
+
+        if release_a_first {
+            drop(a);
+            // still boosted by the waiter of B
+            assert_eq!(priority(), -10);
+            drop(b);
+        } else {
+            drop(b);
+            // the waiter of A still lends its priority
+            assert_eq!(priority(), 0);
+            drop(a);
+        }
+        assert_eq!(priority(), 10);
+
+        while FINISHED.load(Ordering::Acquire) < 2 {
+            thread::yield_now();
+        }
+    }
+    thread::set_priority(0);
+}
+
+#[test]
+fn test_rwlock() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_ITERS: usize = 100;
+    static RW: RwLock<(usize, usize)> = RwLock::new((0, 0));
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            for _ in 0..NUM_ITERS {
+                let mut val = RW.write();
+                val.0 += 1;
+                thread::yield_now();
+                val.1 += 1;
+                drop(val);
+
+                let val = RW.read();
+                assert_eq!(val.0, val.1);
+                thread::yield_now();
+                assert!(RW.try_write().is_none());
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    while FINISHED.load(Ordering::Acquire) < NUM_TASKS {
+        let val = RW.read();
+        assert_eq!(val.0, val.1);
+        drop(val);
+        thread::yield_now();
+    }
+    assert_eq!(*RW.read(), (NUM_TASKS * NUM_ITERS, NUM_TASKS * NUM_ITERS));
+}
+
+#[test]
+fn test_semaphore_and_barrier() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_PERMITS: usize = 3;
+    static SEM: Semaphore = Semaphore::new(NUM_PERMITS);
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS + 1);
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    static LEADERS: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            SEM.acquire();
+            assert!(RUNNING.fetch_add(1, Ordering::Relaxed) < NUM_PERMITS);
+            thread::yield_now();
+            RUNNING.fetch_sub(1, Ordering::Relaxed);
+            SEM.release();
+
+            if BARRIER.wait().is_leader() {
+                LEADERS.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    if BARRIER.wait().is_leader() {
+        LEADERS.fetch_add(1, Ordering::Relaxed);
+    }
+    assert_eq!(LEADERS.load(Ordering::Relaxed), 1);
+    assert_eq!(SEM.available_permits(), NUM_PERMITS);
+}
+
+#[test]
+fn test_once() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    static ONCE: Once = Once::new();
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: LazyLock<usize> = LazyLock::new(|| {
+        thread::yield_now();
+        CALLS.fetch_add(1, Ordering::Relaxed) + 42
+    });
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS + 1);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            ONCE.call_once(|| {
+                thread::yield_now();
+                CALLS.fetch_add(1, Ordering::Relaxed);
+            });
+            assert!(ONCE.is_completed());
+            assert_eq!(*LAZY, 43);
+            BARRIER.wait();
+        });
+    }
+
+    BARRIER.wait();
+    assert_eq!(*LAZY, 43);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+}
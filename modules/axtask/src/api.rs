@@ -1,17 +1,32 @@
 //! Task APIs for multi-task configuration.
 
-use alloc::{string::String, sync::Arc};
+use alloc::{
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
 
-pub(crate) use crate::run_queue::{AxRunQueue, RUN_QUEUE};
+pub(crate) use crate::run_queue::current_run_queue;
 
 #[doc(cfg(feature = "multitask"))]
-pub use crate::task::{CurrentTask, TaskId, TaskInner};
+pub use crate::cpumask::CpuMask;
+#[doc(cfg(feature = "multitask"))]
+pub use crate::registry::TaskInfo;
+#[doc(cfg(feature = "multitask"))]
+pub use crate::stats::{CpuUsage, TaskStats};
+#[doc(cfg(feature = "multitask"))]
+pub use crate::task::{CurrentTask, TaskId, TaskInner, TaskState};
 #[doc(cfg(feature = "multitask"))]
 pub use crate::wait_queue::WaitQueue;
+#[doc(cfg(feature = "multitask"))]
+pub use scheduler::SchedPolicy;
 
 /// The reference type of a task.
 pub type AxTaskRef = Arc<AxTask>;
 
+/// The weak reference type of a task.
+pub type WeakAxTaskRef = Weak<AxTask>;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "sched_rr")] {
         const MAX_TIME_SLICE: usize = 5;
@@ -20,6 +35,13 @@ cfg_if::cfg_if! {
     } else if #[cfg(feature = "sched_cfs")] {
         pub(crate) type AxTask = scheduler::CFSTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::CFScheduler<TaskInner>;
+    } else if #[cfg(feature = "sched_rt")] {
+        const MAX_TIME_SLICE: usize = 5;
+        pub(crate) type AxTask = scheduler::RTTask<TaskInner, MAX_TIME_SLICE>;
+        pub(crate) type Scheduler = scheduler::RTScheduler<TaskInner, MAX_TIME_SLICE>;
+    } else if #[cfg(feature = "sched_edf")] {
+        pub(crate) type AxTask = scheduler::EDFTask<TaskInner>;
+        pub(crate) type Scheduler = scheduler::EDFScheduler<TaskInner>;
     } else {
         // If no scheduler features are set, use FIFO as the default.
         pub(crate) type AxTask = scheduler::FifoTask<TaskInner>;
@@ -65,9 +87,13 @@ pub fn current() -> CurrentTask {
 pub fn init_scheduler() {
     info!("Initialize scheduling...");
 
+    #[cfg(feature = "paging")]
+    crate::stack::init();
     crate::run_queue::init();
     #[cfg(feature = "irq")]
     crate::timers::init();
+    #[cfg(all(feature = "smp", feature = "irq"))]
+    crate::ipi::init_percpu(true);
 
     info!("  use {} scheduler.", Scheduler::scheduler_name());
 }
@@ -75,27 +101,70 @@ pub fn init_scheduler() {
 /// Initializes the task scheduler for secondary CPUs.
 pub fn init_scheduler_secondary() {
     crate::run_queue::init_secondary();
+    #[cfg(all(feature = "smp", feature = "irq"))]
+    crate::ipi::init_percpu(false);
 }
 
 /// Handles periodic timer ticks for the task manager.
 ///
 /// For example, advance scheduler states, checks timed events, etc.
+///
+/// In the tickless mode (the `tickless` feature), it handles all timer
+/// interrupts, and programs the timer for the next one.
 #[cfg(feature = "irq")]
 #[doc(cfg(feature = "irq"))]
 pub fn on_timer_tick() {
     crate::timers::check_events();
-    RUN_QUEUE.lock().scheduler_timer_tick();
+    #[cfg(feature = "tickless")]
+    let tick = crate::tick::tick_elapsed();
+    #[cfg(not(feature = "tickless"))]
+    let tick = true;
+    if tick {
+        current_run_queue().scheduler_timer_tick();
+    }
+    #[cfg(feature = "tickless")]
+    crate::tick::reprogram(!current().is_idle());
+}
+
+/// Accounts the time spent in an IRQ handler to the current task, as its
+/// kernel time.
+///
+/// It is called by the IRQ handler with the elapsed time in nanoseconds.
+pub fn account_irq_time(nanos: u64) {
+    if let Some(curr) = current_may_uninit() {
+        curr.stats().add_irq_time(nanos, !curr.is_idle());
+    }
 }
 
 /// Spawns a new task with the given parameters.
 ///
 /// Returns the task reference.
 pub fn spawn_raw<F>(f: F, name: String, stack_size: usize) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    spawn_raw_with_affinity(f, name, stack_size, CpuMask::full())
+}
+
+/// Spawns a new task with the given parameters, which is only allowed to run
+/// on the CPUs in `cpumask`.
+///
+/// If none of the CPUs in `cpumask` can run tasks, the task can run on any
+/// CPU until its affinity is changed.
+///
+/// Returns the task reference.
+pub fn spawn_raw_with_affinity<F>(
+    f: F,
+    name: String,
+    stack_size: usize,
+    cpumask: CpuMask,
+) -> AxTaskRef
 where
     F: FnOnce() + Send + 'static,
 {
     let task = TaskInner::new(f, name, stack_size);
-    RUN_QUEUE.lock().add_task(task.clone());
+    task.set_cpumask(cpumask);
+    crate::run_queue::select_run_queue(&task).add_task(task.clone());
     task
 }
 
@@ -122,17 +191,267 @@ where
 ///
 /// [CFS]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
 pub fn set_priority(prio: isize) -> bool {
-    RUN_QUEUE.lock().set_current_priority(prio)
+    current_run_queue().set_current_priority(prio)
+}
+
+/// Sets the scheduling policy and the priority of the given task.
+///
+/// `prio` is a nice value (from -20 to 19) for [`SchedPolicy::Normal`], or a
+/// real-time priority (from 0 to 99) for the real-time policies, the higher
+/// the better.
+///
+/// Real-time policies are only supported by the real-time scheduler (the
+/// `sched_rt` feature), which can change the policies of all tasks. Other
+/// schedulers only support [`SchedPolicy::Normal`] for the current task, the
+/// same as [`set_priority`].
+///
+/// Returns `true` if it is set successfully.
+pub fn set_scheduler(task: &AxTaskRef, policy: SchedPolicy, prio: isize) -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "sched_rt")] {
+            crate::run_queue::set_task_policy(task, policy, prio)
+        } else {
+            policy == SchedPolicy::Normal && current().ptr_eq(task) && set_priority(prio)
+        }
+    }
+}
+
+/// Gets the scheduling policy and the priority of the given task.
+///
+/// Without the real-time scheduler, all tasks are [`SchedPolicy::Normal`]
+/// ones, and the priority is the nice value in the CFS and EDF schedulers, or
+/// always 0 in the others.
+pub fn get_scheduler(task: &AxTaskRef) -> (SchedPolicy, isize) {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "sched_rt")] {
+            (task.policy(), task.priority())
+        } else if #[cfg(any(feature = "sched_cfs", feature = "sched_edf"))] {
+            (SchedPolicy::Normal, task.priority())
+        } else {
+            let _ = task;
+            (SchedPolicy::Normal, 0)
+        }
+    }
+}
+
+/// Ranks the scheduling parameters, the higher the more urgent: real-time
+/// tasks are above all normal ones, and a lower nice value is more urgent.
+pub(crate) fn sched_rank((policy, prio): (SchedPolicy, isize)) -> isize {
+    if policy.is_rt() {
+        scheduler::MAX_RT_PRIO as isize + prio
+    } else {
+        -prio
+    }
+}
+
+fn set_task_sched(task: &AxTaskRef, policy: SchedPolicy, prio: isize) -> bool {
+    if get_scheduler(task).0 == policy {
+        crate::run_queue::set_task_priority(task, prio)
+    } else {
+        set_scheduler(task, policy, prio)
+    }
+}
+
+/// Lends the priority of the current task to the given task if it is more
+/// urgent, i.e., the priority inheritance when the current task is blocked by
+/// a lock held by `task`. `lock` identifies the lock, e.g., by its address.
+///
+/// `owned` tells whether `task` still holds the lock, and it is checked with
+/// the lent priorities of `task` locked. So if `task` releases the lock before
+/// it calls [`restore_priority`], it is never left boosted by a lender that
+/// races with the release.
+///
+/// The priority of `task` is boosted with [`BaseScheduler::set_priority`], or
+/// it also takes the real-time policy of the current task (the `sched_rt`
+/// feature), until it calls [`restore_priority`] for the lock. Schedulers
+/// without priorities (FIFO and round-robin) do not support it, and the
+/// deadlines of EDF tasks are not lent.
+///
+/// Returns `true` if the priority is lent, though `task` may be boosted even
+/// higher through the other locks it holds.
+///
+/// [`BaseScheduler::set_priority`]: scheduler::BaseScheduler::set_priority
+pub fn lend_priority<F>(task: &AxTaskRef, lock: usize, owned: F) -> bool
+where
+    F: FnOnce() -> bool,
+{
+    let sched = get_scheduler(current().as_task_ref());
+    // keep the boosts by multiple tasks in order
+    let mut lent = task.lent_sched().lock();
+    if !owned() {
+        return false;
+    }
+    let task_sched = get_scheduler(task);
+    if sched_rank(sched) <= sched_rank(lent.base.unwrap_or(task_sched)) {
+        return false;
+    }
+    if sched_rank(sched) > sched_rank(task_sched) && !set_task_sched(task, sched.0, sched.1) {
+        return false;
+    }
+    lent.base.get_or_insert(task_sched);
+    // remember it even if `task` is boosted higher by another lock, which may
+    // be released first
+    match lent.locks.iter_mut().find(|(l, _)| *l == lock) {
+        Some((_, s)) if sched_rank(*s) >= sched_rank(sched) => {}
+        Some((_, s)) => *s = sched,
+        None => lent.locks.push((lock, sched)),
+    }
+    true
+}
+
+/// Withdraws the priorities lent to the current task through the given lock
+/// by [`lend_priority`], e.g., after it releases the lock.
+///
+/// The current task keeps the most urgent priority lent through the other
+/// locks it still holds, and its original priority is restored only when no
+/// lent priority is left.
+pub fn restore_priority(lock: usize) {
+    let curr = current();
+    let mut lent = curr.lent_sched().lock();
+    lent.locks.retain(|(l, _)| *l != lock);
+    let Some(base) = lent.base else {
+        return;
+    };
+    let sched = lent
+        .locks
+        .iter()
+        .map(|(_, s)| *s)
+        .max_by_key(|s| sched_rank(*s))
+        .unwrap_or(base);
+    if lent.locks.is_empty() {
+        lent.base = None;
+    }
+    if get_scheduler(curr.as_task_ref()) != sched {
+        set_task_sched(curr.as_task_ref(), sched.0, sched.1);
+    }
+}
+
+/// Makes the given task a deadline task of the EDF scheduler (the `sched_edf`
+/// feature), which is guaranteed to run for `runtime` in every `period`,
+/// before `deadline` relative to the start of the period.
+///
+/// The durations are rounded to timer ticks, and a deadline task is pinned to
+/// the CPU it is on, where its bandwidth (`runtime / period`) is reserved.
+///
+/// Returns `false` if it is not `runtime <= deadline <= period`, or the
+/// bandwidth would be overcommitted on the CPU.
+#[cfg(feature = "sched_edf")]
+pub fn set_deadline(
+    task: &AxTaskRef,
+    runtime: core::time::Duration,
+    deadline: core::time::Duration,
+    period: core::time::Duration,
+) -> bool {
+    const NANOS_PER_TICK: u128 =
+        (axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64) as u128;
+    // be conservative: round the runtime up, and others down
+    let params = scheduler::EDFParams {
+        runtime: runtime.as_nanos().div_ceil(NANOS_PER_TICK) as u64,
+        deadline: (deadline.as_nanos() / NANOS_PER_TICK) as u64,
+        period: (period.as_nanos() / NANOS_PER_TICK) as u64,
+    };
+    crate::run_queue::set_task_deadline(task, Some(params))
+}
+
+/// Makes the given task a normal task, and releases its bandwidth reserved by
+/// [`set_deadline`]. Its affinity before [`set_deadline`] is restored.
+#[cfg(feature = "sched_edf")]
+pub fn clear_deadline(task: &AxTaskRef) {
+    crate::run_queue::set_task_deadline(task, None);
+}
+
+/// Sets the CPU affinity of the given task, i.e., the CPUs it is allowed to run
+/// on.
+///
+/// If it is the current task, and the current CPU is not in `cpumask`, it is
+/// migrated to another CPU immediately. Other tasks are moved the next time
+/// they are scheduled (a running task is preempted for this if the
+/// preemption is enabled).
+///
+/// Returns `false` and keeps the affinity unchanged if none of the CPUs in
+/// `cpumask` can run tasks, or it is a deadline task (which is pinned to its
+/// CPU).
+pub fn set_affinity(task: &AxTaskRef, cpumask: CpuMask) -> bool {
+    if !crate::run_queue::is_schedulable(cpumask) {
+        return false;
+    }
+    #[cfg(feature = "sched_edf")]
+    if task.params().is_some() {
+        return false;
+    }
+    task.set_cpumask(cpumask);
+
+    let rq = current_run_queue();
+    if current().ptr_eq(task) {
+        if !cpumask.get(axhal::cpu::this_cpu_id()) {
+            rq.yield_current();
+        }
+    } else {
+        #[cfg(feature = "preempt")]
+        if task.is_running() && !cpumask.get(task.cpu_id()) {
+            task.set_preempt_pending(true);
+        }
+    }
+    true
+}
+
+/// Gets the CPU affinity of the given task, i.e., the CPUs it is allowed to run
+/// on.
+pub fn get_affinity(task: &AxTaskRef) -> CpuMask {
+    task.cpumask()
+}
+
+/// Takes a snapshot of the accounting information of the given task: its CPU
+/// time, context switches, time spent in each state, etc.
+pub fn task_stats(task: &AxTaskRef) -> TaskStats {
+    task.stats().snapshot(task.state(), task.cpu_id())
+}
+
+/// Returns all the live tasks, including the exited ones that are not dropped
+/// yet, sorted by ID.
+pub fn tasks() -> Vec<AxTaskRef> {
+    crate::registry::tasks()
+}
+
+/// Finds the live task with the given ID.
+pub fn find_task(id: u64) -> Option<AxTaskRef> {
+    crate::registry::find(id)
+}
+
+/// Gets the information of the given task: its ID, name, state, scheduling
+/// parameters, stack size and accounting information.
+pub fn task_info(task: &AxTaskRef) -> TaskInfo {
+    let (policy, priority) = get_scheduler(task);
+    TaskInfo {
+        id: task.id().as_u64(),
+        name: task.name().into(),
+        state: task.state(),
+        policy,
+        priority,
+        stack_size: task.stack_size(),
+        stats: task_stats(task),
+    }
+}
+
+/// Returns the total CPU usage of all tasks (except the idle tasks) since
+/// boot, including the exited ones.
+///
+/// The current runs of the tasks running on other CPUs are not included.
+pub fn total_cpu_usage() -> CpuUsage {
+    crate::stats::total_cpu_usage(current_may_uninit().as_deref())
 }
 
 /// Current task gives up the CPU time voluntarily, and switches to another
 /// ready task.
 pub fn yield_now() {
-    RUN_QUEUE.lock().yield_current();
+    current_run_queue().yield_current();
 }
 
 /// Current task is going to sleep for the given duration.
 ///
+/// It returns early if the task is interrupted by [`interrupt`], see
+/// [`sleep_until`].
+///
 /// If the feature `irq` is not enabled, it uses busy-wait instead.
 pub fn sleep(dur: core::time::Duration) {
     sleep_until(axhal::time::current_time() + dur);
@@ -140,27 +459,84 @@ pub fn sleep(dur: core::time::Duration) {
 
 /// Current task is going to sleep, it will be woken up at the given deadline.
 ///
+/// It returns early if the task is interrupted by [`interrupt`], so the
+/// caller should check the current time and sleep again if it needs the full
+/// duration.
+///
 /// If the feature `irq` is not enabled, it uses busy-wait instead.
 pub fn sleep_until(deadline: axhal::time::TimeValue) {
     #[cfg(feature = "irq")]
-    RUN_QUEUE.lock().sleep_until(deadline);
+    current_run_queue().sleep_until(deadline);
     #[cfg(not(feature = "irq"))]
     axhal::time::busy_wait_until(deadline);
 }
 
+/// Interrupts the given task, e.g., to deliver a signal to it.
+///
+/// The task is woken up if it is blocked, otherwise its next blocking returns
+/// immediately. It looks like a spurious wakeup for the task, which should
+/// check its wait condition again (and the reason of the interrupt): it makes
+/// [`sleep`], [`sleep_until`] and [`WaitQueue::wait`] return early, and
+/// [`WaitQueue::wait_timeout`] return `false` before the timeout, while the
+/// waits with conditions only check them again.
+///
+/// [`WaitQueue::wait`]: crate::WaitQueue::wait
+/// [`WaitQueue::wait_timeout`]: crate::WaitQueue::wait_timeout
+pub fn interrupt(task: &AxTaskRef) {
+    crate::run_queue::interrupt_task(task);
+}
+
+/// Calls `callback` with the current time in the timer interrupt at the
+/// `deadline`, and returns an ID to cancel it by [`cancel_timer`].
+///
+/// The callback must not block, and it can set the timer again, e.g., for
+/// periodic timers.
+#[cfg(feature = "irq")]
+pub fn set_timer<F>(deadline: axhal::time::TimeValue, callback: F) -> u64
+where
+    F: FnOnce(axhal::time::TimeValue) + Send + 'static,
+{
+    crate::timers::set_callback(deadline, alloc::boxed::Box::new(callback))
+}
+
+/// Cancels the timer set by [`set_timer`]. Returns `false` if it has fired or
+/// been cancelled.
+#[cfg(feature = "irq")]
+pub fn cancel_timer(id: u64) -> bool {
+    crate::timers::cancel_callback(id)
+}
+
 /// Exits the current task.
 pub fn exit(exit_code: i32) -> ! {
-    RUN_QUEUE.lock().exit_current(exit_code)
+    current_run_queue().exit_current(exit_code)
 }
 
 /// The idle task routine.
 ///
 /// It runs an infinite loop that keeps calling [`yield_now()`].
+///
+/// With `smp`, the CPU is marked idle before yielding, so that other CPUs
+/// wake it up by an IPI when tasks become ready.
 pub fn run_idle() -> ! {
     loop {
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        crate::ipi::set_idle();
         yield_now();
-        debug!("idle task: waiting for IRQs...");
         #[cfg(feature = "irq")]
-        axhal::arch::wait_for_irqs();
+        {
+            // check with IRQs disabled until it waits, otherwise the wakeups
+            // by the IRQs (or IPIs) arriving in between are lost
+            axhal::arch::disable_irqs();
+            // tasks may have been run and left, or woken up since yielding
+            let woken = crate::run_queue::has_ready_tasks();
+            #[cfg(feature = "smp")]
+            let woken = woken || !crate::ipi::is_idle();
+            if woken {
+                axhal::arch::enable_irqs();
+                continue;
+            }
+            debug!("idle task: waiting for IRQs...");
+            axhal::arch::enable_irqs_and_wait();
+        }
     }
 }
@@ -0,0 +1,97 @@
+//! CPU affinity masks.
+
+use core::fmt;
+
+const _: () = assert!(axconfig::SMP <= usize::BITS as usize);
+
+/// All the valid CPUs, i.e., bits `0..SMP`.
+const ALL_CPUS: usize = if axconfig::SMP == usize::BITS as usize {
+    usize::MAX
+} else {
+    (1 << axconfig::SMP) - 1
+};
+
+/// A set of CPUs, e.g., the CPUs that a task is allowed to run on.
+///
+/// CPU `i` is in the set if bit `i` is set. Bits of CPU IDs that are not less
+/// than [`axconfig::SMP`] are always cleared.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CpuMask(usize);
+
+impl CpuMask {
+    /// Creates an empty mask.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Creates a mask with all CPUs.
+    pub const fn full() -> Self {
+        Self(ALL_CPUS)
+    }
+
+    /// Creates a mask with only the given CPU.
+    pub const fn one_shot(cpu_id: usize) -> Self {
+        if cpu_id < axconfig::SMP {
+            Self(1 << cpu_id)
+        } else {
+            Self(0)
+        }
+    }
+
+    /// Creates a mask from the raw bits. Invalid CPUs are ignored.
+    pub const fn from_bits(bits: usize) -> Self {
+        Self(bits & ALL_CPUS)
+    }
+
+    /// Returns the raw bits of the mask.
+    pub const fn bits(&self) -> usize {
+        self.0
+    }
+
+    /// Whether the given CPU is in the mask.
+    pub const fn get(&self, cpu_id: usize) -> bool {
+        cpu_id < axconfig::SMP && self.0 & (1 << cpu_id) != 0
+    }
+
+    /// Adds the given CPU to the mask, or removes it from the mask.
+    ///
+    /// It does nothing if the CPU ID is not less than [`axconfig::SMP`].
+    pub fn set(&mut self, cpu_id: usize, value: bool) {
+        if cpu_id < axconfig::SMP {
+            if value {
+                self.0 |= 1 << cpu_id;
+            } else {
+                self.0 &= !(1 << cpu_id);
+            }
+        }
+    }
+
+    /// Whether the mask contains no CPU.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of CPUs in the mask.
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Returns an iterator over the IDs of the CPUs in the mask, in ascending
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0;
+        (0..axconfig::SMP).filter(move |&id| bits & (1 << id) != 0)
+    }
+}
+
+impl Default for CpuMask {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+impl fmt::Debug for CpuMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,258 @@
+//! Running [`Future`]s on tasks.
+//!
+//! Each future is run by a task with [`block_on`], which blocks the task while
+//! the future is pending, and unblocks it when the future is woken. The wakers
+//! can be woken by [`WaitQueue`](crate::WaitQueue) notifications (see
+//! [`WaitQueue::wait_until_async`](crate::WaitQueue::wait_until_async)), timer
+//! events (see [`sleep`]), or any other sources such as network sockets.
+//!
+//! Many futures can also share a few tasks with [`spawn`], which puts them on
+//! an executor polled by a fixed pool of worker tasks. A spawned future is
+//! put back to the run queue of the executor only when it is woken.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use spinlock::{SpinNoIrq, SpinRaw};
+
+use crate::WaitQueue;
+
+/// The waker of a task running [`block_on`].
+struct TaskWaker {
+    /// Whether the future is woken since it is polled last time.
+    woken: AtomicBool,
+    wq: WaitQueue,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.wq.notify_one(true);
+    }
+}
+
+/// Runs a future to completion on the current task.
+///
+/// The current task is blocked while the future is pending, until it is woken.
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+    let task_waker = Arc::new(TaskWaker {
+        woken: AtomicBool::new(false),
+        wq: WaitQueue::new(),
+    });
+    let waker = Waker::from(task_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+        task_waker
+            .wq
+            .wait_until(|| task_waker.woken.swap(false, Ordering::Acquire));
+    }
+}
+
+/// The number of worker tasks polling the futures passed to [`spawn`].
+const NUM_WORKERS: usize = axconfig::SMP;
+
+/// The spawned future is waiting to be woken.
+const IDLE: u8 = 0;
+/// The spawned future is in the run queue of the executor.
+const QUEUED: u8 = 1;
+/// The spawned future is being polled by a worker.
+const RUNNING: u8 = 2;
+/// The spawned future is woken while it is being polled.
+const WOKEN: u8 = 3;
+/// The spawned future has completed.
+const DONE: u8 = 4;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// A future spawned by [`spawn`], which is also its own waker.
+struct AsyncTask {
+    /// Only accessed by the worker that sets `state` to `RUNNING`.
+    fut: SpinRaw<Option<BoxFuture>>,
+    state: AtomicU8,
+}
+
+impl AsyncTask {
+    /// Runs the future once, and puts it back to the run queue if it is woken
+    /// while being polled.
+    fn run(self: Arc<Self>) {
+        self.state.store(RUNNING, Ordering::Release);
+        let waker = Waker::from(self.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = self.fut.lock();
+        if let Some(f) = fut.as_mut() {
+            if f.as_mut().poll(&mut cx).is_ready() {
+                // drop the future (and anything it holds) as early as possible
+                *fut = None;
+                self.state.store(DONE, Ordering::Release);
+                return;
+            }
+        }
+        drop(fut);
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // woken while being polled
+            self.state.store(QUEUED, Ordering::Release);
+            EXECUTOR.push(self);
+        }
+    }
+}
+
+impl Wake for AsyncTask {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let new_state = match state {
+                IDLE => QUEUED,
+                RUNNING => WOKEN,
+                _ => return,
+            };
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(s) => state = s,
+            }
+        }
+        if state == IDLE {
+            EXECUTOR.push(self.clone());
+        }
+    }
+}
+
+/// The executor of the futures passed to [`spawn`].
+struct Executor {
+    /// The futures ready to be polled, in FIFO order.
+    queue: SpinNoIrq<VecDeque<Arc<AsyncTask>>>,
+    /// The idle workers wait here for the futures.
+    wq: WaitQueue,
+    /// Whether the workers have been spawned.
+    started: AtomicBool,
+}
+
+static EXECUTOR: Executor = Executor {
+    queue: SpinNoIrq::new(VecDeque::new()),
+    wq: WaitQueue::new(),
+    started: AtomicBool::new(false),
+};
+
+impl Executor {
+    fn push(&self, task: Arc<AsyncTask>) {
+        self.queue.lock().push_back(task);
+        self.wq.notify_one(true);
+    }
+
+    fn start_workers(&'static self) {
+        if self.started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        for i in 0..NUM_WORKERS {
+            crate::spawn_raw(
+                move || self.run_worker(),
+                format!("async-worker-{i}"),
+                axconfig::TASK_STACK_SIZE,
+            );
+        }
+    }
+
+    fn run_worker(&self) {
+        loop {
+            let task = self.queue.lock().pop_front();
+            match task {
+                Some(task) => task.run(),
+                None => self.wq.wait_until(|| !self.queue.lock().is_empty()),
+            }
+        }
+    }
+}
+
+/// Spawns a future on the executor shared by all the spawned futures.
+///
+/// Unlike [`block_on`], no task is created for the future. It is polled by
+/// one of a fixed pool of worker tasks (one per CPU, started on the first
+/// call) when it is woken, so it should not block the worker in `poll`.
+pub fn spawn<F>(fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    EXECUTOR.start_workers();
+    EXECUTOR.push(Arc::new(AsyncTask {
+        fut: SpinRaw::new(Some(Box::pin(fut))),
+        state: AtomicU8::new(QUEUED),
+    }));
+}
+
+/// A future that completes at a deadline, returned by [`sleep`] and
+/// [`sleep_until`].
+#[cfg(feature = "irq")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Sleep {
+    deadline: axhal::time::TimeValue,
+    /// The waker registered in the timer list.
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "irq")]
+impl Sleep {
+    /// Returns the deadline of the sleep.
+    pub fn deadline(&self) -> axhal::time::TimeValue {
+        self.deadline
+    }
+}
+
+#[cfg(feature = "irq")]
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if axhal::time::current_time() >= self.deadline {
+            return Poll::Ready(());
+        }
+        // the future may be moved to another task, register the new waker
+        if !matches!(&self.waker, Some(w) if w.will_wake(cx.waker())) {
+            crate::timers::set_alarm_waker(self.deadline, cx.waker().clone());
+            self.waker = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Returns a future that completes after the given duration.
+#[cfg(feature = "irq")]
+pub fn sleep(dur: core::time::Duration) -> Sleep {
+    sleep_until(axhal::time::current_time() + dur)
+}
+
+/// Returns a future that completes at the given deadline (in `TimeValue`).
+#[cfg(feature = "irq")]
+pub fn sleep_until(deadline: axhal::time::TimeValue) -> Sleep {
+    Sleep {
+        deadline,
+        waker: None,
+    }
+}
@@ -0,0 +1,166 @@
+//! Inter-processor interrupts (IPIs) between CPUs.
+//!
+//! IPIs are used to wake up idle CPUs when tasks become ready (to run them, or
+//! to steal them from busy CPUs), to preempt the lower-priority tasks running
+//! on other CPUs, and to run functions on other CPUs, e.g., for TLB shootdowns.
+
+use alloc::{collections::VecDeque, sync::Arc};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axhal::cpu::this_cpu_id;
+use axhal::mem::VirtAddr;
+use spinlock::SpinNoIrq;
+
+const _: () = assert!(axconfig::SMP <= usize::BITS as usize);
+
+/// Bitmap of the CPUs that have initialized the scheduler.
+static ONLINE_CPUS: AtomicUsize = AtomicUsize::new(0);
+
+/// Bitmap of the CPUs that are running their idle tasks.
+static IDLE_CPUS: AtomicUsize = AtomicUsize::new(0);
+
+struct CallRequest {
+    func: Arc<dyn Fn() + Send + Sync>,
+    /// The number of CPUs that have not finished the call.
+    pending: Arc<AtomicUsize>,
+}
+
+static CALL_QUEUES: [SpinNoIrq<VecDeque<CallRequest>>; axconfig::SMP] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: SpinNoIrq<VecDeque<CallRequest>> = SpinNoIrq::new(VecDeque::new());
+    [EMPTY; axconfig::SMP]
+};
+
+fn ipi_handler() {
+    // woken up to run or steal tasks, let the idle task look for them again
+    clear_idle();
+    let queue = &CALL_QUEUES[this_cpu_id()];
+    loop {
+        // do not call the function in the critical section
+        let req = queue.lock().pop_front();
+        let Some(req) = req else {
+            break;
+        };
+        (req.func)();
+        req.pending.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Marks the current CPU as online, and registers the IPI handler on the
+/// primary CPU.
+pub(crate) fn init_percpu(primary: bool) {
+    if primary {
+        axhal::irq::register_handler(axhal::irq::IPI_IRQ_NUM, ipi_handler);
+    }
+    ONLINE_CPUS.fetch_or(1 << this_cpu_id(), Ordering::Release);
+}
+
+/// Marks the current CPU as idle. It is cleared once the CPU switches to
+/// another task.
+pub(crate) fn set_idle() {
+    IDLE_CPUS.fetch_or(1 << this_cpu_id(), Ordering::AcqRel);
+}
+
+/// Clears the idle mark of the current CPU.
+pub(crate) fn clear_idle() {
+    IDLE_CPUS.fetch_and(!(1 << this_cpu_id()), Ordering::AcqRel);
+}
+
+/// Whether the current CPU is still marked idle, i.e., it has not found any
+/// task to run since [`set_idle`].
+pub(crate) fn is_idle() -> bool {
+    IDLE_CPUS.load(Ordering::Acquire) & (1 << this_cpu_id()) != 0
+}
+
+/// Wakes up an idle CPU other than the current one, if any, to run a task that
+/// has just been put into the run queue of `target_cpu`.
+///
+/// It is `target_cpu` itself if it is idle, otherwise another idle CPU will
+/// steal the task.
+pub(crate) fn wake_idle_cpu(target_cpu: usize) {
+    let idle = IDLE_CPUS.load(Ordering::Acquire) & !(1 << this_cpu_id());
+    if idle & (1 << target_cpu) != 0 {
+        axhal::irq::send_ipi(target_cpu);
+    } else if idle != 0 {
+        axhal::irq::send_ipi(idle.trailing_zeros() as usize);
+    }
+}
+
+/// Interrupts `cpu_id` to reschedule, after its running task has been marked
+/// to be preempted. The preemption happens when the IPI handler returns.
+#[cfg(feature = "sched_rt")]
+pub(crate) fn resched_cpu(cpu_id: usize) {
+    axhal::irq::send_ipi(cpu_id);
+}
+
+/// Calls `func` on the CPUs in `mask` (the current CPU included if it is in
+/// it), and waits for all of them to complete.
+fn call_on_cpus(mask: usize, func: Arc<dyn Fn() + Send + Sync>) {
+    // stay on this CPU until the remote calls are done
+    let _guard = kernel_guard::NoPreempt::new();
+    let this_cpu = 1 << this_cpu_id();
+    let remote = mask & !this_cpu & ONLINE_CPUS.load(Ordering::Acquire);
+    let pending = Arc::new(AtomicUsize::new(remote.count_ones() as usize));
+
+    if remote != 0 {
+        // otherwise, two CPUs calling each other would deadlock
+        assert!(
+            axhal::arch::irqs_enabled(),
+            "remote calls with IRQs disabled"
+        );
+    }
+    for cpu_id in (0..axconfig::SMP).filter(|id| remote & (1 << id) != 0) {
+        CALL_QUEUES[cpu_id].lock().push_back(CallRequest {
+            func: func.clone(),
+            pending: pending.clone(),
+        });
+        axhal::irq::send_ipi(cpu_id);
+    }
+    if mask & this_cpu != 0 {
+        func();
+    }
+    while pending.load(Ordering::Acquire) != 0 {
+        core::hint::spin_loop();
+    }
+}
+
+/// Runs `f` on the given CPU in the interrupt context, and waits for it to
+/// complete.
+///
+/// It runs `f` directly if `cpu_id` is the current CPU, and does nothing if the
+/// CPU is not online.
+///
+/// # Panics
+///
+/// Panics if it is called with IRQs disabled and `cpu_id` is another CPU.
+pub fn run_on_cpu<F>(cpu_id: usize, f: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    if cpu_id < axconfig::SMP {
+        call_on_cpus(1 << cpu_id, Arc::new(f));
+    }
+}
+
+/// Runs `f` on all online CPUs, including the current one, and waits for all
+/// of them to complete.
+///
+/// # Panics
+///
+/// Panics if it is called with IRQs disabled while other CPUs are online.
+pub fn run_on_each_cpu<F>(f: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    call_on_cpus(usize::MAX, Arc::new(f));
+}
+
+/// Flushes the TLB of all online CPUs.
+///
+/// If `vaddr` is [`None`], flushes the entire TLB. Otherwise, flushes the TLB
+/// entry that maps the given virtual address.
+///
+/// It should be called after the kernel page table has been modified.
+pub fn flush_tlb_all_cpus(vaddr: Option<VirtAddr>) {
+    run_on_each_cpu(move || axhal::arch::flush_tlb(vaddr));
+}
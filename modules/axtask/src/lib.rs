@@ -2,7 +2,8 @@
 //!
 //! This module provides primitives for task management, including task
 //! creation, scheduling, sleeping, termination, etc. The scheduler algorithm
-//! is configurable by cargo features.
+//! is configurable by cargo features. With the `multitask` feature, futures
+//! can also be run on tasks, see the [`future`] module.
 //!
 //! # Cargo Features
 //!
@@ -13,6 +14,17 @@
 //!    APIs can be used, such as [`sleep`], [`sleep_until`], and
 //!    [`WaitQueue::wait_timeout`].
 //! - `preempt`: Enable preemptive scheduling.
+//! - `smp`: Enable SMP (symmetric multiprocessing) support. Each CPU has its
+//!   own run queue, and idle CPUs steal tasks from busy ones. Together with
+//!   `irq`, idle CPUs are woken up by IPIs when tasks become ready, and
+//!   functions can be run on other CPUs (e.g., [`run_on_each_cpu`]).
+//! - `tickless`: Enable the tickless mode. The timer interrupt is only raised
+//!   when the current CPU needs it: for timer events, and for time slices when
+//!   it is running tasks with a preemptive scheduler. Idle CPUs sleep with the
+//!   tick stopped. It also enables the `irq` feature.
+//! - `paging`: Allocate task stacks in a dedicated virtual region, with an
+//!   unmapped guard page below each stack, so that stack overflows are caught
+//!   by page faults.
 //! - `sched_fifo`: Use the [FIFO cooperative scheduler][1]. It also enables the
 //!   `multitask` feature if it is enabled. This feature is enabled by default,
 //!   and it can be overriden by other scheduler features.
@@ -20,10 +32,17 @@
 //!   the `multitask` and `preempt` features if it is enabled.
 //! - `sched_cfs`: Use the [Completely Fair Scheduler][3]. It also enables the
 //!   the `multitask` and `preempt` features if it is enabled.
+//! - `sched_rt`: Use the [Real-time scheduler][4], with CFS for normal tasks.
+//!   It also enables the `multitask` and `preempt` features if it is enabled.
+//! - `sched_edf`: Use the [Earliest Deadline First scheduler][5], with CFS for
+//!   normal tasks. It also enables the `multitask` and `preempt` features if it
+//!   is enabled.
 //!
 //! [1]: scheduler::FifoScheduler
 //! [2]: scheduler::RRScheduler
 //! [3]: scheduler::CFScheduler
+//! [4]: scheduler::RTScheduler
+//! [5]: scheduler::EDFScheduler
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
@@ -35,17 +54,32 @@ cfg_if::cfg_if! {
         extern crate log;
         extern crate alloc;
 
+        mod cpumask;
+        mod registry;
         mod run_queue;
+        mod stats;
         mod task;
         mod api;
         mod wait_queue;
 
+        #[doc(cfg(feature = "multitask"))]
+        pub mod future;
+
         #[cfg(feature = "irq")]
         mod timers;
+        #[cfg(feature = "tickless")]
+        mod tick;
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        mod ipi;
+        #[cfg(feature = "paging")]
+        mod stack;
 
         #[doc(cfg(feature = "multitask"))]
         pub use self::api::*;
         pub use self::api::{sleep, sleep_until, yield_now};
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        #[doc(cfg(all(feature = "multitask", feature = "smp", feature = "irq")))]
+        pub use self::ipi::{flush_tlb_all_cpus, run_on_cpu, run_on_each_cpu};
     } else {
         mod api_s;
         pub use self::api_s::{sleep, sleep_until, yield_now};
@@ -0,0 +1,62 @@
+//! The global registry of all tasks.
+//!
+//! Tasks are registered when they are created, and unregistered when they are
+//! dropped. Exited tasks stay in the registry until all the references to them
+//! are released, e.g., after they are joined.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+use scheduler::SchedPolicy;
+use spinlock::SpinNoIrq;
+
+use crate::{AxTask, AxTaskRef, TaskState, TaskStats};
+
+/// Information of a task, see [`task_info`](crate::task_info).
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    /// The task ID.
+    pub id: u64,
+    /// The task name.
+    pub name: String,
+    /// The state of the task.
+    pub state: TaskState,
+    /// The scheduling policy, see [`get_scheduler`](crate::get_scheduler).
+    pub policy: SchedPolicy,
+    /// The priority, see [`get_scheduler`](crate::get_scheduler).
+    pub priority: isize,
+    /// The size of its stack in bytes, or 0 if it runs on a boot stack.
+    pub stack_size: usize,
+    /// The accounting information of the task, including the CPU it last ran
+    /// on.
+    pub stats: TaskStats,
+}
+
+static TASKS: SpinNoIrq<BTreeMap<u64, Weak<AxTask>>> = SpinNoIrq::new(BTreeMap::new());
+
+/// Adds a new task to the registry.
+pub(crate) fn register(task: &AxTaskRef) {
+    TASKS
+        .lock()
+        .insert(task.id().as_u64(), Arc::downgrade(task));
+}
+
+/// Removes a task from the registry, when it is being dropped.
+pub(crate) fn unregister(id: u64) {
+    TASKS.lock().remove(&id);
+}
+
+/// Returns all the tasks in the registry, sorted by ID.
+pub(crate) fn tasks() -> Vec<AxTaskRef> {
+    // the references are returned instead of being dropped with the lock held,
+    // as dropping the last one unregisters the task
+    TASKS.lock().values().filter_map(Weak::upgrade).collect()
+}
+
+/// Finds the task with the given ID.
+pub(crate) fn find(id: u64) -> Option<AxTaskRef> {
+    let task = TASKS.lock().get(&id)?.upgrade();
+    task
+}
@@ -1,14 +1,43 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axhal::cpu::this_cpu_id;
+use kernel_guard::NoPreemptIrqSave;
 use lazy_init::LazyInit;
 use scheduler::BaseScheduler;
 use spinlock::SpinNoIrq;
 
 use crate::task::{CurrentTask, TaskState};
-use crate::{AxTaskRef, Scheduler, TaskInner, WaitQueue};
+use crate::{AxTaskRef, CpuMask, Scheduler, TaskInner, WaitQueue};
 
-// TODO: per-CPU
-pub(crate) static RUN_QUEUE: LazyInit<SpinNoIrq<AxRunQueue>> = LazyInit::new();
+/// The run queue of each CPU.
+#[percpu::def_percpu]
+static RUN_QUEUE: LazyInit<AxRunQueue> = LazyInit::new();
+
+/// The task that has just been switched out on each CPU, which is released by
+/// the next task once the switch is done.
+#[percpu::def_percpu]
+static PREV_TASK: Option<AxTaskRef> = None;
+
+/// Whether the task that has just been switched out on each CPU is to be moved
+/// to another CPU, as it is not allowed to run on this one.
+#[percpu::def_percpu]
+static PREV_MIGRATING: bool = false;
+
+/// The number of timer ticks since the last load balancing on each CPU.
+#[cfg(feature = "irq")]
+#[percpu::def_percpu]
+static BALANCE_TICKS: usize = 0;
+
+/// Interval of the periodic load balancing, in timer ticks.
+#[cfg(feature = "irq")]
+const LOAD_BALANCE_INTERVAL: usize = if axconfig::TICKS_PER_SEC >= 10 {
+    axconfig::TICKS_PER_SEC / 10
+} else {
+    1
+};
 
 // TODO: per-CPU
 static EXITED_TASKS: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
@@ -18,55 +47,355 @@ static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
 #[percpu::def_percpu]
 static IDLE_TASK: LazyInit<AxTaskRef> = LazyInit::new();
 
+/// The run queue of a CPU.
+///
+/// Each CPU schedules the tasks in its own run queue. A task is added to the
+/// run queue of the CPU it last ran on when it is woken up, and to the least
+/// loaded one when it is spawned. Idle CPUs steal tasks from the busiest run
+/// queue, and busy ones are balanced periodically on timer ticks. Tasks are
+/// only placed on and migrated to the CPUs in their affinity masks.
+///
+/// The scheduler lock is only held for short operations, never across context
+/// switches, so tasks can be migrated to another CPU while they are switched
+/// out. Operations on the current CPU's run queue are done with IRQs and
+/// preemption disabled (see [`current_run_queue`]).
 pub(crate) struct AxRunQueue {
-    scheduler: Scheduler,
+    cpu_id: usize,
+    scheduler: SpinNoIrq<Scheduler>,
+    /// The number of tasks belonging to this CPU, i.e., the ready ones and the
+    /// running one (not counting the idle task).
+    nr_tasks: AtomicUsize,
+    /// The task running on this CPU, or `None` if it is idle, for the other
+    /// CPUs to check whether the tasks they wake up should preempt it.
+    #[cfg(all(feature = "sched_rt", feature = "smp"))]
+    running: SpinNoIrq<Option<AxTaskRef>>,
+}
+
+/// The run queue of the current CPU. IRQs and preemption are disabled while
+/// it is held, so that the current task is not migrated.
+///
+/// Note that if the current task is switched out by the methods of the run
+/// queue, it may be resumed on another CPU, and the reference is not to be
+/// used anymore.
+pub(crate) struct CurrentRunQueueRef {
+    rq: &'static AxRunQueue,
+    _guard: NoPreemptIrqSave,
+}
+
+impl Deref for CurrentRunQueueRef {
+    type Target = AxRunQueue;
+    fn deref(&self) -> &Self::Target {
+        self.rq
+    }
+}
+
+/// Returns the run queue of the current CPU.
+pub(crate) fn current_run_queue() -> CurrentRunQueueRef {
+    let guard = NoPreemptIrqSave::new();
+    // Safety: preemption is disabled, and the run queue is initialized by
+    // `init()` or `init_secondary()`.
+    let rq = unsafe { RUN_QUEUE.current_ref_raw().get_unchecked() };
+    CurrentRunQueueRef { rq, _guard: guard }
+}
+
+/// Whether there are ready tasks in the run queue of the current CPU.
+///
+/// It is called by the idle task with IRQs disabled, before it waits for IRQs.
+#[cfg(feature = "irq")]
+pub(crate) fn has_ready_tasks() -> bool {
+    // Safety: IRQs are disabled, so the current CPU does not change.
+    unsafe { RUN_QUEUE.current_ref_raw().get_unchecked() }.nr_tasks() > 0
+}
+
+/// Returns the run queue of the given CPU, or `None` if the CPU has not
+/// initialized its scheduler.
+fn run_queue_of(cpu_id: usize) -> Option<&'static AxRunQueue> {
+    if cpu_id < axconfig::SMP {
+        unsafe { RUN_QUEUE.remote_ref_raw(cpu_id) }.try_get()
+    } else {
+        None
+    }
+}
+
+/// Returns the run queues of the CPUs in `cpumask` that have initialized their
+/// schedulers.
+fn run_queues_in(cpumask: CpuMask) -> impl Iterator<Item = &'static AxRunQueue> {
+    cpumask.iter().filter_map(run_queue_of)
+}
+
+/// Whether any CPU in `cpumask` can run tasks.
+pub(crate) fn is_schedulable(cpumask: CpuMask) -> bool {
+    run_queues_in(cpumask).next().is_some()
+}
+
+/// Returns the run queue to add a task to, i.e., the least loaded one of the
+/// CPUs that the task is allowed to run on.
+///
+/// If none of them can run tasks, the task's affinity is ignored.
+pub(crate) fn select_run_queue(task: &AxTaskRef) -> &'static AxRunQueue {
+    let mut cpumask = task.cpumask();
+    if !is_schedulable(cpumask) {
+        cpumask = CpuMask::full();
+    }
+    let this_cpu = this_cpu_id();
+    run_queues_in(cpumask)
+        .min_by_key(|rq| (rq.nr_tasks(), rq.cpu_id != this_cpu))
+        .unwrap()
+}
+
+/// Wakes up a blocked task, and puts it into the run queue of the CPU it last
+/// ran on.
+///
+/// If `resched` is true and it is put into the current CPU's run queue, the
+/// current task will be preempted when the preemption is enabled. If it is put
+/// into another CPU's run queue, the task running there is preempted if the
+/// woken task has a higher real-time priority.
+pub(crate) fn unblock_task(task: AxTaskRef, resched: bool) {
+    debug!("task unblock: {}", task.id_name());
+    if task.transition_state(TaskState::Blocked, TaskState::Ready) {
+        // It is going to sleep on another CPU, wait for it to be switched out
+        // before it can be switched to.
+        while task.on_cpu() {
+            core::hint::spin_loop();
+        }
+        let rq = run_queue_of(task.cpu_id())
+            .filter(|rq| task.cpumask().get(rq.cpu_id))
+            .unwrap_or_else(|| select_run_queue(&task));
+        let cpu_id = rq.cpu_id;
+        #[cfg(all(feature = "sched_rt", feature = "smp"))]
+        let woken = task.clone();
+        rq.enqueue(task);
+        if resched && cpu_id == this_cpu_id() {
+            #[cfg(feature = "preempt")]
+            crate::current().set_preempt_pending(true);
+        }
+        #[cfg(all(feature = "sched_rt", feature = "smp"))]
+        if cpu_id != this_cpu_id() {
+            rq.preempt_running(&woken);
+        }
+    }
+}
+
+/// Makes the current task, which is going to block, keep running if it has
+/// been interrupted. Returns whether it is aborted.
+///
+/// It pairs with [`interrupt_task`]: either the interrupt is seen here, or the
+/// blocked state is seen there.
+fn abort_blocking(curr: &CurrentTask) -> bool {
+    core::sync::atomic::fence(Ordering::SeqCst);
+    curr.take_interrupted() && curr.transition_state(TaskState::Blocked, TaskState::Running)
+}
+
+/// Interrupts a task, i.e., wakes it up if it is blocked, or makes its next
+/// blocking return immediately.
+pub(crate) fn interrupt_task(task: &AxTaskRef) {
+    task.set_interrupted();
+    core::sync::atomic::fence(Ordering::SeqCst);
+    unblock_task(task.clone(), true);
+}
+
+/// Sets the priority of a task, which can be ready in any run queue.
+///
+/// The current task is rescheduled if the task is on the current CPU.
+pub(crate) fn set_task_priority(task: &AxTaskRef, prio: isize) -> bool {
+    loop {
+        let cpu_id = task.cpu_id();
+        let rq = run_queue_of(cpu_id).unwrap();
+        let mut scheduler = rq.scheduler.lock();
+        if task.cpu_id() != cpu_id {
+            // migrated before the run queue is locked
+            continue;
+        }
+        let ok = scheduler.set_priority(task, prio);
+        #[cfg(feature = "preempt")]
+        if ok && cpu_id == this_cpu_id() {
+            // preemption is disabled by the lock, and it is checked on unlock
+            crate::current().set_preempt_pending(true);
+        }
+        return ok;
+    }
+}
+
+/// Sets the scheduling policy and the priority of a task, which can be ready
+/// in any run queue.
+///
+/// The current task is rescheduled if the task is on the current CPU.
+#[cfg(feature = "sched_rt")]
+pub(crate) fn set_task_policy(
+    task: &AxTaskRef,
+    policy: scheduler::SchedPolicy,
+    prio: isize,
+) -> bool {
+    loop {
+        let cpu_id = task.cpu_id();
+        let rq = run_queue_of(cpu_id).unwrap();
+        let mut scheduler = rq.scheduler.lock();
+        if task.cpu_id() != cpu_id {
+            // migrated before the run queue is locked
+            continue;
+        }
+        let ok = scheduler.set_policy(task, policy, prio);
+        if ok && cpu_id == this_cpu_id() {
+            // preemption is disabled by the lock, and it is checked on unlock
+            crate::current().set_preempt_pending(true);
+        }
+        return ok;
+    }
+}
+
+/// Sets the reservation parameters of a task in the EDF scheduler, and pins a
+/// deadline task to its current CPU.
+#[cfg(feature = "sched_edf")]
+pub(crate) fn set_task_deadline(task: &AxTaskRef, params: Option<scheduler::EDFParams>) -> bool {
+    loop {
+        let cpu_id = task.cpu_id();
+        let rq = run_queue_of(cpu_id).unwrap();
+        let mut scheduler = rq.scheduler.lock();
+        if task.cpu_id() != cpu_id {
+            // migrated before the run queue is locked
+            continue;
+        }
+        let was_deadline = task.params().is_some();
+        scheduler.update_clock(clock_ticks());
+        if !scheduler.set_params(task, params) {
+            return false;
+        }
+        match (was_deadline, params.is_some()) {
+            (false, true) => task.pin_cpumask(cpu_id),
+            (true, false) => task.unpin_cpumask(),
+            _ => {}
+        }
+        if cpu_id == this_cpu_id() {
+            // preemption is disabled by the lock, and it is checked on unlock
+            crate::current().set_preempt_pending(true);
+        }
+        return true;
+    }
+}
+
+/// Returns the current time in timer ticks, the time unit of the EDF
+/// scheduler.
+#[cfg(feature = "sched_edf")]
+fn clock_ticks() -> u64 {
+    axhal::time::current_time_nanos()
+        / (axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64)
+}
+
+/// Releases the task that has just been switched out on the current CPU, and
+/// moves it to another CPU if it is migrating.
+///
+/// It must be called by the next task right after the context switch.
+pub(crate) fn finish_switch() {
+    if let Some(prev) = unsafe { PREV_TASK.current_ref_mut_raw().take() } {
+        prev.set_on_cpu(false);
+        if unsafe { PREV_MIGRATING.read_current_raw() } {
+            unsafe { PREV_MIGRATING.write_current_raw(false) };
+            select_run_queue(&prev).enqueue(prev);
+        }
+    }
 }
 
 impl AxRunQueue {
-    pub fn new() -> SpinNoIrq<Self> {
-        let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
-        let mut scheduler = Scheduler::new();
-        scheduler.add_task(gc_task);
-        SpinNoIrq::new(Self { scheduler })
+    fn new(cpu_id: usize) -> Self {
+        Self {
+            cpu_id,
+            scheduler: SpinNoIrq::new(Scheduler::new()),
+            nr_tasks: AtomicUsize::new(0),
+            #[cfg(all(feature = "sched_rt", feature = "smp"))]
+            running: SpinNoIrq::new(None),
+        }
     }
 
-    pub fn add_task(&mut self, task: AxTaskRef) {
-        debug!("task spawn: {}", task.id_name());
+    fn nr_tasks(&self) -> usize {
+        self.nr_tasks.load(Ordering::Acquire)
+    }
+
+    pub fn add_task(&self, task: AxTaskRef) {
+        debug!("task spawn: {} on CPU {}", task.id_name(), self.cpu_id);
         assert!(task.is_ready());
-        self.scheduler.add_task(task);
+        self.enqueue(task);
+    }
+
+    /// Puts a ready task into the scheduler, and wakes up an idle CPU to run
+    /// it if any.
+    fn enqueue(&self, task: AxTaskRef) {
+        task.set_cpu_id(self.cpu_id);
+        self.add_to_scheduler(task);
+        self.nr_tasks.fetch_add(1, Ordering::AcqRel);
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        crate::ipi::wake_idle_cpu(self.cpu_id);
+    }
+
+    /// Adds a task that is new to this CPU to the scheduler, i.e., a woken up,
+    /// spawned or migrated one. The clock of the EDF scheduler is brought up
+    /// to date first, so the server of a deadline task is checked against
+    /// the current time, not the last tick of this CPU.
+    fn add_to_scheduler(&self, task: AxTaskRef) {
+        let mut scheduler = self.scheduler.lock();
+        #[cfg(feature = "sched_edf")]
+        scheduler.update_clock(clock_ticks());
+        scheduler.add_task(task);
+    }
+
+    /// Preempts the task running on this CPU, which is another CPU, if `task`
+    /// that has just been put into the run queue has a higher priority.
+    ///
+    /// An idle CPU is woken up by [`enqueue`](Self::enqueue) instead.
+    #[cfg(all(feature = "sched_rt", feature = "smp"))]
+    fn preempt_running(&self, task: &AxTaskRef) {
+        let Some(curr) = self.running.lock().clone() else {
+            return;
+        };
+        if Scheduler::preempts(task, &curr) {
+            curr.set_preempt_pending(true);
+            crate::ipi::resched_cpu(self.cpu_id);
+        }
     }
 
     #[cfg(feature = "irq")]
-    pub fn scheduler_timer_tick(&mut self) {
+    pub fn scheduler_timer_tick(&self) {
         let curr = crate::current();
-        if !curr.is_idle() && self.scheduler.task_tick(curr.as_task_ref()) {
+        let mut scheduler = self.scheduler.lock();
+        #[cfg(feature = "sched_edf")]
+        scheduler.update_clock(clock_ticks());
+        let resched = !curr.is_idle() && scheduler.task_tick(curr.as_task_ref());
+        drop(scheduler);
+        if resched {
             #[cfg(feature = "preempt")]
             curr.set_preempt_pending(true);
         }
+
+        let ticks = unsafe { BALANCE_TICKS.read_current_raw() } + 1;
+        if ticks >= LOAD_BALANCE_INTERVAL {
+            self.load_balance();
+            unsafe { BALANCE_TICKS.write_current_raw(0) };
+        } else {
+            unsafe { BALANCE_TICKS.write_current_raw(ticks) };
+        }
     }
 
-    pub fn yield_current(&mut self) {
+    pub fn yield_current(&self) {
         let curr = crate::current();
         trace!("task yield: {}", curr.id_name());
         assert!(curr.is_running());
         self.resched(false);
     }
 
-    pub fn set_current_priority(&mut self, prio: isize) -> bool {
+    pub fn set_current_priority(&self, prio: isize) -> bool {
         self.scheduler
+            .lock()
             .set_priority(crate::current().as_task_ref(), prio)
     }
 
     #[cfg(feature = "preempt")]
-    pub fn preempt_resched(&mut self) {
+    pub fn preempt_resched(&self) {
         let curr = crate::current();
         assert!(curr.is_running());
 
-        // When we get the mutable reference of the run queue, we must
-        // have held the `SpinNoIrq` lock with both IRQs and preemption
-        // disabled. So we need to set `current_disable_count` to 1 in
-        // `can_preempt()` to obtain the preemption permission before
-        //  locking the run queue.
+        // When we get the run queue of the current CPU, we must have both
+        // IRQs and preemption disabled. So we need to set
+        // `current_disable_count` to 1 in `can_preempt()` to obtain the
+        // preemption permission.
         let can_preempt = curr.can_preempt(1);
 
         debug!(
@@ -81,7 +410,7 @@ impl AxRunQueue {
         }
     }
 
-    pub fn exit_current(&mut self, exit_code: i32) -> ! {
+    pub fn exit_current(&self, exit_code: i32) -> ! {
         let curr = crate::current();
         debug!("task exit: {}, exit_code={}", curr.id_name(), exit_code);
         assert!(curr.is_running());
@@ -91,15 +420,23 @@ impl AxRunQueue {
             axhal::misc::terminate();
         } else {
             curr.set_state(TaskState::Exited);
-            curr.notify_exit(exit_code, self);
+            // release the reserved bandwidth
+            #[cfg(feature = "sched_edf")]
+            self.scheduler.lock().set_params(curr.as_task_ref(), None);
+            curr.notify_exit(exit_code);
             EXITED_TASKS.lock().push_back(curr.clone());
-            WAIT_FOR_EXIT.notify_one_locked(false, self);
+            WAIT_FOR_EXIT.notify_one(false);
             self.resched(false);
         }
         unreachable!("task exited!");
     }
 
-    pub fn block_current<F>(&mut self, wait_queue_push: F)
+    /// Blocks the current task, and calls `wait_queue_push` to put it into a
+    /// wait queue before switching out.
+    ///
+    /// The task may be woken up as soon as it is in the wait queue, it is
+    /// then switched out and in as usual.
+    pub fn block_current<F>(&self, wait_queue_push: F)
     where
         F: FnOnce(AxTaskRef),
     {
@@ -114,23 +451,13 @@ impl AxRunQueue {
 
         curr.set_state(TaskState::Blocked);
         wait_queue_push(curr.clone());
-        self.resched(false);
-    }
-
-    pub fn unblock_task(&mut self, task: AxTaskRef, resched: bool) {
-        debug!("task unblock: {}", task.id_name());
-        if task.is_blocked() {
-            task.set_state(TaskState::Ready);
-            self.scheduler.add_task(task); // TODO: priority
-            if resched {
-                #[cfg(feature = "preempt")]
-                crate::current().set_preempt_pending(true);
-            }
+        if !abort_blocking(&curr) {
+            self.resched(false);
         }
     }
 
     #[cfg(feature = "irq")]
-    pub fn sleep_until(&mut self, deadline: axhal::time::TimeValue) {
+    pub fn sleep_until(&self, deadline: axhal::time::TimeValue) {
         let curr = crate::current();
         debug!("task sleep: {}, deadline={:?}", curr.id_name(), deadline);
         assert!(curr.is_running());
@@ -138,9 +465,15 @@ impl AxRunQueue {
 
         let now = axhal::time::current_time();
         if now < deadline {
-            crate::timers::set_alarm_wakeup(deadline, curr.clone());
             curr.set_state(TaskState::Blocked);
-            self.resched(false);
+            crate::timers::set_alarm_wakeup(deadline, curr.clone());
+            if !abort_blocking(&curr) {
+                self.resched(false);
+            }
+            if curr.in_timer_list() {
+                // woken up early by `interrupt()`
+                crate::timers::cancel_alarm(curr.as_task_ref());
+            }
         }
     }
 }
@@ -148,22 +481,102 @@ impl AxRunQueue {
 impl AxRunQueue {
     /// Common reschedule subroutine. If `preempt`, keep current task's time
     /// slice, otherwise reset it.
-    fn resched(&mut self, preempt: bool) {
+    fn resched(&self, preempt: bool) {
         let prev = crate::current();
+        // it gives up the CPU voluntarily if it is blocked or exited
+        let voluntary = !prev.is_running();
         if prev.is_running() {
             prev.set_state(TaskState::Ready);
             if !prev.is_idle() {
-                self.scheduler.put_prev_task(prev.clone(), preempt);
+                if self.is_allowed(prev.as_task_ref()) {
+                    self.scheduler.lock().put_prev_task(prev.clone(), preempt);
+                } else {
+                    // it is put into another run queue in `finish_switch()`
+                    debug!("task migrate: {}, CPU {}", prev.id_name(), self.cpu_id);
+                    self.nr_tasks.fetch_sub(1, Ordering::AcqRel);
+                    unsafe { PREV_MIGRATING.write_current_raw(true) };
+                }
             }
+        } else if !prev.is_idle() {
+            // blocked or exited, it leaves this CPU
+            self.nr_tasks.fetch_sub(1, Ordering::AcqRel);
+        }
+        let next = self
+            .pick_next_task()
+            .or_else(|| self.steal_task())
+            .unwrap_or_else(|| unsafe {
+                // Safety: IRQs must be disabled at this time.
+                IDLE_TASK.current_ref_raw().get_unchecked().clone()
+            });
+        if !prev.ptr_eq(&next) {
+            prev.stats().count_switch(voluntary, !prev.is_idle());
         }
-        let next = self.scheduler.pick_next_task().unwrap_or_else(|| unsafe {
-            // Safety: IRQs must be disabled at this time.
-            IDLE_TASK.current_ref_raw().get_unchecked().clone()
-        });
         self.switch_to(prev, next);
     }
 
-    fn switch_to(&mut self, prev_task: CurrentTask, next_task: AxTaskRef) {
+    /// Whether the task can run on this CPU. It is also the case if none of the
+    /// CPUs it is allowed to run on can run tasks.
+    fn is_allowed(&self, task: &AxTaskRef) -> bool {
+        task.cpumask().get(self.cpu_id) || !is_schedulable(task.cpumask())
+    }
+
+    /// Picks the next task that can run on this CPU, and moves the picked tasks
+    /// that are not allowed to run here (as their affinity has been changed) to
+    /// other CPUs.
+    fn pick_next_task(&self) -> Option<AxTaskRef> {
+        loop {
+            let task = self.scheduler.lock().pick_next_task()?;
+            if self.is_allowed(&task) {
+                return Some(task);
+            }
+            self.nr_tasks.fetch_sub(1, Ordering::AcqRel);
+            select_run_queue(&task).enqueue(task);
+        }
+    }
+
+    /// Takes a ready task from the busiest run queue of other CPUs, if it has
+    /// at least two tasks more than this one.
+    ///
+    /// It gives up if the run queue is locked by others, or if the picked
+    /// task is still being switched out or is not allowed to run on this CPU.
+    fn steal_task(&self) -> Option<AxTaskRef> {
+        let busiest = (0..axconfig::SMP)
+            .filter(|&cpu_id| cpu_id != self.cpu_id)
+            .filter_map(run_queue_of)
+            .max_by_key(|rq| rq.nr_tasks())?;
+        if busiest.nr_tasks() < self.nr_tasks() + 2 {
+            return None;
+        }
+
+        let mut scheduler = busiest.scheduler.try_lock()?;
+        let task = scheduler.pick_next_task()?;
+        if task.on_cpu() || !task.cpumask().get(self.cpu_id) {
+            scheduler.put_prev_task(task, true);
+            return None;
+        }
+        drop(scheduler);
+
+        debug!(
+            "task migrate: {}, CPU {} -> {}",
+            task.id_name(),
+            busiest.cpu_id,
+            self.cpu_id
+        );
+        busiest.nr_tasks.fetch_sub(1, Ordering::AcqRel);
+        self.nr_tasks.fetch_add(1, Ordering::AcqRel);
+        task.set_cpu_id(self.cpu_id);
+        Some(task)
+    }
+
+    /// Pulls a task from the busiest CPU to this one, if they are imbalanced.
+    #[cfg(feature = "irq")]
+    fn load_balance(&self) {
+        if let Some(task) = self.steal_task() {
+            self.add_to_scheduler(task);
+        }
+    }
+
+    fn switch_to(&self, prev_task: CurrentTask, next_task: AxTaskRef) {
         trace!(
             "context switch: {} -> {}",
             prev_task.id_name(),
@@ -172,22 +585,47 @@ impl AxRunQueue {
         #[cfg(feature = "preempt")]
         next_task.set_preempt_pending(false);
         next_task.set_state(TaskState::Running);
+        #[cfg(all(feature = "smp", feature = "irq"))]
+        if !next_task.is_idle() {
+            crate::ipi::clear_idle();
+        }
         if prev_task.ptr_eq(&next_task) {
             return;
         }
+        #[cfg(all(feature = "sched_rt", feature = "smp"))]
+        {
+            *self.running.lock() = (!next_task.is_idle()).then(|| next_task.clone());
+        }
+        // stop the tick when the CPU becomes idle, and restart it when not
+        #[cfg(feature = "tickless")]
+        if prev_task.is_idle() != next_task.is_idle() {
+            crate::tick::reprogram(!next_task.is_idle());
+        }
+
+        // tasks switched out on another CPU are only picked after that
+        // switch is done, see `unblock_task()` and `steal_task()`
+        debug_assert!(!next_task.on_cpu());
+        next_task.set_on_cpu(true);
+        next_task.set_cpu_id(self.cpu_id);
+        #[cfg(feature = "paging")]
+        next_task.set_kernel_stack();
 
         unsafe {
             let prev_ctx_ptr = prev_task.ctx_mut_ptr();
             let next_ctx_ptr = next_task.ctx_mut_ptr();
 
             // The strong reference count of `prev_task` will be decremented by 1,
-            // but won't be dropped until `gc_entry()` is called.
+            // but won't be dropped until `gc_entry()` is called, and until the
+            // next task has released it in `finish_switch()`.
             assert!(Arc::strong_count(prev_task.as_task_ref()) > 1);
             assert!(Arc::strong_count(&next_task) >= 1);
 
+            *PREV_TASK.current_ref_mut_raw() = Some(prev_task.clone());
             CurrentTask::set_current(prev_task, next_task);
             (*prev_ctx_ptr).switch_to(&*next_ctx_ptr);
         }
+        // `self` may be another CPU's run queue from now on
+        finish_switch();
     }
 }
 
@@ -216,18 +654,30 @@ fn gc_entry() {
 pub(crate) fn init() {
     const IDLE_TASK_STACK_SIZE: usize = 4096;
     let idle_task = TaskInner::new(|| crate::run_idle(), "idle".into(), IDLE_TASK_STACK_SIZE);
+    idle_task.set_cpumask(CpuMask::one_shot(this_cpu_id()));
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
 
     let main_task = TaskInner::new_init("main".into());
     main_task.set_state(TaskState::Running);
 
-    RUN_QUEUE.init_by(AxRunQueue::new());
+    let rq = AxRunQueue::new(this_cpu_id());
+    rq.nr_tasks.store(1, Ordering::Release); // the main task
+    #[cfg(all(feature = "sched_rt", feature = "smp"))]
+    {
+        *rq.running.lock() = Some(main_task.clone());
+    }
+    RUN_QUEUE.with_current(|r| r.init_by(rq));
     unsafe { CurrentTask::init_current(main_task) }
+
+    let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
+    current_run_queue().add_task(gc_task);
 }
 
 pub(crate) fn init_secondary() {
     let idle_task = TaskInner::new_init("idle".into());
     idle_task.set_state(TaskState::Running);
+    idle_task.set_cpumask(CpuMask::one_shot(this_cpu_id()));
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
+    RUN_QUEUE.with_current(|r| r.init_by(AxRunQueue::new(this_cpu_id())));
     unsafe { CurrentTask::init_current(idle_task) }
 }
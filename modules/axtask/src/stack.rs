@@ -0,0 +1,143 @@
+//! Task stacks with guard pages.
+//!
+//! Task stacks are allocated in a dedicated virtual region (see
+//! [`axconfig::TASK_STACK_REGION_BASE`]), and each of them is mapped to
+//! physical frames with an unmapped guard page below it. A stack overflow then
+//! hits the guard page, and is reported by the trap handler instead of
+//! corrupting the memory below the stack.
+
+use alloc::vec::Vec;
+
+use allocator::{BaseAllocator, BitmapPageAllocator, PageAllocator};
+use axalloc::global_allocator;
+use axhal::mem::{phys_to_virt, virt_to_phys, PAGE_SIZE_4K};
+use axhal::paging::{kernel_page_table, MappingFlags};
+use memory_addr::VirtAddr;
+use spinlock::SpinNoIrq;
+
+const GUARD_SIZE: usize = PAGE_SIZE_4K;
+
+struct StackRegion {
+    /// Allocator of the virtual pages in the region.
+    pages: BitmapPageAllocator<PAGE_SIZE_4K>,
+    /// Freed ranges `(start, num_pages)` that may still be cached in the TLBs
+    /// of other CPUs. They can be reused only after all TLBs are flushed.
+    stale: Vec<(usize, usize)>,
+}
+
+static STACK_REGION: SpinNoIrq<StackRegion> = SpinNoIrq::new(StackRegion {
+    pages: BitmapPageAllocator::new(),
+    stale: Vec::new(),
+});
+
+/// Initializes the task stack region.
+pub(crate) fn init() {
+    assert!(
+        axconfig::TASK_STACK_REGION_SIZE > 0,
+        "the task stack region is not configured for this platform"
+    );
+    STACK_REGION.lock().pages.init(
+        axconfig::TASK_STACK_REGION_BASE,
+        axconfig::TASK_STACK_REGION_SIZE,
+    );
+}
+
+/// Makes the stale ranges allocatable again, after flushing the TLBs of all
+/// CPUs.
+///
+/// It does nothing if IRQs are disabled, as other CPUs cannot be asked to
+/// flush their TLBs. Without `irq`, the stale ranges are never reused.
+#[cfg(feature = "smp")]
+fn reclaim_stale_ranges() {
+    #[cfg(feature = "irq")]
+    if axhal::arch::irqs_enabled() {
+        // do not wait for other CPUs with the lock held
+        let stale = core::mem::take(&mut STACK_REGION.lock().stale);
+        if !stale.is_empty() {
+            crate::flush_tlb_all_cpus(None);
+            let mut region = STACK_REGION.lock();
+            for (start, num_pages) in stale {
+                region.pages.dealloc_pages(start, num_pages);
+            }
+        }
+    }
+}
+
+/// A task stack with an unmapped guard page below it.
+pub(crate) struct TaskStack {
+    bottom: VirtAddr,
+    size: usize,
+}
+
+impl TaskStack {
+    /// Allocates a stack of `size` bytes, which must be aligned to 4K.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the task stack region or the physical memory is exhausted.
+    pub fn alloc(size: usize) -> Self {
+        let num_pages = size / PAGE_SIZE_4K;
+        #[cfg(feature = "smp")]
+        reclaim_stale_ranges();
+        let start = STACK_REGION
+            .lock()
+            .pages
+            .alloc_pages(num_pages + 1, PAGE_SIZE_4K)
+            .expect("task stack region exhausted");
+        let bottom = VirtAddr::from(start + GUARD_SIZE);
+
+        let frames = global_allocator()
+            .alloc_pages(num_pages, PAGE_SIZE_4K)
+            .expect("failed to allocate task stack");
+        kernel_page_table()
+            .lock()
+            .map_region(
+                bottom,
+                virt_to_phys(VirtAddr::from(frames)),
+                size,
+                MappingFlags::READ | MappingFlags::WRITE,
+                false,
+            )
+            .expect("failed to map task stack");
+        // some architectures may cache invalid entries
+        axhal::arch::flush_tlb(None);
+        Self { bottom, size }
+    }
+
+    /// Returns the lowest address of the stack, right above the guard page.
+    pub const fn bottom(&self) -> VirtAddr {
+        self.bottom
+    }
+
+    /// Returns the highest address of the stack.
+    pub fn top(&self) -> VirtAddr {
+        self.bottom + self.size
+    }
+
+    /// Returns the size of the stack in bytes, excluding the guard page.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Drop for TaskStack {
+    fn drop(&mut self) {
+        let num_pages = self.size / PAGE_SIZE_4K;
+        let paddr = {
+            let mut page_table = kernel_page_table().lock();
+            let (paddr, _, _) = page_table.query(self.bottom).unwrap();
+            page_table.unmap_region(self.bottom, self.size).unwrap();
+            paddr
+        };
+        axhal::arch::flush_tlb(None);
+        global_allocator().dealloc_pages(phys_to_virt(paddr).as_usize(), num_pages);
+
+        let start = self.bottom.as_usize() - GUARD_SIZE;
+        let mut region = STACK_REGION.lock();
+        if cfg!(feature = "smp") {
+            region.stale.push((start, num_pages + 1));
+        } else {
+            region.pages.dealloc_pages(start, num_pages + 1);
+        }
+    }
+}
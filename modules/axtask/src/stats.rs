@@ -0,0 +1,185 @@
+//! Per-task accounting of CPU time, context switches and state changes.
+//!
+//! The time is accounted on every state change of a task, so the time spent in
+//! each state is known. As all tasks run in the kernel, the "kernel time" of a
+//! task is the time spent in IRQ handlers while it is running, and the rest of
+//! its running time is the "user time".
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+
+use axhal::time::current_time_nanos;
+
+use crate::task::{TaskInner, TaskState};
+
+/// The total running time of all tasks except the idle tasks, in nanoseconds.
+static TOTAL_RUN_TIME: AtomicU64 = AtomicU64::new(0);
+/// The total time spent in IRQ handlers, except those interrupting the idle
+/// tasks, in nanoseconds.
+static TOTAL_IRQ_TIME: AtomicU64 = AtomicU64::new(0);
+static TOTAL_NVCSW: AtomicU64 = AtomicU64::new(0);
+static TOTAL_NIVCSW: AtomicU64 = AtomicU64::new(0);
+
+/// The CPU usage of a task, or of all tasks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuUsage {
+    /// The running time outside IRQ handlers.
+    pub user_time: Duration,
+    /// The time spent in IRQ handlers.
+    pub kernel_time: Duration,
+    /// The number of times it gives up the CPU by blocking or exiting.
+    pub voluntary_switches: u64,
+    /// The number of times it is preempted, or gives up the CPU by yielding.
+    pub involuntary_switches: u64,
+}
+
+/// A snapshot of the accounting information of a task, see
+/// [`task_stats`](crate::task_stats).
+#[derive(Debug, Clone, Copy)]
+pub struct TaskStats {
+    /// The state of the task.
+    pub state: TaskState,
+    /// The ID of the CPU it last ran on, or is running on.
+    pub last_cpu: usize,
+    /// The CPU usage of the task.
+    pub usage: CpuUsage,
+    /// The time spent ready in run queues, waiting for a CPU.
+    pub ready_time: Duration,
+    /// The time spent blocked, e.g., in wait queues or sleeping.
+    pub blocked_time: Duration,
+    /// The time from the last wakeup to the time it runs.
+    pub last_wakeup_latency: Duration,
+    /// The maximum time from a wakeup to the time it runs.
+    pub max_wakeup_latency: Duration,
+}
+
+/// The accounting information of a task, updated by the scheduler.
+pub(crate) struct TaskAccounting {
+    /// When the task entered its current state.
+    state_since: AtomicU64,
+    run_time: AtomicU64,
+    ready_time: AtomicU64,
+    blocked_time: AtomicU64,
+    irq_time: AtomicU64,
+    nvcsw: AtomicU64,
+    nivcsw: AtomicU64,
+    /// Whether the task is ready after a wakeup, i.e., the time in the ready
+    /// state is a wakeup latency.
+    woken: AtomicBool,
+    last_wakeup_latency: AtomicU64,
+    max_wakeup_latency: AtomicU64,
+}
+
+impl TaskAccounting {
+    pub fn new() -> Self {
+        Self {
+            state_since: AtomicU64::new(current_time_nanos()),
+            run_time: AtomicU64::new(0),
+            ready_time: AtomicU64::new(0),
+            blocked_time: AtomicU64::new(0),
+            irq_time: AtomicU64::new(0),
+            nvcsw: AtomicU64::new(0),
+            nivcsw: AtomicU64::new(0),
+            woken: AtomicBool::new(false),
+            last_wakeup_latency: AtomicU64::new(0),
+            max_wakeup_latency: AtomicU64::new(0),
+        }
+    }
+
+    /// Accounts the time spent in the `old` state, as the task moves to the
+    /// `new` state. `global` is whether it is also added to the totals.
+    pub fn account_state(&self, old: TaskState, new: TaskState, global: bool) {
+        let now = current_time_nanos();
+        let elapsed = now.saturating_sub(self.state_since.swap(now, Ordering::Relaxed));
+        match old {
+            TaskState::Running => {
+                self.run_time.fetch_add(elapsed, Ordering::Relaxed);
+                if global {
+                    TOTAL_RUN_TIME.fetch_add(elapsed, Ordering::Relaxed);
+                }
+            }
+            TaskState::Ready => {
+                self.ready_time.fetch_add(elapsed, Ordering::Relaxed);
+                if new == TaskState::Running && self.woken.swap(false, Ordering::Relaxed) {
+                    self.last_wakeup_latency.store(elapsed, Ordering::Relaxed);
+                    self.max_wakeup_latency
+                        .fetch_max(elapsed, Ordering::Relaxed);
+                }
+            }
+            TaskState::Blocked => {
+                self.blocked_time.fetch_add(elapsed, Ordering::Relaxed);
+                if new == TaskState::Ready {
+                    self.woken.store(true, Ordering::Relaxed);
+                }
+            }
+            TaskState::Exited => {}
+        }
+    }
+
+    /// Counts a context switch from the task.
+    pub fn count_switch(&self, voluntary: bool, global: bool) {
+        let (counter, total) = if voluntary {
+            (&self.nvcsw, &TOTAL_NVCSW)
+        } else {
+            (&self.nivcsw, &TOTAL_NIVCSW)
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if global {
+            total.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Accounts the time spent in an IRQ handler while the task is running.
+    pub fn add_irq_time(&self, nanos: u64, global: bool) {
+        self.irq_time.fetch_add(nanos, Ordering::Relaxed);
+        if global {
+            TOTAL_IRQ_TIME.fetch_add(nanos, Ordering::Relaxed);
+        }
+    }
+
+    /// Takes a snapshot, including the time in the current state so far.
+    pub fn snapshot(&self, state: TaskState, last_cpu: usize) -> TaskStats {
+        let load = |time: &AtomicU64| time.load(Ordering::Relaxed);
+        let current = current_time_nanos().saturating_sub(load(&self.state_since));
+        let in_state = |s| if s == state { current } else { 0 };
+
+        let run_time = load(&self.run_time) + in_state(TaskState::Running);
+        let irq_time = load(&self.irq_time);
+        TaskStats {
+            state,
+            last_cpu,
+            usage: CpuUsage {
+                user_time: Duration::from_nanos(run_time.saturating_sub(irq_time)),
+                kernel_time: Duration::from_nanos(irq_time),
+                voluntary_switches: load(&self.nvcsw),
+                involuntary_switches: load(&self.nivcsw),
+            },
+            ready_time: Duration::from_nanos(load(&self.ready_time) + in_state(TaskState::Ready)),
+            blocked_time: Duration::from_nanos(
+                load(&self.blocked_time) + in_state(TaskState::Blocked),
+            ),
+            last_wakeup_latency: Duration::from_nanos(load(&self.last_wakeup_latency)),
+            max_wakeup_latency: Duration::from_nanos(load(&self.max_wakeup_latency)),
+        }
+    }
+}
+
+/// Returns the total CPU usage of all tasks except the idle tasks, including
+/// the exited ones.
+///
+/// The running time of a task is added to the total when it leaves the running
+/// state, so only the current run of `curr` (if it is running) is included.
+pub(crate) fn total_cpu_usage(curr: Option<&TaskInner>) -> CpuUsage {
+    let mut run_time = TOTAL_RUN_TIME.load(Ordering::Relaxed);
+    if let Some(curr) = curr.filter(|curr| curr.is_running() && !curr.is_idle()) {
+        let since = curr.stats().state_since.load(Ordering::Relaxed);
+        run_time += current_time_nanos().saturating_sub(since);
+    }
+    let irq_time = TOTAL_IRQ_TIME.load(Ordering::Relaxed);
+    CpuUsage {
+        user_time: Duration::from_nanos(run_time.saturating_sub(irq_time)),
+        kernel_time: Duration::from_nanos(irq_time),
+        voluntary_switches: TOTAL_NVCSW.load(Ordering::Relaxed),
+        involuntary_switches: TOTAL_NIVCSW.load(Ordering::Relaxed),
+    }
+}
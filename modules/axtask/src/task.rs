@@ -1,18 +1,22 @@
-use alloc::{boxed::Box, string::String, sync::Arc};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use core::ops::Deref;
-use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, Ordering};
-use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
-
-#[cfg(feature = "preempt")]
-use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+#[cfg(not(feature = "paging"))]
+use core::{alloc::Layout, ptr::NonNull};
+use core::{cell::UnsafeCell, fmt};
 
 #[cfg(feature = "tls")]
 use axhal::tls::TlsArea;
 
 use axhal::arch::TaskContext;
 use memory_addr::{align_up_4k, VirtAddr};
+use scheduler::SchedPolicy;
+use spinlock::SpinNoIrq;
 
-use crate::{AxRunQueue, AxTask, AxTaskRef, WaitQueue};
+#[cfg(feature = "paging")]
+use crate::stack::TaskStack;
+use crate::stats::TaskAccounting;
+use crate::{AxTask, AxTaskRef, CpuMask, WaitQueue};
 
 /// A unique identifier for a thread.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -21,13 +25,35 @@ pub struct TaskId(u64);
 /// The possible states of a task.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub(crate) enum TaskState {
+pub enum TaskState {
+    /// Running on a CPU.
     Running = 1,
+    /// Ready to run, waiting in a run queue.
     Ready = 2,
+    /// Blocked, e.g., in a wait queue or sleeping.
     Blocked = 3,
+    /// Exited, but not dropped yet.
     Exited = 4,
 }
 
+/// The scheduling parameters lent to a task by priority inheritance.
+pub(crate) struct LentSched {
+    /// The original scheduling policy and priority, while the priority is
+    /// boosted.
+    pub base: Option<(SchedPolicy, isize)>,
+    /// The most urgent parameters lent through each lock held by the task.
+    pub locks: Vec<(usize, (SchedPolicy, isize))>,
+}
+
+impl LentSched {
+    const fn new() -> Self {
+        Self {
+            base: None,
+            locks: Vec::new(),
+        }
+    }
+}
+
 /// The inner task structure.
 pub struct TaskInner {
     id: TaskId,
@@ -38,9 +64,24 @@ pub struct TaskInner {
     entry: Option<*mut dyn FnOnce()>,
     state: AtomicU8,
 
+    /// CPU ID of the run queue that the task belongs to.
+    cpu_id: AtomicUsize,
+    /// Whether the context of the task is on a CPU, i.e., it is running or
+    /// being switched out.
+    on_cpu: AtomicBool,
+    /// The CPUs that the task is allowed to run on.
+    cpumask: AtomicUsize,
+    /// The affinity set before it becomes a deadline task (pinned to a CPU),
+    /// restored when the deadline is cleared.
+    #[cfg(feature = "sched_edf")]
+    saved_cpumask: AtomicUsize,
+
     in_wait_queue: AtomicBool,
     #[cfg(feature = "irq")]
     in_timer_list: AtomicBool,
+    /// Whether the task is interrupted, so that it does not block until it
+    /// notices, see [`interrupt`](crate::interrupt).
+    interrupted: AtomicBool,
 
     #[cfg(feature = "preempt")]
     need_resched: AtomicBool,
@@ -50,6 +91,9 @@ pub struct TaskInner {
     exit_code: AtomicI32,
     wait_for_exit: WaitQueue,
 
+    stats: TaskAccounting,
+    lent_sched: SpinNoIrq<LentSched>,
+
     kstack: Option<TaskStack>,
     ctx: UnsafeCell<TaskContext>,
 
@@ -101,6 +145,22 @@ impl TaskInner {
         alloc::format!("Task({}, {:?})", self.id.as_u64(), self.name)
     }
 
+    /// Gets the size of the task stack in bytes, or 0 if the task runs on a
+    /// boot stack.
+    pub fn stack_size(&self) -> usize {
+        self.kstack.as_ref().map_or(0, TaskStack::size)
+    }
+
+    /// Gets the CPUs that the task is allowed to run on.
+    pub fn cpumask(&self) -> CpuMask {
+        CpuMask::from_bits(self.cpumask.load(Ordering::Acquire))
+    }
+
+    /// Whether the task has exited (but not dropped).
+    pub fn is_exited(&self) -> bool {
+        self.state() == TaskState::Exited
+    }
+
     /// Wait for the task to exit, and return the exit code.
     ///
     /// It will return immediately if the task has already exited (but not dropped).
@@ -121,15 +181,23 @@ impl TaskInner {
             is_init: false,
             entry: None,
             state: AtomicU8::new(TaskState::Ready as u8),
+            cpu_id: AtomicUsize::new(0),
+            on_cpu: AtomicBool::new(false),
+            cpumask: AtomicUsize::new(CpuMask::full().bits()),
+            #[cfg(feature = "sched_edf")]
+            saved_cpumask: AtomicUsize::new(CpuMask::full().bits()),
             in_wait_queue: AtomicBool::new(false),
             #[cfg(feature = "irq")]
             in_timer_list: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
             need_resched: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
             preempt_disable_count: AtomicUsize::new(0),
             exit_code: AtomicI32::new(0),
             wait_for_exit: WaitQueue::new(),
+            stats: TaskAccounting::new(),
+            lent_sched: SpinNoIrq::new(LentSched::new()),
             kstack: None,
             ctx: UnsafeCell::new(TaskContext::new()),
             #[cfg(feature = "tls")]
@@ -157,7 +225,9 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        let task = Arc::new(AxTask::new(t));
+        crate::registry::register(&task);
+        task
     }
 
     /// Creates an "init task" using the current CPU states, to use as the
@@ -171,10 +241,14 @@ impl TaskInner {
     pub(crate) fn new_init(name: String) -> AxTaskRef {
         let mut t = Self::new_common(TaskId::new(), name);
         t.is_init = true;
+        t.cpu_id = AtomicUsize::new(axhal::cpu::this_cpu_id());
+        t.on_cpu = AtomicBool::new(true);
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        let task = Arc::new(AxTask::new(t));
+        crate::registry::register(&task);
+        task
     }
 
     #[inline]
@@ -184,7 +258,37 @@ impl TaskInner {
 
     #[inline]
     pub(crate) fn set_state(&self, state: TaskState) {
-        self.state.store(state as u8, Ordering::Release)
+        let old = self.state.swap(state as u8, Ordering::AcqRel);
+        self.stats.account_state(old.into(), state, !self.is_idle);
+    }
+
+    /// Sets the state to `new` if it is `current`. Returns whether it
+    /// succeeded.
+    #[inline]
+    pub(crate) fn transition_state(&self, current: TaskState, new: TaskState) -> bool {
+        let ok = self
+            .state
+            .compare_exchange(
+                current as u8,
+                new as u8,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+        if ok {
+            self.stats.account_state(current, new, !self.is_idle);
+        }
+        ok
+    }
+
+    #[inline]
+    pub(crate) const fn stats(&self) -> &TaskAccounting {
+        &self.stats
+    }
+
+    #[inline]
+    pub(crate) const fn lent_sched(&self) -> &SpinNoIrq<LentSched> {
+        &self.lent_sched
     }
 
     #[inline]
@@ -212,6 +316,47 @@ impl TaskInner {
         self.is_idle
     }
 
+    #[inline]
+    pub(crate) fn cpu_id(&self) -> usize {
+        self.cpu_id.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_cpu_id(&self, cpu_id: usize) {
+        self.cpu_id.store(cpu_id, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn set_cpumask(&self, cpumask: CpuMask) {
+        self.cpumask.store(cpumask.bits(), Ordering::Release);
+    }
+
+    /// Pins the task to `cpu_id` as a deadline task, and saves its affinity.
+    #[cfg(feature = "sched_edf")]
+    pub(crate) fn pin_cpumask(&self, cpu_id: usize) {
+        let old = self
+            .cpumask
+            .swap(CpuMask::one_shot(cpu_id).bits(), Ordering::AcqRel);
+        self.saved_cpumask.store(old, Ordering::Release);
+    }
+
+    /// Restores the affinity saved by [`pin_cpumask`](Self::pin_cpumask).
+    #[cfg(feature = "sched_edf")]
+    pub(crate) fn unpin_cpumask(&self) {
+        let saved = self.saved_cpumask.load(Ordering::Acquire);
+        self.cpumask.store(saved, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn on_cpu(&self) -> bool {
+        self.on_cpu.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_on_cpu(&self, on_cpu: bool) {
+        self.on_cpu.store(on_cpu, Ordering::Release);
+    }
+
     #[inline]
     pub(crate) fn in_wait_queue(&self) -> bool {
         self.in_wait_queue.load(Ordering::Acquire)
@@ -234,6 +379,16 @@ impl TaskInner {
         self.in_timer_list.store(in_timer_list, Ordering::Release);
     }
 
+    #[inline]
+    pub(crate) fn set_interrupted(&self) {
+        self.interrupted.store(true, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn take_interrupted(&self) -> bool {
+        self.interrupted.swap(false, Ordering::AcqRel)
+    }
+
     #[inline]
     #[cfg(feature = "preempt")]
     pub(crate) fn set_preempt_pending(&self, pending: bool) {
@@ -265,22 +420,35 @@ impl TaskInner {
     fn current_check_preempt_pending() {
         let curr = crate::current();
         if curr.need_resched.load(Ordering::Acquire) && curr.can_preempt(0) {
-            let mut rq = crate::RUN_QUEUE.lock();
+            let rq = crate::current_run_queue();
             if curr.need_resched.load(Ordering::Acquire) {
                 rq.preempt_resched();
             }
         }
     }
 
-    pub(crate) fn notify_exit(&self, exit_code: i32, rq: &mut AxRunQueue) {
+    pub(crate) fn notify_exit(&self, exit_code: i32) {
         self.exit_code.store(exit_code, Ordering::Release);
-        self.wait_for_exit.notify_all_locked(false, rq);
+        self.wait_for_exit.notify_all(false);
     }
 
     #[inline]
     pub(crate) const unsafe fn ctx_mut_ptr(&self) -> *mut TaskContext {
         self.ctx.get()
     }
+
+    /// Tells the current CPU the bounds of the guarded kernel stack of the
+    /// task, before switching to it.
+    #[cfg(feature = "paging")]
+    pub(crate) fn set_kernel_stack(&self) {
+        match &self.kstack {
+            Some(kstack) => {
+                axhal::arch::set_kernel_stack(kstack.bottom().as_usize(), kstack.top().as_usize())
+            }
+            // init tasks run on the boot stacks, which are not guarded
+            None => axhal::arch::set_kernel_stack(0, 0),
+        }
+    }
 }
 
 impl fmt::Debug for TaskInner {
@@ -296,14 +464,17 @@ impl fmt::Debug for TaskInner {
 impl Drop for TaskInner {
     fn drop(&mut self) {
         debug!("task drop: {}", self.id_name());
+        crate::registry::unregister(self.id.as_u64());
     }
 }
 
+#[cfg(not(feature = "paging"))]
 struct TaskStack {
     ptr: NonNull<u8>,
     layout: Layout,
 }
 
+#[cfg(not(feature = "paging"))]
 impl TaskStack {
     pub fn alloc(size: usize) -> Self {
         let layout = Layout::from_size_align(size, 16).unwrap();
@@ -316,8 +487,13 @@ impl TaskStack {
     pub const fn top(&self) -> VirtAddr {
         unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
     }
+
+    pub const fn size(&self) -> usize {
+        self.layout.size()
+    }
 }
 
+#[cfg(not(feature = "paging"))]
 impl Drop for TaskStack {
     fn drop(&mut self) {
         unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
@@ -379,8 +555,8 @@ impl Deref for CurrentTask {
 }
 
 extern "C" fn task_entry() -> ! {
-    // release the lock that was implicitly held across the reschedule
-    unsafe { crate::RUN_QUEUE.force_unlock() };
+    // release the previous task, as `switch_to()` does not return here
+    crate::run_queue::finish_switch();
     #[cfg(feature = "irq")]
     axhal::arch::enable_irqs();
     let task = crate::current();
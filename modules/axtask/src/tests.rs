@@ -1,7 +1,7 @@
 use core::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Mutex, Once};
 
-use crate::{self as axtask, current, WaitQueue};
+use crate::{self as axtask, current, CpuMask, WaitQueue};
 
 static INIT: Once = Once::new();
 static SERIAL: Mutex<()> = Mutex::new(());
@@ -128,3 +128,171 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_interrupt() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    let task = axtask::spawn(|| {
+        WQ.wait();
+        WOKEN.fetch_add(1, Ordering::Relaxed);
+        WQ.wait();
+        WOKEN.fetch_add(1, Ordering::Relaxed);
+    });
+    // not blocked yet, its next blocking returns immediately
+    axtask::interrupt(&task);
+    while WOKEN.load(Ordering::Relaxed) == 0 || task.state() != axtask::TaskState::Blocked {
+        axtask::yield_now();
+    }
+    axtask::interrupt(&task);
+    assert_eq!(task.join(), Some(0));
+    assert_eq!(WOKEN.load(Ordering::Relaxed), 2);
+    assert!(!task.in_wait_queue());
+}
+
+#[test]
+fn test_affinity() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let task = axtask::spawn_raw_with_affinity(
+        || {
+            println!("affinity: task {}", current().id_name());
+            assert!(current().cpumask().get(0));
+            axtask::yield_now();
+        },
+        "T0".into(),
+        0x1000,
+        CpuMask::one_shot(0),
+    );
+    assert_eq!(axtask::get_affinity(&task), CpuMask::one_shot(0));
+
+    // no CPU to run on
+    assert!(!axtask::set_affinity(&task, CpuMask::new()));
+    assert_eq!(axtask::get_affinity(&task), CpuMask::one_shot(0));
+
+    let curr = current().as_task_ref().clone();
+    assert!(axtask::set_affinity(&curr, CpuMask::one_shot(0)));
+    axtask::yield_now();
+    assert!(axtask::set_affinity(&curr, CpuMask::full()));
+    assert_eq!(task.join(), Some(0));
+}
+
+#[test]
+fn test_task_stats() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    let task = axtask::spawn_raw(
+        || {
+            axtask::yield_now();
+            WQ.wait_until(|| WOKEN.load(Ordering::Acquire) == 1);
+        },
+        "T0".into(),
+        0x1000,
+    );
+    while !task.is_blocked() {
+        axtask::yield_now();
+    }
+    WOKEN.store(1, Ordering::Release);
+    WQ.notify_one(true);
+    assert_eq!(task.join(), Some(0));
+
+    let stats = axtask::task_stats(&task);
+    assert_eq!(stats.state, axtask::TaskState::Exited);
+    assert_eq!(stats.usage.involuntary_switches, 1); // yield
+    assert_eq!(stats.usage.voluntary_switches, 2); // block and exit
+    assert!(stats.max_wakeup_latency >= stats.last_wakeup_latency);
+
+    let total = axtask::total_cpu_usage();
+    assert!(total.voluntary_switches >= 2);
+}
+
+#[test]
+fn test_task_registry() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let task = axtask::spawn_raw(axtask::yield_now, "registry".into(), 0x1000);
+    let id = task.id().as_u64();
+    assert!(axtask::tasks().iter().any(|t| t.id().as_u64() == id));
+    assert!(axtask::find_task(current().id().as_u64()).is_some());
+
+    let info = axtask::task_info(&task);
+    assert_eq!(info.id, id);
+    assert_eq!(info.name, "registry");
+    assert_eq!(info.stack_size, 0x1000);
+
+    assert_eq!(task.join(), Some(0));
+    drop(task);
+    // the task may be still referenced by the run queue
+    while axtask::find_task(id).is_some() {
+        axtask::yield_now();
+    }
+    assert!(axtask::tasks().iter().all(|t| t.id().as_u64() != id));
+}
+
+#[test]
+fn test_block_on() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    assert_eq!(axtask::future::block_on(async { 42 }), 42);
+
+    for _ in 0..NUM_TASKS {
+        axtask::spawn(|| {
+            axtask::future::block_on(async {
+                COUNTER.fetch_add(1, Ordering::Relaxed);
+                WQ.notify_all(true);
+                WQ.wait_until_async(|| COUNTER.load(Ordering::Relaxed) >= NUM_TASKS)
+                    .await;
+            });
+            COUNTER.fetch_add(1, Ordering::Relaxed);
+            WQ.notify_all(true);
+        });
+    }
+
+    axtask::future::block_on(
+        WQ.wait_until_async(|| COUNTER.load(Ordering::Relaxed) == NUM_TASKS * 2),
+    );
+    assert!(!current().in_wait_queue());
+}
+
+#[test]
+fn test_spawn_async() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    const NUM_FUTURES: usize = 100;
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static STARTED: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_FUTURES {
+        axtask::future::spawn(async {
+            STARTED.fetch_add(1, Ordering::Relaxed);
+            WQ.notify_all(true);
+            // all the futures are pending at the same time on a few workers
+            WQ.wait_until_async(|| STARTED.load(Ordering::Relaxed) == NUM_FUTURES)
+                .await;
+            FINISHED.fetch_add(1, Ordering::Relaxed);
+            WQ.notify_all(true);
+        });
+    }
+
+    WQ.wait_until(|| FINISHED.load(Ordering::Relaxed) == NUM_FUTURES);
+    assert!(!current().in_wait_queue());
+}
@@ -0,0 +1,78 @@
+//! Dynamic ticks for the tickless mode.
+//!
+//! Instead of interrupting every `1 / TICKS_PER_SEC` second, the timer of each
+//! CPU is programmed to the next time it is needed: the earliest timer event,
+//! or the next tick if the CPU is running tasks with a preemptive scheduler,
+//! which counts time slices in ticks. So idle CPUs sleep with the tick stopped.
+
+use axhal::time::{current_time_nanos, set_oneshot_timer, TimeValue, NANOS_PER_SEC};
+
+const TICK_NANOS: u64 = NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+
+/// The longest time between two timer interrupts, as the intervals of some
+/// hardware timers are limited.
+const MAX_SLEEP_NANOS: u64 = NANOS_PER_SEC;
+
+/// The time of the next tick on each CPU.
+#[percpu::def_percpu]
+static NEXT_TICK: u64 = 0;
+
+/// The time that the timer of each CPU is programmed to.
+#[percpu::def_percpu]
+static TIMER_DEADLINE: u64 = 0;
+
+/// Whether a tick has elapsed on the current CPU, i.e., the timer interrupt is
+/// not only for timer events. It moves to the next tick if so.
+///
+/// It is called in the timer interrupt handler.
+pub(crate) fn tick_elapsed() -> bool {
+    let now = current_time_nanos();
+    // Safety: IRQs are disabled in the IRQ handler.
+    let next_tick = unsafe { NEXT_TICK.read_current_raw() };
+    if now < next_tick {
+        return false;
+    }
+    // skip the ticks missed while the tick is stopped
+    let next_tick = if now >= next_tick + TICK_NANOS {
+        now + TICK_NANOS
+    } else {
+        next_tick + TICK_NANOS
+    };
+    unsafe { NEXT_TICK.write_current_raw(next_tick) };
+    true
+}
+
+/// Programs the timer of the current CPU to the next time it is needed.
+///
+/// `busy` is whether the CPU is running (or about to run) a task other than
+/// the idle task, otherwise the tick is stopped. It must be called with IRQs
+/// disabled.
+pub(crate) fn reprogram(busy: bool) {
+    let now = current_time_nanos();
+    let mut deadline = now + MAX_SLEEP_NANOS;
+    if busy && cfg!(feature = "preempt") {
+        let mut next_tick = unsafe { NEXT_TICK.read_current_raw() };
+        if next_tick <= now {
+            // restart the stopped tick
+            next_tick = now + TICK_NANOS;
+            unsafe { NEXT_TICK.write_current_raw(next_tick) };
+        }
+        deadline = deadline.min(next_tick);
+    }
+    if let Some(next_event) = crate::timers::next_deadline() {
+        deadline = deadline.min(next_event.as_nanos() as u64);
+    }
+    unsafe { TIMER_DEADLINE.write_current_raw(deadline) };
+    set_oneshot_timer(deadline);
+}
+
+/// Makes sure that the timer of the current CPU fires no later than
+/// `deadline`, e.g., for a new timer event. It must be called with IRQs
+/// disabled.
+pub(crate) fn fire_before(deadline: TimeValue) {
+    let deadline = deadline.as_nanos() as u64;
+    if deadline < unsafe { TIMER_DEADLINE.read_current_raw() } {
+        unsafe { TIMER_DEADLINE.write_current_raw(deadline) };
+        set_oneshot_timer(deadline);
+    }
+}
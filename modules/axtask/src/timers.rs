@@ -1,34 +1,84 @@
-use alloc::sync::Arc;
+use alloc::{boxed::Box, sync::Arc};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::Waker;
+
 use axhal::time::current_time;
 use lazy_init::LazyInit;
 use spinlock::SpinNoIrq;
 use timer_list::{TimeValue, TimerEvent, TimerList};
 
-use crate::{AxTaskRef, RUN_QUEUE};
+use crate::AxTaskRef;
 
 // TODO: per-CPU
 static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<TaskWakeupEvent>>> = LazyInit::new();
 
-struct TaskWakeupEvent(AxTaskRef);
+enum TaskWakeupEvent {
+    /// Unblocks a sleeping task.
+    Task(AxTaskRef),
+    /// Wakes an asynchronous task, see [`crate::future::sleep`].
+    Waker(Waker),
+    /// Calls a function with its ID, see [`crate::set_timer`].
+    Callback(u64, Box<dyn FnOnce(TimeValue) + Send>),
+}
 
 impl TimerEvent for TaskWakeupEvent {
-    fn callback(self, _now: TimeValue) {
-        let mut rq = RUN_QUEUE.lock();
-        self.0.set_in_timer_list(false);
-        rq.unblock_task(self.0, true);
+    fn callback(self, now: TimeValue) {
+        match self {
+            Self::Task(task) => {
+                task.set_in_timer_list(false);
+                crate::run_queue::unblock_task(task, true);
+            }
+            Self::Waker(waker) => waker.wake(),
+            Self::Callback(_, callback) => callback(now),
+        }
     }
 }
 
+fn set_event(deadline: TimeValue, event: TaskWakeupEvent) {
+    // stay on this CPU with IRQs disabled until its timer is reprogrammed, or
+    // the timer IRQ may overwrite the new deadline in between
+    let _guard = kernel_guard::NoPreemptIrqSave::new();
+    TIMER_LIST.lock().set(deadline, event);
+    #[cfg(feature = "tickless")]
+    crate::tick::fire_before(deadline);
+}
+
 pub fn set_alarm_wakeup(deadline: TimeValue, task: AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
     task.set_in_timer_list(true);
-    timers.set(deadline, TaskWakeupEvent(task));
+    set_event(deadline, TaskWakeupEvent::Task(task));
+}
+
+/// Wakes the `waker` at the `deadline`. It is not cancellable, a spurious
+/// wakeup is harmless for futures.
+pub fn set_alarm_waker(deadline: TimeValue, waker: Waker) {
+    set_event(deadline, TaskWakeupEvent::Waker(waker));
+}
+
+/// Calls `callback` at the `deadline` in the timer interrupt, returns the ID
+/// to cancel it.
+pub fn set_callback(deadline: TimeValue, callback: Box<dyn FnOnce(TimeValue) + Send>) -> u64 {
+    static ID_COUNTER: AtomicU64 = AtomicU64::new(1);
+    let id = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    set_event(deadline, TaskWakeupEvent::Callback(id, callback));
+    id
+}
+
+/// Cancels the callback with the given ID, returns `false` if it has been
+/// called or cancelled.
+pub fn cancel_callback(id: u64) -> bool {
+    let found = core::cell::Cell::new(false);
+    TIMER_LIST.lock().cancel(|e| {
+        let matched = matches!(e, TaskWakeupEvent::Callback(i, _) if *i == id);
+        found.set(found.get() || matched);
+        matched
+    });
+    found.get()
 }
 
 pub fn cancel_alarm(task: &AxTaskRef) {
     let mut timers = TIMER_LIST.lock();
     task.set_in_timer_list(false);
-    timers.cancel(|t| Arc::ptr_eq(&t.0, task));
+    timers.cancel(|t| matches!(t, TaskWakeupEvent::Task(t) if Arc::ptr_eq(t, task)));
 }
 
 pub fn check_events() {
@@ -43,6 +93,12 @@ pub fn check_events() {
     }
 }
 
+/// Returns the deadline of the earliest timer event, if any.
+#[cfg(feature = "tickless")]
+pub fn next_deadline() -> Option<TimeValue> {
+    TIMER_LIST.lock().next_deadline()
+}
+
 pub fn init() {
     TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
 }
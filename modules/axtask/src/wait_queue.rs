@@ -1,8 +1,11 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
 use spinlock::SpinRaw;
 
-use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
+use crate::{current_run_queue, AxTaskRef, CurrentTask};
 
 /// A queue to store sleeping tasks.
 ///
@@ -26,8 +29,12 @@ use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
 /// WQ.wait(); // block until `notify()` is called
 /// assert_eq!(VALUE.load(Ordering::Relaxed), 1);
 /// ```
+///
+/// Asynchronous tasks can also wait in the queue by [`WaitQueue::wait_until_async`],
+/// their wakers are woken by notifications as well.
 pub struct WaitQueue {
-    queue: SpinRaw<VecDeque<AxTaskRef>>, // we already disabled IRQs when lock the `RUN_QUEUE`
+    queue: SpinRaw<VecDeque<AxTaskRef>>, // IRQs are disabled by the callers
+    wakers: SpinRaw<VecDeque<Waker>>,
 }
 
 impl WaitQueue {
@@ -35,6 +42,7 @@ impl WaitQueue {
     pub const fn new() -> Self {
         Self {
             queue: SpinRaw::new(VecDeque::new()),
+            wakers: SpinRaw::new(VecDeque::new()),
         }
     }
 
@@ -42,6 +50,7 @@ impl WaitQueue {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             queue: SpinRaw::new(VecDeque::with_capacity(capacity)),
+            wakers: SpinRaw::new(VecDeque::new()),
         }
     }
 
@@ -50,7 +59,6 @@ impl WaitQueue {
         // the event from another queue.
         if curr.in_wait_queue() {
             // wake up by timer (timeout).
-            // `RUN_QUEUE` is not locked here, so disable IRQs.
             let _guard = kernel_guard::IrqSave::new();
             self.queue.lock().retain(|t| !curr.ptr_eq(t));
             curr.set_in_wait_queue(false);
@@ -64,8 +72,12 @@ impl WaitQueue {
 
     /// Blocks the current task and put it into the wait queue, until other task
     /// notifies it.
+    ///
+    /// It also returns if the task is interrupted by
+    /// [`interrupt`](crate::interrupt), so the caller should check the reason
+    /// of the wakeup, e.g., with [`WaitQueue::wait_until`].
     pub fn wait(&self) {
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -82,13 +94,19 @@ impl WaitQueue {
         F: Fn() -> bool,
     {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let rq = current_run_queue();
+            // the condition is checked with the wait queue locked, so that
+            // the notification after it becomes true is not missed
+            let mut wq = self.queue.lock();
             if condition() {
                 break;
             }
-            rq.block_current(|task| {
-                task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+            rq.block_current(move |task| {
+                // still in the queue after it is interrupted
+                if !task.in_wait_queue() {
+                    task.set_in_wait_queue(true);
+                    wq.push_back(task);
+                }
             });
         }
         self.cancel_events(crate::current());
@@ -96,6 +114,9 @@ impl WaitQueue {
 
     /// Blocks the current task and put it into the wait queue, until other tasks
     /// notify it, or the given duration has elapsed.
+    ///
+    /// Returns `true` if it times out. It returns `false` early as if notified
+    /// if the task is interrupted by [`interrupt`](crate::interrupt).
     #[cfg(feature = "irq")]
     pub fn wait_timeout(&self, dur: core::time::Duration) -> bool {
         let curr = crate::current();
@@ -105,13 +126,14 @@ impl WaitQueue {
             curr.id_name(),
             deadline
         );
-        crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
-            self.queue.lock().push_back(task)
+            self.queue.lock().push_back(task.clone());
+            crate::timers::set_alarm_wakeup(deadline, task);
         });
-        let timeout = curr.in_wait_queue(); // still in the wait queue, must have timed out
+        // still in the wait queue, and not interrupted before the deadline
+        let timeout = curr.in_wait_queue() && axhal::time::current_time() >= deadline;
         self.cancel_events(curr);
         timeout
     }
@@ -133,84 +155,192 @@ impl WaitQueue {
             curr.id_name(),
             deadline
         );
-        crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
         let mut timeout = true;
         while axhal::time::current_time() < deadline {
-            let mut rq = RUN_QUEUE.lock();
+            let rq = current_run_queue();
+            let mut wq = self.queue.lock();
             if condition() {
                 timeout = false;
                 break;
             }
-            rq.block_current(|task| {
-                task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+            rq.block_current(move |task| {
+                // still in the queue after it is interrupted
+                if !task.in_wait_queue() {
+                    task.set_in_wait_queue(true);
+                    wq.push_back(task.clone());
+                }
+                if !task.in_timer_list() {
+                    crate::timers::set_alarm_wakeup(deadline, task);
+                }
             });
         }
         self.cancel_events(curr);
         timeout
     }
 
-    /// Wakes up one task in the wait queue, usually the first one.
+    /// Waits asynchronously until the given `condition` becomes true.
+    ///
+    /// The returned future registers the waker of the asynchronous task in the
+    /// wait queue instead of blocking the current task, so it can be used in
+    /// futures run by [`block_on`](crate::future::block_on). The task is woken
+    /// up by the notifications, and checks the condition again.
+    pub fn wait_until_async<F>(&self, condition: F) -> impl Future<Output = ()> + '_
+    where
+        F: Fn() -> bool,
+    {
+        WaitUntil {
+            wq: self,
+            condition,
+            waker: None,
+        }
+    }
+
+    /// Wakes up one task in the wait queue, usually the first one. If there
+    /// are no blocked tasks, wakes up one asynchronous task instead.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_one(&self, resched: bool) -> bool {
-        let mut rq = RUN_QUEUE.lock();
-        if !self.queue.lock().is_empty() {
-            self.notify_one_locked(resched, &mut rq)
+        let _guard = kernel_guard::IrqSave::new();
+        let task = self.queue.lock().pop_front();
+        if let Some(task) = task {
+            task.set_in_wait_queue(false);
+            crate::run_queue::unblock_task(task, resched);
+            true
         } else {
-            false
+            // wake it with the lock released, as it may wait in this queue again
+            let waker = self.wakers.lock().pop_front();
+            if let Some(waker) = waker {
+                waker.wake();
+                true
+            } else {
+                false
+            }
         }
     }
 
-    /// Wakes all tasks in the wait queue.
+    /// Wakes all tasks in the wait queue, including the asynchronous ones.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_all(&self, resched: bool) {
         loop {
-            let mut rq = RUN_QUEUE.lock();
-            if let Some(task) = self.queue.lock().pop_front() {
+            let _guard = kernel_guard::IrqSave::new();
+            let task = self.queue.lock().pop_front();
+            if let Some(task) = task {
                 task.set_in_wait_queue(false);
-                rq.unblock_task(task, resched);
+                crate::run_queue::unblock_task(task, resched);
             } else {
                 break;
             }
-            drop(rq); // we must unlock `RUN_QUEUE` after unlocking `self.queue`.
         }
+        let wakers = {
+            let _guard = kernel_guard::IrqSave::new();
+            core::mem::take(&mut *self.wakers.lock())
+        };
+        wakers.into_iter().for_each(Waker::wake);
     }
 
-    /// Wake up the given task in the wait queue.
+    /// Wakes up the most urgent task in the wait queue, i.e., the one of the
+    /// highest priority (see [`get_scheduler`](crate::get_scheduler)), or the
+    /// first one among them. The asynchronous tasks are not woken.
+    ///
+    /// `f` is called with the task to be woken up, or `None` if there is no
+    /// blocked task, while the queue is still locked. So the tasks checking
+    /// their conditions with the queue locked, e.g., by
+    /// [`WaitQueue::wait_until`], either see the changes made by `f`, or are
+    /// in the queue when it is called.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
-    pub fn notify_task(&mut self, resched: bool, task: &AxTaskRef) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+    pub fn notify_most_urgent<F>(&self, resched: bool, f: F) -> bool
+    where
+        F: FnOnce(Option<&AxTaskRef>),
+    {
+        let _guard = kernel_guard::IrqSave::new();
         let mut wq = self.queue.lock();
-        if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
+        let rank = |t: &AxTaskRef| crate::api::sched_rank(crate::get_scheduler(t));
+        // the first one of the highest rank
+        let index = wq
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, t)| (rank(t), core::cmp::Reverse(*i)))
+            .map(|(i, _)| i);
+        let task = index.and_then(|i| wq.remove(i));
+        f(task.as_ref());
+        drop(wq);
+        if let Some(task) = task {
             task.set_in_wait_queue(false);
-            rq.unblock_task(wq.remove(index).unwrap(), resched);
+            crate::run_queue::unblock_task(task, resched);
             true
         } else {
             false
         }
     }
 
-    pub(crate) fn notify_one_locked(&self, resched: bool, rq: &mut AxRunQueue) -> bool {
-        if let Some(task) = self.queue.lock().pop_front() {
+    /// Wake up the given task in the wait queue.
+    ///
+    /// If `resched` is true, the current task will be preempted when the
+    /// preemption is enabled.
+    pub fn notify_task(&mut self, resched: bool, task: &AxTaskRef) -> bool {
+        let _guard = kernel_guard::IrqSave::new();
+        let mut wq = self.queue.lock();
+        if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
+            let task = wq.remove(index).unwrap();
+            drop(wq);
             task.set_in_wait_queue(false);
-            rq.unblock_task(task, resched);
+            crate::run_queue::unblock_task(task, resched);
             true
         } else {
             false
         }
     }
+}
 
-    pub(crate) fn notify_all_locked(&self, resched: bool, rq: &mut AxRunQueue) {
-        while let Some(task) = self.queue.lock().pop_front() {
-            task.set_in_wait_queue(false);
-            rq.unblock_task(task, resched);
+/// The future returned by [`WaitQueue::wait_until_async`].
+struct WaitUntil<'a, F> {
+    wq: &'a WaitQueue,
+    condition: F,
+    /// The last registered waker, removed from the queue when it is dropped.
+    waker: Option<Waker>,
+}
+
+// the condition is never pinned
+impl<F> Unpin for WaitUntil<'_, F> {}
+
+impl<F: Fn() -> bool> Future for WaitUntil<'_, F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let wq = self.wq;
+        let _guard = kernel_guard::IrqSave::new();
+        // the condition is checked with the wakers locked, so that the
+        // notification after it becomes true is not missed
+        let mut wakers = wq.wakers.lock();
+        if (self.condition)() {
+            return Poll::Ready(());
+        }
+        // it may be woken (and removed from the queue) or polled by another
+        // task since the last poll
+        if let Some(old) = self.waker.as_ref().filter(|w| !w.will_wake(cx.waker())) {
+            wakers.retain(|w| !w.will_wake(old));
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push_back(cx.waker().clone());
+        }
+        drop(wakers);
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<F> Drop for WaitUntil<'_, F> {
+    fn drop(&mut self) {
+        // do not leave a stale waker to consume a notification
+        if let Some(waker) = self.waker.take() {
+            let _guard = kernel_guard::IrqSave::new();
+            self.wq.wakers.lock().retain(|w| !w.will_wake(&waker));
         }
     }
 }
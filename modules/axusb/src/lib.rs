@@ -0,0 +1,74 @@
+//! [ArceOS](https://github.com/rcore-os/arceos) USB module.
+//!
+//! Watches the USB host controllers for devices being attached or detached
+//! (including behind hubs), and notifies the registered callbacks.
+//!
+//! With the `multitask` feature, a task polls the controllers periodically.
+//! Otherwise [`poll_hotplug`] must be called by the application.
+
+#![no_std]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+#[doc(no_inline)]
+pub use driver_usb::usb::{HotplugEvent, UsbDevice};
+
+use alloc::{boxed::Box, vec::Vec};
+use axdriver::{prelude::*, AxDeviceContainer};
+use axsync::Mutex;
+use lazy_init::LazyInit;
+
+/// A hot-plug callback, called with the host controller the device is
+/// attached to.
+pub type HotplugCallback = Box<dyn Fn(&AxUSBHostDevice, &HotplugEvent) + Send + Sync>;
+
+/// Interval between two polls of the host controllers.
+#[cfg(feature = "multitask")]
+const POLL_INTERVAL: core::time::Duration = core::time::Duration::from_millis(100);
+
+static HOSTS: LazyInit<Vec<AxUSBHostDevice>> = LazyInit::new();
+static CALLBACKS: Mutex<Vec<HotplugCallback>> = Mutex::new(Vec::new());
+
+/// Initializes the USB subsystem with the given host controllers.
+///
+/// The controllers remain usable by the other subsystems.
+pub fn init_usb(usb_hosts: &AxDeviceContainer<AxUSBHostDevice>) {
+    info!("Initialize USB subsystem...");
+    HOSTS.init_by(usb_hosts.iter().cloned().collect());
+
+    #[cfg(feature = "multitask")]
+    axtask::spawn(|| loop {
+        poll_hotplug();
+        axtask::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Registers a callback notified of every device attached or detached from
+/// now on.
+pub fn register_hotplug_callback(callback: HotplugCallback) {
+    CALLBACKS.lock().push(callback);
+}
+
+/// Handles the devices attached or detached since the last call, and
+/// notifies the callbacks.
+pub fn poll_hotplug() {
+    let Some(hosts) = HOSTS.try_get() else {
+        return;
+    };
+    for host in hosts {
+        // The callbacks may use the host controller, it must not be locked.
+        let events = host.clone().poll_hotplug();
+        if events.is_empty() {
+            continue;
+        }
+        let callbacks = CALLBACKS.lock();
+        for event in events.iter() {
+            debug!("usb hotplug on {}: {:?}", host.device_name(), event);
+            for callback in callbacks.iter() {
+                callback(host, event);
+            }
+        }
+    }
+}
@@ -17,6 +17,8 @@ build_args := \
   $(verbose)
 
 RUSTFLAGS := -C link-arg=-T$(LD_SCRIPT) -C link-arg=-no-pie
+# keep frame pointers for backtraces on stack overflows
+RUSTFLAGS += -C force-frame-pointers=yes
 RUSTDOCFLAGS := --enable-index-page -Zunstable-options -D rustdoc::broken_intra_doc_links
 
 ifeq ($(ARCH), x86_64)
@@ -26,11 +26,14 @@ qemu_args-aarch64 := \
   -kernel $(OUT_BIN)
 qemu_args-y := -m 2G -smp $(SMP) $(qemu_args-$(ARCH))
 else
+# GIC version in the platform configuration
+platform_config := $(if $(wildcard $(PLATFORM)),$(PLATFORM),platforms/$(PLATFORM_NAME).toml)
+gic_version := $(shell sed -n 's/^gic-version = "\([0-9]\)"/\1/p' $(platform_config))
 qemu_args-aarch64 := \
   -cpu cortex-a72 \
-  -machine virt \
+  -machine virt,gic-version=$(or $(gic_version),2) \
   -kernel $(OUT_BIN)
-qemu_args-y := -m 128M -smp $(SMP) $(qemu_args-$(ARCH))
+qemu_args-y := -m $(MEM) -smp $(SMP) $(qemu_args-$(ARCH))
 endif
 
 #qemu_args-y := -m 128M -smp $(SMP) $(qemu_args-$(ARCH))
@@ -63,6 +66,15 @@ ifeq ($(NET_DUMP), y)
   qemu_args-$(NET) += -object filter-dump,id=dump0,netdev=net0,file=netdump.pcap
 endif
 
+qemu_args-$(USB) += \
+  -device qemu-xhci,id=xhci \
+  -device usb-kbd,bus=xhci.0 \
+  -device usb-mouse,bus=xhci.0
+
+qemu_args-$(USB_STORAGE) += \
+  -device usb-storage,bus=xhci.0,drive=usbdisk0 \
+  -drive id=usbdisk0,if=none,format=raw,file=$(DISK_IMG)
+
 qemu_args-$(GRAPHIC) += \
   -device virtio-gpu-$(vdev-suffix) -vga none \
   -serial mon:stdio
@@ -33,48 +33,164 @@ int pthread_cancel(pthread_t t)
 }
 
 // TODO
-int pthread_mutex_trylock(pthread_mutex_t *m)
+int pthread_setname_np(pthread_t thread, const char *name)
 {
     unimplemented();
     return 0;
 }
 
-// TODO
-int pthread_setname_np(pthread_t thread, const char *name)
+int pthread_mutexattr_init(pthread_mutexattr_t *a)
 {
-    unimplemented();
+    *a = (pthread_mutexattr_t){0};
+    return 0;
+}
+
+int pthread_mutexattr_destroy(pthread_mutexattr_t *a)
+{
+    return 0;
+}
+
+int pthread_mutexattr_gettype(const pthread_mutexattr_t *restrict a, int *restrict type)
+{
+    *type = a->__attr & 3;
+    return 0;
+}
+
+int pthread_mutexattr_settype(pthread_mutexattr_t *a, int type)
+{
+    if ((unsigned)type > 2)
+        return EINVAL;
+    a->__attr = (a->__attr & ~3) | type;
+    return 0;
+}
+
+int pthread_mutexattr_getpshared(const pthread_mutexattr_t *restrict a, int *restrict pshared)
+{
+    *pshared = a->__attr / 128U % 2;
     return 0;
 }
 
-int pthread_cond_init(pthread_cond_t *restrict c, const pthread_condattr_t *restrict a)
+// All threads share the same address space, so the process-shared objects
+// are the same as the private ones.
+int pthread_mutexattr_setpshared(pthread_mutexattr_t *a, int pshared)
 {
-    *c = (pthread_cond_t){0};
-    if (a) {
-        c->_c_clock = a->__attr & 0x7fffffff;
-        if (a->__attr >> 31)
-            c->_c_shared = (void *)-1;
+    if ((unsigned)pshared > 1U)
+        return EINVAL;
+    a->__attr &= ~128U;
+    a->__attr |= pshared << 7;
+    return 0;
+}
+
+int pthread_mutexattr_getprotocol(const pthread_mutexattr_t *restrict a, int *restrict protocol)
+{
+    *protocol = a->__attr / 8U % 2;
+    return 0;
+}
+
+int pthread_mutexattr_setprotocol(pthread_mutexattr_t *a, int protocol)
+{
+    switch (protocol) {
+    case PTHREAD_PRIO_NONE:
+        a->__attr &= ~8;
+        return 0;
+    case PTHREAD_PRIO_INHERIT:
+        a->__attr |= 8;
+        return 0;
+    case PTHREAD_PRIO_PROTECT:
+        return ENOTSUP;
+    default:
+        return EINVAL;
     }
+}
+
+int pthread_condattr_init(pthread_condattr_t *a)
+{
+    *a = (pthread_condattr_t){0};
     return 0;
 }
 
-// TODO
-int pthread_cond_signal(pthread_cond_t *__cond)
+int pthread_condattr_destroy(pthread_condattr_t *a)
 {
-    unimplemented();
     return 0;
 }
 
-// TODO
-int pthread_cond_wait(pthread_cond_t *__restrict__ __cond, pthread_mutex_t *__restrict__ __mutex)
+int pthread_condattr_getclock(const pthread_condattr_t *restrict a, clockid_t *restrict clk)
 {
-    unimplemented();
+    *clk = a->__attr & 0x7fffffff;
     return 0;
 }
 
-// TODO
-int pthread_cond_broadcast(pthread_cond_t *c)
+int pthread_condattr_setclock(pthread_condattr_t *a, clockid_t clk)
 {
-    unimplemented();
+    if (clk != CLOCK_REALTIME && clk != CLOCK_MONOTONIC)
+        return EINVAL;
+    a->__attr &= 0x80000000;
+    a->__attr |= clk;
+    return 0;
+}
+
+int pthread_condattr_getpshared(const pthread_condattr_t *restrict a, int *restrict pshared)
+{
+    *pshared = a->__attr >> 31;
+    return 0;
+}
+
+int pthread_condattr_setpshared(pthread_condattr_t *a, int pshared)
+{
+    if ((unsigned)pshared > 1U)
+        return EINVAL;
+    a->__attr &= 0x7fffffff;
+    a->__attr |= (unsigned)pshared << 31;
+    return 0;
+}
+
+int pthread_rwlockattr_init(pthread_rwlockattr_t *a)
+{
+    *a = (pthread_rwlockattr_t){0};
+    return 0;
+}
+
+int pthread_rwlockattr_destroy(pthread_rwlockattr_t *a)
+{
+    return 0;
+}
+
+int pthread_rwlockattr_getpshared(const pthread_rwlockattr_t *restrict a, int *restrict pshared)
+{
+    *pshared = a->__attr[0];
+    return 0;
+}
+
+int pthread_rwlockattr_setpshared(pthread_rwlockattr_t *a, int pshared)
+{
+    if ((unsigned)pshared > 1U)
+        return EINVAL;
+    a->__attr[0] = pshared;
+    return 0;
+}
+
+int pthread_barrierattr_init(pthread_barrierattr_t *a)
+{
+    *a = (pthread_barrierattr_t){0};
+    return 0;
+}
+
+int pthread_barrierattr_destroy(pthread_barrierattr_t *a)
+{
+    return 0;
+}
+
+int pthread_barrierattr_getpshared(const pthread_barrierattr_t *restrict a, int *restrict pshared)
+{
+    *pshared = a->__attr;
+    return 0;
+}
+
+int pthread_barrierattr_setpshared(pthread_barrierattr_t *a, int pshared)
+{
+    if ((unsigned)pshared > 1U)
+        return EINVAL;
+    a->__attr = pshared;
     return 0;
 }
 
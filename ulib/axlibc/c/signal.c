@@ -1,19 +1,45 @@
 #include <errno.h>
+#include <limits.h>
 #include <signal.h>
 #include <stddef.h>
 #include <stdio.h>
 
-int sigaction_helper(int signum, const struct sigaction *act, struct sigaction *oldact,
-                     size_t sigsetsize)
+#ifndef AX_CONFIG_MULTITASK
+// Signals are not supported without multitasking
+int sigaction(int sig, const struct sigaction *restrict act, struct sigaction *restrict oact)
 {
-    if (signum == SIGKILL || signum == SIGSTOP)
-        return -EINVAL;
+    if (sig == SIGKILL || sig == SIGSTOP) {
+        errno = EINVAL;
+        return -1;
+    }
+
+    if (oact)
+        *oact = (struct sigaction){0};
+
+    return 0;
+}
+
+// TODO
+int kill(pid_t __pid, int __sig)
+{
+    unimplemented();
+    return 0;
+}
 
-    if (oldact)
-        *oldact = (struct sigaction){0};
+// TODO
+int raise(int __sig)
+{
+    unimplemented();
+    return 0;
+}
 
+// TODO
+int pthread_sigmask(int __how, const sigset_t *restrict __newmask, sigset_t *restrict __oldmask)
+{
+    unimplemented();
     return 0;
 }
+#endif
 
 void (*signal(int signum, void (*handler)(int)))(int)
 {
@@ -22,22 +48,19 @@ void (*signal(int signum, void (*handler)(int)))(int)
         .sa_handler = handler, .sa_flags = SA_RESTART, /* BSD signal semantics */
     };
 
-    if (sigaction_helper(signum, &act, &old, sizeof(sigset_t)) < 0)
+    if (sigaction(signum, &act, &old) < 0)
         return SIG_ERR;
 
     return (old.sa_flags & SA_SIGINFO) ? NULL : old.sa_handler;
 }
 
-int sigaction(int sig, const struct sigaction *restrict act, struct sigaction *restrict oact)
+int sigprocmask(int how, const sigset_t *restrict set, sigset_t *restrict old)
 {
-    return sigaction_helper(sig, act, oact, sizeof(sigset_t));
-}
-
-// TODO
-int kill(pid_t __pid, int __sig)
-{
-    unimplemented();
-    return 0;
+    int r = pthread_sigmask(how, set, old);
+    if (!r)
+        return r;
+    errno = r;
+    return -1;
 }
 
 int sigemptyset(sigset_t *set)
@@ -52,10 +75,20 @@ int sigemptyset(sigset_t *set)
     return 0;
 }
 
-// TODO
-int raise(int __sig)
+int sigfillset(sigset_t *set)
 {
-    unimplemented();
+#if ULONG_MAX == 0xffffffff
+    set->__bits[0] = 0x7ffffffful;
+    set->__bits[1] = 0xfffffffcul;
+    if (_NSIG > 65) {
+        set->__bits[2] = 0xfffffffful;
+        set->__bits[3] = 0xfffffffful;
+    }
+#else
+    set->__bits[0] = 0xfffffffc7ffffffful;
+    if (_NSIG > 65)
+        set->__bits[1] = 0xfffffffffffffffful;
+#endif
     return 0;
 }
 
@@ -70,18 +103,22 @@ int sigaddset(sigset_t *set, int sig)
     return 0;
 }
 
-// TODO
-int pthread_sigmask(int __how, const sigset_t *restrict __newmask, sigset_t *restrict __oldmask)
+int sigdelset(sigset_t *set, int sig)
 {
-    unimplemented();
+    unsigned s = sig - 1;
+    if (s >= _NSIG - 1 || sig - 32U < 3) {
+        errno = EINVAL;
+        return -1;
+    }
+    set->__bits[s / 8 / sizeof *set->__bits] &= ~(1UL << (s & (8 * sizeof *set->__bits - 1)));
     return 0;
 }
 
-#ifdef AX_CONFIG_MULTITASK
-// TODO
-int pthread_kill(pthread_t t, int sig)
+int sigismember(const sigset_t *set, int sig)
 {
-    unimplemented();
-    return 0;
+    unsigned s = sig - 1;
+    if (s >= _NSIG - 1)
+        return 0;
+    return !!(set->__bits[s / 8 / sizeof *set->__bits] &
+              1UL << (s & (8 * sizeof *set->__bits - 1)));
 }
-#endif
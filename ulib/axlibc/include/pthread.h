@@ -2,6 +2,7 @@
 #define _PTHREAD_H
 
 #include <features.h>
+#include <sched.h>
 #include <time.h>
 
 #define PTHREAD_CANCEL_ENABLE  0
@@ -11,16 +12,43 @@
 #define PTHREAD_CANCEL_DEFERRED     0
 #define PTHREAD_CANCEL_ASYNCHRONOUS 1
 
+#define PTHREAD_MUTEX_NORMAL     0
+#define PTHREAD_MUTEX_DEFAULT    0
+#define PTHREAD_MUTEX_RECURSIVE  1
+#define PTHREAD_MUTEX_ERRORCHECK 2
+
+#define PTHREAD_PRIO_NONE    0
+#define PTHREAD_PRIO_INHERIT 1
+#define PTHREAD_PRIO_PROTECT 2
+
+#define PTHREAD_PROCESS_PRIVATE 0
+#define PTHREAD_PROCESS_SHARED  1
+
+#define PTHREAD_ONCE_INIT 0
+
+#define PTHREAD_BARRIER_SERIAL_THREAD (-1)
+
 typedef struct {
     unsigned __attr;
 } pthread_condattr_t;
 
-#include <ax_pthread_mutex.h>
+#include <ax_pthread_types.h>
 
 typedef struct {
     unsigned __attr;
 } pthread_mutexattr_t;
 
+typedef struct {
+    unsigned __attr[2];
+} pthread_rwlockattr_t;
+
+typedef struct {
+    unsigned __attr;
+} pthread_barrierattr_t;
+
+typedef int pthread_once_t;
+typedef unsigned pthread_key_t;
+
 typedef struct {
     union {
         int __i[sizeof(long) == 8 ? 14 : 9];
@@ -32,16 +60,6 @@ typedef struct {
 #define _a_guardsize __u.__s[1]
 #define _a_stackaddr __u.__s[2]
 
-typedef struct {
-    union {
-        int __i[12];
-        volatile int __vi[12];
-        void *__p[12 * sizeof(int) / sizeof(void *)];
-    } __u;
-} pthread_cond_t;
-#define _c_clock  __u.__i[4]
-#define _c_shared __u.__p[0]
-
 typedef void *pthread_t;
 
 #define PTHREAD_CANCELED ((void *)-1)
@@ -65,15 +83,68 @@ int pthread_mutex_init(pthread_mutex_t *__restrict, const pthread_mutexattr_t *_
 int pthread_mutex_lock(pthread_mutex_t *);
 int pthread_mutex_unlock(pthread_mutex_t *);
 int pthread_mutex_trylock(pthread_mutex_t *);
+int pthread_mutex_destroy(pthread_mutex_t *);
+
+int pthread_mutexattr_init(pthread_mutexattr_t *);
+int pthread_mutexattr_destroy(pthread_mutexattr_t *);
+int pthread_mutexattr_gettype(const pthread_mutexattr_t *__restrict, int *__restrict);
+int pthread_mutexattr_settype(pthread_mutexattr_t *, int);
+int pthread_mutexattr_getpshared(const pthread_mutexattr_t *__restrict, int *__restrict);
+int pthread_mutexattr_setpshared(pthread_mutexattr_t *, int);
+int pthread_mutexattr_getprotocol(const pthread_mutexattr_t *__restrict, int *__restrict);
+int pthread_mutexattr_setprotocol(pthread_mutexattr_t *, int);
 
 int pthread_setname_np(pthread_t, const char *);
+int pthread_setaffinity_np(pthread_t, size_t, const cpu_set_t *);
+int pthread_getaffinity_np(pthread_t, size_t, cpu_set_t *);
 
 int pthread_cond_init(pthread_cond_t *__restrict__ __cond,
                       const pthread_condattr_t *__restrict__ __cond_attr);
+int pthread_cond_destroy(pthread_cond_t *__cond);
 int pthread_cond_signal(pthread_cond_t *__cond);
 int pthread_cond_wait(pthread_cond_t *__restrict__ __cond, pthread_mutex_t *__restrict__ __mutex);
+int pthread_cond_timedwait(pthread_cond_t *__restrict__ __cond,
+                           pthread_mutex_t *__restrict__ __mutex,
+                           const struct timespec *__restrict__ __abstime);
 int pthread_cond_broadcast(pthread_cond_t *);
 
+int pthread_condattr_init(pthread_condattr_t *);
+int pthread_condattr_destroy(pthread_condattr_t *);
+int pthread_condattr_getclock(const pthread_condattr_t *__restrict, clockid_t *__restrict);
+int pthread_condattr_setclock(pthread_condattr_t *, clockid_t);
+int pthread_condattr_getpshared(const pthread_condattr_t *__restrict, int *__restrict);
+int pthread_condattr_setpshared(pthread_condattr_t *, int);
+
+int pthread_rwlock_init(pthread_rwlock_t *__restrict, const pthread_rwlockattr_t *__restrict);
+int pthread_rwlock_destroy(pthread_rwlock_t *);
+int pthread_rwlock_rdlock(pthread_rwlock_t *);
+int pthread_rwlock_tryrdlock(pthread_rwlock_t *);
+int pthread_rwlock_wrlock(pthread_rwlock_t *);
+int pthread_rwlock_trywrlock(pthread_rwlock_t *);
+int pthread_rwlock_unlock(pthread_rwlock_t *);
+
+int pthread_rwlockattr_init(pthread_rwlockattr_t *);
+int pthread_rwlockattr_destroy(pthread_rwlockattr_t *);
+int pthread_rwlockattr_getpshared(const pthread_rwlockattr_t *__restrict, int *__restrict);
+int pthread_rwlockattr_setpshared(pthread_rwlockattr_t *, int);
+
+int pthread_barrier_init(pthread_barrier_t *__restrict, const pthread_barrierattr_t *__restrict,
+                         unsigned);
+int pthread_barrier_destroy(pthread_barrier_t *);
+int pthread_barrier_wait(pthread_barrier_t *);
+
+int pthread_barrierattr_init(pthread_barrierattr_t *);
+int pthread_barrierattr_destroy(pthread_barrierattr_t *);
+int pthread_barrierattr_getpshared(const pthread_barrierattr_t *__restrict, int *__restrict);
+int pthread_barrierattr_setpshared(pthread_barrierattr_t *, int);
+
+int pthread_once(pthread_once_t *, void (*)(void));
+
+int pthread_key_create(pthread_key_t *, void (*)(void *));
+int pthread_key_delete(pthread_key_t);
+void *pthread_getspecific(pthread_key_t);
+int pthread_setspecific(pthread_key_t, const void *);
+
 int pthread_attr_init(pthread_attr_t *__attr);
 int pthread_attr_getstacksize(const pthread_attr_t *__restrict__ __attr,
                               size_t *__restrict__ __stacksize);
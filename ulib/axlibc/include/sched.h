@@ -2,6 +2,15 @@
 #define _SCHED_H
 
 #include <stddef.h>
+#include <sys/types.h>
+
+#define SCHED_OTHER 0
+#define SCHED_FIFO  1
+#define SCHED_RR    2
+
+struct sched_param {
+    int sched_priority;
+};
 
 typedef struct cpu_set_t {
     unsigned long __bits[128 / sizeof(long)];
@@ -12,12 +21,28 @@ typedef struct cpu_set_t {
                         : (((unsigned long *)(set))[(i) / 8 / sizeof(long)] op( \
                               1UL << ((i) % (8 * sizeof(long))))))
 
-#define CPU_SET_S(i, size, set) __CPU_op_S(i, size, set, |=)
-#define CPU_ZERO_S(size, set)   memset(set, 0, size)
+#define CPU_SET_S(i, size, set)   __CPU_op_S(i, size, set, |=)
+#define CPU_CLR_S(i, size, set)   __CPU_op_S(i, size, set, &= ~)
+#define CPU_ISSET_S(i, size, set) __CPU_op_S(i, size, set, &)
+#define CPU_ZERO_S(size, set)     memset(set, 0, size)
+
+#define CPU_SET(i, set)   CPU_SET_S(i, sizeof(cpu_set_t), set);
+#define CPU_CLR(i, set)   CPU_CLR_S(i, sizeof(cpu_set_t), set)
+#define CPU_ISSET(i, set) CPU_ISSET_S(i, sizeof(cpu_set_t), set)
+#define CPU_ZERO(set)     CPU_ZERO_S(sizeof(cpu_set_t), set)
 
-#define CPU_SET(i, set) CPU_SET_S(i, sizeof(cpu_set_t), set);
-#define CPU_ZERO(set)   CPU_ZERO_S(sizeof(cpu_set_t), set)
+#ifdef AX_CONFIG_MULTITASK
+
+int sched_setscheduler(pid_t, int, const struct sched_param *);
+int sched_getscheduler(pid_t);
+int sched_setparam(pid_t, const struct sched_param *);
+int sched_getparam(pid_t, struct sched_param *);
+int sched_get_priority_max(int);
+int sched_get_priority_min(int);
 
 int sched_setaffinity(pid_t, size_t, const cpu_set_t *);
+int sched_getaffinity(pid_t, size_t, cpu_set_t *);
+
+#endif // AX_CONFIG_MULTITASK
 
 #endif // _SCHED_H
@@ -166,8 +166,12 @@ struct sigaction {
 void (*signal(int, void (*)(int)))(int);
 int sigaction(int, const struct sigaction *__restrict, struct sigaction *__restrict);
 int sigemptyset(sigset_t *);
+int sigfillset(sigset_t *);
 int raise(int);
 int sigaddset(sigset_t *, int);
+int sigdelset(sigset_t *, int);
+int sigismember(const sigset_t *, int);
+int sigprocmask(int, const sigset_t *__restrict, sigset_t *__restrict);
 int pthread_sigmask(int, const sigset_t *__restrict, sigset_t *__restrict);
 
 int kill(pid_t, int);
@@ -53,6 +53,8 @@ mod utils;
 mod fd_ops;
 #[cfg(feature = "fs")]
 mod fs;
+#[cfg(feature = "multitask")]
+mod futex;
 #[cfg(any(feature = "select", feature = "epoll"))]
 mod io_mpx;
 #[cfg(feature = "alloc")]
@@ -63,6 +65,10 @@ mod net;
 mod pipe;
 #[cfg(feature = "multitask")]
 mod pthread;
+#[cfg(feature = "multitask")]
+mod sched;
+#[cfg(feature = "multitask")]
+mod signal;
 #[cfg(feature = "alloc")]
 mod strftime;
 #[cfg(feature = "fp_simd")]
@@ -85,7 +91,7 @@ pub use self::io::{read, writev};
 pub use self::errno::strerror;
 pub use self::mktime::mktime;
 pub use self::rand::{rand, random, srand};
-pub use self::resource::{getrlimit, setrlimit};
+pub use self::resource::{getrlimit, getrusage, setrlimit};
 pub use self::setjmp::{longjmp, setjmp};
 pub use self::sys::sysconf;
 pub use self::time::{clock_gettime, nanosleep};
@@ -102,16 +108,51 @@ pub use self::fd_ops::{ax_fcntl, close, dup, dup2, dup3};
 #[cfg(feature = "fs")]
 pub use self::fs::{ax_open, fstat, getcwd, lseek, lstat, rename, stat};
 
+#[cfg(feature = "multitask")]
+pub use self::futex::futex;
+
 #[cfg(feature = "net")]
 pub use self::net::{
     accept, bind, connect, freeaddrinfo, getaddrinfo, getpeername, getsockname, listen, recv,
     recvfrom, send, sendto, shutdown, socket,
 };
 
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_barrier_destroy, pthread_barrier_init, pthread_barrier_wait, pthread_once,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_cond_broadcast, pthread_cond_destroy, pthread_cond_init, pthread_cond_signal,
+    pthread_cond_timedwait, pthread_cond_wait,
+};
 #[cfg(feature = "multitask")]
 pub use self::pthread::{pthread_create, pthread_exit, pthread_join, pthread_self};
 #[cfg(feature = "multitask")]
-pub use self::pthread::{pthread_mutex_init, pthread_mutex_lock, pthread_mutex_unlock};
+pub use self::pthread::{pthread_getaffinity_np, pthread_setaffinity_np};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_getspecific, pthread_key_create, pthread_key_delete, pthread_setspecific,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_mutex_destroy, pthread_mutex_init, pthread_mutex_lock, pthread_mutex_trylock,
+    pthread_mutex_unlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_rwlock_destroy, pthread_rwlock_init, pthread_rwlock_rdlock, pthread_rwlock_tryrdlock,
+    pthread_rwlock_trywrlock, pthread_rwlock_unlock, pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::sched::{
+    sched_get_priority_max, sched_get_priority_min, sched_getaffinity, sched_getparam,
+    sched_getscheduler, sched_setaffinity, sched_setparam, sched_setscheduler,
+};
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use self::signal::{alarm, getitimer, setitimer};
+#[cfg(feature = "multitask")]
+pub use self::signal::{kill, pthread_kill, pthread_sigmask, raise, sigaction};
 
 #[cfg(feature = "pipe")]
 pub use self::pipe::pipe;
@@ -1,6 +1,6 @@
-use crate::{ctypes, utils::e};
+use crate::{ctypes, utils::e_pthread};
 use arceos_posix_api as api;
-use core::ffi::{c_int, c_void};
+use core::ffi::{c_int, c_uint, c_void};
 
 /// Returns the `pthread` struct of current thread.
 #[no_mangle]
@@ -19,7 +19,7 @@ pub unsafe extern "C" fn pthread_create(
     start_routine: extern "C" fn(arg: *mut c_void) -> *mut c_void,
     arg: *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_create(res, attr, start_routine, arg))
+    e_pthread(api::sys_pthread_create(res, attr, start_routine, arg))
 }
 
 /// Exits the current thread. The value `retval` will be returned to the joiner.
@@ -34,7 +34,7 @@ pub unsafe extern "C" fn pthread_join(
     thread: ctypes::pthread_t,
     retval: *mut *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_join(thread, retval))
+    e_pthread(api::sys_pthread_join(thread, retval))
 }
 
 /// Initialize a mutex.
@@ -43,17 +43,206 @@ pub unsafe extern "C" fn pthread_mutex_init(
     mutex: *mut ctypes::pthread_mutex_t,
     attr: *const ctypes::pthread_mutexattr_t,
 ) -> c_int {
-    e(api::sys_pthread_mutex_init(mutex, attr))
+    e_pthread(api::sys_pthread_mutex_init(mutex, attr))
+}
+
+/// Destroy a mutex.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutex_destroy(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    e_pthread(api::sys_pthread_mutex_destroy(mutex))
 }
 
 /// Lock the given mutex.
 #[no_mangle]
 pub unsafe extern "C" fn pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_lock(mutex))
+    e_pthread(api::sys_pthread_mutex_lock(mutex))
+}
+
+/// Try to lock the given mutex.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutex_trylock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    e_pthread(api::sys_pthread_mutex_trylock(mutex))
 }
 
 /// Unlock the given mutex.
 #[no_mangle]
 pub unsafe extern "C" fn pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_unlock(mutex))
+    e_pthread(api::sys_pthread_mutex_unlock(mutex))
+}
+
+/// Initialize a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_cond_init(cond, attr))
+}
+
+/// Destroy a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e_pthread(api::sys_pthread_cond_destroy(cond))
+}
+
+/// Wait on the condition variable, with the given mutex unlocked.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_cond_wait(cond, mutex))
+}
+
+/// Wait on the condition variable until the absolute time `abstime`, with the
+/// given mutex unlocked.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    e_pthread(api::sys_pthread_cond_timedwait(cond, mutex, abstime))
+}
+
+/// Wake up one thread waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e_pthread(api::sys_pthread_cond_signal(cond))
+}
+
+/// Wake up all threads waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e_pthread(api::sys_pthread_cond_broadcast(cond))
+}
+
+/// Initialize a reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_init(rwlock, attr))
+}
+
+/// Destroy a reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_destroy(rwlock))
+}
+
+/// Lock the given reader-writer lock for reading.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_rdlock(rwlock))
+}
+
+/// Try to lock the given reader-writer lock for reading.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_tryrdlock(rwlock))
+}
+
+/// Lock the given reader-writer lock for writing.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_wrlock(rwlock))
+}
+
+/// Try to lock the given reader-writer lock for writing.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_trywrlock(rwlock))
+}
+
+/// Unlock the given reader-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e_pthread(api::sys_pthread_rwlock_unlock(rwlock))
+}
+
+/// Initialize a barrier for `count` threads.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    e_pthread(api::sys_pthread_barrier_init(barrier, attr, count))
+}
+
+/// Destroy a barrier.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_destroy(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    e_pthread(api::sys_pthread_barrier_destroy(barrier))
+}
+
+/// Wait until enough threads are waiting on the barrier.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    match api::sys_pthread_barrier_wait(barrier) {
+        // not an error
+        ctypes::PTHREAD_BARRIER_SERIAL_THREAD => ctypes::PTHREAD_BARRIER_SERIAL_THREAD,
+        ret => e_pthread(ret),
+    }
+}
+
+/// Call `init_routine` once and only once for the given `once_control`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    e_pthread(api::sys_pthread_once(once_control, init_routine))
+}
+
+/// Create a new key for thread-specific data.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Option<extern "C" fn(*mut c_void)>,
+) -> c_int {
+    e_pthread(api::sys_pthread_key_create(key, destructor))
+}
+
+/// Delete a key for thread-specific data.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    e_pthread(api::sys_pthread_key_delete(key))
+}
+
+/// Get the value of a key for the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    api::sys_pthread_getspecific(key)
+}
+
+/// Set the value of a key for the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_setspecific(
+    key: ctypes::pthread_key_t,
+    value: *const c_void,
+) -> c_int {
+    e_pthread(api::sys_pthread_setspecific(key, value))
+}
+
+/// Set the CPUs that the given thread is allowed to run on.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_setaffinity_np(thread, cpusetsize, cpuset))
+}
+
+/// Get the CPUs that the given thread is allowed to run on.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_getaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_getaffinity_np(thread, cpusetsize, cpuset))
 }
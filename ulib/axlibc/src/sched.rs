@@ -0,0 +1,79 @@
+use crate::{ctypes, utils::e};
+use arceos_posix_api::{
+    sys_sched_get_priority_max, sys_sched_get_priority_min, sys_sched_getaffinity,
+    sys_sched_getparam, sys_sched_getscheduler, sys_sched_setaffinity, sys_sched_setparam,
+    sys_sched_setscheduler,
+};
+use core::ffi::c_int;
+
+/// Set the CPUs that the thread whose ID is `pid` (the current thread if it is
+/// 0) is allowed to run on.
+#[no_mangle]
+pub unsafe extern "C" fn sched_setaffinity(
+    pid: ctypes::pid_t,
+    cpusetsize: usize,
+    mask: *const ctypes::cpu_set_t,
+) -> c_int {
+    e(sys_sched_setaffinity(pid, cpusetsize, mask))
+}
+
+/// Get the CPUs that the thread whose ID is `pid` (the current thread if it is
+/// 0) is allowed to run on.
+#[no_mangle]
+pub unsafe extern "C" fn sched_getaffinity(
+    pid: ctypes::pid_t,
+    cpusetsize: usize,
+    mask: *mut ctypes::cpu_set_t,
+) -> c_int {
+    e(sys_sched_getaffinity(pid, cpusetsize, mask))
+}
+
+/// Set the scheduling policy and the real-time priority of the thread whose ID
+/// is `pid` (the current thread if it is 0).
+#[no_mangle]
+pub unsafe extern "C" fn sched_setscheduler(
+    pid: ctypes::pid_t,
+    policy: c_int,
+    param: *const ctypes::sched_param,
+) -> c_int {
+    e(sys_sched_setscheduler(pid, policy, param))
+}
+
+/// Get the scheduling policy of the thread whose ID is `pid` (the current
+/// thread if it is 0).
+#[no_mangle]
+pub unsafe extern "C" fn sched_getscheduler(pid: ctypes::pid_t) -> c_int {
+    e(sys_sched_getscheduler(pid))
+}
+
+/// Set the real-time priority of the thread whose ID is `pid` (the current
+/// thread if it is 0).
+#[no_mangle]
+pub unsafe extern "C" fn sched_setparam(
+    pid: ctypes::pid_t,
+    param: *const ctypes::sched_param,
+) -> c_int {
+    e(sys_sched_setparam(pid, param))
+}
+
+/// Get the real-time priority of the thread whose ID is `pid` (the current
+/// thread if it is 0).
+#[no_mangle]
+pub unsafe extern "C" fn sched_getparam(
+    pid: ctypes::pid_t,
+    param: *mut ctypes::sched_param,
+) -> c_int {
+    e(sys_sched_getparam(pid, param))
+}
+
+/// Get the maximum real-time priority of the scheduling policy.
+#[no_mangle]
+pub unsafe extern "C" fn sched_get_priority_max(policy: c_int) -> c_int {
+    e(sys_sched_get_priority_max(policy))
+}
+
+/// Get the minimum real-time priority of the scheduling policy.
+#[no_mangle]
+pub unsafe extern "C" fn sched_get_priority_min(policy: c_int) -> c_int {
+    e(sys_sched_get_priority_min(policy))
+}
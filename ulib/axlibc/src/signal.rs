@@ -0,0 +1,69 @@
+use crate::{
+    ctypes,
+    utils::{e, e_pthread},
+};
+use arceos_posix_api as api;
+use core::ffi::c_int;
+
+/// Examine and change the action taken on receipt of a signal.
+#[no_mangle]
+pub unsafe extern "C" fn sigaction(
+    signum: c_int,
+    act: *const ctypes::sigaction,
+    oldact: *mut ctypes::sigaction,
+) -> c_int {
+    e(api::sys_sigaction(signum, act, oldact))
+}
+
+/// Send a signal to the process.
+#[no_mangle]
+pub unsafe extern "C" fn kill(pid: ctypes::pid_t, sig: c_int) -> c_int {
+    e(api::sys_kill(pid, sig))
+}
+
+/// Send a signal to the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn raise(sig: c_int) -> c_int {
+    e(api::sys_raise(sig))
+}
+
+/// Examine and change the signal mask of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_sigmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    e_pthread(api::sys_pthread_sigmask(how, set, oldset))
+}
+
+/// Send a signal to the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_kill(thread: ctypes::pthread_t, sig: c_int) -> c_int {
+    e_pthread(api::sys_pthread_kill(thread, sig))
+}
+
+/// Send `SIGALRM` to the process after `seconds`.
+#[cfg(feature = "irq")]
+#[no_mangle]
+pub unsafe extern "C" fn alarm(seconds: core::ffi::c_uint) -> core::ffi::c_uint {
+    api::sys_alarm(seconds)
+}
+
+/// Set the value of an interval timer.
+#[cfg(feature = "irq")]
+#[no_mangle]
+pub unsafe extern "C" fn setitimer(
+    which: c_int,
+    new_value: *const ctypes::itimerval,
+    old_value: *mut ctypes::itimerval,
+) -> c_int {
+    e(api::sys_setitimer(which, new_value, old_value))
+}
+
+/// Get the value of an interval timer.
+#[cfg(feature = "irq")]
+#[no_mangle]
+pub unsafe extern "C" fn getitimer(which: c_int, curr_value: *mut ctypes::itimerval) -> c_int {
+    e(api::sys_getitimer(which, curr_value))
+}
@@ -11,7 +11,8 @@ pub unsafe extern "C" fn clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::
 
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// Fails with `EINTR` if it is interrupted by a signal handler, and the
+/// remaining time is stored in `rem`.
 #[no_mangle]
 pub unsafe extern "C" fn nanosleep(
     req: *const ctypes::timespec,
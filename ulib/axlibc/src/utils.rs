@@ -8,3 +8,13 @@ pub fn e(ret: c_int) -> c_int {
         ret as _
     }
 }
+
+/// Like [`e`], but for the pthread functions, which return the error number
+/// instead of setting `errno`.
+pub fn e_pthread(ret: c_int) -> c_int {
+    if ret < 0 {
+        ret.abs()
+    } else {
+        ret
+    }
+}
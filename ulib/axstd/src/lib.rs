@@ -15,30 +15,38 @@
 //!     - `fp_simd`: Enable floating point and SIMD support.
 //! - Interrupts:
 //!     - `irq`: Enable interrupt handling support.
+//!     - `tickless`: Stop the timer tick on idle CPUs, and only raise timer interrupts when needed.
 //! - Memory
 //!     - `alloc`: Enable dynamic memory allocation.
 //!     - `alloc-tlsf`: Use the TLSF allocator.
 //!     - `alloc-slab`: Use the slab allocator.
 //!     - `alloc-buddy`: Use the buddy system allocator.
-//!     - `paging`: Enable page table manipulation.
+//!     - `paging`: Enable page table manipulation, and guard pages for task stacks.
 //!     - `tls`: Enable thread-local storage.
 //! - Task management
 //!     - `multitask`: Enable multi-threading support.
 //!     - `sched_fifo`: Use the FIFO cooperative scheduler.
 //!     - `sched_rr`: Use the Round-robin preemptive scheduler.
 //!     - `sched_cfs`: Use the Completely Fair Scheduler (CFS) preemptive scheduler.
+//!     - `sched_rt`: Use the real-time (FIFO/RR) preemptive scheduler, with CFS for normal tasks.
+//!     - `sched_edf`: Use the Earliest Deadline First (EDF) preemptive scheduler, with CFS for normal tasks.
+//!     - `async`: Enable running futures on threads (the `task` module), and asynchronous socket operations.
 //! - Upperlayer stacks
 //!     - `fs`: Enable file system support.
 //!     - `myfs`: Allow users to define their custom filesystems to override the default.
 //!     - `net`: Enable networking support.
 //!     - `dns`: Enable DNS lookup support.
 //!     - `display`: Enable graphics support.
+//!     - `usb-host`: Enable USB host controllers and hot-plug notifications.
+//!     - `input`: Enable USB keyboards (read through [`io::stdin`]) and mice.
 //! - Device drivers
 //!     - `bus-mmio`: Use device tree to probe all MMIO devices.
 //!     - `bus-pci`: Use PCI bus to probe all PCI devices.
 //!     - `driver-ramdisk`: Use the RAM disk to emulate the block device.
 //!     - `driver-ixgbe`: Enable the Intel 82599 10Gbit NIC driver.
 //!     - `driver-bcm2835-sdhci`: Enable the BCM2835 SDHCI driver (Raspberry Pi SD card).
+//!     - `driver-xhci`: Enable the generic xHCI USB host controller driver.
+//!     - `driver-usb-storage`: Use USB mass storage devices (e.g. USB sticks) as block devices.
 //! - Logging
 //!     - `log-level-off`: Disable all logging.
 //!     - `log-level-error`, `log-level-warn`, `log-level-info`, `log-level-debug`,
@@ -76,3 +84,7 @@ pub mod time;
 pub mod fs;
 #[cfg(feature = "net")]
 pub mod net;
+#[cfg(feature = "async")]
+pub mod task;
+#[cfg(feature = "usb-host")]
+pub mod usb;
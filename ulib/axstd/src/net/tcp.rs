@@ -43,6 +43,30 @@ impl TcpStream {
     pub fn shutdown(&self) -> io::Result<()> {
         api::ax_tcp_shutdown(&self.0)
     }
+
+    /// Opens a TCP connection to a remote host asynchronously.
+    ///
+    /// Unlike [`connect`](Self::connect), it only accepts a resolved address.
+    #[cfg(feature = "async")]
+    pub async fn connect_async(addr: SocketAddr) -> io::Result<TcpStream> {
+        let socket = api::ax_tcp_socket();
+        api::ax_tcp_connect_async(&socket, addr).await?;
+        Ok(TcpStream(socket))
+    }
+
+    /// Reads data from the stream asynchronously, returns the number of bytes
+    /// read.
+    #[cfg(feature = "async")]
+    pub async fn read_async(&self, buf: &mut [u8]) -> io::Result<usize> {
+        api::ax_tcp_recv_async(&self.0, buf).await
+    }
+
+    /// Writes data to the stream asynchronously, returns the number of bytes
+    /// written.
+    #[cfg(feature = "async")]
+    pub async fn write_async(&self, buf: &[u8]) -> io::Result<usize> {
+        api::ax_tcp_send_async(&self.0, buf).await
+    }
 }
 
 impl Read for TcpStream {
@@ -102,4 +126,12 @@ impl TcpListener {
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
         api::ax_tcp_accept(&self.0).map(|(a, b)| (TcpStream(a), b))
     }
+
+    /// Accept a new incoming connection from this listener asynchronously.
+    #[cfg(feature = "async")]
+    pub async fn accept_async(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        api::ax_tcp_accept_async(&self.0)
+            .await
+            .map(|(a, b)| (TcpStream(a), b))
+    }
 }
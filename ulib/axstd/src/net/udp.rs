@@ -93,4 +93,35 @@ impl UdpSocket {
     pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
         api::ax_udp_recv(&self.0, buf)
     }
+
+    /// Receives a single datagram message on the socket asynchronously. On
+    /// success, returns the number of bytes read and the origin.
+    #[cfg(feature = "async")]
+    pub async fn recv_from_async(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        api::ax_udp_recv_from_async(&self.0, buf).await
+    }
+
+    /// Sends data on the socket to the given address asynchronously. On
+    /// success, returns the number of bytes written.
+    ///
+    /// Unlike [`send_to`](Self::send_to), it only accepts a resolved address.
+    #[cfg(feature = "async")]
+    pub async fn send_to_async(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        api::ax_udp_send_to_async(&self.0, buf, addr).await
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected
+    /// asynchronously.
+    #[cfg(feature = "async")]
+    pub async fn send_async(&self, buf: &[u8]) -> io::Result<usize> {
+        api::ax_udp_send_async(&self.0, buf).await
+    }
+
+    /// Receives a single datagram message on the socket from the remote address
+    /// to which it is connected asynchronously. On success, returns the number
+    /// of bytes read.
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&self, buf: &mut [u8]) -> io::Result<usize> {
+        api::ax_udp_recv_async(&self.0, buf).await
+    }
 }
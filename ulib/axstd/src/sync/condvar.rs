@@ -0,0 +1,157 @@
+//! A sleeping condition variable.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use arceos_api::task::{self as api, AxWaitQueueHandle};
+
+use super::MutexGuard;
+#[cfg(feature = "irq")]
+use crate::time::Instant;
+
+/// A type indicating whether a timed wait on a condition variable returned
+/// due to a time out or not.
+///
+/// It is returned by the [`Condvar::wait_timeout`] method.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// Returns `true` if the wait was known to have timed out.
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+/// A Condition Variable, similar to
+/// [`std::sync::Condvar`](https://doc.rust-lang.org/std/sync/struct.Condvar.html).
+///
+/// Condition variables represent the ability to block a task such that it
+/// consumes no CPU time while waiting for an event to occur. They are used
+/// together with a [`Mutex`](super::Mutex).
+///
+/// Each notification bumps a sequence number, and the waiters sleep in the
+/// wait queue until the number changes, so a notification between unlocking
+/// the mutex and going to sleep is not missed. Spurious wakeups are possible,
+/// as with [`std::sync::Condvar`].
+pub struct Condvar {
+    wq: AxWaitQueueHandle,
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    /// Creates a new condition variable which is ready to be waited on and
+    /// notified.
+    pub const fn new() -> Self {
+        Self {
+            wq: AxWaitQueueHandle::new(),
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// Blocks the current task until this condition variable receives a
+    /// notification.
+    ///
+    /// This function will atomically unlock the mutex specified (represented
+    /// by `guard`) and block the current task. When this function call
+    /// returns, the lock specified will have been re-acquired.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.lock;
+        let seq = self.seq.load(Ordering::Acquire);
+        drop(guard);
+        api::ax_wait_queue_wait(&self.wq, || self.seq.load(Ordering::Acquire) != seq, None);
+        mutex.lock()
+    }
+
+    /// Blocks the current task until the provided condition becomes false.
+    ///
+    /// `condition` is checked immediately; if not met (returns `true`), this
+    /// will [`wait`](Self::wait) for the next notification then check again.
+    /// This repeats until `condition` returns `false`, in which case this
+    /// function returns.
+    pub fn wait_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> MutexGuard<'a, T>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// Waits on this condition variable for a notification, timing out after
+    /// a specified duration.
+    ///
+    /// The returned [`WaitTimeoutResult`] value indicates if the timeout is
+    /// known to have elapsed.
+    #[cfg(feature = "irq")]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        dur: core::time::Duration,
+    ) -> (MutexGuard<'a, T>, WaitTimeoutResult) {
+        let mutex = guard.lock;
+        let seq = self.seq.load(Ordering::Acquire);
+        drop(guard);
+        let timed_out = api::ax_wait_queue_wait(
+            &self.wq,
+            || self.seq.load(Ordering::Acquire) != seq,
+            Some(dur),
+        );
+        (mutex.lock(), WaitTimeoutResult(timed_out))
+    }
+
+    /// Waits on this condition variable for a notification, timing out after
+    /// a specified duration, or until the provided condition becomes false.
+    ///
+    /// The returned [`WaitTimeoutResult`] value indicates if the timeout is
+    /// known to have elapsed without the condition being met.
+    #[cfg(feature = "irq")]
+    pub fn wait_timeout_while<'a, T: ?Sized, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        dur: core::time::Duration,
+        mut condition: F,
+    ) -> (MutexGuard<'a, T>, WaitTimeoutResult)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let deadline = Instant::now() + dur;
+        while condition(&mut *guard) {
+            let remaining = deadline.duration_since(Instant::now());
+            if remaining.is_zero() {
+                return (guard, WaitTimeoutResult(true));
+            }
+            guard = self.wait_timeout(guard, remaining).0;
+        }
+        (guard, WaitTimeoutResult(false))
+    }
+
+    /// Wakes up one blocked task on this condvar.
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        api::ax_wait_queue_wake(&self.wq, 1);
+    }
+
+    /// Wakes up all blocked tasks on this condvar.
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        api::ax_wait_queue_wake(&self.wq, u32::MAX);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for Condvar {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad("Condvar { .. }")
+    }
+}
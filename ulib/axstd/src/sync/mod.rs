@@ -7,12 +7,29 @@ pub use core::sync::atomic;
 #[doc(no_inline)]
 pub use alloc::sync::{Arc, Weak};
 
+#[cfg(feature = "multitask")]
+mod barrier;
+#[cfg(feature = "multitask")]
+mod condvar;
 #[cfg(feature = "multitask")]
 mod mutex;
+#[cfg(feature = "multitask")]
+mod once;
+#[cfg(feature = "multitask")]
+mod rwlock;
+#[cfg(feature = "multitask")]
+mod semaphore;
 
 #[cfg(feature = "multitask")]
 #[doc(cfg(feature = "multitask"))]
-pub use self::mutex::{Mutex, MutexGuard};
+pub use self::{
+    barrier::{Barrier, BarrierWaitResult},
+    condvar::{Condvar, WaitTimeoutResult},
+    mutex::{Mutex, MutexGuard},
+    once::{LazyLock, Once},
+    rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    semaphore::Semaphore,
+};
 
 #[cfg(not(feature = "multitask"))]
 #[doc(cfg(not(feature = "multitask")))]
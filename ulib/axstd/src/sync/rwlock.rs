@@ -0,0 +1,262 @@
+//! A sleeping, writer-preferring reader-writer lock.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use arceos_api::task::{self as api, AxWaitQueueHandle};
+
+/// The lock state when it is held by a writer, otherwise it is the number of
+/// readers.
+const WRITER: usize = usize::MAX;
+
+/// A reader-writer lock, similar to
+/// [`std::sync::RwLock`](https://doc.rust-lang.org/std/sync/struct.RwLock.html).
+///
+/// This type of lock allows a number of readers or at most one writer at any
+/// point in time. The tasks that cannot acquire the lock are blocked and put
+/// into the wait queues.
+///
+/// The lock prefers writers: new readers are blocked while any writer is
+/// waiting, so writers are not starved by continuous readers. As a result,
+/// acquiring a read lock recursively may deadlock.
+pub struct RwLock<T: ?Sized> {
+    /// The number of readers, or [`WRITER`].
+    state: AtomicUsize,
+    /// The number of writers waiting for the lock.
+    writers_waiting: AtomicUsize,
+    readers_wq: AxWaitQueueHandle,
+    writers_wq: AxWaitQueueHandle,
+    data: UnsafeCell<T>,
+}
+
+/// RAII structure used to release the shared read access of a lock when
+/// dropped.
+pub struct RwLockReadGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    data: *const T,
+}
+
+/// RAII structure used to release the exclusive write access of a lock when
+/// dropped.
+pub struct RwLockWriteGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    data: *mut T,
+}
+
+// Same unsafe impls as `std::sync::RwLock`
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> RwLock<T> {
+    /// Creates a new instance of an [`RwLock`] which is unlocked.
+    #[inline(always)]
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            writers_waiting: AtomicUsize::new(0),
+            readers_wq: AxWaitQueueHandle::new(),
+            writers_wq: AxWaitQueueHandle::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes this [`RwLock`], returning the underlying data.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        // We know statically that there are no outstanding references to
+        // `self` so there's no need to lock.
+        let RwLock { data, .. } = self;
+        data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Locks this [`RwLock`] with shared read access, blocking the current
+    /// task until it can be acquired.
+    ///
+    /// The current task is blocked while the lock is held by a writer, or
+    /// any writer is waiting for it.
+    pub fn read(&self) -> RwLockReadGuard<T> {
+        loop {
+            if let Some(guard) = self.try_read() {
+                return guard;
+            }
+            api::ax_wait_queue_wait(
+                &self.readers_wq,
+                || {
+                    self.writers_waiting.load(Ordering::Acquire) == 0
+                        && self.state.load(Ordering::Acquire) != WRITER
+                },
+                None,
+            );
+        }
+    }
+
+    /// Attempts to acquire this [`RwLock`] with shared read access.
+    ///
+    /// It fails if the lock is held by a writer, or any writer is waiting for
+    /// it.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<T>> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state == WRITER || self.writers_waiting.load(Ordering::Acquire) != 0 {
+                return None;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Some(RwLockReadGuard {
+                        lock: self,
+                        data: self.data.get(),
+                    })
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    /// Locks this [`RwLock`] with exclusive write access, blocking the current
+    /// task until it can be acquired.
+    pub fn write(&self) -> RwLockWriteGuard<T> {
+        if let Some(guard) = self.try_write() {
+            return guard;
+        }
+        self.writers_waiting.fetch_add(1, Ordering::AcqRel);
+        let guard = loop {
+            if let Some(guard) = self.try_write() {
+                break guard;
+            }
+            api::ax_wait_queue_wait(
+                &self.writers_wq,
+                || self.state.load(Ordering::Acquire) == 0,
+                None,
+            );
+        };
+        self.writers_waiting.fetch_sub(1, Ordering::AcqRel);
+        guard
+    }
+
+    /// Attempts to lock this [`RwLock`] with exclusive write access.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<T>> {
+        if self
+            .state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            Some(RwLockWriteGuard {
+                lock: self,
+                data: self.data.get(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the [`RwLock`] mutably, no actual locking needs
+    /// to take place -- the mutable borrow statically guarantees no locks
+    /// exist.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        // We know statically that there are no other references to `self`, so
+        // there's no need to lock.
+        unsafe { &mut *self.data.get() }
+    }
+
+    fn read_unlock(&self) {
+        if self.state.fetch_sub(1, Ordering::Release) == 1 {
+            // the last reader
+            api::ax_wait_queue_wake(&self.writers_wq, 1);
+        }
+    }
+
+    fn write_unlock(&self) {
+        self.state.store(0, Ordering::Release);
+        if self.writers_waiting.load(Ordering::Acquire) != 0 {
+            api::ax_wait_queue_wake(&self.writers_wq, 1);
+        } else {
+            api::ax_wait_queue_wake(&self.readers_wq, u32::MAX);
+        }
+    }
+}
+
+impl<T: ?Sized + Default> Default for RwLock<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.try_read() {
+            Some(guard) => write!(f, "RwLock {{ data: ")
+                .and_then(|()| (*guard).fmt(f))
+                .and_then(|()| write!(f, "}}")),
+            None => write!(f, "RwLock {{ <locked> }}"),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        // We know statically that there are only readers
+        unsafe { &*self.data }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        // We know statically that only we are referencing data
+        unsafe { &*self.data }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        // We know statically that only we are referencing data
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<'a, T: ?Sized + fmt::Debug> fmt::Debug for RwLockReadGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T: ?Sized + fmt::Debug> fmt::Debug for RwLockWriteGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockReadGuard<'a, T> {
+    /// The dropping of the [`RwLockReadGuard`] will release the shared access.
+    fn drop(&mut self) {
+        self.lock.read_unlock();
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockWriteGuard<'a, T> {
+    /// The dropping of the [`RwLockWriteGuard`] will release the exclusive
+    /// access.
+    fn drop(&mut self) {
+        self.lock.write_unlock();
+    }
+}
@@ -0,0 +1,81 @@
+//! A sleeping counting semaphore.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use arceos_api::task::{self as api, AxWaitQueueHandle};
+
+/// A counting semaphore.
+///
+/// A semaphore maintains a count of permits. [`acquire`](Self::acquire) takes
+/// a permit, and blocks the current task in the wait queue while there are no
+/// permits available. [`release`](Self::release) returns a permit, and wakes
+/// up a waiting task.
+pub struct Semaphore {
+    wq: AxWaitQueueHandle,
+    count: AtomicUsize,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with the initial number of permits.
+    pub const fn new(count: usize) -> Self {
+        Self {
+            wq: AxWaitQueueHandle::new(),
+            count: AtomicUsize::new(count),
+        }
+    }
+
+    /// Acquires a permit, blocking the current task until one is available.
+    pub fn acquire(&self) {
+        while !self.try_acquire() {
+            api::ax_wait_queue_wait(&self.wq, || self.count.load(Ordering::Acquire) > 0, None);
+        }
+    }
+
+    /// Acquires a permit, blocking the current task until one is available,
+    /// or the given duration has elapsed.
+    ///
+    /// Returns `true` if a permit is acquired.
+    #[cfg(feature = "irq")]
+    pub fn acquire_timeout(&self, dur: core::time::Duration) -> bool {
+        let deadline = crate::time::Instant::now() + dur;
+        while !self.try_acquire() {
+            let remaining = deadline.duration_since(crate::time::Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            let cond = || self.count.load(Ordering::Acquire) > 0;
+            api::ax_wait_queue_wait(&self.wq, cond, Some(remaining));
+        }
+        true
+    }
+
+    /// Attempts to acquire a permit without blocking.
+    ///
+    /// Returns `true` if a permit is acquired.
+    pub fn try_acquire(&self) -> bool {
+        self.count
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    /// Releases a permit, waking up a task waiting for it.
+    pub fn release(&self) {
+        self.count.fetch_add(1, Ordering::Release);
+        api::ax_wait_queue_wake(&self.wq, 1);
+    }
+
+    /// Returns the number of permits currently available.
+    pub fn available_permits(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+impl core::fmt::Debug for Semaphore {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Semaphore")
+            .field("permits", &self.available_permits())
+            .finish()
+    }
+}
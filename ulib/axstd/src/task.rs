@@ -0,0 +1,126 @@
+//! Asynchronous tasks.
+//!
+//! [`block_on`] runs a future on the current thread, which sleeps while the
+//! future is pending. [`spawn`] puts a future on an executor shared by all
+//! the spawned futures, which is polled by a fixed pool of worker threads, so
+//! spawning a future is much cheaper than spawning a thread. The futures are
+//! woken by the sockets (e.g., [`TcpStream::read_async`]), timers (see
+//! [`sleep`]) and other events, instead of being polled repeatedly.
+//!
+//! [`TcpStream::read_async`]: crate::net::TcpStream::read_async
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use arceos_api::task as api;
+
+use crate::sync::{Condvar, Mutex};
+
+/// Runs a future to completion on the current thread, returns its output.
+///
+/// The current thread sleeps while the future is pending, until the future is
+/// woken.
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut output = None;
+    api::ax_block_on(async {
+        output = Some(fut.await);
+    });
+    output.unwrap()
+}
+
+/// The output of a spawned future, shared with its [`JoinHandle`].
+struct Packet<T> {
+    /// The output, and the waker of the task awaiting the [`JoinHandle`].
+    state: Mutex<(Option<T>, Option<Waker>)>,
+    done: Condvar,
+}
+
+/// An owned permission to get the output of a spawned future.
+///
+/// The output can be got by [`JoinHandle::join`] from a thread, or by
+/// awaiting the handle from another future. Dropping the handle detaches the
+/// future, which still runs to completion.
+pub struct JoinHandle<T> {
+    packet: Arc<Packet<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Waits for the future to complete, blocking the current thread, and
+    /// returns its output.
+    pub fn join(self) -> T {
+        let mut state = self.packet.state.lock();
+        loop {
+            if let Some(output) = state.0.take() {
+                return output;
+            }
+            state = self.packet.done.wait(state);
+        }
+    }
+
+    /// Checks if the future has completed.
+    pub fn is_finished(&self) -> bool {
+        self.packet.state.lock().0.is_some()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.packet.state.lock();
+        match state.0.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Spawns a future on the shared executor, returning a [`JoinHandle`] for it.
+///
+/// No thread is created for the future. It is polled by one of a fixed pool
+/// of worker threads when it is woken, so it should not block the worker in
+/// `poll` (e.g., by [`thread::sleep`](crate::thread::sleep) or blocking I/O);
+/// await [`sleep`] or the asynchronous I/O methods instead.
+pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let packet = Arc::new(Packet {
+        state: Mutex::new((None, None)),
+        done: Condvar::new(),
+    });
+    let their_packet = packet.clone();
+    api::ax_spawn_async(async move {
+        let output = fut.await;
+        let mut state = their_packet.state.lock();
+        state.0 = Some(output);
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+        drop(state);
+        their_packet.done.notify_all();
+    });
+    JoinHandle { packet }
+}
+
+/// Waits asynchronously for the given duration, without blocking the current
+/// thread.
+#[cfg(feature = "irq")]
+pub async fn sleep(dur: core::time::Duration) {
+    sleep_until(arceos_api::time::ax_current_time() + dur).await
+}
+
+/// Waits asynchronously until the given deadline, without blocking the
+/// current thread.
+#[cfg(feature = "irq")]
+pub async fn sleep_until(deadline: arceos_api::time::AxTimeValue) {
+    api::ax_sleep_until_async(deadline).await
+}
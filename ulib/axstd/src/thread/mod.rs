@@ -3,7 +3,11 @@
 #[cfg(feature = "multitask")]
 mod multi;
 #[cfg(feature = "multitask")]
+mod ps;
+#[cfg(feature = "multitask")]
 pub use multi::*;
+#[cfg(feature = "multitask")]
+pub use ps::{task_table, TaskUsageSampler};
 
 use arceos_api::task as api;
 
@@ -37,5 +41,8 @@ pub fn sleep(dur: core::time::Duration) {
 /// If one of `multitask` or `irq` features is not enabled, it uses busy-wait
 /// instead.
 pub fn sleep_until(deadline: arceos_api::time::AxTimeValue) {
-    api::ax_sleep_until(deadline);
+    // sleep again if it is woken up early, e.g., by a signal
+    while arceos_api::time::ax_current_time() < deadline {
+        api::ax_sleep_until(deadline);
+    }
 }
@@ -3,10 +3,15 @@
 extern crate alloc;
 
 use crate::io;
-use alloc::{string::String, sync::Arc};
+use alloc::{string::String, sync::Arc, vec::Vec};
 use core::{cell::UnsafeCell, num::NonZeroU64};
 
 use arceos_api::task::{self as api, AxTaskHandle};
+
+/// A set of CPUs that a thread is allowed to run on.
+pub use arceos_api::task::AxCpuMask as CpuMask;
+/// Information of a task in the system, see [`tasks`].
+pub use arceos_api::task::AxTaskInfo as TaskInfo;
 use axerrno::ax_err_type;
 
 /// A unique identifier for a running thread.
@@ -49,6 +54,8 @@ pub struct Builder {
     name: Option<String>,
     // The size of the stack for the spawned thread in bytes
     stack_size: Option<usize>,
+    // The CPUs that the spawned thread is allowed to run on
+    affinity: Option<CpuMask>,
 }
 
 impl Builder {
@@ -58,6 +65,7 @@ impl Builder {
         Builder {
             name: None,
             stack_size: None,
+            affinity: None,
         }
     }
 
@@ -73,6 +81,14 @@ impl Builder {
         self
     }
 
+    /// Sets the CPUs that the new thread is allowed to run on.
+    ///
+    /// If none of them is online, the thread can run on any CPU.
+    pub fn affinity(mut self, cpumask: CpuMask) -> Builder {
+        self.affinity = Some(cpumask);
+        self
+    }
+
     /// Spawns a new thread by taking ownership of the `Builder`, and returns an
     /// [`io::Result`] to its [`JoinHandle`].
     ///
@@ -115,7 +131,10 @@ impl Builder {
             drop(their_packet);
         };
 
-        let task = api::ax_spawn(main, name, stack_size);
+        let task = match self.affinity {
+            Some(cpumask) => api::ax_spawn_with_affinity(main, name, stack_size, cpumask),
+            None => api::ax_spawn(main, name, stack_size),
+        };
         Ok(JoinHandle {
             thread: Thread::from_id(task.id()),
             native: task,
@@ -130,6 +149,12 @@ pub fn current() -> Thread {
     Thread::from_id(id)
 }
 
+/// Returns the information of all live tasks in the system, including the
+/// threads and the system tasks (e.g., the idle tasks), sorted by ID.
+pub fn tasks() -> Vec<TaskInfo> {
+    api::ax_task_list()
+}
+
 /// Spawns a new thread, returning a [`JoinHandle`] for it.
 ///
 /// The join handle provides a [`join`] method that can be used to join the
@@ -0,0 +1,116 @@
+//! Task tables for the `ps` and `top` commands of shells.
+
+extern crate alloc;
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use core::fmt::Write;
+use core::time::Duration;
+
+use super::{tasks, TaskInfo};
+use crate::time::Instant;
+
+fn cpu_time(task: &TaskInfo) -> Duration {
+    task.stats.usage.user_time + task.stats.usage.kernel_time
+}
+
+/// Formats a duration as seconds with 3 decimal places.
+fn fmt_secs(dur: Duration) -> String {
+    format!("{}.{:03}", dur.as_secs(), dur.subsec_millis())
+}
+
+/// Renders the table of all tasks, like `ps`.
+pub fn task_table() -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:>5} {:<16} {:<8} {:<10} {:>4} {:>3} {:>8} {:>10}",
+        "ID", "NAME", "STATE", "POLICY", "PRI", "CPU", "STACK", "TIME"
+    );
+    for task in tasks() {
+        let _ = writeln!(
+            out,
+            "{:>5} {:<16} {:<8} {:<10} {:>4} {:>3} {:>8} {:>10}",
+            task.id,
+            task.name,
+            format!("{:?}", task.state),
+            format!("{:?}", task.policy),
+            task.priority,
+            task.stats.last_cpu,
+            task.stack_size,
+            fmt_secs(cpu_time(&task)),
+        );
+    }
+    out
+}
+
+/// Samples the CPU usage of all tasks between calls, like `top`.
+pub struct TaskUsageSampler {
+    last_times: BTreeMap<u64, Duration>,
+    last_instant: Instant,
+}
+
+impl TaskUsageSampler {
+    /// Creates a sampler, starting from the current CPU time of the tasks.
+    pub fn new() -> Self {
+        Self {
+            last_times: tasks()
+                .iter()
+                .map(|task| (task.id, cpu_time(task)))
+                .collect(),
+            last_instant: Instant::now(),
+        }
+    }
+
+    /// Renders the table of the tasks with their CPU usage since the last
+    /// sample (or the creation), sorted by the usage.
+    pub fn sample(&mut self) -> String {
+        let tasks = tasks();
+        let now = Instant::now();
+        let interval = now.duration_since(self.last_instant).as_nanos().max(1);
+
+        // (task, CPU usage in 0.1%)
+        let mut rows: Vec<_> = tasks
+            .iter()
+            .map(|task| {
+                let last = self.last_times.get(&task.id).copied().unwrap_or_default();
+                let delta = cpu_time(task).saturating_sub(last);
+                (task, delta.as_nanos() * 1000 / interval)
+            })
+            .collect();
+        rows.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut out = String::new();
+        let _ = writeln!(out, "tasks: {} total", tasks.len());
+        let _ = writeln!(
+            out,
+            "{:>5} {:<16} {:<8} {:>3} {:>6} {:>10} {:>8} {:>8}",
+            "ID", "NAME", "STATE", "CPU", "%CPU", "TIME", "VCSW", "IVCSW"
+        );
+        for (task, permille) in rows {
+            let usage = &task.stats.usage;
+            let _ = writeln!(
+                out,
+                "{:>5} {:<16} {:<8} {:>3} {:>4}.{} {:>10} {:>8} {:>8}",
+                task.id,
+                task.name,
+                format!("{:?}", task.state),
+                task.stats.last_cpu,
+                permille / 10,
+                permille % 10,
+                fmt_secs(cpu_time(task)),
+                usage.voluntary_switches,
+                usage.involuntary_switches,
+            );
+        }
+
+        self.last_times = tasks.iter().map(|task| (task.id, cpu_time(task))).collect();
+        self.last_instant = now;
+        out
+    }
+}
+
+impl Default for TaskUsageSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,33 @@
+//! USB devices.
+
+use alloc::boxed::Box;
+
+pub use arceos_api::usb::{AxUsbDevice as UsbDevice, AxUsbHotplugEvent as HotplugEvent};
+
+/// Calls `f` every time a USB device is attached or detached, including
+/// devices behind hubs.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::usb::{self, HotplugEvent};
+///
+/// usb::on_hotplug(|event| match event {
+///     HotplugEvent::Attached(dev) => println!("attached: {:?}", dev),
+///     HotplugEvent::Detached(dev) => println!("detached: {:?}", dev),
+/// });
+/// ```
+pub fn on_hotplug<F>(f: F)
+where
+    F: Fn(&HotplugEvent) + Send + Sync + 'static,
+{
+    arceos_api::usb::ax_usb_register_hotplug_callback(Box::new(f))
+}
+
+/// Checks the host controllers for devices attached or detached since the
+/// last call, and notifies the callbacks.
+///
+/// With the `multitask` feature this is done periodically in the background.
+pub fn poll_hotplug() {
+    arceos_api::usb::ax_usb_poll_hotplug()
+}